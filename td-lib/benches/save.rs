@@ -0,0 +1,49 @@
+//! Compares the ways of saving a large database: building the whole file in memory first, as
+//! [`DatabaseFile::from`] does, and serializing the tasks straight into the file.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use td_lib::database::{database_file::DatabaseFile, Database, Task};
+
+const TASK_COUNT: usize = 50_000;
+
+/// Creates a database with a chain of dependencies running through all tasks.
+fn large_database() -> Database {
+    let mut database = Database::default();
+    let mut previous = None;
+    for i in 0..TASK_COUNT {
+        let mut task = Task::create_now(format!("task {i}"));
+        task.description = "some text to make the task a bit larger".to_string();
+        task.tags = vec!["work".into(), format!("group-{}", i % 20).into()];
+        let id = task.id().clone();
+        database.add_task(task);
+        if let Some(previous) = &previous {
+            database.add_dependency(&id, previous);
+        }
+        previous = Some(id);
+    }
+    database
+}
+
+fn save(c: &mut Criterion) {
+    let database = large_database();
+    let dir = std::env::temp_dir().join(format!("td-save-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut group = c.benchmark_group("save 50k tasks");
+    group.sample_size(10);
+    for name in ["todo.json", "todo.json.gz", "todo.msgpack"] {
+        let path = dir.join(name);
+        group.bench_function(format!("{name} in memory"), |b| {
+            b.iter(|| DatabaseFile::from(&database).write(&path).unwrap());
+        });
+        group.bench_function(format!("{name} streaming"), |b| {
+            b.iter(|| DatabaseFile::write_database(&database, &path).unwrap());
+        });
+    }
+    group.finish();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, save);
+criterion_main!(benches);
@@ -0,0 +1,510 @@
+//! An append-only log of who changed what in a database, stored next to it.
+//!
+//! Every line of the log is a JSON object describing a single change, so the log can be appended
+//! to without reading it and processed with other tools.
+
+use std::{
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use time::{format_description::FormatItem, macros::format_description, Date, OffsetDateTime};
+
+use crate::{
+    database::{Database, DatabaseOp, Task, TaskId},
+    errors::{DatabaseReadError, DatabaseWriteError},
+};
+
+const TIME_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+
+/// A single change in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the change was saved.
+    pub time: OffsetDateTime,
+    /// The user that made the change.
+    pub user: String,
+    /// What was changed.
+    #[serde(flatten)]
+    pub change: AuditChange,
+}
+
+/// A change to a database, as recorded in the audit log. Titles of confidential tasks are left
+/// out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AuditChange {
+    /// A task was added.
+    TaskAdded {
+        /// The task that was added.
+        task: TaskId,
+        /// The title of the task.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// One or more fields of a task were changed.
+    TaskChanged {
+        /// The task that was changed.
+        task: TaskId,
+        /// The title of the task after the change.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        /// The names of the fields that were changed.
+        fields: Vec<String>,
+    },
+    /// A task was marked as completed.
+    TaskCompleted {
+        /// The task that was completed.
+        task: TaskId,
+        /// The title of the task.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A completed task was marked as not completed again.
+    TaskReopened {
+        /// The task that was reopened.
+        task: TaskId,
+        /// The title of the task.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A task was removed.
+    TaskRemoved {
+        /// The task that was removed.
+        task: TaskId,
+        /// The title of the task.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A dependency was added or changed.
+    DependencySet {
+        /// The dependent task.
+        task: TaskId,
+        /// The task that is depended on.
+        dependency: TaskId,
+    },
+    /// A dependency was removed.
+    DependencyRemoved {
+        /// The dependent task.
+        task: TaskId,
+        /// The task that was depended on.
+        dependency: TaskId,
+    },
+    /// A setting of the database was changed.
+    SettingChanged {
+        /// The name of the setting.
+        setting: String,
+    },
+}
+
+impl AuditChange {
+    /// Gets the task that was changed, if any.
+    #[must_use]
+    pub fn task(&self) -> Option<&TaskId> {
+        match self {
+            Self::TaskAdded { task, .. }
+            | Self::TaskChanged { task, .. }
+            | Self::TaskCompleted { task, .. }
+            | Self::TaskReopened { task, .. }
+            | Self::TaskRemoved { task, .. }
+            | Self::DependencySet { task, .. }
+            | Self::DependencyRemoved { task, .. } => Some(task),
+            Self::SettingChanged { .. } => None,
+        }
+    }
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = self.time.format(TIME_FORMAT).map_err(|_| std::fmt::Error)?;
+        write!(f, "{time} {} ", self.user)?;
+
+        let title = |title: &Option<String>| match title {
+            Some(title) => format!(" ({title})"),
+            None => String::new(),
+        };
+        match &self.change {
+            AuditChange::TaskAdded { task, title: t } => {
+                write!(f, "added task {}{}", task.as_str(), title(t))
+            }
+            AuditChange::TaskChanged {
+                task,
+                title: t,
+                fields,
+            } => write!(
+                f,
+                "changed {} of task {}{}",
+                fields.join(", "),
+                task.as_str(),
+                title(t)
+            ),
+            AuditChange::TaskCompleted { task, title: t } => {
+                write!(f, "completed task {}{}", task.as_str(), title(t))
+            }
+            AuditChange::TaskReopened { task, title: t } => {
+                write!(f, "reopened task {}{}", task.as_str(), title(t))
+            }
+            AuditChange::TaskRemoved { task, title: t } => {
+                write!(f, "removed task {}{}", task.as_str(), title(t))
+            }
+            AuditChange::DependencySet { task, dependency } => write!(
+                f,
+                "made task {} depend on {}",
+                task.as_str(),
+                dependency.as_str()
+            ),
+            AuditChange::DependencyRemoved { task, dependency } => write!(
+                f,
+                "removed the dependency of task {} on {}",
+                task.as_str(),
+                dependency.as_str()
+            ),
+            AuditChange::SettingChanged { setting } => write!(f, "changed the {setting}"),
+        }
+    }
+}
+
+/// Selects which entries of the audit log to show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// Only show changes to the task with this id.
+    pub task: Option<String>,
+    /// Only show changes made by this user.
+    pub user: Option<String>,
+    /// Only show changes made on or after this day.
+    pub since: Option<Date>,
+}
+
+impl AuditFilter {
+    /// Checks if the entry should be shown.
+    #[must_use]
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.task
+            .as_ref()
+            .is_none_or(|task| entry.change.task().map(TaskId::as_str) == Some(task))
+            && self.user.as_ref().is_none_or(|user| &entry.user == user)
+            && self.since.is_none_or(|since| entry.time.date() >= since)
+    }
+}
+
+/// Gets the path of the audit log that belongs to the given database.
+#[must_use]
+pub fn log_path(database_path: &Path) -> PathBuf {
+    database_path.with_extension("audit.jsonl")
+}
+
+/// Gets the name of the user running td, to record in the audit log.
+#[must_use]
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Describes the changes between 2 versions of a database as audit log entries.
+#[must_use]
+pub fn changes(
+    old: &Database,
+    new: &Database,
+    user: &str,
+    time: OffsetDateTime,
+) -> Vec<AuditEntry> {
+    let title = |task: &Task| {
+        let confidential = old.is_confidential(task) || new.is_confidential(task);
+        (!confidential && !task.is_locked()).then(|| task.title.clone())
+    };
+
+    let changes = new.diff(old).into_iter().flat_map(|op| match op {
+        DatabaseOp::SetTask { id, old, new } => match (old, new) {
+            (None, Some(new)) => vec![AuditChange::TaskAdded {
+                task: id,
+                title: title(&new),
+            }],
+            (Some(old), None) => vec![AuditChange::TaskRemoved {
+                task: id,
+                title: title(&old),
+            }],
+            (Some(old), Some(new)) => task_changes(id, title(&new), &old, &new),
+            (None, None) => vec![],
+        },
+        DatabaseOp::SetDependency { from, to, new, .. } => vec![match new {
+            Some(_) => AuditChange::DependencySet {
+                task: from,
+                dependency: to,
+            },
+            None => AuditChange::DependencyRemoved {
+                task: from,
+                dependency: to,
+            },
+        }],
+        DatabaseOp::SetArchivePolicy { .. } => vec![AuditChange::SettingChanged {
+            setting: "archive policy".to_string(),
+        }],
+        DatabaseOp::SetTagInfo { tag, .. } => vec![AuditChange::SettingChanged {
+            setting: format!("settings of tag '{tag}'"),
+        }],
+        DatabaseOp::SetDatabaseId { .. } => vec![AuditChange::SettingChanged {
+            setting: "database id".to_string(),
+        }],
+        DatabaseOp::SetIdNamespace { .. } => vec![AuditChange::SettingChanged {
+            setting: "id namespace".to_string(),
+        }],
+        DatabaseOp::SetEncryption { .. } => vec![AuditChange::SettingChanged {
+            setting: "encryption settings".to_string(),
+        }],
+        // the clocks are bookkeeping that changes with every other change
+        DatabaseOp::SetCrdtClocks { .. } => vec![],
+    });
+
+    changes
+        .map(|change| AuditEntry {
+            time,
+            user: user.to_string(),
+            change,
+        })
+        .collect()
+}
+
+/// Appends entries to the audit log at the given path, creating it if it does not exist yet.
+pub fn append(path: &Path, entries: &[AuditEntry]) -> Result<(), DatabaseWriteError> {
+    let mut lines = String::new();
+    for entry in entries {
+        let line =
+            serde_json::to_string(entry).map_err(|source| DatabaseWriteError::JsonError {
+                path: Some(path.to_owned()),
+                source,
+            })?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+
+    let io_error = |source| DatabaseWriteError::IoError {
+        path: path.to_owned(),
+        source,
+    };
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .map_err(io_error)
+}
+
+/// Reads all entries from the audit log at the given path. A missing log has no entries.
+pub fn read(path: &Path) -> Result<Vec<AuditEntry>, DatabaseReadError> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(source) => {
+            return Err(DatabaseReadError::IoError {
+                path: path.to_owned(),
+                source,
+            })
+        }
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|source| DatabaseReadError::JsonError {
+                path: Some(path.to_owned()),
+                source,
+            })
+        })
+        .collect()
+}
+
+/// Describes the changes to a single task. Completing or reopening a task gets its own entry, so
+/// they stand out from other changes.
+fn task_changes(id: TaskId, title: Option<String>, old: &Task, new: &Task) -> Vec<AuditChange> {
+    let mut fields = changed_fields(old, new);
+    let mut changes = vec![];
+    match (old.time_completed, new.time_completed) {
+        (None, Some(_)) => changes.push(AuditChange::TaskCompleted {
+            task: id.clone(),
+            title: title.clone(),
+        }),
+        (Some(_), None) => changes.push(AuditChange::TaskReopened {
+            task: id.clone(),
+            title: title.clone(),
+        }),
+        _ => {}
+    }
+    if !changes.is_empty() {
+        fields.retain(|field| field != "time_completed");
+    }
+    if !fields.is_empty() {
+        changes.push(AuditChange::TaskChanged {
+            task: id,
+            title,
+            fields,
+        });
+    }
+    changes
+}
+
+fn changed_fields(old: &Task, new: &Task) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {return vec![];};
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let mut fields = new
+        .keys()
+        .chain(old.keys())
+        .filter(|field| old.get(*field) != new.get(*field))
+        .cloned()
+        .collect::<Vec<_>>();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[test]
+    fn describes_changes() {
+        let mut old = Database::default();
+        let task = Task::create_now("task".into());
+        let removed = Task::create_now("removed".into());
+        let (id, removed_id) = (task.id.clone(), removed.id.clone());
+        old.add_task(task);
+        old.add_task(removed);
+
+        let mut new = old.clone();
+        new[&id].title = "renamed".into();
+        new[&id].tags.push("tag".into());
+        new.remove_task(&removed_id);
+
+        let time = datetime!(2024-01-02 3:04:05 UTC);
+        let entries = changes(&old, &new, "alice", time);
+        assert_eq!(
+            entries.iter().map(|e| e.change.clone()).collect::<Vec<_>>(),
+            vec![
+                AuditChange::TaskChanged {
+                    task: id.clone(),
+                    title: Some("renamed".into()),
+                    fields: vec!["tags".into(), "title".into()],
+                },
+                AuditChange::TaskRemoved {
+                    task: removed_id,
+                    title: Some("removed".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            entries[0].to_string(),
+            format!(
+                "2024-01-02 03:04:05 alice changed tags, title of task {} (renamed)",
+                id.as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn records_completion_separately() {
+        let mut old = Database::default();
+        let task = Task::create_now("task".into());
+        let id = task.id.clone();
+        old.add_task(task);
+
+        let mut new = old.clone();
+        new[&id].time_completed = Some(datetime!(2024-01-02 3:04:05 UTC));
+        new[&id].tags.push("tag".into());
+
+        let time = datetime!(2024-01-02 3:04:05 UTC);
+        let completed = changes(&old, &new, "alice", time);
+        assert_eq!(
+            completed
+                .iter()
+                .map(|e| e.change.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                AuditChange::TaskCompleted {
+                    task: id.clone(),
+                    title: Some("task".into()),
+                },
+                AuditChange::TaskChanged {
+                    task: id.clone(),
+                    title: Some("task".into()),
+                    fields: vec!["tags".into()],
+                },
+            ]
+        );
+
+        let reopened = changes(&new, &old, "alice", time);
+        assert_eq!(
+            reopened[0].change,
+            AuditChange::TaskReopened {
+                task: id.clone(),
+                title: Some("task".into()),
+            }
+        );
+        assert_eq!(
+            reopened[0].to_string(),
+            format!(
+                "2024-01-02 03:04:05 alice reopened task {} (task)",
+                id.as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn appends_and_filters() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = log_path(&dir.join("todo.json"));
+
+        let task = Task::create_now("task".into());
+        let id = task.id.clone();
+        let mut db = Database::default();
+        db.add_task(task);
+
+        let old = changes(
+            &Database::default(),
+            &db,
+            "alice",
+            datetime!(2024-01-01 0:00 UTC),
+        );
+        let recent = changes(
+            &db,
+            &Database::default(),
+            "bob",
+            datetime!(2024-02-01 0:00 UTC),
+        );
+        append(&path, &old).unwrap();
+        append(&path, &recent).unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], old[0]);
+
+        let filter = AuditFilter {
+            task: Some(id.as_str().to_string()),
+            since: Some(date!(2024 - 01 - 15)),
+            ..Default::default()
+        };
+        let filtered = entries
+            .iter()
+            .filter(|e| filter.matches(e))
+            .collect::<Vec<_>>();
+        assert_eq!(filtered, vec![&recent[0]]);
+
+        let filter = AuditFilter {
+            user: Some("alice".into()),
+            ..Default::default()
+        };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(read(&path).unwrap(), vec![]);
+    }
+}
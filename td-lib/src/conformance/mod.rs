@@ -0,0 +1,338 @@
+//! Checks that a pair of importer and exporter keeps a database intact, so integrations with other
+//! formats can be tested the same way as the built-in ones.
+//!
+//! [`check_round_trip`] exports a [`synthetic_database`] that uses every feature of td, imports the
+//! result again and reports every difference in the parts of a database the format claims to
+//! support, see [`FormatSupport`].
+
+use std::fmt::{Debug, Display};
+
+use time::{macros::datetime, Duration};
+
+use crate::database::{Database, DependencyRelation, Task, TaskDependency, WorkSession};
+
+/// The parts of a database a format can represent. Tasks, their titles and their creation times
+/// are always compared, the parts that are not supported are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatSupport {
+    /// When tasks were started.
+    pub started: bool,
+    /// When tasks were completed.
+    pub completed: bool,
+    /// When tasks were archived.
+    pub archived: bool,
+    /// When tasks are due.
+    pub due: bool,
+    /// The estimates of tasks.
+    pub estimate: bool,
+    /// The reminders of tasks.
+    pub reminders: bool,
+    /// The tags of tasks.
+    pub tags: bool,
+    /// The work sessions of tasks.
+    pub work_sessions: bool,
+    /// The metadata of tasks.
+    pub metadata: bool,
+    /// Which tasks depend on which.
+    pub dependencies: bool,
+    /// The lag of dependencies.
+    pub dependency_lag: bool,
+    /// The label, relation and creation time of dependencies.
+    pub dependency_properties: bool,
+}
+
+impl FormatSupport {
+    /// Support for everything, as td's own file format has.
+    pub const ALL: Self = Self {
+        started: true,
+        completed: true,
+        archived: true,
+        due: true,
+        estimate: true,
+        reminders: true,
+        tags: true,
+        work_sessions: true,
+        metadata: true,
+        dependencies: true,
+        dependency_lag: true,
+        dependency_properties: true,
+    };
+}
+
+/// A difference between the original and the round-tripped database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The title of the task that differs.
+    pub task: String,
+    /// The part of the task that differs.
+    pub field: &'static str,
+    /// The original value.
+    pub expected: String,
+    /// The value after round-tripping.
+    pub actual: String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of task {:?}: expected {}, got {}",
+            self.field, self.task, self.expected, self.actual
+        )
+    }
+}
+
+/// Creates a database that uses every feature of td. All tasks have a different title and all
+/// timestamps are whole seconds in UTC, so formats with less precision can still round-trip it.
+#[must_use]
+pub fn synthetic_database() -> Database {
+    let created = datetime!(2024-01-01 9:00 UTC);
+    let task = |title: &str| {
+        let mut task = Task::create_now(title.to_string());
+        task.time_created = created;
+        task
+    };
+
+    let todo = task("todo");
+
+    let mut started = task("started");
+    started.time_started = Some(created + Duration::hours(1));
+
+    let mut completed = task("completed, with \"special\" <characters> & tags");
+    completed.time_started = Some(created + Duration::hours(1));
+    completed.time_completed = Some(created + Duration::days(1));
+    completed.tags = vec!["work".into(), "urgent".into()];
+
+    let mut archived = task("archived");
+    archived.time_completed = Some(created + Duration::days(1));
+    archived.time_archived = Some(created + Duration::days(8));
+
+    let mut planned = task("planned");
+    planned.time_due = Some(created + Duration::days(14));
+    planned.estimate = Some(Duration::hours(3));
+    planned.reminders = vec![created + Duration::days(13)];
+    planned.tags = vec!["work".into()];
+    planned.metadata.insert("jira.key".into(), "TD-42".into());
+
+    let mut tracked = task("tracked");
+    tracked.time_started = Some(created + Duration::hours(2));
+    tracked.work_sessions = vec![
+        WorkSession {
+            start: created + Duration::hours(2),
+            end: Some(created + Duration::hours(3)),
+        },
+        WorkSession {
+            start: created + Duration::days(1),
+            end: None,
+        },
+    ];
+
+    let ids = [&todo, &started, &completed, &planned].map(|t| t.id().clone());
+    let mut database = Database::default();
+    for task in [todo, started, completed, archived, planned, tracked] {
+        database.add_task(task);
+    }
+
+    let [todo, started, completed, planned] = ids;
+    database.add_dependency(&started, &todo);
+    database.add_dependency_with(
+        &planned,
+        &completed,
+        TaskDependency {
+            lag: Duration::days(1),
+            label: Some("needs the results".to_string()),
+            relation: DependencyRelation::RelatesTo,
+            created: Some(created + Duration::hours(1)),
+        },
+    );
+    database
+}
+
+/// Exports the [`synthetic_database`], imports the result again and returns all differences in
+/// the supported parts. Tasks are matched by title, since formats may not keep their ids.
+pub fn check_round_trip<T, E>(
+    support: &FormatSupport,
+    export: impl FnOnce(&Database) -> T,
+    import: impl FnOnce(T) -> Result<Database, E>,
+) -> Result<Vec<Mismatch>, E> {
+    let original = synthetic_database();
+    let imported = import(export(&original))?;
+    Ok(compare(support, &original, &imported))
+}
+
+/// Compares the supported parts of 2 databases, matching tasks by title.
+#[must_use]
+pub fn compare(support: &FormatSupport, expected: &Database, actual: &Database) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+    let find = |database: &'_ Database, title: &str| {
+        database.get_all_tasks().find(|t| t.title == title).cloned()
+    };
+
+    for task in expected.get_all_tasks() {
+        let Some(other) = find(actual, &task.title) else {
+            mismatches.push(Mismatch {
+                task: task.title.clone(),
+                field: "task",
+                expected: "present".to_string(),
+                actual: "missing".to_string(),
+            });
+            continue;
+        };
+
+        let mut check = |supported: bool, field, expected: &dyn Debug, actual: &dyn Debug| {
+            let (expected, actual) = (format!("{expected:?}"), format!("{actual:?}"));
+            if supported && expected != actual {
+                mismatches.push(Mismatch {
+                    task: task.title.clone(),
+                    field,
+                    expected,
+                    actual,
+                });
+            }
+        };
+
+        let utc =
+            |time: Option<time::OffsetDateTime>| time.map(|t| t.to_offset(time::UtcOffset::UTC));
+        check(
+            true,
+            "time_created",
+            &utc(Some(task.time_created)),
+            &utc(Some(other.time_created)),
+        );
+        check(
+            support.started,
+            "time_started",
+            &utc(task.time_started),
+            &utc(other.time_started),
+        );
+        check(
+            support.completed,
+            "time_completed",
+            &utc(task.time_completed),
+            &utc(other.time_completed),
+        );
+        check(
+            support.archived,
+            "time_archived",
+            &utc(task.time_archived),
+            &utc(other.time_archived),
+        );
+        check(
+            support.due,
+            "time_due",
+            &utc(task.time_due),
+            &utc(other.time_due),
+        );
+        check(
+            support.estimate,
+            "estimate",
+            &task.estimate,
+            &other.estimate,
+        );
+        check(
+            support.reminders,
+            "reminders",
+            &task.reminders,
+            &other.reminders,
+        );
+        check(support.tags, "tags", &task.tags, &other.tags);
+        check(
+            support.work_sessions,
+            "work_sessions",
+            &task.work_sessions,
+            &other.work_sessions,
+        );
+        check(
+            support.metadata,
+            "metadata",
+            &task.metadata,
+            &other.metadata,
+        );
+
+        let dependencies = |database: &Database, task: &Task| {
+            let mut dependencies = database
+                .get_dependencies(task.id())
+                .map(|dependency| {
+                    let properties = database
+                        .get_dependency(task.id(), dependency.id())
+                        .cloned()
+                        .unwrap_or_default();
+                    (
+                        dependency.title.clone(),
+                        support.dependency_lag.then_some(properties.lag),
+                        support.dependency_properties.then_some((
+                            properties.label,
+                            properties.relation,
+                            properties.created,
+                        )),
+                    )
+                })
+                .collect::<Vec<_>>();
+            dependencies.sort();
+            dependencies
+        };
+        check(
+            support.dependencies,
+            "dependencies",
+            &dependencies(expected, task),
+            &dependencies(actual, &other),
+        );
+    }
+
+    for task in actual.get_all_tasks() {
+        if find(expected, &task.title).is_none() {
+            mismatches.push(Mismatch {
+                task: task.title.clone(),
+                field: "task",
+                expected: "missing".to_string(),
+                actual: "present".to_string(),
+            });
+        }
+    }
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export, import};
+
+    #[test]
+    fn native_format_round_trips() {
+        let mismatches = check_round_trip(
+            &FormatSupport::ALL,
+            |db| serde_json::to_string(db).unwrap(),
+            |json| serde_json::from_str::<Database>(&json),
+        )
+        .unwrap();
+        assert_eq!(mismatches, vec![]);
+    }
+
+    #[test]
+    fn taskwarrior_round_trips() {
+        let support = FormatSupport {
+            started: true,
+            completed: true,
+            due: true,
+            tags: true,
+            dependencies: true,
+            ..Default::default()
+        };
+        let mismatches = check_round_trip(&support, export::taskwarrior::to_json, |json| {
+            import::taskwarrior::parse(&json)
+        })
+        .unwrap();
+        assert_eq!(mismatches, vec![]);
+    }
+
+    #[test]
+    fn reports_lost_data() {
+        let mismatches =
+            check_round_trip(&FormatSupport::ALL, export::taskwarrior::to_json, |json| {
+                import::taskwarrior::parse(&json)
+            })
+            .unwrap();
+        assert!(mismatches.iter().any(|m| m.field == "estimate"));
+    }
+}
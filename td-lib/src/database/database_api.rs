@@ -1,4 +1,8 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    ops::{Index, IndexMut},
+};
 
 use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
 use time::OffsetDateTime;
@@ -25,6 +29,9 @@ impl IndexMut<&TaskId> for Database {
             panic!("Index not found");
         };
 
+        self.record_task(task_id);
+        self.index.mark_stale(task_id);
+        self.invalidate_dependency_stats();
         &mut self.graph[node_index]
     }
 }
@@ -32,16 +39,112 @@ impl IndexMut<&TaskId> for Database {
 impl Database {
     /// Adds a new task to the database.
     pub fn add_task(&mut self, task: Task) {
+        self.record_task(&task.id);
+        self.refresh_index();
+        self.index.insert(&task);
         let id = task.id.clone();
         let index = self.graph.add_node(task);
         self.task_id_to_index.insert(id, index);
+        self.invalidate_dependency_stats();
+        self.validate_after_mutation();
     }
 
     /// Removes a task from the database. If the given task id was not found, no changes are made.
     pub fn remove_task(&mut self, task_id: &TaskId) {
+        if self.changes.is_some() {
+            let dependencies = self.get_dependencies(task_id).map(|t| (task_id, &t.id));
+            let dependents = self
+                .get_inverse_dependencies(task_id)
+                .map(|t| (&t.id, task_id));
+            let edges = dependencies
+                .chain(dependents)
+                .map(|(from, to)| (from.clone(), to.clone()))
+                .collect::<Vec<_>>();
+            for (from, to) in edges {
+                self.record_dependency(&from, &to);
+            }
+            self.record_task(task_id);
+        }
+        self.index.remove(task_id);
+        self.refresh_index();
         self.task_id_to_index.remove(task_id);
         let Some(task_index) = self.get_node_index(task_id) else {return;};
         self.graph.remove_node(task_index);
+        self.invalidate_dependency_stats();
+        self.validate_after_mutation();
+    }
+
+    /// Adds a copy of the given task, with a new id and created at `now`. The title, description,
+    /// tags, project, assignee and estimate are copied, but not its progress, time tracking or reminders.
+    /// With `copy_dependencies`, the copy depends on the same tasks as the original. Returns the id
+    /// of the copy, or `None` if the task does not exist.
+    pub fn duplicate_task(
+        &mut self,
+        task_id: &TaskId,
+        copy_dependencies: bool,
+        now: OffsetDateTime,
+    ) -> Option<TaskId> {
+        let original = self.get_task(task_id)?;
+        let mut copy = Task::create_now(original.title.clone());
+        copy.time_created = now;
+        copy.description = original.description.clone();
+        copy.tags = original.tags.clone();
+        copy.project = original.project.clone();
+        copy.assignee = original.assignee.clone();
+        copy.estimate = original.estimate;
+        copy.encrypted = original.encrypted;
+
+        let copy_id = copy.id.clone();
+        let dependencies = match copy_dependencies {
+            true => {
+                let index = self.get_node_index(task_id)?;
+                self.graph
+                    .edges_directed(index, Direction::Outgoing)
+                    .map(|edge| (self.graph[edge.target()].id.clone(), edge.weight().clone()))
+                    .collect()
+            }
+            false => vec![],
+        };
+
+        self.add_task(copy);
+        for (dependency_id, dependency) in dependencies {
+            self.add_dependency_with(&copy_id, &dependency_id, dependency);
+        }
+        Some(copy_id)
+    }
+
+    /// Gets the task with the given id, if it is in the database.
+    #[must_use]
+    pub fn get_task(&self, task_id: &TaskId) -> Option<&Task> {
+        self.get_node_index(task_id).map(|index| &self.graph[index])
+    }
+
+    /// Gets a short version of the id of the given task to show to the user: the shortest start of
+    /// the id that no other task id starts with, but at least 6 characters. It is only unique among
+    /// the current tasks, so adding tasks can make it longer.
+    #[must_use]
+    pub fn short_id<'a>(&self, task_id: &'a TaskId) -> &'a str {
+        const MIN_LENGTH: usize = 6;
+
+        let id = task_id.as_str();
+        let shared = self
+            .get_all_tasks()
+            .map(Task::id)
+            .filter(|other| *other != task_id)
+            .map(|other| {
+                id.chars()
+                    .zip(other.as_str().chars())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0);
+
+        let length = (shared + 1).max(MIN_LENGTH);
+        match id.char_indices().nth(length) {
+            Some((end, _)) => &id[..end],
+            None => id,
+        }
     }
 
     /// Get all tasks in the database.
@@ -49,8 +152,202 @@ impl Database {
         self.graph.node_weights()
     }
 
+    /// Gets the first time after `now` that a snoozed task comes back, which changes what a
+    /// filtered list of tasks shows without changing the database.
+    #[must_use]
+    pub fn next_snooze_end(&self, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        self.get_all_tasks()
+            .filter_map(|t| t.snoozed_until)
+            .filter(|&until| until > now)
+            .min()
+    }
+
+    /// Gets all tags used in the database, along with the amount of tasks that have them.
+    #[must_use]
+    pub fn all_tags(&self) -> BTreeMap<&str, usize> {
+        let mut tags = BTreeMap::new();
+        for task in self.get_all_tasks() {
+            for tag in &task.tags {
+                *tags.entry(tag.as_str()).or_default() += 1;
+            }
+        }
+        tags
+    }
+
+    /// Gets all projects used in the database, along with the amount of tasks in them.
+    #[must_use]
+    pub fn all_projects(&self) -> BTreeMap<&str, usize> {
+        let mut projects = BTreeMap::new();
+        for project in self.get_all_tasks().filter_map(|t| t.project.as_deref()) {
+            *projects.entry(project).or_default() += 1;
+        }
+        projects
+    }
+
+    /// Gets everyone that tasks are assigned to, along with the amount of tasks assigned to them.
+    #[must_use]
+    pub fn all_assignees(&self) -> BTreeMap<&str, usize> {
+        let mut assignees = BTreeMap::new();
+        for assignee in self.get_all_tasks().filter_map(|t| t.assignee.as_deref()) {
+            *assignees.entry(assignee).or_default() += 1;
+        }
+        assignees
+    }
+
+    /// Gets the settings of the given tag, if it has any.
+    #[must_use]
+    pub fn tag_info(&self, tag: &str) -> Option<&TagInfo> {
+        self.tag_registry.get(tag)
+    }
+
+    /// Gets the color the given tag is shown in, if it has one.
+    #[must_use]
+    pub fn tag_color(&self, tag: &str) -> Option<TagColor> {
+        self.tag_info(tag).and_then(|info| info.color)
+    }
+
+    /// Sets the color the given tag is shown in, or removes it.
+    pub fn set_tag_color(&mut self, tag: &str, color: Option<TagColor>) {
+        self.record_settings();
+        let info = self.tag_registry.entry(Tag::new(tag)).or_default();
+        info.color = color;
+        if info.is_empty() {
+            self.tag_registry.remove(tag);
+        }
+    }
+
+    /// Removes the given tag from all tasks that have it, along with its settings. Returns how
+    /// many tasks had the tag.
+    pub fn remove_tag(&mut self, tag: &str) -> usize {
+        let task_ids = self
+            .get_all_tasks()
+            .filter(|t| t.tags.iter().any(|t| t == tag))
+            .map(|t| t.id().clone())
+            .collect::<Vec<_>>();
+        for task_id in &task_ids {
+            self[task_id].tags.retain(|t| t != tag);
+        }
+        self.record_settings();
+        self.tag_registry.remove(tag);
+        task_ids.len()
+    }
+
+    /// Removes all archived tasks from this database and returns them in a new database.
+    /// Dependencies between archived tasks are kept, dependencies between an archived and a
+    /// non-archived task are dropped. The new database keeps the encryption settings, so
+    /// confidential tasks stay confidential, and the settings of tags.
+    pub fn take_archived(&mut self) -> Self {
+        let mut archive = Self {
+            encryption: self.encryption.clone(),
+            tag_registry: self.tag_registry.clone(),
+            ..Self::default()
+        };
+
+        let archived_ids = self
+            .get_all_tasks()
+            .filter(|t| t.time_archived.is_some())
+            .map(|t| t.id.clone())
+            .collect::<Vec<_>>();
+
+        for id in &archived_ids {
+            archive.add_task(self[id].clone());
+        }
+        for id in &archived_ids {
+            let dependencies = self
+                .get_dependencies(id)
+                .filter(|dep| dep.time_archived.is_some())
+                .map(|dep| dep.id.clone())
+                .collect::<Vec<_>>();
+            for dependency in dependencies {
+                let weight = self
+                    .get_dependency(id, &dependency)
+                    .expect("dependency should exist")
+                    .clone();
+                archive.add_dependency_with(id, &dependency, weight);
+            }
+        }
+        for id in &archived_ids {
+            self.remove_task(id);
+        }
+
+        archive
+    }
+
+    /// Gets the random id that identifies this database in links to its tasks, if it has one.
+    #[must_use]
+    pub fn database_id(&self) -> Option<&str> {
+        self.database_id.as_deref()
+    }
+
+    /// Gets the id that identifies this database in links to its tasks, creating it the first
+    /// time. The id is stored in the database, so links keep working when the file is moved or
+    /// synced to another machine.
+    pub fn get_or_create_database_id(&mut self) -> &str {
+        if self.database_id.is_none() {
+            self.record_settings();
+        }
+        self.database_id.get_or_insert_with(|| nanoid::nanoid!(16))
+    }
+
+    /// Gets the rules for automatically archiving tasks in this database.
+    #[must_use]
+    pub fn archive_policy(&self) -> ArchivePolicy {
+        self.archive_policy
+    }
+
+    /// Sets the rules for automatically archiving tasks in this database.
+    pub fn set_archive_policy(&mut self, policy: ArchivePolicy) {
+        self.record_settings();
+        self.archive_policy = policy;
+    }
+
+    /// Checks whether copies of this database are merged as a CRDT, see [`crate::sync::crdt`].
+    #[must_use]
+    pub fn is_crdt(&self) -> bool {
+        self.crdt.is_some()
+    }
+
+    /// Starts tracking when each change is made, so copies of this database can be merged as a
+    /// CRDT. This can not be turned off again.
+    pub fn enable_crdt(&mut self) {
+        self.record_settings();
+        self.crdt.get_or_insert_with(CrdtClocks::default);
+    }
+
+    /// Gets the tasks that should be archived at `now` according to the archive policy, but are
+    /// not archived yet.
+    #[must_use]
+    pub fn tasks_to_auto_archive(&self, now: OffsetDateTime) -> Vec<TaskId> {
+        let Some(after) = self.archive_policy.archive_completed_after else {return vec![];};
+
+        self.get_all_tasks()
+            .filter(|t| t.time_archived.is_none())
+            .filter(|t| {
+                t.time_completed
+                    .is_some_and(|completed| completed + after <= now)
+            })
+            .map(|t| t.id.clone())
+            .collect()
+    }
+
+    /// Archives all tasks that should be archived at `now` according to the archive policy.
+    /// Returns the ids of the tasks that were archived.
+    pub fn apply_archive_policy(&mut self, now: OffsetDateTime) -> Vec<TaskId> {
+        let task_ids = self.tasks_to_auto_archive(now);
+        for id in &task_ids {
+            self[id].time_archived = Some(now);
+        }
+        task_ids
+    }
+
     /// Add a task dependency between 2 tasks. This indicates that one task depends on another.
     pub fn add_dependency(&mut self, from: &TaskId, to: &TaskId) {
+        self.add_dependency_with(from, to, TaskDependency::default());
+    }
+
+    /// Add a task dependency between 2 tasks with the given properties, such as a lag between the
+    /// dependency being completed and the dependent task being able to start.
+    pub fn add_dependency_with(&mut self, from: &TaskId, to: &TaskId, dependency: TaskDependency) {
         let from_index = self
             .get_node_index(from)
             .expect("should be able to resolve task id");
@@ -58,7 +355,62 @@ impl Database {
             .get_node_index(to)
             .expect("should be able to resolve task id");
 
-        self.graph.add_edge(from_index, to_index, TaskDependency);
+        self.record_dependency(from, to);
+        self.graph.add_edge(from_index, to_index, dependency);
+        self.invalidate_dependency_stats();
+        self.validate_after_mutation();
+    }
+
+    /// Gets the properties of the dependency from one task on another, if it exists.
+    #[must_use]
+    pub fn get_dependency(&self, from: &TaskId, to: &TaskId) -> Option<&TaskDependency> {
+        let edge_index = self
+            .graph
+            .find_edge(self.get_node_index(from)?, self.get_node_index(to)?)?;
+        Some(&self.graph[edge_index])
+    }
+
+    /// Gets the properties of the dependency from one task on another mutably, if it exists.
+    pub fn get_dependency_mut(
+        &mut self,
+        from: &TaskId,
+        to: &TaskId,
+    ) -> Option<&mut TaskDependency> {
+        let edge_index = self
+            .graph
+            .find_edge(self.get_node_index(from)?, self.get_node_index(to)?)?;
+        self.record_dependency(from, to);
+        Some(&mut self.graph[edge_index])
+    }
+
+    /// Removes the dependency from one task on another. Returns the properties of the removed
+    /// dependency, or `None` if it did not exist.
+    pub fn remove_dependency(&mut self, from: &TaskId, to: &TaskId) -> Option<TaskDependency> {
+        let edge_index = self
+            .graph
+            .find_edge(self.get_node_index(from)?, self.get_node_index(to)?)?;
+        self.record_dependency(from, to);
+        let dependency = self.graph.remove_edge(edge_index);
+        self.invalidate_dependency_stats();
+        self.validate_after_mutation();
+        dependency
+    }
+
+    /// Gets the earliest moment the given task can be started, taking the lag of its dependencies
+    /// into account. Returns `None` if any of its dependencies are not completed yet, and the
+    /// creation time of the task if it has no dependencies.
+    #[must_use]
+    pub fn earliest_start(&self, task_id: &TaskId) -> Option<OffsetDateTime> {
+        let task_index = self
+            .get_node_index(task_id)
+            .expect("should be able to resolve task id");
+
+        let mut earliest = self.graph[task_index].time_created;
+        for edge in self.graph.edges_directed(task_index, Direction::Outgoing) {
+            let completed = self.graph[edge.target()].time_completed?;
+            earliest = earliest.max(completed + edge.weight().lag);
+        }
+        Some(earliest)
     }
 
     /// Gets all the tasks the given task depends on.
@@ -85,7 +437,56 @@ impl Database {
             .map(|source| &self.graph[source])
     }
 
-    fn get_node_index(&self, task_id: &TaskId) -> Option<NodeIndex> {
+    /// Gets all tasks ordered so that every task comes after the tasks it depends on. Tasks that
+    /// are not ordered by their dependencies are sorted by creation time. If the dependency graph
+    /// contains cycles, the tasks in the cycle are appended at the end.
+    #[must_use]
+    pub fn tasks_in_dependency_order(&self) -> Vec<&Task> {
+        let mut unresolved_dependencies = self
+            .graph
+            .node_indices()
+            .map(|index| {
+                let count = self
+                    .graph
+                    .edges_directed(index, Direction::Outgoing)
+                    .count();
+                (index, count)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut ready = unresolved_dependencies
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(index, _)| Reverse((self.graph[*index].time_created, *index)))
+            .collect::<BinaryHeap<_>>();
+
+        let mut ordered = Vec::with_capacity(self.graph.node_count());
+        while let Some(Reverse((_, index))) = ready.pop() {
+            unresolved_dependencies.remove(&index);
+            ordered.push(&self.graph[index]);
+
+            for edge in self.graph.edges_directed(index, Direction::Incoming) {
+                let dependent = edge.source();
+                let Some(count) = unresolved_dependencies.get_mut(&dependent) else {continue;};
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(Reverse((self.graph[dependent].time_created, dependent)));
+                }
+            }
+        }
+
+        // anything left is part of or depends on a cycle
+        let mut remaining = unresolved_dependencies
+            .into_keys()
+            .map(|index| &self.graph[index])
+            .collect::<Vec<_>>();
+        remaining.sort_by_key(|t| t.time_created);
+        ordered.extend(remaining);
+
+        ordered
+    }
+
+    pub(crate) fn get_node_index(&self, task_id: &TaskId) -> Option<NodeIndex> {
         self.task_id_to_index.get(task_id).copied().or_else(|| {
             // this fallback check exists in case we add a new node and it isn't in the cache.
             // this check should be removed when insertion of new tasks is managed here.
@@ -109,10 +510,22 @@ impl Task {
         Self {
             id: TaskId::new(),
             title,
+            description: String::new(),
             time_created,
             time_started: None,
             time_completed: None,
+            time_archived: None,
+            time_due: None,
+            snoozed_until: None,
+            estimate: None,
+            work_sessions: vec![],
+            reminders: vec![],
             tags: vec![],
+            project: None,
+            assignee: None,
+            sync_state: BTreeMap::new(),
+            encrypted: false,
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -121,4 +534,39 @@ impl Task {
     pub fn id(&self) -> &TaskId {
         &self.id
     }
+
+    /// Checks if this task is in the someday/maybe bucket, by having the [`SOMEDAY_TAG`] tag.
+    #[must_use]
+    pub fn is_someday(&self) -> bool {
+        self.tags
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(SOMEDAY_TAG))
+    }
+
+    /// Checks if this task is snoozed, meaning it is hidden until a time after `now`.
+    #[must_use]
+    pub fn is_snoozed(&self, now: OffsetDateTime) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Moves this task into or out of the someday/maybe bucket.
+    pub fn set_someday(&mut self, someday: bool) {
+        if someday && !self.is_someday() {
+            self.tags.push(SOMEDAY_TAG.into());
+        } else if !someday {
+            self.tags.retain(|t| !t.eq_ignore_ascii_case(SOMEDAY_TAG));
+        }
+    }
+
+    /// Gets the current status of this task.
+    #[must_use]
+    pub fn status(&self) -> TaskStatus {
+        if self.time_completed.is_some() {
+            TaskStatus::Completed
+        } else if self.time_started.is_some() {
+            TaskStatus::Started
+        } else {
+            TaskStatus::Todo
+        }
+    }
 }
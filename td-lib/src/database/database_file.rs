@@ -1,11 +1,17 @@
 //! Contains a version-agnostic wrapper structure around the version-specific database structure.
 
-use std::path::Path;
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions},
+    io::{BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
 
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{Deserialize, Serialize};
 
 use super::{Database, DatabaseImpl};
-use crate::errors::DatabaseReadError;
+use crate::errors::{DatabaseLockError, DatabaseReadError, DatabaseWriteError};
 
 /// A version-agnostic container for a database structure.
 #[derive(Serialize, Deserialize)]
@@ -15,19 +21,241 @@ pub struct DatabaseFile {
     data: serde_json::Value,
 }
 
+/// A [`DatabaseFile`] that borrows the database instead of holding a json copy of it, so it can be
+/// written without building the whole file in memory first.
+#[derive(Serialize)]
+struct DatabaseFileRef<'a> {
+    version: u8,
+    data: &'a Database,
+}
+
+/// The encoding of a database file on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageFormat {
+    /// Pretty-printed json, which is easy to read and diff.
+    #[default]
+    Json,
+    /// Gzip-compressed json.
+    GzipJson,
+    /// [MessagePack](https://msgpack.org), a compact binary format.
+    MessagePack,
+}
+
+impl StorageFormat {
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    /// Detects the format of the contents of a database file.
+    #[must_use]
+    pub fn detect(contents: &[u8]) -> Self {
+        match contents.iter().find(|b| !b.is_ascii_whitespace()) {
+            _ if contents.starts_with(&Self::GZIP_MAGIC) => Self::GzipJson,
+            Some(b'{') | None => Self::Json,
+            Some(_) => Self::MessagePack,
+        }
+    }
+
+    /// Gets the format that belongs to the extension of the given path, defaulting to json.
+    #[must_use]
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::GzipJson,
+            Some("msgpack" | "mpk") => Self::MessagePack,
+            _ => Self::Json,
+        }
+    }
+}
+
 impl DatabaseFile {
-    /// Read the database file from disk in json format.
+    /// Read the database file from disk. The storage format is detected automatically.
     pub fn read(path: &Path) -> Result<Self, DatabaseReadError> {
-        let file = std::fs::read(path)?;
+        let file = std::fs::read(path).map_err(|source| DatabaseReadError::IoError {
+            path: path.to_owned(),
+            source,
+        })?;
 
-        Ok(serde_json::from_slice(&file)?)
+        Self::decode(&file, Some(path))
     }
 
-    /// Write the database file to disk in json format.
-    pub fn write(&self, path: &Path) -> Result<(), DatabaseReadError> {
-        let json = serde_json::to_vec_pretty(self)?;
-        std::fs::write(path, json)?;
-        Ok(())
+    /// Parses a database file that was not read from disk directly, such as an older version of
+    /// the file. The storage format is detected automatically.
+    pub fn parse(contents: &[u8]) -> Result<Self, DatabaseReadError> {
+        Self::decode(contents, None)
+    }
+
+    fn decode(contents: &[u8], path: Option<&Path>) -> Result<Self, DatabaseReadError> {
+        match StorageFormat::detect(contents) {
+            StorageFormat::Json => {
+                serde_json::from_slice(contents).map_err(|source| DatabaseReadError::JsonError {
+                    path: path.map(Path::to_owned),
+                    source,
+                })
+            }
+            StorageFormat::GzipJson => {
+                let mut json = vec![];
+                GzDecoder::new(contents)
+                    .read_to_end(&mut json)
+                    .map_err(|source| DatabaseReadError::IoError {
+                        path: path.map(Path::to_owned).unwrap_or_default(),
+                        source,
+                    })?;
+                Self::decode(&json, path)
+            }
+            StorageFormat::MessagePack => rmp_serde::from_slice(contents).map_err(|source| {
+                DatabaseReadError::MessagePackError {
+                    path: path.map(Path::to_owned),
+                    source,
+                }
+            }),
+        }
+    }
+
+    /// Gets the path of the side file that archived tasks are moved to when they are purged from
+    /// the database at the given path.
+    #[must_use]
+    pub fn archive_path(path: &Path) -> PathBuf {
+        path.with_extension("archive.json")
+    }
+
+    /// Gets the path of the lock file used to mark the database at the given path as opened.
+    #[must_use]
+    pub fn lock_path(path: &Path) -> PathBuf {
+        path.with_extension("lock")
+    }
+
+    /// Acquires an advisory lock on the database at the given path, to signal other instances that
+    /// it is opened. The lock is released when the returned guard is dropped.
+    ///
+    /// Fails with [`DatabaseLockError::AlreadyLocked`] if another process holds the lock.
+    pub fn lock(path: &Path) -> Result<DatabaseLock, DatabaseLockError> {
+        let lock_path = Self::lock_path(path);
+        let io_error = |source| DatabaseLockError::IoError {
+            path: lock_path.clone(),
+            source,
+        };
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(mut file) => {
+                write!(file, "{}", std::process::id()).map_err(io_error)?;
+                Ok(DatabaseLock { path: lock_path })
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let owner_pid = std::fs::read_to_string(&lock_path)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok());
+                Err(DatabaseLockError::AlreadyLocked {
+                    path: lock_path,
+                    owner_pid,
+                })
+            }
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Acquires the lock on the database at the given path, taking it over from another process
+    /// if needed. This should only be done after confirming with the user, or if the other process
+    /// is known to be gone.
+    pub fn lock_forced(path: &Path) -> Result<DatabaseLock, DatabaseLockError> {
+        let lock_path = Self::lock_path(path);
+        std::fs::write(&lock_path, std::process::id().to_string()).map_err(|source| {
+            DatabaseLockError::IoError {
+                path: lock_path.clone(),
+                source,
+            }
+        })?;
+        Ok(DatabaseLock { path: lock_path })
+    }
+
+    /// Write the database file to disk, in the format that belongs to the extension of the path.
+    /// See [`StorageFormat::for_path`].
+    pub fn write(&self, path: &Path) -> Result<(), DatabaseWriteError> {
+        self.write_as(path, StorageFormat::for_path(path))
+    }
+
+    /// Write the database file to disk in the given format.
+    pub fn write_as(&self, path: &Path, format: StorageFormat) -> Result<(), DatabaseWriteError> {
+        write_file(self, path, format)
+    }
+
+    /// Writes the given database to disk, in the format that belongs to the extension of the
+    /// path. Unlike going through [`DatabaseFile::from`], the tasks are serialized straight into
+    /// the file one at a time, which is much faster and uses far less memory for large databases.
+    pub fn write_database(database: &Database, path: &Path) -> Result<(), DatabaseWriteError> {
+        let file = DatabaseFileRef {
+            version: Database::VERSION,
+            data: database,
+        };
+        write_file(&file, path, StorageFormat::for_path(path))
+    }
+}
+
+/// Serializes a database file into a temporary file next to `path`, which then replaces the file
+/// at `path`. This way, the old file is kept intact if serializing fails halfway through.
+fn write_file(
+    file: &impl Serialize,
+    path: &Path,
+    format: StorageFormat,
+) -> Result<(), DatabaseWriteError> {
+    let json_error = |source| DatabaseWriteError::JsonError {
+        path: Some(path.to_owned()),
+        source,
+    };
+    let io_error = |source| DatabaseWriteError::IoError {
+        path: path.to_owned(),
+        source,
+    };
+
+    let mut temp_path = OsString::from(path);
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let write = || {
+        let mut writer = BufWriter::new(File::create(&temp_path).map_err(io_error)?);
+        match format {
+            StorageFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, file).map_err(json_error)?;
+            }
+            StorageFormat::GzipJson => {
+                let mut encoder = GzEncoder::new(&mut writer, Compression::default());
+                serde_json::to_writer(&mut encoder, file).map_err(json_error)?;
+                encoder.finish().map_err(io_error)?;
+            }
+            StorageFormat::MessagePack => {
+                rmp_serde::encode::write_named(&mut writer, file).map_err(|source| {
+                    DatabaseWriteError::MessagePackError {
+                        path: path.to_owned(),
+                        source,
+                    }
+                })?;
+            }
+        }
+        writer.flush().map_err(io_error)?;
+        std::fs::rename(&temp_path, path).map_err(io_error)
+    };
+
+    let result = write();
+    if result.is_err() {
+        _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// A guard for the lock acquired with [`DatabaseFile::lock`]. Dropping it removes the lock file.
+#[derive(Debug)]
+pub struct DatabaseLock {
+    path: PathBuf,
+}
+
+impl Drop for DatabaseLock {
+    fn drop(&mut self) {
+        // only remove the lock if it still belongs to us, it may have been taken over
+        let owner = std::fs::read_to_string(&self.path).ok();
+        if owner.as_deref().map(str::trim) == Some(&std::process::id().to_string()) {
+            _ = std::fs::remove_file(&self.path);
+        }
     }
 }
 
@@ -47,9 +275,13 @@ impl TryInto<Database> for DatabaseFile {
     // NOTE: migrations would happen here
     fn try_into(self) -> Result<Database, Self::Error> {
         if self.version != 1 {
-            return Err(DatabaseReadError::UnknownVersion(self.version));
+            return Err(DatabaseReadError::UnknownVersion {
+                version: self.version,
+                supported: Database::VERSION,
+            });
         }
-        Ok(serde_json::from_value(self.data)?)
+        serde_json::from_value(self.data)
+            .map_err(|source| DatabaseReadError::JsonError { path: None, source })
     }
 }
 
@@ -0,0 +1,162 @@
+//! Counting the dependencies of many tasks at once.
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use super::{Database, TaskId};
+
+/// How many tasks a task is connected to through dependencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DependencyCounts {
+    /// The amount of tasks that depend on the task.
+    pub dependents: usize,
+    /// The amount of tasks the task depends on that are not completed yet.
+    pub unfulfilled_dependencies: usize,
+}
+
+/// The dependency counts of every task in a database, see [`Database::dependency_stats`].
+#[derive(Debug, Clone, Default)]
+pub struct DependencyStats {
+    /// The counts of all tasks that have dependencies or dependents.
+    counts: HashMap<TaskId, DependencyCounts>,
+}
+
+impl DependencyStats {
+    /// Gets the counts of a task. Tasks that don't exist have no dependencies or dependents.
+    #[must_use]
+    pub fn get(&self, task_id: &TaskId) -> DependencyCounts {
+        self.counts.get(task_id).copied().unwrap_or_default()
+    }
+
+    /// Whether the task depends on tasks that are not completed yet.
+    #[must_use]
+    pub fn has_unfulfilled_dependencies(&self, task_id: &TaskId) -> bool {
+        self.get(task_id).unfulfilled_dependencies > 0
+    }
+
+    /// Gets the tasks that depend on tasks that are not completed yet, in no particular order.
+    pub fn blocked_tasks(&self) -> impl Iterator<Item = &TaskId> {
+        self.counts
+            .iter()
+            .filter(|(_, counts)| counts.unfulfilled_dependencies > 0)
+            .map(|(id, _)| id)
+    }
+}
+
+impl Database {
+    /// Gets the dependency counts of all tasks. They are counted on first use and kept until the
+    /// database is changed, so looking them up every frame doesn't go over all dependencies again.
+    #[must_use]
+    pub fn dependency_stats(&self) -> &DependencyStats {
+        self.dependency_stats.get_or_init(|| {
+            let mut counts = HashMap::<TaskId, DependencyCounts>::new();
+            for edge in (&self.graph).edge_references() {
+                let dependency = &self.graph[edge.target()];
+                counts
+                    .entry(dependency.id().clone())
+                    .or_default()
+                    .dependents += 1;
+                if dependency.time_completed.is_none() {
+                    let dependent = self.graph[edge.source()].id().clone();
+                    counts
+                        .entry(dependent)
+                        .or_default()
+                        .unfulfilled_dependencies += 1;
+                }
+            }
+            DependencyStats { counts }
+        })
+    }
+
+    /// Counts the dependents and unfulfilled dependencies of the given tasks, using
+    /// [`Self::dependency_stats`]. Task ids that don't exist are left out.
+    #[must_use]
+    pub fn dependency_counts<'a>(
+        &self,
+        task_ids: impl IntoIterator<Item = &'a TaskId>,
+    ) -> HashMap<TaskId, DependencyCounts> {
+        let stats = self.dependency_stats();
+        task_ids
+            .into_iter()
+            .filter(|id| self.get_node_index(id).is_some())
+            .map(|id| (id.clone(), stats.get(id)))
+            .collect()
+    }
+
+    /// Forgets the cached [`Self::dependency_stats`], after the tasks or dependencies changed.
+    pub(crate) fn invalidate_dependency_stats(&mut self) {
+        self.dependency_stats = OnceLock::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn counts_match_per_task_queries() {
+        let mut db = Database::default();
+        let tasks = (0..4)
+            .map(|i| Task::create_now(format!("task {i}")))
+            .collect::<Vec<_>>();
+        let ids = tasks.iter().map(|t| t.id().clone()).collect::<Vec<_>>();
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&ids[0], &ids[1]);
+        db.add_dependency(&ids[0], &ids[2]);
+        db.add_dependency(&ids[1], &ids[2]);
+        db[&ids[2]].time_completed = Some(OffsetDateTime::now_utc());
+
+        let counts = db.dependency_counts(&ids[..3]);
+        assert_eq!(counts.len(), 3);
+        for id in &ids[..3] {
+            let expected = DependencyCounts {
+                dependents: db.get_inverse_dependencies(id).count(),
+                unfulfilled_dependencies: db
+                    .get_dependencies(id)
+                    .filter(|t| t.time_completed.is_none())
+                    .count(),
+            };
+            assert_eq!(counts[id], expected);
+        }
+        assert_eq!(counts[&ids[0]].unfulfilled_dependencies, 1);
+        assert_eq!(counts[&ids[2]].dependents, 2);
+    }
+
+    #[test]
+    fn stats_are_counted_again_after_changes() {
+        let mut db = Database::default();
+        let a = Task::create_now("a".into());
+        let b = Task::create_now("b".into());
+        let (a_id, b_id) = (a.id().clone(), b.id().clone());
+        db.add_task(a);
+        db.add_task(b);
+        assert_eq!(
+            db.dependency_stats().get(&a_id),
+            DependencyCounts::default()
+        );
+
+        db.add_dependency(&a_id, &b_id);
+        assert!(db.dependency_stats().has_unfulfilled_dependencies(&a_id));
+        assert_eq!(db.dependency_stats().get(&b_id).dependents, 1);
+        assert_eq!(
+            db.dependency_stats().blocked_tasks().collect::<Vec<_>>(),
+            [&a_id]
+        );
+
+        db[&b_id].time_completed = Some(OffsetDateTime::now_utc());
+        assert!(!db.dependency_stats().has_unfulfilled_dependencies(&a_id));
+        assert_eq!(db.dependency_stats().get(&b_id).dependents, 1);
+
+        db.remove_dependency(&a_id, &b_id);
+        assert_eq!(
+            db.dependency_stats().get(&b_id),
+            DependencyCounts::default()
+        );
+    }
+}
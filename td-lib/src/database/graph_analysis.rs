@@ -0,0 +1,203 @@
+//! Analysis of chains of dependencies between tasks.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+
+use super::{Database, Task, TaskId};
+
+impl Database {
+    /// Gets all tasks the given task directly or indirectly depends on, each only once.
+    pub fn transitive_dependencies(&self, task_id: &TaskId) -> impl Iterator<Item = &Task> + '_ {
+        self.reachable_tasks(task_id, Direction::Outgoing)
+    }
+
+    /// Gets all tasks that directly or indirectly depend on the given task, each only once.
+    pub fn transitive_dependents(&self, task_id: &TaskId) -> impl Iterator<Item = &Task> + '_ {
+        self.reachable_tasks(task_id, Direction::Incoming)
+    }
+
+    /// Gets the longest chain of uncompleted tasks that have to be completed before the given
+    /// task can be. The chain is in the order the tasks have to be done in, so the first task can
+    /// be worked on right away and the last one is a direct dependency of the given task.
+    #[must_use]
+    pub fn critical_path(&self, task_id: &TaskId) -> Vec<&Task> {
+        let Some(start) = self.get_node_index(task_id) else {return vec![];};
+
+        let mut longest = HashMap::new();
+        let mut path = self.longest_open_chain(start, &mut longest, &mut HashSet::new());
+        // the chain starts with the given task itself
+        path.remove(0);
+        path.reverse();
+        path.into_iter().map(|index| &self.graph[index]).collect()
+    }
+
+    /// Gets the uncompleted tasks that have to be completed before the given task can be, in the
+    /// order they can be done in. Dependencies of completed tasks are not included, since they no
+    /// longer block anything.
+    #[must_use]
+    pub fn open_dependencies(&self, task_id: &TaskId) -> Vec<&Task> {
+        let mut open = HashSet::new();
+        let mut stack = vec![task_id];
+        while let Some(id) = stack.pop() {
+            for dependency in self.get_dependencies(id) {
+                let is_open = dependency.time_completed.is_none() && dependency.id != *task_id;
+                if is_open && open.insert(&dependency.id) {
+                    stack.push(&dependency.id);
+                }
+            }
+        }
+
+        self.tasks_in_dependency_order()
+            .into_iter()
+            .filter(|task| open.contains(&task.id))
+            .collect()
+    }
+
+    /// Walks the dependency graph from the given task in the given direction.
+    fn reachable_tasks(
+        &self,
+        task_id: &TaskId,
+        direction: Direction,
+    ) -> impl Iterator<Item = &Task> + '_ {
+        let start = self.get_node_index(task_id);
+        let mut seen = start.into_iter().collect::<HashSet<_>>();
+        let mut stack = start.into_iter().collect::<Vec<_>>();
+        let mut pending = Vec::new();
+
+        std::iter::from_fn(move || loop {
+            if let Some(index) = pending.pop() {
+                return Some(&self.graph[index]);
+            }
+
+            let index = stack.pop()?;
+            for edge in self.graph.edges_directed(index, direction) {
+                let next = match direction {
+                    Direction::Outgoing => edge.target(),
+                    Direction::Incoming => edge.source(),
+                };
+                if seen.insert(next) {
+                    stack.push(next);
+                    pending.push(next);
+                }
+            }
+        })
+    }
+
+    /// Finds the longest chain of uncompleted dependencies starting at the given task, including
+    /// the task itself. Chains that loop back on themselves are cut off.
+    fn longest_open_chain(
+        &self,
+        index: NodeIndex,
+        longest: &mut HashMap<NodeIndex, Vec<NodeIndex>>,
+        visiting: &mut HashSet<NodeIndex>,
+    ) -> Vec<NodeIndex> {
+        if let Some(chain) = longest.get(&index) {
+            return chain.clone();
+        }
+
+        visiting.insert(index);
+        let dependencies = self
+            .graph
+            .edges_directed(index, Direction::Outgoing)
+            .map(|edge| edge.target())
+            .filter(|dep| self.graph[*dep].time_completed.is_none() && !visiting.contains(dep))
+            .collect::<Vec<_>>();
+        let mut chain = dependencies
+            .into_iter()
+            .map(|dep| self.longest_open_chain(dep, longest, visiting))
+            .max_by_key(Vec::len)
+            .unwrap_or_default();
+        visiting.remove(&index);
+
+        chain.insert(0, index);
+        longest.insert(index, chain.clone());
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Vec<&'a str> {
+        let mut titles = tasks
+            .into_iter()
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>();
+        titles.sort_unstable();
+        titles
+    }
+
+    #[test]
+    fn finds_transitive_tasks_and_critical_path() {
+        let mut db = Database::default();
+        let tasks = ["release", "docs", "build", "design", "done"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [release, docs, build, design, done] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db[&done].time_completed = Some(db[&done].time_created);
+
+        // release -> docs, release -> build -> design -> done
+        db.add_dependency(&release, &docs);
+        db.add_dependency(&release, &build);
+        db.add_dependency(&build, &design);
+        db.add_dependency(&design, &done);
+
+        assert_eq!(
+            titles(db.transitive_dependencies(&release)),
+            ["build", "design", "docs", "done"]
+        );
+        assert_eq!(
+            titles(db.transitive_dependents(&design)),
+            ["build", "release"]
+        );
+        assert!(db.transitive_dependents(&release).next().is_none());
+
+        let path = db.critical_path(&release);
+        let path = path.iter().map(|t| t.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(path, ["design", "build"]);
+        assert!(db.critical_path(&design).is_empty());
+    }
+
+    #[test]
+    fn finds_open_dependencies_in_order() {
+        let mut db = Database::default();
+        let tasks = ["release", "docs", "build", "design", "done", "old"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [release, docs, build, design, done, old] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db[&done].time_completed = Some(db[&done].time_created);
+
+        // release -> docs, release -> build -> design, build -> done -> old
+        db.add_dependency(&release, &docs);
+        db.add_dependency(&release, &build);
+        db.add_dependency(&build, &design);
+        db.add_dependency(&build, &done);
+        db.add_dependency(&done, &old);
+
+        let open = db.open_dependencies(&release);
+        let open = open.iter().map(|t| t.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(open, ["docs", "design", "build"]);
+        assert!(db.open_dependencies(&design).is_empty());
+    }
+
+    #[test]
+    fn cycles_end_the_walk() {
+        let mut db = Database::default();
+        let tasks = ["a", "b"].map(|title| Task::create_now(title.to_string()));
+        let [a, b] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&a, &b);
+        db.add_dependency(&b, &a);
+
+        assert_eq!(titles(db.transitive_dependencies(&a)), ["b"]);
+        assert_eq!(titles(db.critical_path(&a)), ["b"]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Secondary indexes over the tasks in a database, so looking up tasks by tag or by a part of
+//! their title does not have to look at every task.
+//!
+//! Tasks that are changed through [`IndexMut`](std::ops::IndexMut) may no longer match what they
+//! were indexed with. They are marked as stale and always considered as candidates until they are
+//! indexed again, which happens on the next mutation that goes through the database itself.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::stable_graph::NodeIndex;
+
+use super::{Database, Task, TaskId};
+
+/// The lowercase tags and title trigrams of all tasks in a database.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskIndex {
+    tags: HashMap<String, HashSet<TaskId>>,
+    trigrams: HashMap<[char; 3], HashSet<TaskId>>,
+    /// The keys every task is indexed under, so it can be removed from the index again.
+    entries: HashMap<TaskId, (Vec<String>, Vec<[char; 3]>)>,
+    /// Tasks that may have changed since they were indexed.
+    stale: HashSet<TaskId>,
+}
+
+impl TaskIndex {
+    pub(crate) fn insert(&mut self, task: &Task) {
+        self.remove(&task.id);
+
+        let tags = task
+            .tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect::<Vec<_>>();
+        let trigrams = trigrams(&task.title.to_lowercase());
+        for tag in &tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(task.id.clone());
+        }
+        for trigram in &trigrams {
+            self.trigrams
+                .entry(*trigram)
+                .or_default()
+                .insert(task.id.clone());
+        }
+        self.entries.insert(task.id.clone(), (tags, trigrams));
+    }
+
+    pub(crate) fn remove(&mut self, task_id: &TaskId) {
+        self.stale.remove(task_id);
+        let Some((tags, trigrams)) = self.entries.remove(task_id) else {return;};
+
+        for tag in tags {
+            remove_from(&mut self.tags, &tag, task_id);
+        }
+        for trigram in trigrams {
+            remove_from(&mut self.trigrams, &trigram, task_id);
+        }
+    }
+
+    pub(crate) fn mark_stale(&mut self, task_id: &TaskId) {
+        self.stale.insert(task_id.clone());
+    }
+}
+
+impl Database {
+    /// Gets all tasks that have the given tag, ignoring case, in the order of
+    /// [`Self::get_all_tasks`].
+    #[must_use]
+    pub fn tasks_with_tag(&self, tag: &str) -> Vec<&Task> {
+        let tag = tag.to_lowercase();
+        let candidates = self.index.tags.get(&tag).into_iter().flatten();
+        self.resolve_candidates(candidates, |task| {
+            task.tags.iter().any(|t| t.to_lowercase() == tag)
+        })
+    }
+
+    /// Gets all tasks whose title contains the given text, ignoring case, in the order of
+    /// [`Self::get_all_tasks`].
+    #[must_use]
+    pub fn tasks_with_title_containing(&self, text: &str) -> Vec<&Task> {
+        let text = text.to_lowercase();
+        let matches = |task: &Task| task.title.to_lowercase().contains(&text);
+
+        // every trigram of the text must be in the title, so the task with the rarest one are the
+        // only candidates
+        let trigrams = trigrams(&text);
+        if trigrams.is_empty() {
+            return self.get_all_tasks().filter(|t| matches(t)).collect();
+        }
+        let candidates = trigrams
+            .iter()
+            .map(|trigram| self.index.trigrams.get(trigram))
+            .min_by_key(|ids| ids.map_or(0, HashSet::len))
+            .flatten();
+        self.resolve_candidates(candidates.into_iter().flatten(), matches)
+    }
+
+    /// Indexes the tasks that were changed since they were last indexed.
+    pub(crate) fn refresh_index(&mut self) {
+        for task_id in std::mem::take(&mut self.index.stale) {
+            if let Some(node_index) = self.get_node_index(&task_id) {
+                self.index.insert(&self.graph[node_index]);
+            }
+        }
+    }
+
+    /// Indexes all tasks again, for databases that were built without going through
+    /// [`Self::add_task`].
+    pub(crate) fn rebuild_index(&mut self) {
+        self.index = TaskIndex::default();
+        for task in self.graph.node_weights() {
+            self.index.insert(task);
+        }
+    }
+
+    /// Turns candidates from the index into the tasks that actually match, including stale
+    /// tasks, in the order of [`Self::get_all_tasks`].
+    fn resolve_candidates<'a>(
+        &'a self,
+        candidates: impl Iterator<Item = &'a TaskId>,
+        matches: impl Fn(&Task) -> bool,
+    ) -> Vec<&'a Task> {
+        let mut indices = candidates
+            .chain(&self.index.stale)
+            .filter_map(|task_id| self.get_node_index(task_id))
+            .collect::<Vec<NodeIndex>>();
+        indices.sort();
+        indices.dedup();
+
+        indices
+            .into_iter()
+            .map(|index| &self.graph[index])
+            .filter(|task| matches(task))
+            .collect()
+    }
+}
+
+fn trigrams(text: &str) -> Vec<[char; 3]> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut trigrams = chars
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+        .collect::<Vec<_>>();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+fn remove_from<K: std::hash::Hash + Eq>(
+    map: &mut HashMap<K, HashSet<TaskId>>,
+    key: &K,
+    task_id: &TaskId,
+) {
+    if let Some(ids) = map.get_mut(key) {
+        ids.remove(task_id);
+        if ids.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_tasks_by_tag_and_title() {
+        let mut db = Database::default();
+        let mut groceries = Task::create_now("Buy Groceries".into());
+        groceries.tags = vec!["Errands".into()];
+        let mut garden = Task::create_now("Water the garden".into());
+        garden.tags = vec!["home".into()];
+        let (groceries_id, garden_id) = (groceries.id.clone(), garden.id.clone());
+        db.add_task(groceries);
+        db.add_task(garden);
+
+        let ids = |tasks: Vec<&Task>| tasks.into_iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(db.tasks_with_tag("errands")),
+            vec![groceries_id.clone()]
+        );
+        assert_eq!(
+            ids(db.tasks_with_title_containing("GROCER")),
+            vec![groceries_id.clone()]
+        );
+        assert_eq!(
+            ids(db.tasks_with_title_containing("r")),
+            vec![groceries_id.clone(), garden_id.clone()]
+        );
+
+        // changes made through IndexMut are found before the task is indexed again
+        db[&garden_id].tags.push("errands".into());
+        db[&groceries_id].title = "Buy flowers".into();
+        assert_eq!(
+            ids(db.tasks_with_tag("errands")),
+            vec![groceries_id.clone(), garden_id.clone()]
+        );
+        assert_eq!(ids(db.tasks_with_title_containing("grocer")), vec![]);
+
+        db.remove_task(&groceries_id);
+        assert_eq!(ids(db.tasks_with_tag("errands")), vec![garden_id.clone()]);
+        assert_eq!(ids(db.tasks_with_title_containing("flowers")), vec![]);
+
+        let loaded: Database = serde_json::from_value(serde_json::to_value(&db).unwrap()).unwrap();
+        assert_eq!(ids(loaded.tasks_with_tag("home")), vec![garden_id]);
+    }
+}
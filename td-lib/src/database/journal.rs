@@ -0,0 +1,224 @@
+//! Records the changes made to a database as [`DatabaseOp`]s while they are made, so a change
+//! can be stored without comparing the whole database before and after it.
+
+use std::collections::BTreeMap;
+
+use super::{Database, DatabaseOp, Task, TaskDependency, TaskId};
+
+/// The values that were changed since [`Database::record_changes`] started recording, as they
+/// were before they were first changed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChangeJournal {
+    tasks: BTreeMap<TaskId, Option<Task>>,
+    dependencies: BTreeMap<(TaskId, TaskId), Option<TaskDependency>>,
+    /// A copy of the database with only its settings, such as the archive policy and tag colors.
+    settings: Option<Box<Database>>,
+    /// A copy of the whole database, for changes that can not be recorded one by one. Once this
+    /// is set, nothing else is recorded.
+    snapshot: Option<Box<Database>>,
+}
+
+impl Database {
+    /// Changes this database with `func` and gets the operations that describe the change. Unlike
+    /// [`Self::diff`], this only looks at the tasks, dependencies and settings that are changed.
+    ///
+    /// Replacing the whole database inside `func` must be done with [`Self::replace`], assigning
+    /// another database to it would lose the recorded changes.
+    pub fn record_changes(&mut self, func: impl FnOnce(&mut Self)) -> Vec<DatabaseOp> {
+        debug_assert!(
+            self.changes.is_none(),
+            "changes should not be recorded twice"
+        );
+        self.changes = Some(Box::default());
+        func(self);
+
+        let Some(changes) = self.changes.take() else {
+            debug_assert!(false, "the database should not be replaced while recording");
+            return vec![];
+        };
+        self.recorded_ops(*changes)
+    }
+
+    /// Replaces this database with another one, such as a version that was loaded again.
+    pub fn replace(&mut self, other: Self) {
+        self.record_everything();
+        let changes = self.changes.take();
+        *self = Self { changes, ..other };
+    }
+
+    /// Notes that the given task is about to be added, changed or removed.
+    pub(crate) fn record_task(&mut self, id: &TaskId) {
+        let Some(changes) = self.changes.as_deref_mut() else {return;};
+        if changes.snapshot.is_some() || changes.tasks.contains_key(id) {
+            return;
+        }
+
+        let task = self
+            .task_id_to_index
+            .get(id)
+            .map(|&i| self.graph[i].clone());
+        changes.tasks.insert(id.clone(), task);
+    }
+
+    /// Notes that the dependency from one task on another is about to be added, changed or
+    /// removed.
+    pub(crate) fn record_dependency(&mut self, from: &TaskId, to: &TaskId) {
+        let Some(changes) = self.changes.as_deref_mut() else {return;};
+        let key = (from.clone(), to.clone());
+        if changes.snapshot.is_some() || changes.dependencies.contains_key(&key) {
+            return;
+        }
+
+        let dependency = self.get_dependency(from, to).cloned();
+        let changes = self.changes.as_deref_mut().expect("changes are recorded");
+        changes.dependencies.insert(key, dependency);
+    }
+
+    /// Notes that the settings of the database are about to be changed.
+    pub(crate) fn record_settings(&mut self) {
+        let Some(changes) = self.changes.as_deref_mut() else {return;};
+        if changes.snapshot.is_some() || changes.settings.is_some() {
+            return;
+        }
+
+        changes.settings = Some(Box::new(Self {
+            database_id: self.database_id.clone(),
+            archive_policy: self.archive_policy,
+            tag_registry: self.tag_registry.clone(),
+            id_namespace: self.id_namespace.clone(),
+            crdt: self.crdt.clone(),
+            encryption: self.encryption.clone(),
+            ..Self::default()
+        }));
+    }
+
+    /// Notes that the database is about to be changed in a way that can not be recorded one task
+    /// at a time, such as giving tasks a new id. The whole database is compared afterwards.
+    pub(crate) fn record_everything(&mut self) {
+        let Some(changes) = self.changes.take() else {return;};
+        if changes.snapshot.is_some() {
+            self.changes = Some(changes);
+            return;
+        }
+
+        // the snapshot has to be the database as it was before anything was recorded
+        let ops = self.recorded_ops(*changes);
+        let mut snapshot = self.clone();
+        snapshot.apply_ops(&ops, true);
+        self.changes = Some(Box::new(ChangeJournal {
+            snapshot: Some(Box::new(snapshot)),
+            ..ChangeJournal::default()
+        }));
+    }
+
+    fn recorded_ops(&self, changes: ChangeJournal) -> Vec<DatabaseOp> {
+        if let Some(snapshot) = changes.snapshot {
+            return self.diff(&snapshot);
+        }
+
+        let mut ops = vec![];
+        if let Some(settings) = changes.settings {
+            self.diff_settings(&settings, &mut ops);
+        }
+        for (id, old) in changes.tasks {
+            let new = self.get_task(&id);
+            if old.as_ref() != new {
+                ops.push(DatabaseOp::SetTask {
+                    id,
+                    old: old.map(Box::new),
+                    new: new.cloned().map(Box::new),
+                });
+            }
+        }
+        for ((from, to), old) in changes.dependencies {
+            let new = self.get_dependency(&from, &to);
+            if old.as_ref() != new {
+                let new = new.cloned();
+                ops.push(DatabaseOp::SetDependency { from, to, old, new });
+            }
+        }
+        ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+    use crate::database::{ArchivePolicy, TagColor};
+
+    fn create_database() -> (Database, Vec<TaskId>) {
+        let mut db = Database::default();
+        let tasks = ["task 1", "task 2", "task 3"].map(|title| Task::create_now(title.into()));
+        let ids = tasks.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&ids[0], &ids[1]);
+        db.add_dependency(&ids[1], &ids[2]);
+        (db, ids)
+    }
+
+    #[test]
+    fn records_the_same_ops_as_a_diff() {
+        let (old, ids) = create_database();
+        let mut db = old.clone();
+
+        let ops = db.record_changes(|db| {
+            db[&ids[0]].title = "renamed".into();
+            db[&ids[0]].title = "renamed again".into();
+            db.remove_task(&ids[2]);
+            let task = Task::create_now("task 4".into());
+            let id = task.id.clone();
+            db.add_task(task);
+            db.add_dependency(&id, &ids[0]);
+            db.get_dependency_mut(&ids[0], &ids[1]).unwrap().lag = Duration::days(1);
+            db.set_archive_policy(ArchivePolicy {
+                archive_completed_after: Some(Duration::days(7)),
+            });
+            db.set_tag_color("work", Some(TagColor::Blue));
+        });
+
+        let mut expected = db.diff(&old);
+        let sort_key = |op: &DatabaseOp| format!("{op:?}");
+        expected.sort_by_key(sort_key);
+        let mut recorded = ops;
+        recorded.sort_by_key(sort_key);
+        assert_eq!(recorded, expected);
+        assert!(db.changes.is_none());
+    }
+
+    #[test]
+    fn leaves_out_changes_that_were_reverted() {
+        let (mut db, ids) = create_database();
+
+        let ops = db.record_changes(|db| {
+            db[&ids[0]].title = "renamed".into();
+            db[&ids[0]].title = "task 1".into();
+            let task = Task::create_now("task 4".into());
+            let id = task.id.clone();
+            db.add_task(task);
+            db.remove_task(&id);
+        });
+        assert_eq!(ops, vec![]);
+    }
+
+    #[test]
+    fn records_replaced_and_repaired_databases_from_a_snapshot() {
+        let (old, ids) = create_database();
+        let mut db = old.clone();
+
+        let ops = db.record_changes(|db| {
+            db[&ids[0]].title = "renamed".into();
+            let mut other = Database::default();
+            other.add_task(Task::create_now("other".into()));
+            db.replace(other);
+            db.repair();
+        });
+        assert_eq!(ops, db.diff(&old));
+
+        db.apply_ops(&ops, true);
+        assert_eq!(db.diff(&old), vec![]);
+    }
+}
@@ -0,0 +1,208 @@
+//! Merging tasks from one database into another.
+
+use std::collections::HashMap;
+
+use super::{Database, Task, TaskId};
+
+/// The namespace used for colliding ids when the source database does not configure one.
+pub const DEFAULT_ID_NAMESPACE: &str = "merged";
+
+impl Database {
+    /// Gets the namespace used to rewrite colliding task ids when this database is merged into
+    /// another one.
+    #[must_use]
+    pub fn id_namespace(&self) -> Option<&str> {
+        self.id_namespace.as_deref()
+    }
+
+    /// Sets the namespace used to rewrite colliding task ids when this database is merged into
+    /// another one.
+    pub fn set_id_namespace(&mut self, namespace: Option<String>) {
+        self.record_settings();
+        self.id_namespace = namespace;
+    }
+
+    /// Copies all tasks and dependencies from `other` into this database.
+    ///
+    /// Tasks from `other` whose id already exists in this database get a new id, prefixed by the
+    /// namespace of `other` (or [`DEFAULT_ID_NAMESPACE`]). This is deterministic, so merging the
+    /// same databases always gives the same ids. Dependencies are rewritten to use the new ids.
+    ///
+    /// Returns a map from the original id to the new id for every task that was renamed.
+    pub fn merge(&mut self, other: &Self) -> HashMap<TaskId, TaskId> {
+        let namespace = other.id_namespace().unwrap_or(DEFAULT_ID_NAMESPACE);
+        let mut remapped = HashMap::new();
+
+        for task in other.get_all_tasks() {
+            let mut new_id = task.id.clone();
+            let mut attempt = 1;
+            while self.task_id_to_index.contains_key(&new_id)
+                || other.task_id_to_index.contains_key(&new_id) && new_id != task.id
+            {
+                new_id = match attempt {
+                    1 => task.id.with_namespace(namespace),
+                    n => task.id.with_namespace(&format!("{namespace}~{n}")),
+                };
+                attempt += 1;
+            }
+
+            if new_id != task.id {
+                remapped.insert(task.id.clone(), new_id.clone());
+            }
+
+            self.add_task(Task {
+                id: new_id,
+                ..task.clone()
+            });
+        }
+
+        for edge_index in other.graph.edge_indices() {
+            let (start_index, end_index) = other
+                .graph
+                .edge_endpoints(edge_index)
+                .expect("each edge should be connected");
+
+            let resolve = |id: &TaskId| remapped.get(id).unwrap_or(id).clone();
+            let from = resolve(&other.graph[start_index].id);
+            let to = resolve(&other.graph[end_index].id);
+            self.add_dependency_with(&from, &to, other.graph[edge_index].clone());
+        }
+
+        self.merge_tag_registry(other);
+        remapped
+    }
+
+    /// Copies the tasks from `other` whose id does not exist in this database yet, along with
+    /// the dependencies in `other` that are missing here. Tasks that exist in both databases are
+    /// left untouched, so changes in this database always win.
+    ///
+    /// This is useful to combine two copies of the same database that were edited separately.
+    /// Returns the amount of tasks that were added.
+    pub fn merge_new_tasks(&mut self, other: &Self) -> usize {
+        let mut added = 0;
+        for task in other.get_all_tasks() {
+            if self.get_node_index(&task.id).is_none() {
+                self.add_task(task.clone());
+                added += 1;
+            }
+        }
+
+        for edge_index in other.graph.edge_indices() {
+            let (start_index, end_index) = other
+                .graph
+                .edge_endpoints(edge_index)
+                .expect("each edge should be connected");
+            let from = &other.graph[start_index].id;
+            let to = &other.graph[end_index].id;
+
+            if self.get_dependency(from, to).is_none() {
+                self.add_dependency_with(from, to, other.graph[edge_index].clone());
+            }
+        }
+
+        self.merge_tag_registry(other);
+        added
+    }
+
+    /// Copies the settings of the tags that have no settings in this database yet.
+    fn merge_tag_registry(&mut self, other: &Self) {
+        self.record_settings();
+        for (tag, info) in &other.tag_registry {
+            self.tag_registry
+                .entry(tag.clone())
+                .or_insert_with(|| info.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::TagColor;
+
+    /// Creates a database with 2 tasks where the first depends on the second.
+    fn create_database() -> (Database, TaskId, TaskId) {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let (id_1, id_2) = (task_1.id.clone(), task_2.id.clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_dependency(&id_1, &id_2);
+        (db, id_1, id_2)
+    }
+
+    #[test]
+    fn merge_without_collisions_keeps_ids() {
+        let (mut db, _, _) = create_database();
+        let (other, other_id_1, other_id_2) = create_database();
+
+        let remapped = db.merge(&other);
+
+        assert!(remapped.is_empty());
+        assert_eq!(db.get_all_tasks().count(), 4);
+        assert_eq!(
+            db.get_dependencies(&other_id_1).next().unwrap().id(),
+            &other_id_2
+        );
+    }
+
+    #[test]
+    fn merge_rewrites_colliding_ids_and_edges() {
+        let (mut db, id_1, id_2) = create_database();
+        let mut other = db.clone();
+        other.set_id_namespace(Some("work".into()));
+
+        let remapped = db.merge(&other);
+
+        let new_id_1 = id_1.with_namespace("work");
+        let new_id_2 = id_2.with_namespace("work");
+        assert_eq!(remapped[&id_1], new_id_1);
+        assert_eq!(remapped[&id_2], new_id_2);
+        assert_eq!(db.get_all_tasks().count(), 4);
+        assert_eq!(
+            db.get_dependencies(&new_id_1).next().unwrap().id(),
+            &new_id_2
+        );
+        assert_eq!(db.get_dependencies(&id_1).count(), 1);
+    }
+
+    #[test]
+    fn merge_new_tasks_keeps_existing_tasks() {
+        let (mut db, id_1, id_2) = create_database();
+        let mut other = db.clone();
+        db[&id_1].title = "renamed here".into();
+        other[&id_1].title = "renamed there".into();
+        let task_3 = Task::create_now("task 3".into());
+        let id_3 = task_3.id.clone();
+        other.add_task(task_3);
+        other.add_dependency(&id_3, &id_2);
+        db.set_tag_color("work", Some(TagColor::Red));
+        other.set_tag_color("work", Some(TagColor::Blue));
+        other.set_tag_color("home", Some(TagColor::Green));
+
+        assert_eq!(db.merge_new_tasks(&other), 1);
+
+        assert_eq!(db.get_all_tasks().count(), 3);
+        assert_eq!(db[&id_1].title, "renamed here");
+        assert_eq!(db.get_dependencies(&id_1).count(), 1);
+        assert_eq!(db.get_dependencies(&id_3).next().unwrap().id(), &id_2);
+        assert_eq!(db.tag_color("work"), Some(TagColor::Red));
+        assert_eq!(db.tag_color("home"), Some(TagColor::Green));
+    }
+
+    #[test]
+    fn merge_is_deterministic_with_repeated_collisions() {
+        let (mut db, id_1, _) = create_database();
+        let other = db.clone();
+
+        db.merge(&other);
+        let remapped = db.merge(&other);
+
+        assert_eq!(
+            remapped[&id_1],
+            id_1.with_namespace(&format!("{DEFAULT_ID_NAMESPACE}~2"))
+        );
+        assert_eq!(db.get_all_tasks().count(), 6);
+    }
+}
@@ -2,11 +2,29 @@
 
 mod database_api;
 pub mod database_file;
+mod dependency_counts;
+mod graph_analysis;
+mod index;
+mod journal;
+mod merge;
+mod ops;
+mod suggestions;
+mod sync_state;
+mod tag;
 mod v1;
+mod validation;
+mod work_sessions;
 
 use serde::{de::DeserializeOwned, Serialize};
 // NOTE: this import should import the current version of the database schema
+pub use dependency_counts::{DependencyCounts, DependencyStats};
+pub use merge::DEFAULT_ID_NAMESPACE;
+pub use ops::{DatabaseOp, DiffSummary};
+pub use suggestions::Suggestion;
+pub use sync_state::SyncChange;
+pub use tag::{Tag, SOMEDAY_TAG};
 pub use v1::*;
+pub use validation::{ValidationIssue, ValidationOptions, ValidationReport};
 
 /// The current version of the database model.
 pub const CURRENT_DATABASE_VERSION: u8 = Database::VERSION;
@@ -18,10 +36,375 @@ trait DatabaseImpl: Default + Serialize + DeserializeOwned {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::{DatabaseLockError, DatabaseReadError};
 
     #[test]
     pub fn new_db_is_valid_json() {
         let db = v1::Database::default();
         serde_json::to_value(db).expect("new database should always be valid json");
     }
+
+    #[test]
+    pub fn read_errors_contain_context() {
+        let path = std::path::Path::new("does/not/exist.json");
+        let error = database_file::DatabaseFile::read(path).err().unwrap();
+        assert!(matches!(error, DatabaseReadError::IoError { .. }));
+        assert_eq!(error.path(), Some(path));
+
+        let mut file = database_file::DatabaseFile::default();
+        file.version = 200;
+        let error = TryInto::<Database>::try_into(file).err().unwrap();
+        assert!(matches!(
+            error,
+            DatabaseReadError::UnknownVersion {
+                version: 200,
+                supported: CURRENT_DATABASE_VERSION
+            }
+        ));
+    }
+
+    #[test]
+    pub fn all_storage_formats_roundtrip() {
+        use database_file::{DatabaseFile, StorageFormat};
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let mut db = Database::default();
+        let mut task = Task::create_now("task".into());
+        task.tags = vec!["tag".into()];
+        task.estimate = Some(time::Duration::hours(2));
+        task.metadata.insert("jira.key".into(), "TD-42".into());
+        let task_id = task.id.clone();
+        db.add_task(task);
+
+        for (name, format) in [
+            ("todo.json", StorageFormat::Json),
+            ("todo.json.gz", StorageFormat::GzipJson),
+            ("todo.msgpack", StorageFormat::MessagePack),
+        ] {
+            let path = dir.join(name);
+            DatabaseFile::from(&db).write(&path).unwrap();
+
+            let contents = std::fs::read(&path).unwrap();
+            assert_eq!(StorageFormat::detect(&contents), format);
+
+            let read: Database = DatabaseFile::read(&path).unwrap().try_into().unwrap();
+            assert_eq!(read[&task_id], db[&task_id]);
+
+            // writing the database directly gives the same file
+            DatabaseFile::write_database(&db, &path).unwrap();
+            let streamed = std::fs::read(&path).unwrap();
+            if format == StorageFormat::Json {
+                assert_eq!(streamed, contents);
+            }
+            let read: Database = DatabaseFile::read(&path).unwrap().try_into().unwrap();
+            assert_eq!(read[&task_id], db[&task_id]);
+        }
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 3);
+    }
+
+    #[test]
+    pub fn lock_is_exclusive_until_dropped() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+
+        let lock = database_file::DatabaseFile::lock(&path).unwrap();
+        let error = database_file::DatabaseFile::lock(&path).unwrap_err();
+        assert!(matches!(
+            error,
+            DatabaseLockError::AlreadyLocked { owner_pid: Some(pid), .. } if pid == std::process::id()
+        ));
+
+        drop(lock);
+        assert!(!database_file::DatabaseFile::lock_path(&path).exists());
+        let lock = database_file::DatabaseFile::lock(&path).unwrap();
+        drop(lock);
+    }
+
+    #[test]
+    pub fn take_archived_moves_tasks() {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let mut task_2 = Task::create_now("task 2".into());
+        let mut task_3 = Task::create_now("task 3".into());
+        task_2.time_archived = Some(task_2.time_created);
+        task_3.time_archived = Some(task_3.time_created);
+        let (id_1, id_2, id_3) = (task_1.id.clone(), task_2.id.clone(), task_3.id.clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(task_3);
+        db.add_dependency(&id_1, &id_2);
+        db.add_dependency(&id_2, &id_3);
+
+        let archive = db.take_archived();
+
+        assert_eq!(db.get_all_tasks().count(), 1);
+        assert_eq!(db.get_dependencies(&id_1).count(), 0);
+        assert_eq!(archive.get_all_tasks().count(), 2);
+        assert_eq!(archive.get_dependencies(&id_2).next().unwrap().id(), &id_3);
+    }
+
+    #[test]
+    pub fn dependency_lag_roundtrips_and_delays_start() {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let mut task_2 = Task::create_now("task 2".into());
+        let task_3 = Task::create_now("task 3".into());
+        let completed = task_2.time_created + time::Duration::days(1);
+        task_2.time_completed = Some(completed);
+        let (id_1, id_2, id_3) = (task_1.id.clone(), task_2.id.clone(), task_3.id.clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(task_3);
+        let lag = TaskDependency {
+            lag: time::Duration::days(2),
+            ..Default::default()
+        };
+        db.add_dependency_with(&id_1, &id_2, lag.clone());
+
+        assert_eq!(db.earliest_start(&id_1), Some(completed + lag.lag));
+
+        db.add_dependency(&id_1, &id_3);
+        assert_eq!(db.earliest_start(&id_1), None);
+
+        let json = serde_json::to_string(&db).unwrap();
+        let db: Database = serde_json::from_str(&json).unwrap();
+        assert_eq!(db.get_dependency(&id_1, &id_2), Some(&lag));
+        assert_eq!(
+            db.get_dependency(&id_1, &id_3),
+            Some(&TaskDependency::default())
+        );
+        assert_eq!(db.get_dependency(&id_2, &id_1), None);
+    }
+
+    #[test]
+    pub fn dependency_properties_roundtrip() {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let task_3 = Task::create_now("task 3".into());
+        let (id_1, id_2, id_3) = (task_1.id.clone(), task_2.id.clone(), task_3.id.clone());
+        let created = task_1.time_created;
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(task_3);
+        let dependency = TaskDependency {
+            label: Some("same bug".into()),
+            relation: DependencyRelation::Duplicates,
+            created: Some(created),
+            ..Default::default()
+        };
+        db.add_dependency_with(&id_1, &id_2, dependency.clone());
+        db.add_dependency(&id_1, &id_3);
+
+        let json = serde_json::to_value(&db).unwrap();
+        let task_json = json["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|task| task["title"] == "task 1")
+            .unwrap();
+        let dependencies = task_json["dependencies"].as_array().unwrap();
+        let detailed = dependencies.iter().find(|d| d.is_object()).unwrap();
+        assert_eq!(detailed["relation"], "duplicates");
+        assert_eq!(detailed.get("lag_seconds"), None);
+        assert!(dependencies.contains(&id_3.as_str().into()));
+
+        let db: Database = serde_json::from_value(json).unwrap();
+        assert_eq!(db.get_dependency(&id_1, &id_2), Some(&dependency));
+        assert_eq!(
+            db.get_dependency(&id_1, &id_3),
+            Some(&TaskDependency::default())
+        );
+    }
+
+    #[test]
+    pub fn saves_tasks_and_dependencies_sorted_by_id() {
+        let mut db = Database::default();
+        let tasks = ["c", "a", "b"].map(|id| Task {
+            id: TaskId::from_raw(id.to_string()),
+            ..Task::create_now(format!("task {id}"))
+        });
+        let [c, a, b] = tasks.each_ref().map(|t| t.id.clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&a, &c);
+        db.add_dependency(&a, &b);
+
+        let json = serde_json::to_value(&db).unwrap();
+        let ids = json["tasks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|task| task["id"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(
+            json["tasks"][0]["dependencies"],
+            serde_json::json!(["b", "c"])
+        );
+
+        let reloaded: Database = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reloaded).unwrap(), json);
+    }
+
+    #[test]
+    pub fn archive_policy_archives_old_completed_tasks() {
+        let mut db = Database::default();
+        let mut task_old = Task::create_now("old".into());
+        let mut task_recent = Task::create_now("recent".into());
+        let task_open = Task::create_now("open".into());
+        let now = task_old.time_created + time::Duration::days(40);
+        task_old.time_completed = Some(task_old.time_created);
+        task_recent.time_completed = Some(now - time::Duration::days(1));
+        let id_old = task_old.id.clone();
+        db.add_task(task_old);
+        db.add_task(task_recent);
+        db.add_task(task_open);
+
+        assert!(db.apply_archive_policy(now).is_empty());
+
+        let policy = ArchivePolicy {
+            archive_completed_after: Some(time::Duration::days(30)),
+        };
+        db.set_archive_policy(policy);
+        let json = serde_json::to_string(&db).unwrap();
+        let mut db: Database = serde_json::from_str(&json).unwrap();
+        assert_eq!(db.archive_policy(), policy);
+
+        assert_eq!(db.apply_archive_policy(now), vec![id_old.clone()]);
+        assert_eq!(db[&id_old].time_archived, Some(now));
+        assert!(db.tasks_to_auto_archive(now).is_empty());
+    }
+
+    #[test]
+    pub fn all_tags_counts_tasks() {
+        let mut db = Database::default();
+        let mut task_1 = Task::create_now("task 1".into());
+        task_1.tags = vec!["work".into(), "urgent".into()];
+        let mut task_2 = Task::create_now("task 2".into());
+        task_2.tags = vec!["work".into()];
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(Task::create_now("task 3".into()));
+
+        let tags = db.all_tags();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags["work"], 2);
+        assert_eq!(tags["urgent"], 1);
+
+        assert_eq!(db.remove_tag("work"), 2);
+        assert_eq!(db.all_tags().into_keys().collect::<Vec<_>>(), ["urgent"]);
+        assert!(db.tasks_with_tag("work").is_empty());
+        assert_eq!(db.remove_tag("work"), 0);
+    }
+
+    #[test]
+    pub fn tag_colors_roundtrip_and_are_removed_with_the_tag() {
+        let mut db = Database::default();
+        let mut task = Task::create_now("task".into());
+        task.tags = vec!["work".into()];
+        db.add_task(task);
+        db.set_tag_color("work", Some(TagColor::Green));
+        db.set_tag_color("home", Some(TagColor::Red));
+        db.set_tag_color("home", None);
+
+        let json = serde_json::to_value(&db).unwrap();
+        assert_eq!(
+            json["tag_registry"],
+            serde_json::json!({"work": {"color": "green"}})
+        );
+        let mut db: Database = serde_json::from_value(json).unwrap();
+        assert_eq!(db.tag_color("work"), Some(TagColor::Green));
+        assert_eq!(db.tag_info("home"), None);
+
+        db.remove_tag("work");
+        assert_eq!(db.tag_color("work"), None);
+        let json = serde_json::to_value(&db).unwrap();
+        assert!(json.get("tag_registry").is_none());
+    }
+
+    #[test]
+    pub fn all_projects_counts_tasks() {
+        let mut db = Database::default();
+        for project in [Some("work"), Some("work"), Some("home"), None] {
+            let mut task = Task::create_now("task".into());
+            task.project = project.map(String::from);
+            db.add_task(task);
+        }
+
+        let projects = db.all_projects();
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects["work"], 2);
+        assert_eq!(projects["home"], 1);
+    }
+
+    #[test]
+    pub fn all_assignees_counts_tasks() {
+        let mut db = Database::default();
+        for assignee in [Some("alex"), None, Some("sam"), Some("alex")] {
+            let mut task = Task::create_now("task".into());
+            task.assignee = assignee.map(String::from);
+            db.add_task(task);
+        }
+
+        let assignees = db.all_assignees();
+        assert_eq!(
+            assignees.into_iter().collect::<Vec<_>>(),
+            [("alex", 2), ("sam", 1)]
+        );
+    }
+
+    #[test]
+    pub fn duplicate_task_copies_details_and_dependencies() {
+        let mut db = Database::default();
+        let mut task = Task::create_now("task".into());
+        task.description = "details".into();
+        task.tags.push("work".into());
+        task.time_started = Some(task.time_created);
+        let dependency = Task::create_now("dependency".into());
+        let (task_id, dependency_id) = (task.id.clone(), dependency.id.clone());
+        db.add_task(task);
+        db.add_task(dependency);
+        let lag = TaskDependency {
+            lag: time::Duration::days(1),
+            ..Default::default()
+        };
+        db.add_dependency_with(&task_id, &dependency_id, lag.clone());
+
+        let now = time::OffsetDateTime::now_utc() + time::Duration::hours(1);
+        let copy_id = db.duplicate_task(&task_id, false, now).unwrap();
+        let copy = &db[&copy_id];
+        assert_ne!(copy_id, task_id);
+        assert_eq!(
+            (copy.title.as_str(), copy.description.as_str()),
+            ("task", "details")
+        );
+        assert_eq!(copy.tags, db[&task_id].tags);
+        assert_eq!((copy.time_created, copy.time_started), (now, None));
+        assert_eq!(db.get_dependencies(&copy_id).count(), 0);
+
+        let copy_id = db.duplicate_task(&task_id, true, now).unwrap();
+        assert_eq!(db.get_dependency(&copy_id, &dependency_id), Some(&lag));
+        assert_eq!(db.get_all_tasks().count(), 4);
+
+        assert_eq!(db.duplicate_task(&TaskId::new(), true, now), None);
+    }
+
+    #[test]
+    pub fn short_ids_are_unique_prefixes() {
+        let mut db = Database::default();
+        let ids = ["abcdefgh", "abcdefxy", "zzzzzzzz", "short"].map(|id| {
+            let mut task = Task::create_now(id.into());
+            task.id = TaskId::from_raw(id.into());
+            db.add_task(task);
+            TaskId::from_raw(id.into())
+        });
+
+        let short_ids = ids.each_ref().map(|id| db.short_id(id));
+        assert_eq!(short_ids, ["abcdefg", "abcdefx", "zzzzzz", "short"]);
+    }
 }
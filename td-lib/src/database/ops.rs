@@ -0,0 +1,426 @@
+//! Reversible changes to a database, so a change can be stored without storing the whole
+//! database before and after it.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ArchivePolicy, CrdtClocks, Database, EncryptionSettings, Tag, TagInfo, Task, TaskDependency,
+    TaskId,
+};
+
+/// A single reversible change to a database. Every operation stores both the old and the new
+/// value, so it can be applied in either direction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DatabaseOp {
+    /// A task was added (`old` is `None`), removed (`new` is `None`) or changed.
+    SetTask {
+        /// The task that changed.
+        id: TaskId,
+        /// The task before the change.
+        old: Option<Box<Task>>,
+        /// The task after the change.
+        new: Option<Box<Task>>,
+    },
+    /// A dependency was added (`old` is `None`), removed (`new` is `None`) or changed.
+    SetDependency {
+        /// The dependent task.
+        from: TaskId,
+        /// The task that is depended on.
+        to: TaskId,
+        /// The dependency before the change.
+        old: Option<TaskDependency>,
+        /// The dependency after the change.
+        new: Option<TaskDependency>,
+    },
+    /// The archive policy was changed.
+    SetArchivePolicy {
+        /// The policy before the change.
+        old: ArchivePolicy,
+        /// The policy after the change.
+        new: ArchivePolicy,
+    },
+    /// The settings of a tag were added (`old` is `None`), removed (`new` is `None`) or changed.
+    SetTagInfo {
+        /// The tag whose settings changed.
+        tag: Tag,
+        /// The settings before the change.
+        old: Option<TagInfo>,
+        /// The settings after the change.
+        new: Option<TagInfo>,
+    },
+    /// The id that identifies the database in links was created.
+    SetDatabaseId {
+        /// The id before the change.
+        old: Option<String>,
+        /// The id after the change.
+        new: Option<String>,
+    },
+    /// The id namespace was changed.
+    SetIdNamespace {
+        /// The namespace before the change.
+        old: Option<String>,
+        /// The namespace after the change.
+        new: Option<String>,
+    },
+    /// The CRDT clocks were changed.
+    SetCrdtClocks {
+        /// The clocks before the change.
+        old: Option<Box<CrdtClocks>>,
+        /// The clocks after the change.
+        new: Option<Box<CrdtClocks>>,
+    },
+    /// The encryption settings were changed.
+    SetEncryption {
+        /// The settings before the change.
+        old: Option<EncryptionSettings>,
+        /// The settings after the change.
+        new: Option<EncryptionSettings>,
+    },
+}
+
+/// How many tasks and dependencies a list of [`DatabaseOp`]s changes, to preview a change before
+/// it is made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    /// The amount of tasks that are added.
+    pub tasks_added: usize,
+    /// The amount of existing tasks that are changed.
+    pub tasks_modified: usize,
+    /// The amount of tasks that are removed.
+    pub tasks_removed: usize,
+    /// The amount of dependencies that are added.
+    pub dependencies_added: usize,
+    /// The amount of existing dependencies that are changed, such as their lag.
+    pub dependencies_modified: usize,
+    /// The amount of dependencies that are removed.
+    pub dependencies_removed: usize,
+    /// Whether any setting of the database is changed, such as the archive policy.
+    pub settings_changed: bool,
+}
+
+impl DiffSummary {
+    /// Counts the changes made by the given operations.
+    #[must_use]
+    pub fn of(ops: &[DatabaseOp]) -> Self {
+        let mut summary = Self::default();
+        for op in ops {
+            match op {
+                DatabaseOp::SetTask { old, new, .. } => match (old, new) {
+                    (None, Some(_)) => summary.tasks_added += 1,
+                    (Some(_), Some(_)) => summary.tasks_modified += 1,
+                    (Some(_), None) => summary.tasks_removed += 1,
+                    (None, None) => (),
+                },
+                DatabaseOp::SetDependency { old, new, .. } => match (old, new) {
+                    (None, Some(_)) => summary.dependencies_added += 1,
+                    (Some(_), Some(_)) => summary.dependencies_modified += 1,
+                    (Some(_), None) => summary.dependencies_removed += 1,
+                    (None, None) => (),
+                },
+                DatabaseOp::SetArchivePolicy { .. }
+                | DatabaseOp::SetTagInfo { .. }
+                | DatabaseOp::SetDatabaseId { .. }
+                | DatabaseOp::SetIdNamespace { .. }
+                | DatabaseOp::SetCrdtClocks { .. }
+                | DatabaseOp::SetEncryption { .. } => summary.settings_changed = true,
+            }
+        }
+        summary
+    }
+
+    /// Returns `true` if nothing is changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+
+        let counts = [
+            (self.tasks_added, "task", "added"),
+            (self.tasks_modified, "task", "modified"),
+            (self.tasks_removed, "task", "removed"),
+            (self.dependencies_added, "dependency", "added"),
+            (self.dependencies_modified, "dependency", "modified"),
+            (self.dependencies_removed, "dependency", "removed"),
+        ];
+        let mut parts = counts
+            .into_iter()
+            .filter(|(count, _, _)| *count > 0)
+            .map(|(count, noun, verb)| match (count, noun) {
+                (1, _) => format!("1 {noun} {verb}"),
+                (_, "dependency") => format!("{count} dependencies {verb}"),
+                _ => format!("{count} {noun}s {verb}"),
+            })
+            .collect::<Vec<_>>();
+        if self.settings_changed {
+            parts.push("settings changed".to_string());
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl Database {
+    /// Gets the operations that turn `old` into this database.
+    #[must_use]
+    pub fn diff(&self, old: &Self) -> Vec<DatabaseOp> {
+        let mut ops = vec![];
+        self.diff_settings(old, &mut ops);
+
+        for task in self.get_all_tasks() {
+            let old_task = old.get_node_index(&task.id).map(|i| &old.graph[i]);
+            if old_task != Some(task) {
+                ops.push(DatabaseOp::SetTask {
+                    id: task.id.clone(),
+                    old: old_task.cloned().map(Box::new),
+                    new: Some(Box::new(task.clone())),
+                });
+            }
+        }
+        for task in old.get_all_tasks() {
+            if self.get_node_index(&task.id).is_none() {
+                ops.push(DatabaseOp::SetTask {
+                    id: task.id.clone(),
+                    old: Some(Box::new(task.clone())),
+                    new: None,
+                });
+            }
+        }
+
+        for (from, to, dependency) in self.all_dependencies() {
+            let old_dependency = old.get_dependency(from, to);
+            if old_dependency != Some(dependency) {
+                ops.push(DatabaseOp::SetDependency {
+                    from: from.clone(),
+                    to: to.clone(),
+                    old: old_dependency.cloned(),
+                    new: Some(dependency.clone()),
+                });
+            }
+        }
+        for (from, to, dependency) in old.all_dependencies() {
+            if self.get_dependency(from, to).is_none() {
+                ops.push(DatabaseOp::SetDependency {
+                    from: from.clone(),
+                    to: to.clone(),
+                    old: Some(dependency.clone()),
+                    new: None,
+                });
+            }
+        }
+
+        ops
+    }
+
+    /// Adds the operations that turn the settings of `old` into the settings of this database.
+    pub(crate) fn diff_settings(&self, old: &Self, ops: &mut Vec<DatabaseOp>) {
+        if self.archive_policy != old.archive_policy {
+            ops.push(DatabaseOp::SetArchivePolicy {
+                old: old.archive_policy,
+                new: self.archive_policy,
+            });
+        }
+        for (tag, info) in &self.tag_registry {
+            let old_info = old.tag_registry.get(tag);
+            if old_info != Some(info) {
+                ops.push(DatabaseOp::SetTagInfo {
+                    tag: tag.clone(),
+                    old: old_info.cloned(),
+                    new: Some(info.clone()),
+                });
+            }
+        }
+        for (tag, info) in &old.tag_registry {
+            if !self.tag_registry.contains_key(tag) {
+                ops.push(DatabaseOp::SetTagInfo {
+                    tag: tag.clone(),
+                    old: Some(info.clone()),
+                    new: None,
+                });
+            }
+        }
+        if self.database_id != old.database_id {
+            ops.push(DatabaseOp::SetDatabaseId {
+                old: old.database_id.clone(),
+                new: self.database_id.clone(),
+            });
+        }
+        if self.id_namespace != old.id_namespace {
+            ops.push(DatabaseOp::SetIdNamespace {
+                old: old.id_namespace.clone(),
+                new: self.id_namespace.clone(),
+            });
+        }
+        if self.crdt != old.crdt {
+            ops.push(DatabaseOp::SetCrdtClocks {
+                old: old.crdt.clone().map(Box::new),
+                new: self.crdt.clone().map(Box::new),
+            });
+        }
+        if self.encryption != old.encryption {
+            ops.push(DatabaseOp::SetEncryption {
+                old: old.encryption.clone(),
+                new: self.encryption.clone(),
+            });
+        }
+    }
+
+    /// Applies operations created by [`Self::diff`], or reverts them if `reverse` is set.
+    pub fn apply_ops(&mut self, ops: &[DatabaseOp], reverse: bool) {
+        // dependencies have to be removed before their tasks, and added after them
+        for op in ops {
+            if let DatabaseOp::SetDependency { from, to, old, new } = op {
+                if target(old, new, reverse).is_none() {
+                    self.remove_dependency(from, to);
+                }
+            }
+        }
+
+        let changes_settings = |op: &DatabaseOp| {
+            !matches!(
+                op,
+                DatabaseOp::SetTask { .. } | DatabaseOp::SetDependency { .. }
+            )
+        };
+        if ops.iter().any(changes_settings) {
+            self.record_settings();
+        }
+        for op in ops {
+            match op {
+                DatabaseOp::SetTask { id, old, new } => match target(old, new, reverse) {
+                    Some(task) if self.get_node_index(id).is_some() => {
+                        self[id] = (**task).clone();
+                    }
+                    Some(task) => self.add_task((**task).clone()),
+                    None => self.remove_task(id),
+                },
+                DatabaseOp::SetArchivePolicy { old, new } => {
+                    self.archive_policy = *target(old, new, reverse);
+                }
+                DatabaseOp::SetTagInfo { tag, old, new } => match target(old, new, reverse) {
+                    Some(info) => _ = self.tag_registry.insert(tag.clone(), info.clone()),
+                    None => _ = self.tag_registry.remove(tag),
+                },
+                DatabaseOp::SetDatabaseId { old, new } => {
+                    self.database_id = target(old, new, reverse).clone();
+                }
+                DatabaseOp::SetIdNamespace { old, new } => {
+                    self.id_namespace = target(old, new, reverse).clone();
+                }
+                DatabaseOp::SetCrdtClocks { old, new } => {
+                    self.crdt = target(old, new, reverse).as_deref().cloned();
+                }
+                DatabaseOp::SetEncryption { old, new } => {
+                    self.encryption = target(old, new, reverse).clone();
+                }
+                DatabaseOp::SetDependency { .. } => (),
+            }
+        }
+
+        for op in ops {
+            if let DatabaseOp::SetDependency { from, to, old, new } = op {
+                if let Some(dependency) = target(old, new, reverse) {
+                    match self.get_dependency_mut(from, to) {
+                        Some(existing) => *existing = dependency.clone(),
+                        None => self.add_dependency_with(from, to, dependency.clone()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn all_dependencies(&self) -> impl Iterator<Item = (&TaskId, &TaskId, &TaskDependency)> {
+        self.graph.edge_indices().map(|edge_index| {
+            let (from, to) = self
+                .graph
+                .edge_endpoints(edge_index)
+                .expect("each edge should be connected");
+            (
+                &self.graph[from].id,
+                &self.graph[to].id,
+                &self.graph[edge_index],
+            )
+        })
+    }
+}
+
+/// Gets the value an operation changes something to, which is the old value if it is reversed.
+fn target<'a, T>(old: &'a T, new: &'a T, reverse: bool) -> &'a T {
+    if reverse {
+        old
+    } else {
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+    use crate::database::TagColor;
+
+    fn snapshot(db: &Database) -> String {
+        let mut tasks = db.get_all_tasks().cloned().collect::<Vec<_>>();
+        tasks.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut dependencies = db
+            .all_dependencies()
+            .map(|(from, to, dep)| (from.clone(), to.clone(), dep.clone()))
+            .collect::<Vec<_>>();
+        dependencies.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        format!(
+            "{tasks:?} {dependencies:?} {:?} {:?}",
+            db.archive_policy, db.tag_registry
+        )
+    }
+
+    #[test]
+    fn applies_and_reverts_diff() {
+        let mut old = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let task_3 = Task::create_now("task 3".into());
+        let (id_1, id_2, id_3) = (task_1.id.clone(), task_2.id.clone(), task_3.id.clone());
+        old.add_task(task_1);
+        old.add_task(task_2);
+        old.add_task(task_3);
+        old.add_dependency(&id_1, &id_2);
+        old.add_dependency(&id_2, &id_3);
+
+        let mut new = old.clone();
+        new[&id_1].title = "renamed".into();
+        new.remove_task(&id_3);
+        let task_4 = Task::create_now("task 4".into());
+        let id_4 = task_4.id.clone();
+        new.add_task(task_4);
+        new.add_dependency(&id_4, &id_1);
+        new.get_dependency_mut(&id_1, &id_2).unwrap().lag = Duration::days(1);
+        new.set_archive_policy(ArchivePolicy {
+            archive_completed_after: Some(Duration::days(7)),
+        });
+        new.set_tag_color("work", Some(TagColor::Blue));
+
+        let ops = new.diff(&old);
+        assert!(new.diff(&new).is_empty());
+        assert_eq!(
+            DiffSummary::of(&ops).to_string(),
+            "1 task added, 1 task modified, 1 task removed, 1 dependency added, \
+             1 dependency modified, 1 dependency removed, settings changed"
+        );
+        assert_eq!(DiffSummary::of(&[]).to_string(), "no changes");
+
+        let mut db = old.clone();
+        db.apply_ops(&ops, false);
+        assert_eq!(snapshot(&db), snapshot(&new));
+
+        db.apply_ops(&ops, true);
+        assert_eq!(snapshot(&db), snapshot(&old));
+    }
+}
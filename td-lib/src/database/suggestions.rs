@@ -0,0 +1,254 @@
+//! Suggestions for which tasks to work on next.
+
+use std::collections::HashMap;
+
+use rand::{seq::IteratorRandom, Rng};
+use time::OffsetDateTime;
+
+use super::{Database, Task, TaskId};
+
+/// The score for each open task that (transitively) depends on a suggested task.
+const SCORE_PER_BLOCKED_TASK: i64 = 10;
+/// The score for tasks that have already been started.
+const SCORE_STARTED: i64 = 20;
+/// The score for every day a due date is closer than [`URGENCY_DAYS`].
+const SCORE_PER_URGENT_DAY: i64 = 5;
+/// How many days before a due date a task starts becoming urgent.
+const URGENCY_DAYS: i64 = 14;
+
+/// A task suggested by [`Database::suggest_tasks`].
+#[derive(Debug, Clone, Copy)]
+pub struct Suggestion<'a> {
+    /// The suggested task.
+    pub task: &'a Task,
+    /// How strongly this task is suggested. Higher is better.
+    pub score: i64,
+}
+
+impl Database {
+    /// Checks whether a task can be worked on: it must not be completed or archived, and all of
+    /// its dependencies must be completed.
+    #[must_use]
+    pub fn is_actionable(&self, task_id: &TaskId) -> bool {
+        let task = &self[task_id];
+        task.time_completed.is_none()
+            && task.time_archived.is_none()
+            && self
+                .get_dependencies(task_id)
+                .all(|dep| dep.time_completed.is_some())
+    }
+
+    /// Gets the tasks that can be worked on once the given task is completed, because it is the
+    /// last of their dependencies that is not completed yet. Whether the given task itself is
+    /// completed already does not matter.
+    #[must_use]
+    pub fn newly_actionable_after(&self, task_id: &TaskId) -> Vec<&Task> {
+        self.get_inverse_dependencies(task_id)
+            .filter(|task| task.time_completed.is_none() && task.time_archived.is_none())
+            .filter(|task| {
+                self.get_dependencies(&task.id)
+                    .all(|dep| dep.id == *task_id || dep.time_completed.is_some())
+            })
+            .collect()
+    }
+
+    /// Suggests up to `count` actionable tasks to work on next, best suggestion first. Tasks in
+    /// the someday/maybe bucket are never suggested.
+    ///
+    /// Tasks are scored by how many open tasks are waiting on them, whether they have already
+    /// been started, and how close their due date is compared to `now`. Ties are broken by
+    /// creation time, oldest first.
+    #[must_use]
+    pub fn suggest_tasks(&self, now: OffsetDateTime, count: usize) -> Vec<Suggestion<'_>> {
+        let mut suggestions = self
+            .get_all_tasks()
+            .filter(|task| self.is_actionable(&task.id) && !task.is_someday())
+            .map(|task| Suggestion {
+                task,
+                score: self.suggestion_score(task, now),
+            })
+            .collect::<Vec<_>>();
+
+        suggestions.sort_by_key(|s| (-s.score, s.task.time_created));
+        suggestions.truncate(count);
+        suggestions
+    }
+
+    /// Picks a random actionable task that matches the given filter, or `None` if there are no
+    /// such tasks. Tasks in the someday/maybe bucket are never picked.
+    pub fn pick_random_task<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        filter: impl Fn(&Task) -> bool,
+    ) -> Option<&Task> {
+        self.get_all_tasks()
+            .filter(|task| self.is_actionable(&task.id) && !task.is_someday() && filter(task))
+            .choose(rng)
+    }
+
+    /// Scores every task by how many open tasks directly or indirectly depend on it. Working on
+    /// the tasks with the highest score unblocks the most other work.
+    #[must_use]
+    pub fn blocking_scores(&self) -> HashMap<TaskId, usize> {
+        self.get_all_tasks()
+            .map(|task| (task.id.clone(), self.count_blocked_tasks(&task.id)))
+            .collect()
+    }
+
+    fn suggestion_score(&self, task: &Task, now: OffsetDateTime) -> i64 {
+        let mut score = self.count_blocked_tasks(&task.id) as i64 * SCORE_PER_BLOCKED_TASK;
+
+        if task.time_started.is_some() {
+            score += SCORE_STARTED;
+        }
+
+        if let Some(due) = task.time_due {
+            let days_left = (due - now).whole_days();
+            score += (URGENCY_DAYS - days_left).max(0) * SCORE_PER_URGENT_DAY;
+        }
+
+        score
+    }
+
+    /// Counts the open tasks that directly or indirectly depend on the given task, leaving out the
+    /// ones in the someday/maybe bucket.
+    fn count_blocked_tasks(&self, task_id: &TaskId) -> usize {
+        self.transitive_dependents(task_id)
+            .filter(|task| task.time_completed.is_none() && !task.is_someday())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+    use time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn suggests_actionable_bottlenecks_first() {
+        let mut db = Database::default();
+        let blocker = Task::create_now("blocker".into());
+        let blocked = Task::create_now("blocked".into());
+        let waiting = Task::create_now("waiting on blocked".into());
+        let mut independent = Task::create_now("independent".into());
+        independent.time_created = blocker.time_created - Duration::days(1);
+        let mut done = Task::create_now("done".into());
+        done.time_completed = Some(done.time_created);
+
+        let now = blocker.time_created;
+        let (blocker_id, blocked_id, waiting_id) =
+            (blocker.id.clone(), blocked.id.clone(), waiting.id.clone());
+        let independent_id = independent.id.clone();
+        db.add_task(blocker);
+        db.add_task(blocked);
+        db.add_task(waiting);
+        db.add_task(independent);
+        db.add_task(done);
+        db.add_dependency(&blocked_id, &blocker_id);
+        db.add_dependency(&waiting_id, &blocked_id);
+
+        let suggestions = db.suggest_tasks(now, 5);
+        let ids = suggestions.iter().map(|s| s.task.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![&blocker_id, &independent_id]);
+        assert_eq!(suggestions[0].score, 2 * SCORE_PER_BLOCKED_TASK);
+
+        assert_eq!(db.suggest_tasks(now, 1).len(), 1);
+
+        let scores = db.blocking_scores();
+        assert_eq!(scores[&blocker_id], 2);
+        assert_eq!(scores[&blocked_id], 1);
+        assert_eq!(scores[&waiting_id], 0);
+        assert_eq!(scores[&independent_id], 0);
+    }
+
+    #[test]
+    fn finds_tasks_unblocked_by_completing_a_task() {
+        let mut db = Database::default();
+        let tasks = ["blocker", "ready", "waiting", "other", "done"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [blocker, ready, waiting, other, done] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&ready, &blocker);
+        db.add_dependency(&waiting, &blocker);
+        db.add_dependency(&waiting, &other);
+        db.add_dependency(&done, &blocker);
+        db[&done].time_completed = Some(db[&done].time_created);
+
+        let titles = |tasks: Vec<&Task>| tasks.iter().map(|t| t.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(db.newly_actionable_after(&blocker)), ["ready"]);
+
+        db[&blocker].time_completed = Some(db[&blocker].time_created);
+        assert_eq!(titles(db.newly_actionable_after(&blocker)), ["ready"]);
+        assert_eq!(titles(db.newly_actionable_after(&other)), ["waiting"]);
+    }
+
+    #[test]
+    fn picks_matching_actionable_task() {
+        let mut db = Database::default();
+        let blocker = Task::create_now("blocker".into());
+        let blocked = Task::create_now("blocked".into());
+        let mut other = Task::create_now("other".into());
+        other.tags = vec!["skip".into()];
+        let (blocker_id, blocked_id) = (blocker.id.clone(), blocked.id.clone());
+        db.add_task(blocker);
+        db.add_task(blocked);
+        db.add_task(other);
+        db.add_dependency(&blocked_id, &blocker_id);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            let picked = db.pick_random_task(&mut rng, |t| t.tags.is_empty());
+            assert_eq!(picked.map(Task::id), Some(&blocker_id));
+        }
+        assert!(db.pick_random_task(&mut rng, |_| false).is_none());
+    }
+
+    #[test]
+    fn urgent_and_started_tasks_score_higher() {
+        let mut db = Database::default();
+        let plain = Task::create_now("plain".into());
+        let now = plain.time_created;
+        let mut started = Task::create_now("started".into());
+        started.time_started = Some(now);
+        let mut urgent = Task::create_now("urgent".into());
+        urgent.time_due = Some(now + Duration::days(1));
+        let (started_id, urgent_id) = (started.id.clone(), urgent.id.clone());
+        db.add_task(plain);
+        db.add_task(started);
+        db.add_task(urgent);
+
+        let suggestions = db.suggest_tasks(now, 2);
+        let ids = suggestions.iter().map(|s| s.task.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![&urgent_id, &started_id]);
+    }
+
+    #[test]
+    fn someday_tasks_are_not_suggested() {
+        let mut db = Database::default();
+        let blocker = Task::create_now("blocker".into());
+        let mut idea = Task::create_now("idea".into());
+        idea.set_someday(true);
+        let mut maybe = Task::create_now("maybe".into());
+        maybe.set_someday(true);
+        let now = blocker.time_created;
+        let (blocker_id, idea_id, maybe_id) =
+            (blocker.id.clone(), idea.id.clone(), maybe.id.clone());
+        db.add_task(blocker);
+        db.add_task(idea);
+        db.add_task(maybe);
+        db.add_dependency(&idea_id, &blocker_id);
+
+        let suggestions = db.suggest_tasks(now, 5);
+        let ids = suggestions.iter().map(|s| s.task.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![&blocker_id]);
+        assert_eq!(suggestions[0].score, 0);
+
+        db[&maybe_id].set_someday(false);
+        assert!(db[&maybe_id].tags.is_empty());
+        assert_eq!(db.suggest_tasks(now, 5).len(), 2);
+    }
+}
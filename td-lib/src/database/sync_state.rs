@@ -0,0 +1,148 @@
+//! Bookkeeping for tasks that are synchronized with external services.
+
+use time::OffsetDateTime;
+
+use super::{Database, SyncState, Task};
+
+/// How a task changed since it was last synchronized with an external service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncChange {
+    /// Neither copy changed.
+    Unchanged,
+    /// Only the local task changed, so it should be sent to the service.
+    Local,
+    /// Only the task in the service changed, so it should be applied locally.
+    Remote,
+    /// Both copies changed.
+    Both,
+}
+
+impl Task {
+    /// Gets a hash of the contents of this task, not including its sync state. This is stable
+    /// between versions of td, so it can be stored to detect changes later.
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        let task = Self {
+            sync_state: Default::default(),
+            ..self.clone()
+        };
+        let bytes = serde_json::to_vec(&task).expect("task should be serializable");
+        fnv1a(&bytes)
+    }
+
+    /// Records that this task was just synchronized with the task `remote_id` in the service
+    /// `provider`.
+    pub fn mark_synced(&mut self, provider: &str, remote_id: String, now: OffsetDateTime) {
+        let state = SyncState {
+            remote_id,
+            last_synced: now,
+            hash: self.content_hash(),
+        };
+        self.sync_state.insert(provider.to_string(), state);
+    }
+
+    /// Determines which copies of this task changed since it was last synchronized with
+    /// `provider`. Returns `None` if it was never synchronized with it.
+    ///
+    /// `remote` is a copy of this task with the fields from the service applied to it, so that
+    /// fields the service does not know about are not seen as changes.
+    #[must_use]
+    pub fn sync_change(&self, provider: &str, remote: &Self) -> Option<SyncChange> {
+        let state = self.sync_state.get(provider)?;
+        let local_changed = self.content_hash() != state.hash;
+        let remote_changed = remote.content_hash() != state.hash;
+
+        Some(match (local_changed, remote_changed) {
+            (false, false) => SyncChange::Unchanged,
+            (true, false) => SyncChange::Local,
+            (false, true) => SyncChange::Remote,
+            (true, true) if self.content_hash() == remote.content_hash() => SyncChange::Unchanged,
+            (true, true) => SyncChange::Both,
+        })
+    }
+}
+
+impl Database {
+    /// Finds the task that is synchronized with the task `remote_id` in the service `provider`.
+    #[must_use]
+    pub fn find_synced_task(&self, provider: &str, remote_id: &str) -> Option<&Task> {
+        self.get_all_tasks().find(|task| {
+            task.sync_state
+                .get(provider)
+                .is_some_and(|state| state.remote_id == remote_id)
+        })
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hashers in the standard library is guaranteed to stay
+/// the same.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn detects_local_and_remote_changes() {
+        let mut task = Task::create_now("task".into());
+        task.mark_synced("service", "remote-1".into(), datetime!(2024-01-01 0:00 UTC));
+        assert_eq!(task.sync_state["service"].remote_id, "remote-1");
+
+        let mut remote = task.clone();
+        assert_eq!(
+            task.sync_change("service", &remote),
+            Some(SyncChange::Unchanged)
+        );
+        assert_eq!(task.sync_change("other", &remote), None);
+
+        remote.title = "remote title".into();
+        assert_eq!(
+            task.sync_change("service", &remote),
+            Some(SyncChange::Remote)
+        );
+
+        task.tags.push("local".into());
+        assert_eq!(task.sync_change("service", &remote), Some(SyncChange::Both));
+
+        remote.title = "task".into();
+        assert_eq!(
+            task.sync_change("service", &remote),
+            Some(SyncChange::Local)
+        );
+    }
+
+    #[test]
+    fn content_hash_ignores_sync_state() {
+        let mut task = Task::create_now("task".into());
+        let hash = task.content_hash();
+        task.mark_synced("service", "remote-1".into(), datetime!(2024-01-01 0:00 UTC));
+
+        assert_eq!(task.content_hash(), hash);
+        assert_eq!(fnv1a(b"td"), 0x08c8_3407_b56a_b825);
+    }
+
+    #[test]
+    fn finds_synced_tasks() {
+        let mut db = Database::default();
+        let mut task = Task::create_now("task".into());
+        task.mark_synced("service", "remote-1".into(), datetime!(2024-01-01 0:00 UTC));
+        let id = task.id().clone();
+        db.add_task(task);
+
+        assert_eq!(
+            db.find_synced_task("service", "remote-1").unwrap().id(),
+            &id
+        );
+        assert!(db.find_synced_task("service", "remote-2").is_none());
+        assert!(db.find_synced_task("other", "remote-1").is_none());
+    }
+}
@@ -0,0 +1,171 @@
+//! Interned tag names.
+
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt::{Debug, Display},
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The tag of the tasks in the someday/maybe bucket: ideas that are not planned yet. These tasks
+/// are hidden from the task list unless asked for, and are never suggested to work on.
+pub const SOMEDAY_TAG: &str = "someday";
+
+/// The name of a tag on a task.
+///
+/// Tags are interned: all tags with the same name share a single allocation, so they take up
+/// little memory and cloning them is cheap. This matters because the same few tags are used by
+/// many tasks, and the database is cloned for every undo step. A tag can be used as a `&str`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag(Arc<str>);
+
+/// All tag names that are in use, to share allocations between tags with the same name.
+struct TagPool {
+    names: HashSet<Arc<str>>,
+    /// The amount of names after names that were no longer used were last removed.
+    size_after_prune: usize,
+}
+
+impl Tag {
+    /// Gets the tag with the given name.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        static POOL: OnceLock<Mutex<TagPool>> = OnceLock::new();
+        let mut pool = POOL
+            .get_or_init(|| {
+                Mutex::new(TagPool {
+                    names: HashSet::new(),
+                    size_after_prune: 0,
+                })
+            })
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        if let Some(existing) = pool.names.get(name) {
+            return Self(existing.clone());
+        }
+
+        // forget names that only the pool refers to once it has grown a lot
+        if pool.names.len() >= (pool.size_after_prune * 2).max(64) {
+            pool.names.retain(|name| Arc::strong_count(name) > 1);
+            pool.size_after_prune = pool.names.len();
+        }
+
+        let name = Arc::<str>::from(name);
+        pool.names.insert(name.clone());
+        Self(name)
+    }
+
+    /// Gets the name of this tag.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Tag {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Tag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Tag {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&*self.0, f)
+    }
+}
+
+impl Debug for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Tag {
+    fn from(value: String) -> Self {
+        Self::new(&value)
+    }
+}
+
+impl From<&String> for Tag {
+    fn from(value: &String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq<str> for Tag {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Tag {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Tag {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::new(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_with_the_same_name_share_memory() {
+        let work = Tag::new("work");
+        let other = Tag::from(String::from("work"));
+        assert_eq!(work, other);
+        assert!(Arc::ptr_eq(&work.0, &other.0));
+
+        assert_eq!(work, "work");
+        assert_ne!(work, Tag::new("home"));
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let tags = vec![Tag::new("work"), Tag::new("P:1")];
+        let json = serde_json::to_string(&tags).unwrap();
+        assert_eq!(json, r#"["work","P:1"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Tag>>(&json).unwrap(), tags);
+    }
+}
@@ -1,46 +1,90 @@
-use petgraph::stable_graph::StableDiGraph;
-use serde::{Deserialize, Serialize};
+use petgraph::{stable_graph::StableDiGraph, visit::EdgeRef, Direction};
+use serde::{ser::SerializeSeq, Deserialize, Serialize};
 
 use super::*;
 
 /// The database model as stored to disk.
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize)]
 pub struct DatabaseDiskModel {
+    #[serde(default)]
+    database_id: Option<String>,
+    #[serde(default)]
+    id_namespace: Option<String>,
+    #[serde(default)]
+    archive_policy: ArchivePolicyDiskModel,
+    #[serde(default)]
+    tag_registry: BTreeMap<Tag, TagInfo>,
+    #[serde(default)]
+    crdt: Option<CrdtClocks>,
+    #[serde(default)]
+    encryption: Option<EncryptionSettings>,
     tasks: Vec<TaskDiskModel>,
 }
 
-impl From<Database> for DatabaseDiskModel {
-    fn from(value: Database) -> Self {
-        let mut list = vec![];
-
-        // collect nodes
-        for node_idx in value.graph.node_indices() {
-            let node_weight = value.graph[node_idx].clone();
-            list.push((node_idx, TaskDiskModel::new(node_weight)));
-        }
+/// The same structure as [`DatabaseDiskModel`], but borrowing from the database instead of
+/// copying it. Tasks are converted one at a time while they are serialized, so saving a large
+/// database does not need a second copy of it in memory.
+#[derive(Serialize)]
+pub struct DatabaseDiskModelRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    database_id: Option<&'a String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_namespace: Option<&'a String>,
+    #[serde(skip_serializing_if = "ArchivePolicyDiskModel::is_empty")]
+    archive_policy: ArchivePolicyDiskModel,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tag_registry: &'a BTreeMap<Tag, TagInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crdt: Option<&'a CrdtClocks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encryption: Option<&'a EncryptionSettings>,
+    tasks: TasksDiskModelRef<'a>,
+}
 
-        // collect edges
-        for edge_idx in value.graph.edge_indices() {
-            let (start_index, end_index) = value
-                .graph
-                .edge_endpoints(edge_idx)
-                .expect("each edge should be connected");
-
-            let end_id = list
-                .iter()
-                .find_map(|x| (x.0 == end_index).then(|| x.1.task.id.clone()))
-                .expect("should be able to find end node");
-            let start_node = list
-                .iter_mut()
-                .find(|x| x.0 == start_index)
-                .expect("should be able to find start node");
-
-            start_node.1.dependencies.push(end_id);
+impl<'a> From<&'a Database> for DatabaseDiskModelRef<'a> {
+    fn from(value: &'a Database) -> Self {
+        Self {
+            database_id: value.database_id.as_ref(),
+            id_namespace: value.id_namespace.as_ref(),
+            archive_policy: value.archive_policy.into(),
+            tag_registry: &value.tag_registry,
+            crdt: value.crdt.as_ref(),
+            encryption: value.encryption.as_ref(),
+            tasks: TasksDiskModelRef(value),
         }
+    }
+}
 
-        Self {
-            tasks: list.into_iter().map(|x| x.1).collect(),
+/// Serializes the tasks of a database with their dependencies.
+struct TasksDiskModelRef<'a>(&'a Database);
+
+impl Serialize for TasksDiskModelRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let graph = &self.0.graph;
+
+        // tasks and their dependencies are sorted by id, so unrelated changes don't move them
+        // around in the file
+        let mut node_indices = graph.node_indices().collect::<Vec<_>>();
+        node_indices.sort_by(|a, b| graph[*a].id.cmp(&graph[*b].id));
+
+        let mut seq = serializer.serialize_seq(Some(node_indices.len()))?;
+        for node_idx in node_indices {
+            let mut dependencies = graph
+                .edges_directed(node_idx, Direction::Outgoing)
+                .map(|edge| {
+                    DependencyDiskModel::new(graph[edge.target()].id.clone(), edge.weight())
+                })
+                .collect::<Vec<_>>();
+            dependencies.sort_by(|a, b| a.id().cmp(b.id()));
+            seq.serialize_element(&TaskDiskModelRef {
+                dependencies,
+                task: &graph[node_idx],
+            })?;
         }
+        seq.end()
     }
 }
 
@@ -56,38 +100,151 @@ impl From<DatabaseDiskModel> for Database {
             id_index_map.insert(id, index);
         }
 
-        // store edges
+        // store edges, keeping track of the ones to tasks that do not exist
+        let mut dangling_dependencies = vec![];
         for task in &value.tasks {
             let source_id = task.task.id.clone();
-            for target_id in task.dependencies.iter() {
+            for dependency in task.dependencies.iter() {
                 let source_index = id_index_map[&source_id];
-                let target_index = id_index_map[target_id];
+                let Some(&target_index) = id_index_map.get(dependency.id()) else {
+                    dangling_dependencies.push((source_id.clone(), dependency.id().clone()));
+                    continue;
+                };
 
-                graph.add_edge(source_index, target_index, TaskDependency);
+                graph.add_edge(source_index, target_index, dependency.to_dependency());
             }
         }
 
-        Self {
+        let mut database = Self {
             graph,
             task_id_to_index: id_index_map,
+            database_id: value.database_id,
+            id_namespace: value.id_namespace,
+            archive_policy: value.archive_policy.into(),
+            tag_registry: value.tag_registry,
+            crdt: value.crdt,
+            encryption: value.encryption,
+            index: Default::default(),
+            dangling_dependencies,
+            dependency_stats: Default::default(),
+            changes: None,
+        };
+        database.rebuild_index();
+        database
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct ArchivePolicyDiskModel {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archive_completed_after_seconds: Option<i64>,
+}
+
+impl ArchivePolicyDiskModel {
+    fn is_empty(&self) -> bool {
+        self.archive_completed_after_seconds.is_none()
+    }
+}
+
+impl From<ArchivePolicy> for ArchivePolicyDiskModel {
+    fn from(value: ArchivePolicy) -> Self {
+        Self {
+            archive_completed_after_seconds: value
+                .archive_completed_after
+                .map(|d| d.whole_seconds()),
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+impl From<ArchivePolicyDiskModel> for ArchivePolicy {
+    fn from(value: ArchivePolicyDiskModel) -> Self {
+        Self {
+            archive_completed_after: value.archive_completed_after_seconds.map(Duration::seconds),
+        }
+    }
+}
+
+#[derive(Deserialize)]
 struct TaskDiskModel {
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    dependencies: Vec<TaskId>,
+    #[serde(default)]
+    dependencies: Vec<DependencyDiskModel>,
 
     #[serde(flatten)]
     task: Task,
 }
 
-impl TaskDiskModel {
-    pub fn new(task: Task) -> Self {
-        Self {
-            task,
-            dependencies: vec![],
+#[derive(Serialize)]
+struct TaskDiskModelRef<'a> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<DependencyDiskModel>,
+
+    #[serde(flatten)]
+    task: &'a Task,
+}
+
+/// A dependency as stored on disk. Plain dependencies are stored as just the task id, so files
+/// without dependency properties look the same as before they were introduced.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum DependencyDiskModel {
+    Plain(TaskId),
+    WithProperties {
+        id: TaskId,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        lag_seconds: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(default, skip_serializing_if = "is_default_relation")]
+        relation: DependencyRelation,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        created: Option<OffsetDateTime>,
+    },
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
+}
+
+fn is_default_relation(relation: &DependencyRelation) -> bool {
+    *relation == DependencyRelation::default()
+}
+
+impl DependencyDiskModel {
+    fn new(id: TaskId, dependency: &TaskDependency) -> Self {
+        if *dependency == TaskDependency::default() {
+            Self::Plain(id)
+        } else {
+            Self::WithProperties {
+                id,
+                lag_seconds: dependency.lag.whole_seconds(),
+                label: dependency.label.clone(),
+                relation: dependency.relation,
+                created: dependency.created,
+            }
+        }
+    }
+
+    fn id(&self) -> &TaskId {
+        match self {
+            Self::Plain(id) | Self::WithProperties { id, .. } => id,
+        }
+    }
+
+    fn to_dependency(&self) -> TaskDependency {
+        match self {
+            Self::Plain(_) => TaskDependency::default(),
+            Self::WithProperties {
+                lag_seconds,
+                label,
+                relation,
+                created,
+                ..
+            } => TaskDependency {
+                lag: Duration::seconds(*lag_seconds),
+                label: label.clone(),
+                relation: *relation,
+                created: *created,
+            },
         }
     }
 }
@@ -2,34 +2,136 @@
 
 mod file_model;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 
 use self::file_model::*;
+use super::Tag;
 
 /// The in-memory representation of the database
 #[derive(Debug, Clone, Default)]
 pub struct Database {
     /// The graph of tasks in this database.
     ///
-    /// This uses a StableDiGraph to keep a stable order, which means insertions and removals will
-    /// not cause large changes to the database file.
+    /// This uses a [`StableDiGraph`] so node indices stay valid when other tasks are removed. The
+    /// order of the database file does not depend on it, tasks are saved sorted by id.
     pub(crate) graph: StableDiGraph<Task, TaskDependency>,
 
     /// A lookup cache
     pub(crate) task_id_to_index: HashMap<TaskId, NodeIndex>,
+
+    /// A random id that identifies this database in links to its tasks, once one was created.
+    pub(crate) database_id: Option<String>,
+
+    /// The namespace used to rewrite colliding task ids when this database is merged into another.
+    pub(crate) id_namespace: Option<String>,
+
+    /// The rules for automatically archiving tasks in this database.
+    pub(crate) archive_policy: ArchivePolicy,
+
+    /// The settings of tags, such as their color. Tags without settings have no entry.
+    pub(crate) tag_registry: BTreeMap<Tag, TagInfo>,
+
+    /// The timestamps of all changes, if copies of this database are merged as a CRDT.
+    pub(crate) crdt: Option<CrdtClocks>,
+
+    /// How confidential tasks are encrypted, if encryption is enabled.
+    pub(crate) encryption: Option<EncryptionSettings>,
+
+    /// Lookups of tasks by tag and title.
+    pub(crate) index: super::index::TaskIndex,
+
+    /// Dependencies in the loaded file on tasks that do not exist, by dependent and dependency.
+    /// They are not part of the graph, see [`super::ValidationIssue::DanglingDependency`].
+    pub(crate) dangling_dependencies: Vec<(TaskId, TaskId)>,
+
+    /// The dependency counts of all tasks, counted when they are first needed.
+    pub(crate) dependency_stats: std::sync::OnceLock<super::DependencyStats>,
+
+    /// The changes that are being recorded, see [`Database::record_changes`].
+    pub(crate) changes: Option<Box<super::journal::ChangeJournal>>,
+}
+
+/// Rules for automatically archiving tasks, stored in the database they apply to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivePolicy {
+    /// Completed tasks are archived once they have been completed for at least this long.
+    pub archive_completed_after: Option<Duration>,
+}
+
+/// The settings of a tag, shared by all tasks that have it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagInfo {
+    /// The color the tag is shown in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<TagColor>,
+}
+
+impl TagInfo {
+    /// Checks if the tag has no settings, so it does not need an entry in the registry.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A color a tag can be shown in. These are the basic terminal colors, so they fit the color
+/// scheme of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagColor {
+    /// Red.
+    Red,
+    /// Green.
+    Green,
+    /// Yellow.
+    Yellow,
+    /// Blue.
+    Blue,
+    /// Magenta.
+    Magenta,
+    /// Cyan.
+    Cyan,
+}
+
+impl TagColor {
+    /// All colors, in the order they are offered in.
+    pub const ALL: [Self; 6] = [
+        Self::Red,
+        Self::Green,
+        Self::Yellow,
+        Self::Blue,
+        Self::Magenta,
+        Self::Cyan,
+    ];
+
+    /// Gets the name of the color, such as "red".
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Green => "green",
+            Self::Yellow => "yellow",
+            Self::Blue => "blue",
+            Self::Magenta => "magenta",
+            Self::Cyan => "cyan",
+        }
+    }
 }
 
 /// A completable task.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Task {
     /// A unique id for this task
     pub(crate) id: TaskId,
     /// A short description of this task.
     pub title: String,
+    /// A longer, free-form description of this task.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
     /// When the task has been created.
     pub time_created: OffsetDateTime,
     /// If the task has been started, this is when that happened.
@@ -38,19 +140,183 @@ pub struct Task {
     /// If the task has been completed, this is when that happened.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub time_completed: Option<OffsetDateTime>,
+    /// If the task has been archived, this is when that happened. Archived tasks are kept for
+    /// history, but are usually hidden.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_archived: Option<OffsetDateTime>,
+    /// If the task has a deadline, this is when it should be completed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_due: Option<OffsetDateTime>,
+    /// If the task is snoozed, it is hidden until this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<OffsetDateTime>,
+    /// How long this task is expected to take.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Duration>,
+    /// The periods of time that were spent working on this task, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub work_sessions: Vec<WorkSession>,
+    /// Moments at which the user wants to be reminded of this task.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<OffsetDateTime>,
     /// A list of tags for this task.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub tags: Vec<String>,
+    pub tags: Vec<Tag>,
+    /// The project this task belongs to, to keep unrelated tasks such as work and personal ones
+    /// apart in the same database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Who is responsible for this task, for databases that are shared between people.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// Whether the title of this task is stored encrypted, see [`crate::encryption`]. Tasks with
+    /// one of the encrypted tags of the database are encrypted as well.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// The state of this task in external services it is synchronized with, by the name of the
+    /// service.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sync_state: BTreeMap<String, SyncState>,
+    /// Values that integrations and scripts store on this task by key, such as the id of the task
+    /// in another system. td keeps them intact but does not use them itself. Keys are best prefixed
+    /// with the name of what uses them, like `jira.key`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
-/// A marker type to indicate that the relation between 2 tasks is a dependency.
-#[derive(Debug, Clone, Default)]
-pub struct TaskDependency;
+/// A period of time that was spent working on a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkSession {
+    /// When work on the task started.
+    pub start: OffsetDateTime,
+    /// When work on the task stopped, or `None` if it is still ongoing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<OffsetDateTime>,
+}
+
+/// What is known about a task in an external service, as of the last time it was synchronized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// The id of the task in the external service.
+    pub remote_id: String,
+    /// When the task was last synchronized.
+    pub last_synced: OffsetDateTime,
+    /// The [`Task::content_hash`] of the task when it was last synchronized.
+    pub hash: u64,
+}
+
+/// The progress state of a task, derived from its timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TaskStatus {
+    /// The task has not been started or completed yet.
+    Todo,
+    /// The task has been started, but is not completed yet.
+    Started,
+    /// The task has been completed.
+    Completed,
+}
+
+/// The relation between 2 tasks where one task depends on the other.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskDependency {
+    /// The minimum amount of time between the dependency being completed and the dependent task
+    /// being able to start. This is zero for regular dependencies.
+    pub lag: Duration,
+    /// A short note on why the task depends on the other one.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// What kind of relation the dependency stands for.
+    #[serde(default)]
+    pub relation: DependencyRelation,
+    /// When the dependency was added, if known.
+    #[serde(default)]
+    pub created: Option<OffsetDateTime>,
+}
+
+/// What kind of relation a [`TaskDependency`] stands for. This only describes the dependency, the
+/// dependent task waits for its dependency regardless of the relation.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyRelation {
+    /// The dependency has to be done before the dependent task.
+    #[default]
+    Blocks,
+    /// The tasks are related, without one having to be done first.
+    RelatesTo,
+    /// The dependent task is a duplicate of the dependency.
+    Duplicates,
+}
+
+impl DependencyRelation {
+    /// Gets a short description of the relation, such as "relates to".
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::RelatesTo => "relates to",
+            Self::Duplicates => "duplicates",
+        }
+    }
+
+    /// Gets the relation after this one, wrapping around at the end.
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Blocks => Self::RelatesTo,
+            Self::RelatesTo => Self::Duplicates,
+            Self::Duplicates => Self::Blocks,
+        }
+    }
+}
 
 /// A task ID.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TaskId(String);
 
+/// A Lamport timestamp: a counter that is higher than any timestamp the replica has seen before,
+/// with the id of the replica that made the change to break ties.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LamportTimestamp(pub u64, pub String);
+
+/// When every task field and dependency was last changed, used to merge copies of a database
+/// without conflicts. See [`crate::sync::crdt`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrdtClocks {
+    /// For each task, when each of its fields was last changed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) tasks: BTreeMap<TaskId, BTreeMap<String, LamportTimestamp>>,
+    /// When each removed task was removed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) removed_tasks: BTreeMap<TaskId, LamportTimestamp>,
+    /// When each dependency was last added, changed or removed, by dependent and dependency.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) dependencies: BTreeMap<TaskId, BTreeMap<TaskId, DependencyClock>>,
+}
+
+/// When a dependency was last changed, and whether that change removed it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyClock {
+    /// When the dependency was last changed.
+    pub(crate) time: LamportTimestamp,
+    /// Whether the dependency was removed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) removed: bool,
+}
+
+/// How confidential tasks in a database are encrypted. See [`crate::encryption`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionSettings {
+    /// The salt used to derive the key from the passphrase.
+    pub(crate) salt: String,
+    /// A known value encrypted with the key, to check if a passphrase is correct.
+    pub(crate) verifier: String,
+    /// Tasks with one of these tags are encrypted, as well as tasks that are marked as encrypted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) tags: Vec<String>,
+}
+
 // -- end public structs --
 
 impl Serialize for Database {
@@ -58,8 +324,7 @@ impl Serialize for Database {
     where
         S: serde::Serializer,
     {
-        let model: DatabaseDiskModel = self.clone().into();
-        model.serialize(serializer)
+        DatabaseDiskModelRef::from(self).serialize(serializer)
     }
 }
 
@@ -85,6 +350,23 @@ impl TaskId {
 
         Self(nanoid::nanoid!(8, &SAFE_ALPHABET))
     }
+
+    /// Creates a task id from an existing string, such as an id from another application.
+    pub(crate) fn from_raw(id: String) -> Self {
+        Self(id)
+    }
+
+    /// Creates a new task id by prefixing this one with the given namespace.
+    #[must_use]
+    pub fn with_namespace(&self, namespace: &str) -> Self {
+        Self(format!("{namespace}:{}", self.0))
+    }
+
+    /// Gets the string representation of this task id.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl super::DatabaseImpl for Database {
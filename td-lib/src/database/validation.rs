@@ -0,0 +1,415 @@
+//! Checks for the internal invariants of the database, and for problems in its contents that can
+//! be reported to the user and often repaired.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{algo::is_cyclic_directed, visit::EdgeRef, Direction};
+
+use super::{Database, TaskId};
+use crate::errors::ValidationError;
+
+/// Options for [`Database::debug_validate`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationOptions {
+    /// Also require the dependency graph to be free of cycles.
+    pub require_acyclic: bool,
+}
+
+impl Database {
+    /// Checks the internal invariants of the database: task ids must be unique and the id lookup
+    /// cache must be consistent with the task graph. Optionally, the dependency graph can be
+    /// required to be acyclic.
+    ///
+    /// When the `validate` feature is enabled, this is run after every mutation and a violation
+    /// causes a panic. This helps to catch misuse of the API early.
+    pub fn debug_validate(&self, options: ValidationOptions) -> Result<(), ValidationError> {
+        let mut seen_ids = HashSet::new();
+        for node_index in self.graph.node_indices() {
+            let id = &self.graph[node_index].id;
+            if !seen_ids.insert(id) {
+                return Err(ValidationError::DuplicateTaskId {
+                    task_id: id.clone(),
+                });
+            }
+            if self.task_id_to_index.get(id) != Some(&node_index) {
+                return Err(ValidationError::InconsistentCache {
+                    task_id: id.clone(),
+                });
+            }
+        }
+
+        if let Some(stale_id) = self
+            .task_id_to_index
+            .keys()
+            .find(|id| !seen_ids.contains(id))
+        {
+            return Err(ValidationError::StaleCacheEntry {
+                task_id: stale_id.clone(),
+            });
+        }
+
+        if options.require_acyclic && is_cyclic_directed(&self.graph) {
+            return Err(ValidationError::Cycle);
+        }
+
+        Ok(())
+    }
+
+    /// Validates the database after a mutation if the `validate` feature is enabled.
+    #[inline]
+    pub(crate) fn validate_after_mutation(&self) {
+        #[cfg(feature = "validate")]
+        if let Err(e) = self.debug_validate(ValidationOptions::default()) {
+            panic!("database invariant violated: {e}");
+        }
+    }
+}
+
+/// A problem in the contents of a database, as found by [`Database::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// Multiple tasks share the same id.
+    DuplicateTaskId {
+        /// The id that is used more than once.
+        task_id: TaskId,
+        /// How many tasks use the id.
+        count: usize,
+    },
+    /// The file the database was loaded from had a dependency on a task that does not exist. It
+    /// was left out when loading, and is dropped when the database is saved.
+    DanglingDependency {
+        /// The task that had the dependency.
+        task: TaskId,
+        /// The id of the task that does not exist.
+        dependency: TaskId,
+    },
+    /// A completed task depends on a task that is not completed.
+    CompletedWithOpenDependency {
+        /// The completed task.
+        task: TaskId,
+        /// The dependency that is not completed.
+        dependency: TaskId,
+    },
+    /// A task was completed before it was created.
+    CompletedBeforeCreated {
+        /// The task with the impossible completion time.
+        task: TaskId,
+    },
+}
+
+impl ValidationIssue {
+    /// Checks if [`Database::repair`] fixes this issue. Open dependencies of completed tasks are
+    /// left alone, since only the user knows which of the tasks is wrong.
+    #[must_use]
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::CompletedWithOpenDependency { .. })
+    }
+
+    /// Describes the issue, using the titles of the tasks in the given database.
+    #[must_use]
+    pub fn describe(&self, database: &Database) -> String {
+        let title = |id: &TaskId| match database.get_task(id) {
+            Some(task) => format!("'{}'", task.title),
+            None => format!("{:?}", id.as_str()),
+        };
+        match self {
+            Self::DuplicateTaskId { task_id, count } => {
+                format!("{count} tasks share the id {:?}", task_id.as_str())
+            }
+            Self::DanglingDependency { task, dependency } => format!(
+                "{} depends on task {:?}, which does not exist",
+                title(task),
+                dependency.as_str()
+            ),
+            Self::CompletedWithOpenDependency { task, dependency } => format!(
+                "{} is completed, but its dependency {} is not",
+                title(task),
+                title(dependency)
+            ),
+            Self::CompletedBeforeCreated { task } => {
+                format!("{} was completed before it was created", title(task))
+            }
+        }
+    }
+}
+
+/// The problems found in a database by [`Database::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The problems, in the order of the tasks they are about.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Checks if no problems were found.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Gets the amount of problems that [`Database::repair`] would fix.
+    #[must_use]
+    pub fn repairable_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.is_repairable()).count()
+    }
+}
+
+impl Database {
+    /// Checks the contents of the database for problems, such as tasks that share an id or that
+    /// were completed before they were created. Unlike [`Self::debug_validate`], these problems
+    /// can come from files that were edited by hand or by other programs.
+    #[must_use]
+    pub fn validate(&self) -> ValidationReport {
+        let mut issues = vec![];
+
+        let mut id_counts = HashMap::<&TaskId, usize>::new();
+        for task in self.graph.node_weights() {
+            *id_counts.entry(&task.id).or_default() += 1;
+        }
+        let mut reported_ids = HashSet::new();
+        for task in self.graph.node_weights() {
+            let count = id_counts[&task.id];
+            if count > 1 && reported_ids.insert(&task.id) {
+                issues.push(ValidationIssue::DuplicateTaskId {
+                    task_id: task.id.clone(),
+                    count,
+                });
+            }
+        }
+
+        issues.extend(self.dangling_dependencies.iter().map(|(task, dependency)| {
+            ValidationIssue::DanglingDependency {
+                task: task.clone(),
+                dependency: dependency.clone(),
+            }
+        }));
+
+        for index in self.graph.node_indices() {
+            let task = &self.graph[index];
+            let Some(completed) = task.time_completed else {continue;};
+            if completed < task.time_created {
+                issues.push(ValidationIssue::CompletedBeforeCreated {
+                    task: task.id.clone(),
+                });
+            }
+            for edge in self.graph.edges_directed(index, Direction::Outgoing) {
+                let dependency = &self.graph[edge.target()];
+                if dependency.time_completed.is_none() {
+                    issues.push(ValidationIssue::CompletedWithOpenDependency {
+                        task: task.id.clone(),
+                        dependency: dependency.id.clone(),
+                    });
+                }
+            }
+        }
+
+        ValidationReport { issues }
+    }
+
+    /// Repairs the problems that [`Self::validate`] finds where possible: tasks that share an id
+    /// get a new one except for the first, dangling dependencies are dropped and tasks completed
+    /// before they were created are completed at the moment they were created instead. Returns
+    /// the problems that were repaired.
+    pub fn repair(&mut self) -> Vec<ValidationIssue> {
+        let repaired = self
+            .validate()
+            .issues
+            .into_iter()
+            .filter(ValidationIssue::is_repairable)
+            .collect::<Vec<_>>();
+        if repaired.is_empty() {
+            return repaired;
+        }
+
+        self.record_everything();
+        let mut seen_ids = HashSet::new();
+        let indices = self.graph.node_indices().collect::<Vec<_>>();
+        for index in indices {
+            let task = &mut self.graph[index];
+            if !seen_ids.insert(task.id.clone()) {
+                task.id = TaskId::new();
+            }
+            if task.time_completed.is_some_and(|t| t < task.time_created) {
+                task.time_completed = Some(task.time_created);
+            }
+        }
+        self.dangling_dependencies.clear();
+
+        self.task_id_to_index = self
+            .graph
+            .node_indices()
+            .map(|index| (self.graph[index].id.clone(), index))
+            .collect();
+        self.rebuild_index();
+        self.invalidate_dependency_stats();
+        self.validate_after_mutation();
+        repaired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::database::Task;
+
+    #[derive(Debug, Clone)]
+    enum Operation {
+        AddTask,
+        RemoveTask(usize),
+        AddDependency(usize, usize),
+    }
+
+    fn operation() -> impl Strategy<Value = Operation> {
+        prop_oneof![
+            Just(Operation::AddTask),
+            any::<usize>().prop_map(Operation::RemoveTask),
+            (any::<usize>(), any::<usize>()).prop_map(|(a, b)| Operation::AddDependency(a, b)),
+        ]
+    }
+
+    fn apply(db: &mut Database, operation: &Operation) {
+        let ids = db
+            .get_all_tasks()
+            .map(|t| t.id().clone())
+            .collect::<Vec<_>>();
+        match *operation {
+            Operation::AddTask => db.add_task(Task::create_now("task".into())),
+            Operation::RemoveTask(_) if ids.is_empty() => (),
+            Operation::RemoveTask(i) => db.remove_task(&ids[i % ids.len()]),
+            Operation::AddDependency(..) if ids.is_empty() => (),
+            Operation::AddDependency(from, to) => {
+                db.add_dependency(&ids[from % ids.len()], &ids[to % ids.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_database_is_valid() {
+        let options = ValidationOptions {
+            require_acyclic: true,
+        };
+        assert_eq!(Database::default().debug_validate(options), Ok(()));
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let (id_1, id_2) = (task_1.id().clone(), task_2.id().clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_dependency(&id_1, &id_2);
+        db.add_dependency(&id_2, &id_1);
+
+        let options = ValidationOptions {
+            require_acyclic: true,
+        };
+        assert_eq!(db.debug_validate(options), Err(ValidationError::Cycle));
+        assert_eq!(db.debug_validate(ValidationOptions::default()), Ok(()));
+    }
+
+    #[test]
+    fn detects_stale_cache_entries() {
+        let mut db = Database::default();
+        let task = Task::create_now("task".into());
+        let id = task.id().clone();
+        db.add_task(task);
+        let index = db.task_id_to_index[&id];
+        db.graph.remove_node(index);
+
+        assert_eq!(
+            db.debug_validate(ValidationOptions::default()),
+            Err(ValidationError::StaleCacheEntry { task_id: id })
+        );
+    }
+
+    #[test]
+    fn finds_and_repairs_problems() {
+        let mut completed = Task::create_now("completed".into());
+        completed.time_completed = Some(completed.time_created - time::Duration::days(1));
+        let open = Task::create_now("open".into());
+        let mut copy = Task::create_now("copy".into());
+        copy.id = open.id.clone();
+        let (completed_id, open_id) = (completed.id.clone(), open.id.clone());
+        let missing = TaskId::new();
+
+        // the checked mutators refuse a duplicate id, so the problems are written into the file
+        let mut json = serde_json::json!({ "tasks": [completed, open, copy] });
+        json["tasks"][0]["dependencies"] = serde_json::json!([open_id, missing]);
+        let mut db: Database = serde_json::from_value(json).unwrap();
+
+        let report = db.validate();
+        assert_eq!(
+            report.issues,
+            [
+                ValidationIssue::DuplicateTaskId {
+                    task_id: open_id.clone(),
+                    count: 2
+                },
+                ValidationIssue::DanglingDependency {
+                    task: completed_id.clone(),
+                    dependency: missing,
+                },
+                ValidationIssue::CompletedBeforeCreated {
+                    task: completed_id.clone()
+                },
+                ValidationIssue::CompletedWithOpenDependency {
+                    task: completed_id.clone(),
+                    dependency: open_id.clone(),
+                },
+            ]
+        );
+        assert_eq!(
+            report.issues[2].describe(&db),
+            "'completed' was completed before it was created"
+        );
+
+        assert_eq!(db.repair().len(), 3);
+        let report = db.validate();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.repairable_count(), 0);
+        assert_eq!(db.get_all_tasks().count(), 3);
+        assert_eq!(db[&open_id].title, "open");
+        assert_eq!(db.debug_validate(ValidationOptions::default()), Ok(()));
+    }
+
+    #[test]
+    fn keeps_dangling_dependencies_out_of_loaded_databases() {
+        let task = Task::create_now("task".into());
+        let id = task.id.clone();
+        let mut db = Database::default();
+        db.add_task(task);
+        let mut json = serde_json::to_value(&db).unwrap();
+        json["tasks"][0]["dependencies"] = serde_json::json!(["missing"]);
+
+        let db: Database = serde_json::from_value(json).unwrap();
+        assert_eq!(db.get_dependencies(&id).count(), 0);
+        assert_eq!(db.validate().issues.len(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn random_operations_keep_invariants(operations in prop::collection::vec(operation(), 0..64)) {
+            let mut db = Database::default();
+            for operation in &operations {
+                apply(&mut db, operation);
+                prop_assert_eq!(db.debug_validate(ValidationOptions::default()), Ok(()));
+            }
+        }
+
+        #[test]
+        fn serialization_keeps_invariants(operations in prop::collection::vec(operation(), 0..64)) {
+            let mut db = Database::default();
+            for operation in &operations {
+                apply(&mut db, operation);
+            }
+
+            let json = serde_json::to_value(&db).expect("serialize database");
+            let db: Database = serde_json::from_value(json).expect("deserialize database");
+            prop_assert_eq!(db.debug_validate(ValidationOptions::default()), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Tracking the time spent working on tasks.
+
+use time::{Duration, OffsetDateTime};
+
+use super::{Task, WorkSession};
+
+impl Task {
+    /// Checks if work on this task is currently being tracked.
+    #[must_use]
+    pub fn is_clocked_in(&self) -> bool {
+        self.work_sessions.last().is_some_and(|s| s.end.is_none())
+    }
+
+    /// Starts tracking work on this task, marking it as started if it wasn't yet. Does nothing if
+    /// work is already being tracked.
+    pub fn clock_in(&mut self, now: OffsetDateTime) {
+        if self.is_clocked_in() {
+            return;
+        }
+
+        self.time_started.get_or_insert(now);
+        self.work_sessions.push(WorkSession {
+            start: now,
+            end: None,
+        });
+    }
+
+    /// Stops tracking work on this task. Does nothing if work isn't being tracked.
+    pub fn clock_out(&mut self, now: OffsetDateTime) {
+        if let Some(session) = self.work_sessions.last_mut().filter(|s| s.end.is_none()) {
+            session.end = Some(now);
+        }
+    }
+
+    /// Gets the total time worked on this task, counting an ongoing session up to `now`.
+    #[must_use]
+    pub fn time_worked(&self, now: OffsetDateTime) -> Duration {
+        self.work_sessions
+            .iter()
+            .map(|s| s.end.unwrap_or(now) - s.start)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn tracks_time_worked() {
+        let mut task = Task::create_now("task".into());
+        task.clock_in(datetime!(2024-01-01 9:00 UTC));
+        task.clock_in(datetime!(2024-01-01 9:30 UTC));
+        assert!(task.is_clocked_in());
+        assert_eq!(task.time_started, Some(datetime!(2024-01-01 9:00 UTC)));
+
+        task.clock_out(datetime!(2024-01-01 10:00 UTC));
+        assert!(!task.is_clocked_in());
+        task.clock_out(datetime!(2024-01-01 11:00 UTC));
+
+        task.clock_in(datetime!(2024-01-02 9:00 UTC));
+        assert_eq!(task.work_sessions.len(), 2);
+        assert_eq!(
+            task.time_worked(datetime!(2024-01-02 9:15 UTC)),
+            Duration::minutes(75)
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Encryption of confidential tasks, so a shared database can contain a few private items.
+//!
+//! Only the titles and descriptions of confidential tasks are encrypted. Everything else, such as
+//! tags, timestamps and dependencies, stays readable so the database can still be merged and
+//! synchronized by people who do not know the passphrase.
+
+use std::fmt::Debug;
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{Blake2b512, Digest};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+
+use crate::{
+    database::{Database, EncryptionSettings, Task},
+    errors::EncryptionError,
+};
+
+/// The prefix of encrypted values, followed by the base64 encoded nonce and ciphertext.
+const PREFIX: &str = "td-encrypted:v1:";
+const NONCE_LENGTH: usize = 12;
+const VERIFIER_CONTEXT: &str = "verifier";
+
+/// The key that confidential tasks are encrypted with, derived from the passphrase of a database.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .expect("salt and key length should be valid");
+        Self(key)
+    }
+
+    fn encrypt(&self, context: &str, plaintext: &str) -> String {
+        // the nonce is derived from the contents, so saving an unchanged task again does not
+        // change the file
+        let hash = Blake2b512::new()
+            .chain_update(self.0)
+            .chain_update(context)
+            .chain_update([0])
+            .chain_update(plaintext)
+            .finalize();
+        let nonce = Nonce::from_slice(&hash[..NONCE_LENGTH]);
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&self.0))
+            .encrypt(nonce, plaintext.as_bytes())
+            .expect("encrypting should not fail");
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend(ciphertext);
+        format!("{PREFIX}{}", STANDARD.encode(bytes))
+    }
+
+    fn decrypt(&self, value: &str) -> Option<String> {
+        let bytes = STANDARD.decode(value.strip_prefix(PREFIX)?).ok()?;
+        if bytes.len() < NONCE_LENGTH {
+            return None;
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&self.0))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl Task {
+    /// Checks if the title of this task is currently encrypted, meaning it could not be
+    /// decrypted yet.
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        self.title.starts_with(PREFIX)
+    }
+}
+
+impl Database {
+    /// Checks if confidential tasks in this database are encrypted.
+    #[must_use]
+    pub fn is_encryption_enabled(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    /// Enables encryption of confidential tasks, using a key derived from the given passphrase.
+    pub fn enable_encryption(
+        &mut self,
+        passphrase: &str,
+    ) -> Result<EncryptionKey, EncryptionError> {
+        if self.encryption.is_some() {
+            return Err(EncryptionError::AlreadyEnabled);
+        }
+
+        let mut salt = [0; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let key = EncryptionKey::derive(passphrase, &salt);
+
+        self.record_settings();
+        self.encryption = Some(EncryptionSettings {
+            salt: STANDARD.encode(salt),
+            verifier: key.encrypt(VERIFIER_CONTEXT, ""),
+            tags: vec![],
+        });
+        Ok(key)
+    }
+
+    /// Gets the key for the confidential tasks in this database, if the passphrase is correct.
+    pub fn unlock(&self, passphrase: &str) -> Result<EncryptionKey, EncryptionError> {
+        let settings = self
+            .encryption
+            .as_ref()
+            .ok_or(EncryptionError::NotEnabled)?;
+        let salt = STANDARD
+            .decode(&settings.salt)
+            .map_err(|_| EncryptionError::WrongPassphrase)?;
+        let key = EncryptionKey::derive(passphrase, &salt);
+
+        match key.decrypt(&settings.verifier) {
+            Some(_) => Ok(key),
+            None => Err(EncryptionError::WrongPassphrase),
+        }
+    }
+
+    /// Gets the tags that make tasks confidential.
+    #[must_use]
+    pub fn encrypted_tags(&self) -> &[String] {
+        self.encryption.as_ref().map_or(&[], |e| &e.tags)
+    }
+
+    /// Sets the tags that make tasks confidential.
+    pub fn set_encrypted_tags(&mut self, tags: Vec<String>) -> Result<(), EncryptionError> {
+        self.record_settings();
+        let settings = self
+            .encryption
+            .as_mut()
+            .ok_or(EncryptionError::NotEnabled)?;
+        settings.tags = tags;
+        Ok(())
+    }
+
+    /// Checks if the title of the given task should be encrypted.
+    #[must_use]
+    pub fn is_confidential(&self, task: &Task) -> bool {
+        self.is_encryption_enabled()
+            && (task.encrypted
+                || task
+                    .tags
+                    .iter()
+                    .any(|t| self.encrypted_tags().iter().any(|e| t == e)))
+    }
+
+    /// Checks if there are confidential tasks whose title is not encrypted, such as tasks that
+    /// became confidential while the key was not known.
+    #[must_use]
+    pub fn has_unencrypted_confidential_tasks(&self) -> bool {
+        self.get_all_tasks()
+            .any(|t| self.is_confidential(t) && !t.is_locked())
+    }
+
+    /// Encrypts the titles and descriptions of all confidential tasks, which is the form they
+    /// should be saved in.
+    pub fn encrypt_confidential_tasks(&mut self, key: &EncryptionKey) {
+        let tasks = self
+            .get_all_tasks()
+            .filter(|t| self.is_confidential(t) && !t.is_locked())
+            .map(|t| t.id().clone())
+            .collect::<Vec<_>>();
+
+        for task_id in tasks {
+            // every field has its own context, so a title and description that are the same do
+            // not get the same nonce
+            let id = task_id.as_str();
+            let task = &mut self[&task_id];
+            task.title = key.encrypt(&format!("{id}:title"), &task.title);
+            if !task.description.is_empty() {
+                task.description = key.encrypt(&format!("{id}:description"), &task.description);
+            }
+        }
+    }
+
+    /// Decrypts the titles and descriptions of all encrypted tasks. Nothing is changed if one of
+    /// them can not be decrypted.
+    pub fn decrypt_confidential_tasks(
+        &mut self,
+        key: &EncryptionKey,
+    ) -> Result<(), EncryptionError> {
+        let decrypted = self
+            .get_all_tasks()
+            .filter(|t| t.is_locked())
+            .map(|t| {
+                let title = key.decrypt(&t.title);
+                let description = match t.description.is_empty() {
+                    true => Some(String::new()),
+                    false => key.decrypt(&t.description),
+                };
+                match title.zip(description) {
+                    Some((title, description)) => Ok((t.id().clone(), title, description)),
+                    None => Err(EncryptionError::Corrupted {
+                        task_id: t.id().clone(),
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (task_id, title, description) in decrypted {
+            let task = &mut self[&task_id];
+            task.title = title;
+            task.description = description;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypts_only_confidential_titles() {
+        let mut db = Database::default();
+        let mut secret = Task::create_now("secret".into());
+        secret.encrypted = true;
+        secret.description = "the details".into();
+        let mut tagged = Task::create_now("tagged".into());
+        tagged.tags = vec!["private".into()];
+        let public = Task::create_now("public".into());
+        let (secret_id, tagged_id, public_id) =
+            (secret.id.clone(), tagged.id.clone(), public.id.clone());
+        db.add_task(secret);
+        db.add_task(tagged);
+        db.add_task(public);
+
+        let key = db.enable_encryption("hunter2").unwrap();
+        db.set_encrypted_tags(vec!["private".into()]).unwrap();
+        assert!(db.has_unencrypted_confidential_tasks());
+
+        let mut encrypted = db.clone();
+        encrypted.encrypt_confidential_tasks(&key);
+        assert!(encrypted[&secret_id].is_locked());
+        assert!(encrypted[&tagged_id].is_locked());
+        assert_ne!(encrypted[&secret_id].description, "the details");
+        assert_eq!(encrypted[&public_id].title, "public");
+        assert!(!encrypted.has_unencrypted_confidential_tasks());
+
+        // encrypting the same title again gives the same result
+        let mut again = db.clone();
+        again.encrypt_confidential_tasks(&key);
+        assert_eq!(again[&secret_id].title, encrypted[&secret_id].title);
+
+        let key = encrypted.unlock("hunter2").unwrap();
+        encrypted.decrypt_confidential_tasks(&key).unwrap();
+        assert_eq!(encrypted[&secret_id].title, "secret");
+        assert_eq!(encrypted[&secret_id].description, "the details");
+        assert_eq!(encrypted[&tagged_id].title, "tagged");
+    }
+
+    #[test]
+    fn encrypts_titles_and_descriptions_with_their_own_nonce() {
+        let mut db = Database::default();
+        let mut task = Task::create_now("same text".into());
+        task.encrypted = true;
+        task.description = "same text".into();
+        let id = task.id.clone();
+        db.add_task(task);
+
+        let key = db.enable_encryption("hunter2").unwrap();
+        db.encrypt_confidential_tasks(&key);
+        assert_ne!(db[&id].title, db[&id].description);
+
+        db.decrypt_confidential_tasks(&key).unwrap();
+        assert_eq!(db[&id].title, "same text");
+        assert_eq!(db[&id].description, "same text");
+    }
+
+    #[test]
+    fn rejects_wrong_passphrase() {
+        let mut db = Database::default();
+        assert_eq!(
+            db.unlock("hunter2").unwrap_err(),
+            EncryptionError::NotEnabled
+        );
+
+        db.enable_encryption("hunter2").unwrap();
+        assert_eq!(
+            db.unlock("hunter3").unwrap_err(),
+            EncryptionError::WrongPassphrase
+        );
+        assert_eq!(
+            db.enable_encryption("hunter3").unwrap_err(),
+            EncryptionError::AlreadyEnabled
+        );
+    }
+}
@@ -1,19 +1,354 @@
 //! Error types used by this crate
+//!
+//! Errors are grouped by the class of operation that failed. All enums are marked
+//! `#[non_exhaustive]` so new failure cases can be added without breaking downstream matches.
+
+use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
+use crate::database::TaskId;
+
+/// Any error that can be returned by this crate.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error occured while reading a database.
+    #[error(transparent)]
+    Read(#[from] DatabaseReadError),
+
+    /// An error occured while writing a database.
+    #[error(transparent)]
+    Write(#[from] DatabaseWriteError),
+
+    /// The database could not be locked.
+    #[error(transparent)]
+    Lock(#[from] DatabaseLockError),
+
+    /// The database violates one of its invariants.
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    /// An error occured while synchronizing a database.
+    #[error(transparent)]
+    Sync(#[from] SyncError),
+
+    /// A search query could not be parsed.
+    #[error(transparent)]
+    Query(#[from] QueryParseError),
+
+    /// Tasks from another application could not be imported.
+    #[error(transparent)]
+    Import(#[from] ImportError),
+
+    /// Confidential tasks could not be encrypted or decrypted.
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+}
+
 /// Errors that can occur when reading the task database.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum DatabaseReadError {
     /// A database was loaded with an unsupported database version.
-    #[error("unknown database version: {0}")]
-    UnknownVersion(u8),
+    #[error("unknown database version {version}, the latest supported version is {supported}")]
+    UnknownVersion {
+        /// The version of the database that was read.
+        version: u8,
+        /// The latest version that is supported.
+        supported: u8,
+    },
 
     /// A json deserialization error occured while reading the database structure.
+    #[error("json deserialization error{}: {source}", fmt_path(path.as_deref()))]
+    JsonError {
+        /// The file that was being read, if any.
+        path: Option<PathBuf>,
+        /// The underlying error.
+        source: serde_json::Error,
+    },
+
+    /// A `MessagePack` deserialization error occured while reading the database structure.
+    #[error("messagepack deserialization error{}: {source}", fmt_path(path.as_deref()))]
+    MessagePackError {
+        /// The file that was being read, if any.
+        path: Option<PathBuf>,
+        /// The underlying error.
+        source: rmp_serde::decode::Error,
+    },
+
+    /// An IO error occured while reading the database file.
+    #[error("io error while reading {}: {source}", path.display())]
+    IoError {
+        /// The file that was being read.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// An `SQLite` error occured while reading the database.
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error while reading {}: {source}", path.display())]
+    SqliteError {
+        /// The database file that was being read.
+        path: PathBuf,
+        /// The underlying error.
+        source: rusqlite::Error,
+    },
+}
+
+impl DatabaseReadError {
+    /// Gets the path of the file that was being read, if known.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::UnknownVersion { .. } => None,
+            Self::JsonError { path, .. } | Self::MessagePackError { path, .. } => path.as_deref(),
+            Self::IoError { path, .. } => Some(path),
+            #[cfg(feature = "sqlite")]
+            Self::SqliteError { path, .. } => Some(path),
+        }
+    }
+}
+
+/// Errors that can occur when writing the task database.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum DatabaseWriteError {
+    /// A json serialization error occured while writing the database structure.
+    #[error("json serialization error{}: {source}", fmt_path(path.as_deref()))]
+    JsonError {
+        /// The file that was being written, if any.
+        path: Option<PathBuf>,
+        /// The underlying error.
+        source: serde_json::Error,
+    },
+
+    /// A `MessagePack` serialization error occured while writing the database structure.
+    #[error("messagepack serialization error in {}: {source}", path.display())]
+    MessagePackError {
+        /// The file that was being written.
+        path: PathBuf,
+        /// The underlying error.
+        source: rmp_serde::encode::Error,
+    },
+
+    /// An IO error occured while writing the database file.
+    #[error("io error while writing {}: {source}", path.display())]
+    IoError {
+        /// The file that was being written.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// The database file was changed by another program since it was read, and writing it would
+    /// throw those changes away.
+    #[error("{} was changed by another program", path.display())]
+    ChangedOnDisk {
+        /// The database file that was changed.
+        path: PathBuf,
+    },
+
+    /// An `SQLite` error occured while writing the database.
+    #[cfg(feature = "sqlite")]
+    #[error("sqlite error while writing {}: {source}", path.display())]
+    SqliteError {
+        /// The database file that was being written.
+        path: PathBuf,
+        /// The underlying error.
+        source: rusqlite::Error,
+    },
+}
+
+impl DatabaseWriteError {
+    /// Gets the path of the file that was being written, if known.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::JsonError { path, .. } => path.as_deref(),
+            Self::MessagePackError { path, .. }
+            | Self::IoError { path, .. }
+            | Self::ChangedOnDisk { path } => Some(path),
+            #[cfg(feature = "sqlite")]
+            Self::SqliteError { path, .. } => Some(path),
+        }
+    }
+}
+
+/// Errors that can occur when locking the task database.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum DatabaseLockError {
+    /// Another process already holds the lock.
+    #[error("the database is already opened by {}", fmt_pid(*owner_pid))]
+    AlreadyLocked {
+        /// The lock file.
+        path: PathBuf,
+        /// The process id of the process holding the lock, if known.
+        owner_pid: Option<u32>,
+    },
+
+    /// An IO error occured while creating the lock file.
+    #[error("io error while locking {}: {source}", path.display())]
+    IoError {
+        /// The lock file.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+}
+
+/// Violations of the internal invariants of a database, as reported by
+/// [`Database::debug_validate`](crate::database::Database::debug_validate).
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// Multiple tasks in the database share the same id.
+    #[error("duplicate task id: {task_id:?}")]
+    DuplicateTaskId {
+        /// The id that is used more than once.
+        task_id: TaskId,
+    },
+
+    /// A task is missing from the id lookup cache, or the cache points to the wrong task.
+    #[error("lookup cache is inconsistent for task id: {task_id:?}")]
+    InconsistentCache {
+        /// The id of the task that has a wrong or missing cache entry.
+        task_id: TaskId,
+    },
+
+    /// The id lookup cache contains an entry for a task that does not exist.
+    #[error("lookup cache contains a removed task id: {task_id:?}")]
+    StaleCacheEntry {
+        /// The id of the task that no longer exists.
+        task_id: TaskId,
+    },
+
+    /// The dependency graph contains a cycle, while it was required to be acyclic.
+    #[error("the dependency graph contains a cycle")]
+    Cycle,
+}
+
+/// Errors that can occur when synchronizing a database with another copy of it.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum SyncError {
+    /// The local and remote copies both changed the same task in incompatible ways.
+    #[error("conflicting changes to task {task_id:?}")]
+    Conflict {
+        /// The id of the conflicting task.
+        task_id: TaskId,
+    },
+
+    /// An IO error occured while synchronizing.
+    #[error("io error while synchronizing {}: {source}", path.display())]
+    IoError {
+        /// The file or directory that was being synchronized.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// A git command failed.
+    #[error("`git {command}` failed: {message}")]
+    GitError {
+        /// The arguments that git was called with.
+        command: String,
+        /// The error output of git.
+        message: String,
+    },
+
+    /// A request to the GitHub API failed.
+    #[error("request to {url} failed: {message}")]
+    GitHubError {
+        /// The URL that was requested.
+        url: String,
+        /// What went wrong.
+        message: String,
+    },
+
+    /// The merged database could not be written.
+    #[error(transparent)]
+    Write(#[from] DatabaseWriteError),
+
+    /// A version of the database in the history could not be read.
+    #[error("could not read the database at revision {revision}: {source}")]
+    InvalidRevision {
+        /// The revision that was read.
+        revision: String,
+        /// The underlying error.
+        source: DatabaseReadError,
+    },
+}
+
+/// Errors that can occur when encrypting or decrypting confidential tasks, see
+/// [`crate::encryption`].
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncryptionError {
+    /// Encryption was used on a database that does not have it enabled.
+    #[error("encryption is not enabled for this database")]
+    NotEnabled,
+
+    /// Encryption was enabled on a database that already has it enabled.
+    #[error("encryption is already enabled for this database")]
+    AlreadyEnabled,
+
+    /// The passphrase does not match the one the database was encrypted with.
+    #[error("wrong passphrase")]
+    WrongPassphrase,
+
+    /// The encrypted title of a task could not be decrypted.
+    #[error("could not decrypt task {task_id:?}")]
+    Corrupted {
+        /// The task that could not be decrypted.
+        task_id: TaskId,
+    },
+}
+
+/// Errors that can occur when parsing a [`TaskQuery`](crate::query::TaskQuery).
+#[derive(Error, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryParseError {
+    /// A `status:` filter was given a value that is not a known status.
+    #[error("unknown status: {0}")]
+    UnknownStatus(String),
+
+    /// A date filter was given a value that is not a date in `YYYY-MM-DD` format.
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+
+    /// A quoted value was not closed.
+    #[error("unterminated quote")]
+    UnterminatedQuote,
+
+    /// An `in:` or `include:` filter was given a value that is not a group of hidden tasks.
+    #[error(
+        "unknown group of tasks: {0}, expected 'completed', 'archived', 'someday' or 'snoozed'"
+    )]
+    UnknownHiddenTasks(String),
+}
+
+/// Errors that can occur when importing tasks from another application.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ImportError {
+    /// The input is not valid json, or does not have the expected structure.
     #[error("json deserialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 
-    /// An IO error occured while reading the database file.
-    #[error("io error: {0}")]
-    IoError(#[from] std::io::Error),
+    /// A date in the input has an unexpected format.
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+}
+
+fn fmt_pid(pid: Option<u32>) -> String {
+    pid.map(|pid| format!("process {pid}"))
+        .unwrap_or_else(|| "another process".to_string())
+}
+
+fn fmt_path(path: Option<&Path>) -> String {
+    path.map(|p| format!(" in {}", p.display()))
+        .unwrap_or_default()
 }
@@ -0,0 +1,168 @@
+//! A printable HTML status report.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use time::{format_description::FormatItem, macros::format_description, OffsetDateTime};
+
+use crate::database::{Database, Task, TaskStatus};
+
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+/// The heading used for tasks that do not have a project tag.
+const NO_PROJECT: &str = "No project";
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+h1 { margin-bottom: 0; }
+.generated { color: #666; margin-top: 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { border-bottom: 1px solid #ccc; padding: 0.3em 0.5em; text-align: left; }
+.started { font-weight: bold; }
+.overdue { color: #b00; }
+@media print { body { margin: 0; } h2 { page-break-after: avoid; } tr { page-break-inside: avoid; } }
+";
+
+/// Renders the open tasks as a standalone HTML page that is meant to be printed.
+///
+/// Tasks are grouped by project, which are tags that start with `+` like in todo.txt. Within a
+/// project, tasks come after the tasks they depend on. Completed and archived tasks are left out.
+#[must_use]
+pub fn to_html_report(database: &Database, now: OffsetDateTime) -> String {
+    let mut projects = BTreeMap::<&str, Vec<&Task>>::new();
+    for task in database.tasks_in_dependency_order() {
+        if task.time_completed.is_some() || task.time_archived.is_some() {
+            continue;
+        }
+
+        let mut task_projects = task
+            .tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix('+'))
+            .peekable();
+        if task_projects.peek().is_none() {
+            projects.entry(NO_PROJECT).or_default().push(task);
+        }
+        for project in task_projects {
+            projects.entry(project).or_default().push(task);
+        }
+    }
+
+    // tasks without a project come last
+    let no_project = projects.remove(NO_PROJECT);
+    let projects = projects
+        .into_iter()
+        .chain(no_project.map(|tasks| (NO_PROJECT, tasks)));
+
+    let mut output = String::new();
+    output.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str("<title>Status report</title>\n");
+    writeln!(output, "<style>\n{STYLE}</style>").unwrap();
+    output.push_str("</head>\n<body>\n<h1>Status report</h1>\n");
+    writeln!(
+        output,
+        "<p class=\"generated\">Generated on {}</p>",
+        now.format(DATE_FORMAT).unwrap()
+    )
+    .unwrap();
+
+    for (project, tasks) in projects {
+        writeln!(output, "<h2>{}</h2>", escape_html(project)).unwrap();
+        output.push_str(
+            "<table>\n<tr><th>Task</th><th>Status</th><th>Due</th><th>Waiting on</th></tr>\n",
+        );
+
+        for task in tasks {
+            let (class, status) = match task.status() {
+                TaskStatus::Started => (" class=\"started\"", "In progress"),
+                TaskStatus::Todo | TaskStatus::Completed => ("", "Todo"),
+            };
+            let due = match task.time_due {
+                Some(due) if due < now => format!(
+                    "<span class=\"overdue\">{}</span>",
+                    due.format(DATE_FORMAT).unwrap()
+                ),
+                Some(due) => due.format(DATE_FORMAT).unwrap(),
+                None => String::new(),
+            };
+            let waiting_on = database
+                .get_dependencies(task.id())
+                .filter(|dependency| dependency.time_completed.is_none())
+                .map(|dependency| escape_html(&dependency.title))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(
+                output,
+                "<tr{class}><td>{}</td><td>{status}</td><td>{due}</td><td>{waiting_on}</td></tr>",
+                escape_html(&task.title)
+            )
+            .unwrap();
+        }
+
+        output.push_str("</table>\n");
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    #[test]
+    fn groups_open_tasks_by_project() {
+        let mut db = Database::default();
+        let mut task_1 = Task::create_now("Ship <it>".into());
+        task_1.tags = vec!["+release".into()];
+        task_1.time_due = Some(datetime!(2024-01-01 0:00 UTC));
+        let mut task_2 = Task::create_now("Write code".into());
+        task_2.time_created = task_1.time_created + Duration::seconds(1);
+        task_2.tags = vec!["+release".into(), "+backend".into()];
+        let mut task_3 = Task::create_now("Done already".into());
+        task_3.time_completed = Some(task_3.time_created);
+        let task_4 = Task::create_now("Loose end".into());
+        let (id_1, id_2) = (task_1.id().clone(), task_2.id().clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(task_3);
+        db.add_task(task_4);
+        db.add_dependency(&id_1, &id_2);
+
+        let report = to_html_report(&db, datetime!(2024-02-01 0:00 UTC));
+
+        assert!(report.contains("Generated on 2024-02-01"));
+        assert!(!report.contains("Done already"));
+        assert!(report.contains("Ship &lt;it&gt;"));
+        assert!(report.contains("<span class=\"overdue\">2024-01-01</span>"));
+        assert!(report.contains("<td>Write code</td>"));
+
+        let backend = report.find("<h2>backend</h2>").unwrap();
+        let release = report.find("<h2>release</h2>").unwrap();
+        let no_project = report.find("<h2>No project</h2>").unwrap();
+        assert!(backend < release && release < no_project);
+
+        // dependencies come first within a project
+        let release_section = &report[release..no_project];
+        assert!(
+            release_section.find("Write code").unwrap()
+                < release_section.find("Ship &lt;it&gt;").unwrap()
+        );
+    }
+}
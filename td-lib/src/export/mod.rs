@@ -0,0 +1,94 @@
+//! Renderers that turn a database into human-readable reports.
+
+mod html;
+pub mod taskwarrior;
+
+use std::fmt::Write;
+
+pub use self::html::to_html_report;
+use crate::database::{Database, TaskStatus};
+
+/// Renders all non-archived tasks as a Markdown checklist, ordered so that tasks come after the
+/// tasks they depend on. Completed tasks are checked, started tasks are marked as in progress and
+/// tags are listed inline.
+#[must_use]
+pub fn to_markdown(database: &Database) -> String {
+    let mut output = String::new();
+
+    for task in database.tasks_in_dependency_order() {
+        if task.time_archived.is_some() {
+            continue;
+        }
+
+        let checkbox = match task.status() {
+            TaskStatus::Completed => 'x',
+            TaskStatus::Todo | TaskStatus::Started => ' ',
+        };
+        write!(output, "- [{checkbox}] {}", escape_markdown(&task.title)).unwrap();
+
+        if task.status() == TaskStatus::Started {
+            output.push_str(" *(in progress)*");
+        }
+        for tag in &task.tags {
+            write!(output, " `{tag}`").unwrap();
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Escapes characters that would otherwise be interpreted as inline Markdown formatting.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn markdown_is_dependency_ordered() {
+        let mut db = Database::default();
+        let mut task_1 = Task::create_now("Ship *it*".into());
+        task_1.tags = vec!["release".into()];
+        let mut task_2 = Task::create_now("Write code".into());
+        task_2.time_created = task_1.time_created + Duration::seconds(1);
+        task_2.time_completed = Some(task_2.time_created);
+        let mut task_3 = Task::create_now("Review".into());
+        task_3.time_created = task_1.time_created + Duration::seconds(2);
+        task_3.time_started = Some(task_3.time_created);
+        let mut task_4 = Task::create_now("Old stuff".into());
+        task_4.time_archived = Some(task_4.time_created);
+
+        let (id_1, id_2, id_3) = (
+            task_1.id().clone(),
+            task_2.id().clone(),
+            task_3.id().clone(),
+        );
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_task(task_3);
+        db.add_task(task_4);
+        db.add_dependency(&id_1, &id_3);
+        db.add_dependency(&id_3, &id_2);
+
+        assert_eq!(
+            to_markdown(&db),
+            "- [x] Write code\n\
+             - [ ] Review *(in progress)*\n\
+             - [ ] Ship \\*it\\* `release`\n"
+        );
+    }
+}
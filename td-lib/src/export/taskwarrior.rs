@@ -0,0 +1,88 @@
+//! Export to the json format that can be read by `task import` in
+//! [Taskwarrior](https://taskwarrior.org/).
+
+use uuid::Uuid;
+
+use crate::{
+    database::{Database, Task, TaskStatus},
+    import::taskwarrior::{format_date, Depends, TaskwarriorTask, PROVIDER},
+};
+
+/// The namespace used to derive stable Taskwarrior UUIDs from td task ids.
+const UUID_NAMESPACE: Uuid = Uuid::from_u128(0x4f3c_62a1_8d0e_4b7a_9c55_2e61_d0a8_7b13);
+
+/// Renders all tasks as a json array of Taskwarrior tasks. Tasks that were imported from
+/// Taskwarrior keep their UUID. Other task ids that are not UUIDs, such as ids created by td
+/// itself, are converted into a UUID that is the same on every export.
+#[must_use]
+pub fn to_json(database: &Database) -> String {
+    let tasks = database
+        .get_all_tasks()
+        .map(|task| TaskwarriorTask {
+            uuid: to_uuid(task),
+            description: task.title.clone(),
+            status: match task.status() {
+                TaskStatus::Completed => "completed",
+                TaskStatus::Todo | TaskStatus::Started => "pending",
+            }
+            .to_string(),
+            entry: format_date(task.time_created),
+            start: task.time_started.map(format_date),
+            end: task.time_completed.map(format_date),
+            due: task.time_due.map(format_date),
+            tags: task.tags.iter().map(|t| t.to_string()).collect(),
+            project: task.project.clone(),
+            depends: Depends::List(database.get_dependencies(task.id()).map(to_uuid).collect()),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&tasks).expect("taskwarrior tasks should always serialize")
+}
+
+fn to_uuid(task: &Task) -> String {
+    if let Some(state) = task.sync_state.get(PROVIDER) {
+        return state.remote_id.clone();
+    }
+
+    let id = task.id();
+    Uuid::try_parse(id.as_str())
+        .unwrap_or_else(|_| Uuid::new_v5(&UUID_NAMESPACE, id.as_str().as_bytes()))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::TaskId, import};
+
+    #[test]
+    fn roundtrips_through_import() {
+        let mut db = Database::default();
+        let mut task_1 = Task::create_now("task 1".into());
+        task_1.tags = vec!["work".into()];
+        task_1.time_completed = Some(task_1.time_created);
+        let task_2 = Task::create_now("task 2".into());
+        let (id_1, id_2) = (task_1.id().clone(), task_2.id().clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        db.add_dependency(&id_2, &id_1);
+
+        let json = to_json(&db);
+        assert_eq!(json, to_json(&db), "export should be deterministic");
+
+        let imported = import::taskwarrior::parse(&json).unwrap();
+        let uuid_1 = TaskId::from_raw(to_uuid(&db[&id_1]));
+        let uuid_2 = TaskId::from_raw(to_uuid(&db[&id_2]));
+        assert_eq!(imported[&uuid_1].title, "task 1");
+        assert_eq!(imported[&uuid_1].tags, vec!["work"]);
+        assert_eq!(imported[&uuid_1].status(), TaskStatus::Completed);
+        assert_eq!(imported[&uuid_2].status(), TaskStatus::Todo);
+        assert_eq!(
+            imported.get_dependencies(&uuid_2).next().unwrap().id(),
+            &uuid_1
+        );
+
+        // ids that are already UUIDs are kept as-is
+        assert_eq!(to_json(&imported), json);
+    }
+}
@@ -0,0 +1,4 @@
+//! Parsers that turn tasks from other todo applications into [`Task`](crate::database::Task)s.
+
+pub mod taskwarrior;
+pub mod todotxt;
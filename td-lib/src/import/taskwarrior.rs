@@ -0,0 +1,186 @@
+//! Import of the json format produced by `task export` in
+//! [Taskwarrior](https://taskwarrior.org/).
+//!
+//! The `uuid` of a Taskwarrior task is used as its task id. The `description`, `tags`, `entry`,
+//! `start`, `end`, `due` and `depends` fields are converted to their td equivalents, other fields
+//! are ignored. Deleted tasks are skipped, as are dependencies on tasks that are not part of the
+//! input.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use time::{
+    format_description::FormatItem, macros::format_description, OffsetDateTime, PrimitiveDateTime,
+};
+
+use crate::{
+    database::{Database, Tag, Task, TaskId},
+    errors::ImportError,
+};
+
+/// The name under which the sync state of imported tasks is stored, see [`Task::sync_state`].
+pub const PROVIDER: &str = "taskwarrior";
+
+const DATE_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year][month][day]T[hour][minute][second]Z");
+
+/// A task as it appears in Taskwarrior's json format.
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TaskwarriorTask {
+    pub uuid: String,
+    pub description: String,
+    pub status: String,
+    pub entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Depends::is_empty")]
+    pub depends: Depends,
+}
+
+/// The dependencies of a task. Older versions of Taskwarrior store these as a comma-separated
+/// string, newer ones as a list.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Depends {
+    List(Vec<String>),
+    CommaSeparated(String),
+}
+
+impl Default for Depends {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl Depends {
+    fn is_empty(&self) -> bool {
+        self.uuids().next().is_none()
+    }
+
+    fn uuids(&self) -> Box<dyn Iterator<Item = &str> + '_> {
+        match self {
+            Self::List(list) => Box::new(list.iter().map(String::as_str)),
+            Self::CommaSeparated(string) => {
+                Box::new(string.split(',').map(str::trim).filter(|s| !s.is_empty()))
+            }
+        }
+    }
+}
+
+/// Parses the output of `task export` into a new database.
+pub fn parse(json: &str) -> Result<Database, ImportError> {
+    let tasks: Vec<TaskwarriorTask> = serde_json::from_str(json)?;
+    let tasks = tasks
+        .into_iter()
+        .filter(|task| task.status != "deleted")
+        .collect::<Vec<_>>();
+
+    let now = OffsetDateTime::now_utc();
+    let mut database = Database::default();
+    for tw_task in &tasks {
+        let mut task = Task::create_now(tw_task.description.clone());
+        task.id = TaskId::from_raw(tw_task.uuid.clone());
+        task.time_created = parse_date(&tw_task.entry)?;
+        task.time_started = tw_task.start.as_deref().map(parse_date).transpose()?;
+        task.time_due = tw_task.due.as_deref().map(parse_date).transpose()?;
+        task.tags = tw_task.tags.iter().map(Tag::from).collect();
+        task.project = tw_task.project.clone();
+        if tw_task.status == "completed" {
+            task.time_completed = Some(match &tw_task.end {
+                Some(end) => parse_date(end)?,
+                None => task.time_created,
+            });
+        }
+        task.mark_synced(PROVIDER, tw_task.uuid.clone(), now);
+        database.add_task(task);
+    }
+
+    let known_uuids = tasks
+        .iter()
+        .map(|t| t.uuid.as_str())
+        .collect::<HashSet<_>>();
+    for tw_task in &tasks {
+        let from = TaskId::from_raw(tw_task.uuid.clone());
+        for dependency in tw_task.depends.uuids() {
+            if known_uuids.contains(dependency) {
+                database.add_dependency(&from, &TaskId::from_raw(dependency.to_string()));
+            }
+        }
+    }
+
+    Ok(database)
+}
+
+pub(crate) fn parse_date(date: &str) -> Result<OffsetDateTime, ImportError> {
+    PrimitiveDateTime::parse(date, DATE_FORMAT)
+        .map(PrimitiveDateTime::assume_utc)
+        .map_err(|_| ImportError::InvalidDate(date.to_string()))
+}
+
+pub(crate) fn format_date(date: OffsetDateTime) -> String {
+    date.to_offset(time::UtcOffset::UTC)
+        .format(DATE_FORMAT)
+        .expect("date should be formattable")
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    const EXPORT: &str = r#"[
+        {"uuid": "a0000000-0000-0000-0000-000000000001", "description": "Write code",
+         "status": "completed", "entry": "20240101T100000Z", "end": "20240102T100000Z",
+         "tags": ["work"], "project": "td", "urgency": 1.2},
+        {"uuid": "a0000000-0000-0000-0000-000000000002", "description": "Ship it",
+         "status": "pending", "entry": "20240101T110000Z", "due": "20240110T000000Z",
+         "depends": "a0000000-0000-0000-0000-000000000001,a0000000-0000-0000-0000-000000000003"},
+        {"uuid": "a0000000-0000-0000-0000-000000000003", "description": "Gone",
+         "status": "deleted", "entry": "20240101T120000Z"}
+    ]"#;
+
+    #[test]
+    fn parses_export() {
+        let database = parse(EXPORT).unwrap();
+        assert_eq!(database.get_all_tasks().count(), 2);
+
+        let code_id = TaskId::from_raw("a0000000-0000-0000-0000-000000000001".into());
+        let ship_id = TaskId::from_raw("a0000000-0000-0000-0000-000000000002".into());
+
+        let code = &database[&code_id];
+        assert_eq!(code.title, "Write code");
+        assert_eq!(code.tags, vec!["work"]);
+        assert_eq!(code.project.as_deref(), Some("td"));
+        assert_eq!(code.time_created, datetime!(2024-01-01 10:00 UTC));
+        assert_eq!(code.time_completed, Some(datetime!(2024-01-02 10:00 UTC)));
+        assert_eq!(
+            code.sync_state[PROVIDER].remote_id,
+            "a0000000-0000-0000-0000-000000000001"
+        );
+
+        let ship = &database[&ship_id];
+        assert_eq!(ship.project, None);
+        assert_eq!(ship.time_completed, None);
+        assert_eq!(ship.time_due, Some(datetime!(2024-01-10 0:00 UTC)));
+        let dependencies = database.get_dependencies(&ship_id).collect::<Vec<_>>();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].id(), &code_id);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        let json =
+            r#"[{"uuid": "x", "description": "a", "status": "pending", "entry": "yesterday"}]"#;
+        assert!(matches!(parse(json), Err(ImportError::InvalidDate(_))));
+        assert!(matches!(parse("{"), Err(ImportError::JsonError(_))));
+    }
+}
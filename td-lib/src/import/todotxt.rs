@@ -0,0 +1,129 @@
+//! Import of the [todo.txt](https://github.com/todotxt/todo.txt) format.
+//!
+//! Each non-empty line is converted into a task:
+//! - A leading `x` marks the task as completed, optionally followed by the completion date.
+//! - A priority such as `(A)` is converted to a `P:0` tag, `(B)` to `P:1` and so on.
+//! - The creation date is used as the creation time of the task.
+//! - Projects (`+project`) and contexts (`@context`) are kept as tags, including their prefix.
+//!
+//! All other words, including `key:value` extensions, are kept in the title.
+
+use time::{format_description::FormatItem, macros::format_description, Date, OffsetDateTime};
+
+use crate::database::{Tag, Task};
+
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+/// Parses a todo.txt file into a list of tasks. Lines that do not contain a task are skipped.
+#[must_use]
+pub fn parse(input: &str) -> Vec<Task> {
+    input.lines().filter_map(parse_line).collect()
+}
+
+/// Parses a single todo.txt line into a task, or returns `None` if the line is empty.
+#[must_use]
+pub fn parse_line(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+
+    let completed = words.next_if_eq(&"x").is_some();
+    let priority = words.next_if(|w| parse_priority(w).is_some());
+    let is_date = |w: &&str| parse_date(w).is_some();
+
+    // completed tasks have their completion date first, followed by the creation date
+    let (completion_date, creation_date) = if completed {
+        (words.next_if(is_date), words.next_if(is_date))
+    } else {
+        (None, words.next_if(is_date))
+    };
+
+    let mut title_words = vec![];
+    let mut tags = vec![];
+    if let Some(priority) = priority.and_then(parse_priority) {
+        tags.push(format!("P:{priority}"));
+    }
+    for word in words {
+        if word.len() > 1 && (word.starts_with('+') || word.starts_with('@')) {
+            tags.push(word.to_string());
+        } else {
+            title_words.push(word);
+        }
+    }
+
+    if title_words.is_empty() && tags.is_empty() {
+        return None;
+    }
+
+    let mut task = Task::create_now(title_words.join(" "));
+    task.tags = tags.into_iter().map(Tag::from).collect();
+    if let Some(date) = creation_date.and_then(parse_date) {
+        task.time_created = date;
+    }
+    if completed {
+        task.time_completed = Some(
+            completion_date
+                .and_then(parse_date)
+                .unwrap_or(task.time_created),
+        );
+    }
+
+    Some(task)
+}
+
+/// Parses a priority in the form `(A)` into a number, where `A` is 0.
+fn parse_priority(word: &str) -> Option<u8> {
+    match word.as_bytes() {
+        [b'(', letter @ b'A'..=b'Z', b')'] => Some(letter - b'A'),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<OffsetDateTime> {
+    Date::parse(word, DATE_FORMAT)
+        .ok()
+        .map(|date| date.midnight().assume_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parses_full_line() {
+        let task = parse_line("(A) 2011-03-01 Call Mom +Family @phone due:2011-03-04").unwrap();
+        assert_eq!(task.title, "Call Mom due:2011-03-04");
+        assert_eq!(task.tags, vec!["P:0", "+Family", "@phone"]);
+        assert_eq!(task.time_created, datetime!(2011-03-01 0:00 UTC));
+        assert_eq!(task.time_completed, None);
+    }
+
+    #[test]
+    fn parses_completion() {
+        let task = parse_line("x 2011-03-03 2011-03-01 Review proposal").unwrap();
+        assert_eq!(task.title, "Review proposal");
+        assert_eq!(task.time_created, datetime!(2011-03-01 0:00 UTC));
+        assert_eq!(task.time_completed, Some(datetime!(2011-03-03 0:00 UTC)));
+
+        let task = parse_line("x 2011-03-03 Review proposal").unwrap();
+        assert_eq!(task.time_completed, Some(datetime!(2011-03-03 0:00 UTC)));
+
+        let task = parse_line("x Review proposal").unwrap();
+        assert_eq!(task.time_completed, Some(task.time_created));
+    }
+
+    #[test]
+    fn ignores_markers_in_wrong_position() {
+        let task = parse_line("Buy milk x (B) 2011-03-01").unwrap();
+        assert_eq!(task.title, "Buy milk x (B) 2011-03-01");
+        assert!(task.tags.is_empty());
+        assert_eq!(task.time_completed, None);
+    }
+
+    #[test]
+    fn skips_empty_lines() {
+        let tasks = parse("first\n\n   \nsecond\n");
+        let titles = tasks.iter().map(|t| t.title.as_str()).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["first", "second"]);
+    }
+}
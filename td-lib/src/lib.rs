@@ -7,7 +7,22 @@
 )]
 #![warn(missing_docs, clippy::doc_markdown, clippy::must_use_candidate)]
 
+pub mod audit;
+#[cfg(any(test, feature = "conformance"))]
+pub mod conformance;
 pub mod database;
+pub mod encryption;
 pub mod errors;
+pub mod export;
+pub mod import;
+pub mod links;
+pub mod query;
+pub mod reminders;
+pub mod rollover;
+pub mod sort;
+pub mod stats;
+pub mod storage;
+pub mod sync;
+pub mod timeline;
 
 pub use time;
@@ -0,0 +1,108 @@
+//! Links that point to a task in a specific database, such as `td://3f2a9c1e/k4bW7xnP`.
+
+use std::fmt::Display;
+
+use uuid::Uuid;
+
+use crate::database::{Database, TaskId};
+
+const SCHEME: &str = "td://";
+
+const UUID_NAMESPACE: Uuid = Uuid::from_u128(0x9a1d_5e37_c2f0_4b68_8e14_73b9_06ad_25c1);
+
+/// A link to a task in a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskLink {
+    /// The hash of the database the task is in, see [`database_hash`].
+    pub database: String,
+    /// The task that is linked to.
+    pub task: TaskId,
+}
+
+impl TaskLink {
+    /// Creates a link to a task in the database with the given id, see
+    /// [`Database::get_or_create_database_id`].
+    #[must_use]
+    pub fn new(database_id: &str, task: TaskId) -> Self {
+        Self {
+            database: database_hash(database_id),
+            task,
+        }
+    }
+
+    /// Parses a link in the form `td://<database-hash>/<task-id>`.
+    #[must_use]
+    pub fn parse(link: &str) -> Option<Self> {
+        let (database, task) = link.strip_prefix(SCHEME)?.split_once('/')?;
+        if database.is_empty() || task.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            database: database.to_string(),
+            task: TaskId::from_raw(task.to_string()),
+        })
+    }
+
+    /// Checks if this link points to a task in the given database. Databases that never had a
+    /// link made to them have no id yet, so no link belongs to them.
+    #[must_use]
+    pub fn belongs_to(&self, database: &Database) -> bool {
+        database
+            .database_id()
+            .is_some_and(|id| self.database == database_hash(id))
+    }
+}
+
+impl Display for TaskLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{SCHEME}{}/{}", self.database, self.task.as_str())
+    }
+}
+
+/// Gets a short hash of the id of a database, which identifies it in links. It does not depend on
+/// where the database is stored, so links can be opened on other machines.
+#[must_use]
+pub fn database_hash(database_id: &str) -> String {
+    let uuid = Uuid::new_v5(&UUID_NAMESPACE, database_id.as_bytes());
+    uuid.simple().to_string()[..8].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_through_string() {
+        let mut database = Database::default();
+        let database_id = database.get_or_create_database_id().to_string();
+        let link = TaskLink::new(&database_id, TaskId::from_raw("k4bW7xnP".into()));
+        let text = link.to_string();
+
+        assert!(text.starts_with("td://"));
+        assert!(text.ends_with("/k4bW7xnP"));
+        assert_eq!(TaskLink::parse(&text), Some(link.clone()));
+        assert!(link.belongs_to(&database));
+        assert!(!link.belongs_to(&Database::default()));
+    }
+
+    #[test]
+    fn database_id_is_kept_when_saved() {
+        let mut database = Database::default();
+        let database_id = database.get_or_create_database_id().to_string();
+        assert_eq!(database.get_or_create_database_id(), database_id);
+
+        let json = serde_json::to_string(&database).unwrap();
+        let mut loaded: Database = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_or_create_database_id(), database_id);
+        assert_ne!(Database::default().get_or_create_database_id(), database_id);
+    }
+
+    #[test]
+    fn rejects_invalid_links() {
+        assert_eq!(TaskLink::parse("https://example.com/k4bW7xnP"), None);
+        assert_eq!(TaskLink::parse("td://3f2a9c1e"), None);
+        assert_eq!(TaskLink::parse("td://3f2a9c1e/"), None);
+        assert_eq!(TaskLink::parse("td:///k4bW7xnP"), None);
+    }
+}
@@ -0,0 +1,515 @@
+//! A small query language to search for tasks.
+//!
+//! A query consists of whitespace-separated terms. Terms in the form `key:value` filter on a
+//! specific property of a task, all other terms are free text. A task matches a query if it
+//! matches every term. Values containing whitespace can be wrapped in double quotes.
+//!
+//! Supported filters:
+//! - `tag:<tag>`: the task has the given tag.
+//! - `assignee:<name>`: the task is assigned to the given person.
+//! - `status:<todo|started|done>`: the task has the given status. Prefixes such as `s` are
+//!   accepted.
+//! - `id:<id>`: the task id starts with the given value.
+//! - `before:<YYYY-MM-DD>` and `after:<YYYY-MM-DD>`: the task was created before or after the
+//!   given day.
+//! - `on:<YYYY-MM-DD>`: the task is due or was completed on the given day.
+//! - `include:<completed|archived|someday|snoozed>`: also search the given tasks, which are
+//!   usually hidden.
+//! - `in:<completed|archived|someday|snoozed>`: only search the given tasks, which are usually
+//!   hidden.
+//!
+//! Free text is matched case-insensitively against the title, description, tags and id of a task.
+
+use time::{format_description::FormatItem, macros::format_description, Date, OffsetDateTime};
+
+use crate::{
+    database::{Database, Task, TaskStatus},
+    errors::QueryParseError,
+};
+
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+/// A parsed search query that can be matched against tasks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskQuery {
+    terms: Vec<QueryTerm>,
+}
+
+/// A single term in a [`TaskQuery`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryTerm {
+    /// The task must have this tag.
+    Tag(String),
+    /// The task must be assigned to this person.
+    Assignee(String),
+    /// The task must have this status.
+    Status(TaskStatus),
+    /// The task id must start with this value.
+    Id(String),
+    /// The task must be created before this day.
+    Before(Date),
+    /// The task must be created on or after this day.
+    After(Date),
+    /// The task must be due or completed on this day.
+    On(Date),
+    /// The title, the description, a tag or the id of the task must contain this text.
+    Text(String),
+    /// The search also covers these hidden tasks. This matches every task.
+    Include(HiddenTasks),
+    /// The task must be one of these hidden tasks.
+    In(HiddenTasks),
+}
+
+/// A group of tasks that is usually hidden, but can be searched with `include:` and `in:`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenTasks {
+    /// Tasks that are completed.
+    Completed,
+    /// Tasks that are archived.
+    Archived,
+    /// Tasks in the someday/maybe bucket.
+    Someday,
+    /// Tasks that are snoozed until later.
+    Snoozed,
+}
+
+impl HiddenTasks {
+    /// Checks if the given task is in this group.
+    #[must_use]
+    pub fn contains(self, task: &Task) -> bool {
+        match self {
+            Self::Completed => task.time_completed.is_some(),
+            Self::Archived => task.time_archived.is_some(),
+            Self::Someday => task.is_someday(),
+            Self::Snoozed => task.is_snoozed(OffsetDateTime::now_utc()),
+        }
+    }
+}
+
+impl TaskQuery {
+    /// Parses a query string.
+    pub fn parse(input: &str) -> Result<Self, QueryParseError> {
+        let terms = tokenize(input)?
+            .into_iter()
+            .filter_map(|token| parse_term(&token).transpose())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { terms })
+    }
+
+    /// Gets the terms in this query.
+    #[must_use]
+    pub fn terms(&self) -> &[QueryTerm] {
+        &self.terms
+    }
+
+    /// Returns `true` if this query does not contain any terms and thus matches every task.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Checks if this query searches the given hidden tasks, with `include:` or `in:`.
+    #[must_use]
+    pub fn includes(&self, hidden: HiddenTasks) -> bool {
+        self.terms.iter().any(|term| match term {
+            QueryTerm::Include(included) | QueryTerm::In(included) => *included == hidden,
+            _ => false,
+        })
+    }
+
+    /// Gets all free text terms in this query, joined by spaces.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.terms
+            .iter()
+            .filter_map(|term| match term {
+                QueryTerm::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Gets the tasks in the database that may match the filters of this query, using the indexes
+    /// of the database so not every task has to be checked. Returns `None` if any task may match.
+    #[must_use]
+    pub fn candidates<'db>(&self, database: &'db Database) -> Option<Vec<&'db Task>> {
+        self.terms.iter().find_map(|term| match term {
+            QueryTerm::Tag(tag) => Some(database.tasks_with_tag(tag)),
+            _ => None,
+        })
+    }
+
+    /// Checks if every task that matches this query also matches `previous`. This is the case when
+    /// the terms of `previous` were only extended or new terms were added after them, such as while
+    /// a query is being typed. The results of `previous` can then be filtered instead of searching
+    /// all tasks again.
+    #[must_use]
+    pub fn narrows(&self, previous: &Self) -> bool {
+        let (texts, filters) = self.split_terms();
+        let (previous_texts, previous_filters) = previous.split_terms();
+
+        texts.len() >= previous_texts.len()
+            && filters.len() >= previous_filters.len()
+            && previous_texts
+                .iter()
+                .zip(&texts)
+                .chain(previous_filters.iter().zip(&filters))
+                .all(|(previous, term)| term.narrows(previous))
+    }
+
+    /// Splits the terms of this query into free text terms and filters.
+    fn split_terms(&self) -> (Vec<&QueryTerm>, Vec<&QueryTerm>) {
+        self.terms
+            .iter()
+            .partition(|term| matches!(term, QueryTerm::Text(_)))
+    }
+
+    /// Checks if the given task matches all terms in this query.
+    #[must_use]
+    pub fn matches(&self, task: &Task) -> bool {
+        self.matches_filters(task) && self.matches_text(task)
+    }
+
+    /// Checks if the given task matches all terms in this query, except for the free text terms.
+    #[must_use]
+    pub fn matches_filters(&self, task: &Task) -> bool {
+        self.terms
+            .iter()
+            .filter(|term| !matches!(term, QueryTerm::Text(_)))
+            .all(|term| term.matches(task))
+    }
+
+    /// Checks if the given task matches all free text terms in this query.
+    #[must_use]
+    pub fn matches_text(&self, task: &Task) -> bool {
+        self.terms
+            .iter()
+            .filter(|term| matches!(term, QueryTerm::Text(_)))
+            .all(|term| term.matches(task))
+    }
+}
+
+impl QueryTerm {
+    /// Checks if the given task matches this term.
+    #[must_use]
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Self::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Self::Assignee(name) => task
+                .assignee
+                .as_ref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(name)),
+            Self::Status(status) => task.status() == *status,
+            Self::Id(id) => task.id().as_str().starts_with(id.as_str()),
+            Self::Before(date) => task.time_created.date() < *date,
+            Self::After(date) => task.time_created.date() >= *date,
+            Self::On(date) => [task.time_due, task.time_completed]
+                .into_iter()
+                .flatten()
+                .any(|time| time.date() == *date),
+            Self::Include(_) => true,
+            Self::In(hidden) => hidden.contains(task),
+            Self::Text(text) => {
+                let text = text.to_lowercase();
+                task.title.to_lowercase().contains(&text)
+                    || task.description.to_lowercase().contains(&text)
+                    || task.tags.iter().any(|t| t.to_lowercase().contains(&text))
+                    || task.id().as_str().to_lowercase().starts_with(&text)
+            }
+        }
+    }
+}
+
+impl QueryTerm {
+    /// Checks if every task that matches this term also matches `previous`.
+    fn narrows(&self, previous: &Self) -> bool {
+        match (self, previous) {
+            // free text can match the start of an id, so it can only be extended at the end
+            (Self::Text(text), Self::Text(previous)) => {
+                text.to_lowercase().starts_with(&previous.to_lowercase())
+            }
+            (Self::Id(id), Self::Id(previous)) => id.starts_with(previous.as_str()),
+            (term, previous) => term == previous,
+        }
+    }
+}
+
+/// Splits the input on whitespace, keeping quoted sections together and removing the quotes.
+fn tokenize(input: &str) -> Result<Vec<String>, QueryParseError> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(QueryParseError::UnterminatedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a single token. Filters without a value are ignored, so incomplete queries such as
+/// `tag:` still match while they are being typed.
+fn parse_term(token: &str) -> Result<Option<QueryTerm>, QueryParseError> {
+    let Some((key, value)) = token.split_once(':') else {return Ok(Some(QueryTerm::Text(token.to_string())));};
+
+    let is_filter = matches!(
+        key,
+        "tag" | "assignee" | "status" | "id" | "before" | "after" | "on" | "include" | "in"
+    );
+    if is_filter && value.is_empty() {
+        return Ok(None);
+    }
+
+    let term = match key {
+        "tag" => QueryTerm::Tag(value.to_string()),
+        "assignee" => QueryTerm::Assignee(value.to_string()),
+        "status" => QueryTerm::Status(parse_status(value)?),
+        "id" => QueryTerm::Id(value.to_string()),
+        "before" => QueryTerm::Before(parse_date(value)?),
+        "after" => QueryTerm::After(parse_date(value)?),
+        "on" => QueryTerm::On(parse_date(value)?),
+        "include" => QueryTerm::Include(parse_hidden_tasks(value)?),
+        "in" => QueryTerm::In(parse_hidden_tasks(value)?),
+        _ => QueryTerm::Text(token.to_string()),
+    };
+
+    Ok(Some(term))
+}
+
+fn parse_status(value: &str) -> Result<TaskStatus, QueryParseError> {
+    let value = value.to_lowercase();
+    [
+        ("todo", TaskStatus::Todo),
+        ("started", TaskStatus::Started),
+        ("done", TaskStatus::Completed),
+        ("completed", TaskStatus::Completed),
+    ]
+    .into_iter()
+    .find(|(name, _)| name.starts_with(&value))
+    .map(|(_, status)| status)
+    .ok_or(QueryParseError::UnknownStatus(value))
+}
+
+fn parse_hidden_tasks(value: &str) -> Result<HiddenTasks, QueryParseError> {
+    match value.to_lowercase().as_str() {
+        "completed" | "done" => Ok(HiddenTasks::Completed),
+        "archived" | "archive" => Ok(HiddenTasks::Archived),
+        "someday" | "maybe" => Ok(HiddenTasks::Someday),
+        "snoozed" => Ok(HiddenTasks::Snoozed),
+        _ => Err(QueryParseError::UnknownHiddenTasks(value.to_string())),
+    }
+}
+
+fn parse_date(value: &str) -> Result<Date, QueryParseError> {
+    Date::parse(value, DATE_FORMAT).map_err(|_| QueryParseError::InvalidDate(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Month, OffsetDateTime};
+
+    use super::*;
+
+    fn task(title: &str, tags: &[&str]) -> Task {
+        let mut task = Task::create_now(title.to_string());
+        task.tags = tags.iter().map(|&t| t.into()).collect();
+        task
+    }
+
+    #[test]
+    fn parses_terms() {
+        let query = TaskQuery::parse("tag:work status:s before:2024-01-01 foo").unwrap();
+        assert_eq!(
+            query.terms(),
+            &[
+                QueryTerm::Tag("work".into()),
+                QueryTerm::Status(TaskStatus::Started),
+                QueryTerm::Before(Date::from_calendar_date(2024, Month::January, 1).unwrap()),
+                QueryTerm::Text("foo".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_quoted_values() {
+        let query = TaskQuery::parse(r#"tag:"two words" "free text""#).unwrap();
+        assert_eq!(
+            query.terms(),
+            &[
+                QueryTerm::Tag("two words".into()),
+                QueryTerm::Text("free text".into())
+            ]
+        );
+        assert_eq!(query.text(), "free text");
+    }
+
+    #[test]
+    fn unknown_keys_are_text() {
+        let query = TaskQuery::parse("P:1").unwrap();
+        assert_eq!(query.terms(), &[QueryTerm::Text("P:1".into())]);
+    }
+
+    #[test]
+    fn incomplete_filters_are_ignored() {
+        assert!(TaskQuery::parse("tag: status:").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            TaskQuery::parse("status:nope"),
+            Err(QueryParseError::UnknownStatus("nope".into()))
+        );
+        assert_eq!(
+            TaskQuery::parse("after:2024-13"),
+            Err(QueryParseError::InvalidDate("2024-13".into()))
+        );
+        assert_eq!(
+            TaskQuery::parse(r#"tag:"oops"#),
+            Err(QueryParseError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn matches_tasks() {
+        let mut started = task("Fix the login bug", &["work", "P:1"]);
+        started.time_started = Some(OffsetDateTime::now_utc());
+        let todo = task("Buy milk", &["home"]);
+
+        let query = TaskQuery::parse("tag:work status:started login").unwrap();
+        assert!(query.matches(&started));
+        assert!(!query.matches(&todo));
+
+        let query = TaskQuery::parse("milk").unwrap();
+        assert!(!query.matches(&started));
+        assert!(query.matches(&todo));
+
+        // free text also searches tags and ids
+        assert!(TaskQuery::parse("p:1").unwrap().matches(&started));
+        let id = todo.id().as_str()[..4].to_string();
+        assert!(TaskQuery::parse(&id).unwrap().matches(&todo));
+        assert!(TaskQuery::parse(&format!("id:{id}"))
+            .unwrap()
+            .matches(&todo));
+    }
+
+    #[test]
+    fn matches_descriptions() {
+        let mut task = task("Buy milk", &[]);
+        task.description = "Get the Oat milk from the corner shop".into();
+
+        assert!(TaskQuery::parse("oat").unwrap().matches(&task));
+        assert!(TaskQuery::parse("\"corner shop\"").unwrap().matches(&task));
+        assert!(!TaskQuery::parse("bakery").unwrap().matches(&task));
+    }
+
+    #[test]
+    fn matches_assignees() {
+        let mut assigned = task("Book the flights", &[]);
+        assigned.assignee = Some("Alex".into());
+        let unassigned = task("Pack", &[]);
+
+        let query = TaskQuery::parse("assignee:alex").unwrap();
+        assert_eq!(query.terms(), &[QueryTerm::Assignee("alex".into())]);
+        assert!(query.matches(&assigned));
+        assert!(!query.matches(&unassigned));
+        assert!(!TaskQuery::parse("assignee:sam").unwrap().matches(&assigned));
+    }
+
+    #[test]
+    fn searches_hidden_tasks() {
+        let mut archived = task("old", &[]);
+        archived.time_archived = Some(OffsetDateTime::now_utc());
+        let todo = task("new", &[]);
+
+        let query = TaskQuery::parse("include:archived").unwrap();
+        assert!(query.includes(HiddenTasks::Archived));
+        assert!(!query.includes(HiddenTasks::Completed));
+        assert!(query.matches(&archived) && query.matches(&todo));
+
+        let query = TaskQuery::parse("in:archived").unwrap();
+        assert!(query.includes(HiddenTasks::Archived));
+        assert!(query.matches(&archived) && !query.matches(&todo));
+
+        let mut snoozed = task("later", &[]);
+        snoozed.snoozed_until = Some(OffsetDateTime::now_utc() + time::Duration::days(1));
+        let query = TaskQuery::parse("in:snoozed").unwrap();
+        assert!(query.matches(&snoozed) && !query.matches(&todo));
+
+        assert_eq!(
+            TaskQuery::parse("in:trash"),
+            Err(QueryParseError::UnknownHiddenTasks("trash".into()))
+        );
+    }
+
+    #[test]
+    fn narrows_extended_queries() {
+        let narrows = |query: &str, previous: &str| {
+            TaskQuery::parse(query)
+                .unwrap()
+                .narrows(&TaskQuery::parse(previous).unwrap())
+        };
+
+        assert!(narrows("fo", ""));
+        assert!(narrows("foo", "fo"));
+        assert!(narrows("Foo bar", "foo"));
+        assert!(narrows("tag:work foo", "foo"));
+        assert!(narrows("id:abc", "id:ab"));
+        assert!(narrows("foo", "foo"));
+
+        assert!(!narrows("fo", "foo"));
+        assert!(!narrows("xfoo", "foo"));
+        assert!(!narrows("tag:wo", "tag:w"));
+        assert!(!narrows("status:d", "status:s"));
+    }
+
+    #[test]
+    fn matches_dates() {
+        let task = task("task", &[]);
+        let today = task.time_created.date();
+        let tomorrow = today.next_day().unwrap();
+
+        let format = |date: Date| date.format(DATE_FORMAT).unwrap();
+        assert!(TaskQuery::parse(&format!("before:{}", format(tomorrow)))
+            .unwrap()
+            .matches(&task));
+        assert!(!TaskQuery::parse(&format!("before:{}", format(today)))
+            .unwrap()
+            .matches(&task));
+        assert!(TaskQuery::parse(&format!("after:{}", format(today)))
+            .unwrap()
+            .matches(&task));
+    }
+
+    #[test]
+    fn matches_due_and_completion_days() {
+        let mut task = task("task", &[]);
+        let today = task.time_created.date();
+        let tomorrow = today.next_day().unwrap();
+        let on = |date: Date| TaskQuery::parse(&format!("on:{date}")).unwrap();
+
+        assert!(!on(today).matches(&task));
+        task.time_due = Some(task.time_created + time::Duration::days(1));
+        task.time_completed = Some(task.time_created);
+        assert!(on(today).matches(&task));
+        assert!(on(tomorrow).matches(&task));
+        assert!(!on(tomorrow.next_day().unwrap()).matches(&task));
+    }
+}
@@ -0,0 +1,264 @@
+//! Reminders that can be attached to tasks, separate from their due date, and notices for tasks
+//! that become due.
+
+use std::collections::BTreeMap;
+
+use time::{
+    format_description::FormatItem, macros::format_description, Duration, OffsetDateTime,
+    PrimitiveDateTime, Time,
+};
+
+use crate::database::{Database, TaskId};
+
+const DATE_TIME_FORMAT: &[FormatItem<'static>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]");
+
+/// A reminder that has gone off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueReminder {
+    /// The task the reminder belongs to.
+    pub task: TaskId,
+    /// When the reminder went off.
+    pub time: OffsetDateTime,
+}
+
+/// Gets the reminders of uncompleted tasks that went off after `since`, up to and including
+/// `now`, ordered by time.
+#[must_use]
+pub fn due_reminders(
+    database: &Database,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+) -> Vec<DueReminder> {
+    let mut due = database
+        .get_all_tasks()
+        .filter(|t| t.time_completed.is_none() && t.time_archived.is_none())
+        .flat_map(|t| {
+            t.reminders
+                .iter()
+                .filter(|time| **time > since && **time <= now)
+                .map(|time| DueReminder {
+                    task: t.id().clone(),
+                    time: *time,
+                })
+        })
+        .collect::<Vec<_>>();
+    due.sort_by_key(|r| r.time);
+    due
+}
+
+/// How long before its due time a task is announced as due soon.
+pub const DUE_SOON: Duration = Duration::hours(1);
+
+/// How close a task is to its due time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueState {
+    /// The task is due within [`DUE_SOON`].
+    DueSoon,
+    /// The due time of the task has passed.
+    Overdue,
+}
+
+/// A task that became due soon or overdue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueNotice {
+    /// The task that became due.
+    pub task: TaskId,
+    /// Whether it is due soon or overdue.
+    pub state: DueState,
+}
+
+/// Gets the uncompleted tasks that became due soon or overdue after `since`, up to and including
+/// `now`.
+///
+/// Tasks in `snoozed` are left out until the time they are snoozed until. If that time passes
+/// between `since` and `now`, they are announced again if they are still due.
+#[must_use]
+pub fn due_notices(
+    database: &Database,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+    snoozed: &BTreeMap<TaskId, OffsetDateTime>,
+) -> Vec<DueNotice> {
+    let passed = |time: OffsetDateTime| time > since && time <= now;
+
+    let mut notices = database
+        .get_all_tasks()
+        .filter(|t| t.time_completed.is_none() && t.time_archived.is_none())
+        .filter_map(|t| {
+            let due = t.time_due?;
+            let snoozed_until = snoozed.get(t.id()).copied();
+            if snoozed_until.is_some_and(|until| until > now) {
+                return None;
+            }
+
+            let state = match due <= now {
+                true => DueState::Overdue,
+                false if due - DUE_SOON <= now => DueState::DueSoon,
+                false => return None,
+            };
+            let changed = match state {
+                DueState::Overdue => passed(due),
+                DueState::DueSoon => passed(due - DUE_SOON),
+            };
+            (changed || snoozed_until.is_some_and(passed)).then(|| DueNotice {
+                task: t.id().clone(),
+                state,
+            })
+        })
+        .collect::<Vec<_>>();
+    notices.sort_by_key(|n| database[&n.task].time_due);
+    notices
+}
+
+/// Parses a reminder time entered by a user. This is either a time relative to `now` such as
+/// `+30m`, `+2h` or `+1d`, or a time in the format `YYYY-MM-DD HH:MM` in the offset of `now`.
+#[must_use]
+pub fn parse_time(input: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let input = input.trim();
+
+    if let Some(relative) = input.strip_prefix('+') {
+        let unit_index = relative.find(|c: char| !c.is_ascii_digit())?;
+        let (amount, unit) = relative.split_at(unit_index);
+        let amount = amount.parse::<i64>().ok()?;
+        let duration = match unit {
+            "m" => Duration::minutes(amount),
+            "h" => Duration::hours(amount),
+            "d" => Duration::days(amount),
+            "w" => Duration::weeks(amount),
+            _ => return None,
+        };
+        return now.checked_add(duration);
+    }
+
+    PrimitiveDateTime::parse(input, DATE_TIME_FORMAT)
+        .ok()
+        .map(|time| time.assume_offset(now.offset()))
+}
+
+/// Gets the start of the day after `now`, in the offset of `now`. Used to snooze tasks until
+/// tomorrow.
+#[must_use]
+pub fn tomorrow(now: OffsetDateTime) -> OffsetDateTime {
+    now.replace_time(Time::MIDNIGHT) + Duration::days(1)
+}
+
+/// Gets the start of the next week, which is the first Monday after `now`, in the offset of `now`.
+/// Used to snooze tasks until next week.
+#[must_use]
+pub fn next_week(now: OffsetDateTime) -> OffsetDateTime {
+    let days_left = 7 - i64::from(now.weekday().number_days_from_monday());
+    now.replace_time(Time::MIDNIGHT) + Duration::days(days_left)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn parses_relative_and_absolute_times() {
+        let now = datetime!(2024-05-01 12:00 +2);
+        assert_eq!(
+            parse_time("+30m", now),
+            Some(datetime!(2024-05-01 12:30 +2))
+        );
+        assert_eq!(
+            parse_time(" +2d ", now),
+            Some(datetime!(2024-05-03 12:00 +2))
+        );
+        assert_eq!(
+            parse_time("2024-06-01 09:15", now),
+            Some(datetime!(2024-06-01 09:15 +2))
+        );
+
+        assert_eq!(parse_time("+", now), None);
+        assert_eq!(parse_time("+5y", now), None);
+        assert_eq!(parse_time("tomorrow", now), None);
+    }
+
+    #[test]
+    fn finds_snooze_presets() {
+        // a wednesday
+        let now = datetime!(2024-05-01 12:30 +2);
+        assert_eq!(tomorrow(now), datetime!(2024-05-02 00:00 +2));
+        assert_eq!(next_week(now), datetime!(2024-05-06 00:00 +2));
+
+        // on a monday, next week is a week later
+        let monday = datetime!(2024-05-06 08:00 +2);
+        assert_eq!(next_week(monday), datetime!(2024-05-13 00:00 +2));
+    }
+
+    #[test]
+    fn finds_reminders_in_window() {
+        let mut db = Database::default();
+        let mut task_open = Task::create_now("open".into());
+        let mut task_done = Task::create_now("done".into());
+        task_open.reminders = vec![
+            datetime!(2024-05-01 09:00 UTC),
+            datetime!(2024-05-01 11:00 UTC),
+            datetime!(2024-05-01 13:00 UTC),
+        ];
+        task_done.reminders = vec![datetime!(2024-05-01 10:00 UTC)];
+        task_done.time_completed = Some(task_done.time_created);
+        let id_open = task_open.id().clone();
+        db.add_task(task_open);
+        db.add_task(task_done);
+
+        let due = due_reminders(
+            &db,
+            datetime!(2024-05-01 09:00 UTC),
+            datetime!(2024-05-01 12:00 UTC),
+        );
+        assert_eq!(
+            due,
+            vec![DueReminder {
+                task: id_open,
+                time: datetime!(2024-05-01 11:00 UTC),
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_tasks_that_became_due() {
+        let mut db = Database::default();
+        let mut add_task = |title: &str, due| {
+            let mut task = Task::create_now(title.into());
+            task.time_due = Some(due);
+            let id = task.id().clone();
+            db.add_task(task);
+            id
+        };
+        let overdue = add_task("overdue", datetime!(2024-05-01 11:30 UTC));
+        let due_soon = add_task("due soon", datetime!(2024-05-01 12:30 UTC));
+        let snoozed = add_task("snoozed", datetime!(2024-05-01 11:45 UTC));
+        let woke_up = add_task("woke up", datetime!(2024-05-01 08:00 UTC));
+        add_task("earlier", datetime!(2024-05-01 10:00 UTC));
+        add_task("later", datetime!(2024-05-01 14:00 UTC));
+
+        let snoozes = BTreeMap::from([
+            (snoozed.clone(), datetime!(2024-05-01 13:00 UTC)),
+            (woke_up.clone(), datetime!(2024-05-01 11:50 UTC)),
+        ]);
+        let notices = due_notices(
+            &db,
+            datetime!(2024-05-01 11:00 UTC),
+            datetime!(2024-05-01 12:00 UTC),
+            &snoozes,
+        );
+        let notice = |task: &TaskId, state| DueNotice {
+            task: task.clone(),
+            state,
+        };
+        assert_eq!(
+            notices,
+            [
+                notice(&woke_up, DueState::Overdue),
+                notice(&overdue, DueState::Overdue),
+                notice(&due_soon, DueState::DueSoon),
+            ]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Actions that run once per day, the first time td is opened on a new day.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::database::{Database, TaskId};
+
+/// Configures which actions are run by [`rollover`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RolloverOptions {
+    /// Report the open tasks that have become due since the last rollover.
+    pub surface_due: bool,
+    /// Find the tasks whose snooze has ended, so their snooze can be cleared with
+    /// [`RolloverReport::clear_snoozes`].
+    pub clear_ended_snoozes: bool,
+}
+
+impl Default for RolloverOptions {
+    fn default() -> Self {
+        Self {
+            surface_due: true,
+            clear_ended_snoozes: true,
+        }
+    }
+}
+
+/// The results of a [`rollover`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RolloverReport {
+    /// Open tasks that have become due since the last rollover, ordered by due date.
+    pub newly_due: Vec<TaskId>,
+    /// Tasks that are snoozed until a time that has passed, ordered by that time.
+    pub unsnoozed: Vec<TaskId>,
+}
+
+impl RolloverReport {
+    /// Checks whether the rollover found nothing to report.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.newly_due.is_empty() && self.unsnoozed.is_empty()
+    }
+
+    /// Clears the snooze of the tasks in [`Self::unsnoozed`], so they don't keep a time that has
+    /// passed.
+    pub fn clear_snoozes(&self, database: &mut Database) {
+        for task_id in &self.unsnoozed {
+            if database.get_task(task_id).is_some() {
+                database[task_id].snoozed_until = None;
+            }
+        }
+    }
+}
+
+/// Checks whether `now` is on a later day than the last rollover, in the offset of `now`.
+#[must_use]
+pub fn should_run(last_run: Option<OffsetDateTime>, now: OffsetDateTime) -> bool {
+    last_run.is_none_or(|last_run| last_run.to_offset(now.offset()).date() < now.date())
+}
+
+/// Runs the rollover actions enabled in `options`. `last_run` is the time of the previous
+/// rollover, if any.
+#[must_use]
+pub fn rollover(
+    database: &Database,
+    last_run: Option<OffsetDateTime>,
+    now: OffsetDateTime,
+    options: &RolloverOptions,
+) -> RolloverReport {
+    let mut report = RolloverReport::default();
+
+    if options.surface_due {
+        let end_of_day = |time: OffsetDateTime| {
+            let time = time.to_offset(now.offset());
+            time.replace_time(time::Time::MIDNIGHT) + time::Duration::DAY
+        };
+        let today_end = end_of_day(now);
+        let last_run_end = last_run.map(end_of_day);
+
+        let mut newly_due = database
+            .get_all_tasks()
+            .filter(|t| t.time_completed.is_none() && t.time_archived.is_none())
+            .filter_map(|t| t.time_due.map(|due| (due, t.id())))
+            .filter(|(due, _)| *due < today_end && last_run_end.is_none_or(|end| *due >= end))
+            .collect::<Vec<_>>();
+        newly_due.sort_by_key(|(due, _)| *due);
+
+        report.newly_due = newly_due.into_iter().map(|(_, id)| id.clone()).collect();
+    }
+
+    if options.clear_ended_snoozes {
+        let mut unsnoozed = database
+            .get_all_tasks()
+            .filter_map(|t| t.snoozed_until.map(|until| (until, t.id())))
+            .filter(|(until, _)| *until <= now)
+            .collect::<Vec<_>>();
+        unsnoozed.sort_by_key(|(until, _)| *until);
+
+        report.unsnoozed = unsnoozed.into_iter().map(|(_, id)| id.clone()).collect();
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn runs_once_per_day() {
+        let now = datetime!(2024-03-10 09:00 +1);
+        assert!(should_run(None, now));
+        assert!(should_run(Some(now - Duration::DAY), now));
+        assert!(!should_run(Some(now - Duration::hours(8)), now));
+        // 23:30 the previous day in the local offset, even though it is the same day in utc
+        assert!(should_run(Some(datetime!(2024-03-09 22:30 UTC)), now));
+    }
+
+    #[test]
+    fn surfaces_tasks_due_since_last_run() {
+        let now = datetime!(2024-03-10 09:00 UTC);
+        let mut db = Database::default();
+        let mut ids = vec![];
+        for (title, due) in [
+            ("overdue", now - Duration::days(5)),
+            ("due yesterday", now - Duration::DAY),
+            ("due tonight", now + Duration::hours(12)),
+            ("due tomorrow", now + Duration::DAY),
+        ] {
+            let mut task = Task::create_now(title.into());
+            task.time_due = Some(due);
+            ids.push(task.id().clone());
+            db.add_task(task);
+        }
+        let mut completed = Task::create_now("completed".into());
+        completed.time_due = Some(now);
+        completed.time_completed = Some(now);
+        db.add_task(completed);
+
+        let options = RolloverOptions::default();
+        let report = rollover(&db, Some(now - Duration::days(2)), now, &options);
+        assert_eq!(report.newly_due, vec![ids[1].clone(), ids[2].clone()]);
+        assert_eq!(report.unsnoozed, vec![]);
+
+        let report = rollover(&db, None, now, &options);
+        assert_eq!(report.newly_due, ids[..3].to_vec());
+
+        let options = RolloverOptions {
+            surface_due: false,
+            ..RolloverOptions::default()
+        };
+        let report = rollover(&db, None, now, &options);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn clears_snoozes_that_ended() {
+        let now = datetime!(2024-03-10 09:00 UTC);
+        let mut db = Database::default();
+        let mut ids = vec![];
+        for until in [now - Duration::hours(1), now + Duration::hours(1)] {
+            let mut task = Task::create_now("snoozed".into());
+            task.snoozed_until = Some(until);
+            ids.push(task.id().clone());
+            db.add_task(task);
+        }
+
+        let options = RolloverOptions {
+            clear_ended_snoozes: false,
+            ..RolloverOptions::default()
+        };
+        assert!(rollover(&db, None, now, &options).is_empty());
+
+        let report = rollover(&db, None, now, &RolloverOptions::default());
+        assert_eq!(report.unsnoozed, vec![ids[0].clone()]);
+        report.clear_snoozes(&mut db);
+        assert_eq!(db[&ids[0]].snoozed_until, None);
+        assert_eq!(db[&ids[1]].snoozed_until, Some(now + Duration::hours(1)));
+    }
+}
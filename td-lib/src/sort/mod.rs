@@ -0,0 +1,185 @@
+//! Orders in which lists of tasks can be shown.
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{Database, Task, TaskId};
+
+/// What a list of tasks is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    /// Oldest tasks first.
+    #[default]
+    Created,
+    /// Alphabetically by title, ignoring case.
+    Title,
+    /// Highest priority first, as given by a `P:<n>` tag where `P:1` is the highest. Tasks
+    /// without a priority come last.
+    Priority,
+    /// Earliest due date first. Tasks without a due date come last.
+    Due,
+    /// Tasks that the most other tasks depend on first.
+    Dependents,
+    /// Dependencies before the tasks that depend on them.
+    Topological,
+    /// Actionable tasks first, with the tasks that the most open tasks directly or indirectly
+    /// depend on at the top. This is a good order to work on tasks in.
+    Blocking,
+}
+
+impl SortMode {
+    /// All sort modes, in the order they are cycled through.
+    pub const ALL: [Self; 7] = [
+        Self::Created,
+        Self::Title,
+        Self::Priority,
+        Self::Due,
+        Self::Dependents,
+        Self::Topological,
+        Self::Blocking,
+    ];
+
+    /// Gets the name of this sort mode, as shown to the user.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Title => "title",
+            Self::Priority => "priority",
+            Self::Due => "due",
+            Self::Dependents => "dependents",
+            Self::Topological => "topological",
+            Self::Blocking => "blocking",
+        }
+    }
+
+    /// Gets the sort mode with the given name.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Gets the sort mode that comes after this one in [`Self::ALL`].
+    #[must_use]
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Sorts the given tasks of the database. Tasks that are equal for this sort mode keep their
+    /// order.
+    pub fn sort(self, database: &Database, tasks: &mut [&Task]) {
+        match self {
+            Self::Created => tasks.sort_by_key(|t| t.time_created),
+            Self::Title => tasks.sort_by_cached_key(|t| t.title.to_lowercase()),
+            Self::Priority => tasks.sort_by_cached_key(|t| (priority(t).is_none(), priority(t))),
+            Self::Due => tasks.sort_by_key(|t| (t.time_due.is_none(), t.time_due)),
+            Self::Dependents => tasks
+                .sort_by_cached_key(|t| Reverse(database.get_inverse_dependencies(t.id()).count())),
+            Self::Topological => {
+                let mut depths = HashMap::new();
+                tasks.sort_by_cached_key(|t| dependency_depth(database, t.id(), &mut depths));
+            }
+            Self::Blocking => {
+                let scores = database.blocking_scores();
+                tasks.sort_by_cached_key(|t| {
+                    let score = scores.get(t.id()).copied().unwrap_or_default();
+                    (!database.is_actionable(t.id()), Reverse(score))
+                });
+            }
+        }
+    }
+}
+
+/// Gets the priority of a task from its `P:<n>` tag, if it has one.
+fn priority(task: &Task) -> Option<u32> {
+    task.tags
+        .iter()
+        .filter_map(|tag| tag.strip_prefix("P:")?.parse().ok())
+        .min()
+}
+
+/// Gets the length of the longest chain of dependencies below a task. A task always has a larger
+/// depth than all of its dependencies, so sorting by depth puts dependencies first.
+fn dependency_depth(
+    database: &Database,
+    task_id: &TaskId,
+    depths: &mut HashMap<TaskId, usize>,
+) -> usize {
+    if let Some(depth) = depths.get(task_id) {
+        return *depth;
+    }
+
+    // mark the task as visited first, so a cycle can't recurse forever
+    depths.insert(task_id.clone(), 0);
+    let dependencies = database
+        .get_dependencies(task_id)
+        .map(|t| t.id().clone())
+        .collect::<Vec<_>>();
+    let depth = dependencies
+        .iter()
+        .map(|id| dependency_depth(database, id, depths) + 1)
+        .max()
+        .unwrap_or(0);
+    depths.insert(task_id.clone(), depth);
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+
+    fn titles(database: &Database, mode: SortMode) -> Vec<String> {
+        let mut tasks = database.get_all_tasks().collect::<Vec<_>>();
+        tasks.sort_by_key(|t| t.time_created);
+        mode.sort(database, &mut tasks);
+        tasks.iter().map(|t| t.title.clone()).collect()
+    }
+
+    #[test]
+    fn sorts_by_mode() {
+        let mut db = Database::default();
+        let mut deploy = Task::create_now("deploy".into());
+        let mut build = Task::create_now("Build".into());
+        let mut test = Task::create_now("test".into());
+        build.time_created = deploy.time_created + Duration::seconds(1);
+        test.time_created = deploy.time_created + Duration::seconds(2);
+        deploy.tags = vec!["P:2".into()];
+        test.tags = vec!["P:1".into()];
+        test.time_due = Some(deploy.time_created + Duration::days(1));
+        let (deploy_id, build_id, test_id) =
+            (deploy.id().clone(), build.id().clone(), test.id().clone());
+        db.add_task(deploy);
+        db.add_task(build);
+        db.add_task(test);
+        db.add_dependency(&deploy_id, &test_id);
+        db.add_dependency(&test_id, &build_id);
+
+        assert_eq!(titles(&db, SortMode::Created), ["deploy", "Build", "test"]);
+        assert_eq!(titles(&db, SortMode::Title), ["Build", "deploy", "test"]);
+        assert_eq!(titles(&db, SortMode::Priority), ["test", "deploy", "Build"]);
+        assert_eq!(titles(&db, SortMode::Due), ["test", "deploy", "Build"]);
+        assert_eq!(
+            titles(&db, SortMode::Dependents),
+            ["Build", "test", "deploy"]
+        );
+        assert_eq!(
+            titles(&db, SortMode::Topological),
+            ["Build", "test", "deploy"]
+        );
+        assert_eq!(titles(&db, SortMode::Blocking), ["Build", "test", "deploy"]);
+    }
+
+    #[test]
+    fn names_roundtrip() {
+        for mode in SortMode::ALL {
+            assert_eq!(SortMode::parse(mode.name()), Some(mode));
+        }
+        assert_eq!(SortMode::parse("size"), None);
+        assert_eq!(SortMode::Blocking.next(), SortMode::Created);
+    }
+}
@@ -0,0 +1,96 @@
+//! The amount of open tasks over time, for burndown charts.
+
+use time::{Date, UtcOffset};
+
+use crate::database::Task;
+
+/// The amount of open tasks at the end of each day in a range of days.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Burndown {
+    /// The amount of tasks that were created but not completed at the end of each day, ordered by
+    /// day.
+    pub days: Vec<(Date, usize)>,
+}
+
+impl Burndown {
+    /// Counts the open tasks at the end of every day from `from` up to and including `to`, where
+    /// days start at midnight in the given offset.
+    #[must_use]
+    pub fn new<'a>(
+        tasks: impl IntoIterator<Item = &'a Task>,
+        from: Date,
+        to: Date,
+        offset: UtcOffset,
+    ) -> Self {
+        // the days on which each task was open, as (created, completed)
+        let open_days = tasks
+            .into_iter()
+            .map(|task| {
+                let created = task.time_created.to_offset(offset).date();
+                let completed = task.time_completed.map(|t| t.to_offset(offset).date());
+                (created, completed)
+            })
+            .collect::<Vec<_>>();
+
+        let mut days = vec![];
+        let mut date = from;
+        while date <= to {
+            let open = open_days
+                .iter()
+                .filter(|(created, completed)| {
+                    *created <= date && completed.is_none_or(|completed| completed > date)
+                })
+                .count();
+            days.push((date, open));
+            let Some(next) = date.next_day() else {break;};
+            date = next;
+        }
+
+        Self { days }
+    }
+
+    /// Gets the highest amount of open tasks on any day.
+    #[must_use]
+    pub fn max(&self) -> usize {
+        self.days.iter().map(|(_, open)| *open).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    #[test]
+    fn counts_open_tasks_per_day() {
+        let day = datetime!(2024-05-01 12:00 UTC);
+        let task = |created_day: i64, completed_day: Option<i64>| {
+            let mut task = Task::create_now("task".into());
+            task.time_created = day + Duration::days(created_day);
+            task.time_completed = completed_day.map(|d| day + Duration::days(d));
+            task
+        };
+        let tasks = [
+            task(0, Some(2)),
+            task(0, None),
+            task(1, Some(1)),
+            task(3, None),
+        ];
+
+        let burndown = Burndown::new(
+            &tasks,
+            day.date() - Duration::days(1),
+            day.date() + Duration::days(3),
+            UtcOffset::UTC,
+        );
+        let counts = burndown
+            .days
+            .iter()
+            .map(|(_, open)| *open)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [0, 2, 2, 1, 2]);
+        assert_eq!(burndown.days[0].0, day.date() - Duration::days(1));
+        assert_eq!(burndown.max(), 2);
+    }
+}
@@ -0,0 +1,109 @@
+//! Summaries of sets of tasks.
+
+mod burndown;
+mod time_report;
+
+use time::{Duration, OffsetDateTime};
+
+pub use self::{
+    burndown::Burndown,
+    time_report::{TimeEntry, TimeReport},
+};
+use crate::database::{Task, TaskStatus};
+
+/// A summary of a set of tasks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// The amount of tasks that have not been started yet.
+    pub todo: usize,
+    /// The amount of tasks that have been started but are not completed yet.
+    pub started: usize,
+    /// The amount of completed tasks.
+    pub completed: usize,
+    /// The sum of the estimates of all tasks that are not completed yet.
+    pub estimate_remaining: Duration,
+    /// The amount of tasks that are not completed yet and have no estimate, so are missing from
+    /// [`Self::estimate_remaining`].
+    pub unestimated: usize,
+    /// The earliest due date of all tasks that are not completed yet.
+    pub nearest_due: Option<OffsetDateTime>,
+}
+
+impl TaskStats {
+    /// Calculates the stats for the given tasks.
+    #[must_use]
+    pub fn from_tasks<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Self {
+        let mut stats = Self::default();
+
+        for task in tasks {
+            match task.status() {
+                TaskStatus::Todo => stats.todo += 1,
+                TaskStatus::Started => stats.started += 1,
+                TaskStatus::Completed => {
+                    stats.completed += 1;
+                    continue;
+                }
+            }
+
+            match task.estimate {
+                Some(estimate) => stats.estimate_remaining += estimate,
+                None => stats.unestimated += 1,
+            }
+            if let Some(due) = task.time_due {
+                stats.nearest_due = Some(stats.nearest_due.map_or(due, |nearest| nearest.min(due)));
+            }
+        }
+
+        stats
+    }
+
+    /// Gets the total amount of tasks.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.todo + self.started + self.completed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_and_sums_open_tasks() {
+        let mut todo = Task::create_now("todo".into());
+        todo.estimate = Some(Duration::hours(2));
+        todo.time_due = Some(todo.time_created + Duration::days(3));
+
+        let mut started = Task::create_now("started".into());
+        started.time_started = Some(started.time_created);
+        started.estimate = Some(Duration::minutes(30));
+        started.time_due = Some(started.time_created + Duration::days(1));
+
+        let mut completed = Task::create_now("completed".into());
+        completed.time_completed = Some(completed.time_created);
+        completed.estimate = Some(Duration::hours(8));
+        completed.time_due = Some(completed.time_created);
+
+        let stats = TaskStats::from_tasks([&todo, &started, &completed]);
+
+        assert_eq!((stats.todo, stats.started, stats.completed), (1, 1, 1));
+        assert_eq!(stats.total(), 3);
+        assert_eq!(stats.estimate_remaining, Duration::minutes(150));
+        assert_eq!(stats.unestimated, 0);
+        assert_eq!(stats.nearest_due, started.time_due);
+    }
+
+    #[test]
+    fn counts_open_tasks_without_estimate() {
+        let mut estimated = Task::create_now("estimated".into());
+        estimated.estimate = Some(Duration::hours(1));
+        let unestimated = Task::create_now("unestimated".into());
+        let mut completed = Task::create_now("completed".into());
+        completed.time_completed = Some(completed.time_created);
+
+        let stats = TaskStats::from_tasks([&estimated, &unestimated, &completed]);
+
+        assert_eq!(stats.estimate_remaining, Duration::hours(1));
+        assert_eq!(stats.unestimated, 1);
+    }
+}
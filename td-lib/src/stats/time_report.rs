@@ -0,0 +1,221 @@
+//! Summaries of the time worked on tasks, based on their work sessions.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+
+use crate::database::{Database, Tag, TaskId};
+
+/// The time worked on a single task on a single day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeEntry {
+    /// The day the work happened on.
+    pub date: Date,
+    /// The task that was worked on.
+    pub task: TaskId,
+    /// The title of the task.
+    pub title: String,
+    /// The tags of the task.
+    pub tags: Vec<Tag>,
+    /// How long was worked on the task that day.
+    pub duration: Duration,
+}
+
+/// The time worked on tasks within a range of days.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimeReport {
+    /// The time worked per task and day, ordered by day.
+    pub entries: Vec<TimeEntry>,
+}
+
+impl TimeReport {
+    /// Sums the work sessions of all tasks from the start of `from` until the end of `to`, where
+    /// days start at midnight in the given offset. Sessions that are still ongoing count until
+    /// `now`.
+    #[must_use]
+    pub fn new(
+        database: &Database,
+        from: Date,
+        to: Date,
+        offset: UtcOffset,
+        now: OffsetDateTime,
+    ) -> Self {
+        let start_of_day = |date: Date| date.midnight().assume_offset(offset);
+        let range_start = start_of_day(from);
+        let range_end = start_of_day(to.next_day().unwrap_or(to));
+
+        let mut durations = BTreeMap::<(Date, &TaskId), Duration>::new();
+        for task in database.get_all_tasks() {
+            for session in &task.work_sessions {
+                let mut start = session.start.max(range_start);
+                let end = session.end.unwrap_or(now).min(range_end);
+
+                // sessions that go past midnight count for both days
+                while start < end {
+                    let date = start.to_offset(offset).date();
+                    let part_end = end.min(start_of_day(date.next_day().unwrap_or(date)));
+                    *durations.entry((date, task.id())).or_default() += part_end - start;
+                    start = part_end;
+                }
+            }
+        }
+
+        let entries = durations
+            .into_iter()
+            .map(|((date, task_id), duration)| {
+                let task = &database[task_id];
+                TimeEntry {
+                    date,
+                    task: task_id.clone(),
+                    title: task.title.clone(),
+                    tags: task.tags.clone(),
+                    duration,
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Gets the total time worked.
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.entries.iter().map(|e| e.duration).sum()
+    }
+
+    /// Gets the time worked per task, with the most worked on tasks first.
+    #[must_use]
+    pub fn by_task(&self) -> Vec<(&TaskId, &str, Duration)> {
+        let mut tasks = BTreeMap::<&TaskId, (&str, Duration)>::new();
+        for entry in &self.entries {
+            tasks
+                .entry(&entry.task)
+                .or_insert((&entry.title, Duration::ZERO))
+                .1 += entry.duration;
+        }
+
+        let mut tasks = tasks
+            .into_iter()
+            .map(|(task, (title, duration))| (task, title, duration))
+            .collect::<Vec<_>>();
+        tasks.sort_by_key(|(_, _, duration)| std::cmp::Reverse(*duration));
+        tasks
+    }
+
+    /// Gets the time worked per tag, with the most worked on tags first. Tasks with multiple tags
+    /// count for each of them.
+    #[must_use]
+    pub fn by_tag(&self) -> Vec<(&str, Duration)> {
+        let mut tags = BTreeMap::<&str, Duration>::new();
+        for entry in &self.entries {
+            for tag in &entry.tags {
+                *tags.entry(tag).or_default() += entry.duration;
+            }
+        }
+
+        let mut tags = tags.into_iter().collect::<Vec<_>>();
+        tags.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+        tags
+    }
+
+    /// Gets the time worked per day, in chronological order.
+    #[must_use]
+    pub fn by_day(&self) -> Vec<(Date, Duration)> {
+        let mut days = BTreeMap::<Date, Duration>::new();
+        for entry in &self.entries {
+            *days.entry(entry.date).or_default() += entry.duration;
+        }
+        days.into_iter().collect()
+    }
+
+    /// Renders the report as CSV, with a row for every task on every day it was worked on.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut output = String::from("date,task,title,tags,minutes\n");
+        for entry in &self.entries {
+            writeln!(
+                output,
+                "{},{},{},{},{}",
+                entry.date,
+                entry.task.as_str(),
+                escape_csv(&entry.title),
+                escape_csv(&entry.tags.join(" ")),
+                entry.duration.whole_minutes()
+            )
+            .unwrap();
+        }
+        output
+    }
+}
+
+/// Quotes a CSV field if it contains characters that would otherwise end it.
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::database::{Task, WorkSession};
+
+    #[test]
+    fn sums_sessions_within_range() {
+        let mut db = Database::default();
+        let mut task = Task::create_now("write, report".into());
+        task.tags = vec!["work".into()];
+        task.work_sessions = vec![
+            WorkSession {
+                start: datetime!(2024-01-01 9:00 UTC),
+                end: Some(datetime!(2024-01-01 10:00 UTC)),
+            },
+            // crosses midnight, and the end of the range
+            WorkSession {
+                start: datetime!(2024-01-02 23:00 UTC),
+                end: Some(datetime!(2024-01-03 1:00 UTC)),
+            },
+        ];
+        let id = task.id().clone();
+        db.add_task(task);
+
+        let now = datetime!(2024-02-01 0:00 UTC);
+        let report = TimeReport::new(
+            &db,
+            date!(2024 - 01 - 02),
+            date!(2024 - 01 - 02),
+            UtcOffset::UTC,
+            now,
+        );
+        assert_eq!(report.total(), Duration::hours(1));
+
+        let report = TimeReport::new(
+            &db,
+            date!(2024 - 01 - 01),
+            date!(2024 - 01 - 31),
+            UtcOffset::UTC,
+            now,
+        );
+        assert_eq!(report.total(), Duration::hours(3));
+        assert_eq!(
+            report.by_day(),
+            vec![
+                (date!(2024 - 01 - 01), Duration::hours(1)),
+                (date!(2024 - 01 - 02), Duration::hours(1)),
+                (date!(2024 - 01 - 03), Duration::hours(1)),
+            ]
+        );
+        assert_eq!(report.by_tag(), vec![("work", Duration::hours(3))]);
+        assert_eq!(
+            report.by_task(),
+            vec![(&id, "write, report", Duration::hours(3))]
+        );
+        assert_eq!(
+            report.to_csv().lines().nth(1),
+            Some(format!("2024-01-01,{},\"write, report\",work,60", id.as_str()).as_str())
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Backends that a database can be stored in.
+//!
+//! The default is a single file in one of the [`StorageFormat`](crate::database::database_file::StorageFormat)s, which is rewritten completely on
+//! every save. Very large databases can be kept in `SQLite` instead (with the `sqlite` feature),
+//! which only writes the tasks and dependencies that changed, and can leave archived tasks out
+//! until they are needed.
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteBackend;
+use crate::{
+    database::{database_file::DatabaseFile, Database, DatabaseOp},
+    errors::{DatabaseReadError, DatabaseWriteError},
+};
+
+/// Loads and saves a database.
+pub trait StorageBackend {
+    /// Loads the stored database.
+    fn load(&mut self) -> Result<Database, DatabaseReadError>;
+
+    /// Stores the given database, replacing the previous version.
+    fn save(&mut self, database: &Database) -> Result<(), DatabaseWriteError>;
+
+    /// Loads the archived tasks that [`Self::load`] left out, as operations that add them and
+    /// their dependencies to the loaded database. Backends that always load everything return no
+    /// operations.
+    fn load_archived(&mut self) -> Result<Vec<DatabaseOp>, DatabaseReadError> {
+        Ok(vec![])
+    }
+}
+
+impl Default for Box<dyn StorageBackend> {
+    fn default() -> Self {
+        Box::<MemoryBackend>::default()
+    }
+}
+
+/// Stores the database in a single file, see [`DatabaseFile`].
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+impl FileBackend {
+    /// Creates a backend for the file at the given path. The storage format is detected when
+    /// loading and derived from the extension when saving.
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn load(&mut self) -> Result<Database, DatabaseReadError> {
+        DatabaseFile::read(&self.path)?.try_into()
+    }
+
+    fn save(&mut self, database: &Database) -> Result<(), DatabaseWriteError> {
+        DatabaseFile::write_database(database, &self.path)
+    }
+}
+
+/// Keeps the database in memory only, which is useful for tests and previews.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    database: Database,
+}
+
+impl StorageBackend for MemoryBackend {
+    fn load(&mut self) -> Result<Database, DatabaseReadError> {
+        Ok(self.database.clone())
+    }
+
+    fn save(&mut self, database: &Database) -> Result<(), DatabaseWriteError> {
+        self.database = database.clone();
+        Ok(())
+    }
+}
+
+/// Checks if the given path should be stored in `SQLite`, based on its extension.
+#[must_use]
+pub fn is_sqlite_path(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("sqlite" | "sqlite3" | "db")
+    )
+}
+
+/// Opens the backend that belongs to the given path: `SQLite` for `.sqlite`, `.sqlite3` and `.db`
+/// files if the `sqlite` feature is enabled, a [`FileBackend`] otherwise.
+pub fn open(path: &Path) -> Result<Box<dyn StorageBackend>, DatabaseReadError> {
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_path(path) {
+        return Ok(Box::new(SqliteBackend::open(path)?));
+    }
+
+    Ok(Box::new(FileBackend::new(path.to_owned())))
+}
+
+/// Like [`open`], but archived tasks are left out until [`StorageBackend::load_archived`] is
+/// called if the backend supports it. This keeps loading large databases with a long history fast.
+pub fn open_lazy(path: &Path) -> Result<Box<dyn StorageBackend>, DatabaseReadError> {
+    #[cfg(feature = "sqlite")]
+    if is_sqlite_path(path) {
+        let mut backend = SqliteBackend::open(path)?;
+        backend.set_lazy_archive(true);
+        return Ok(Box::new(backend));
+    }
+
+    open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn file_backend_round_trips() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let mut database = Database::default();
+        database.add_task(Task::create_now("task".into()));
+
+        let mut backend = open(&dir.join("todo.json")).unwrap();
+        backend.save(&database).unwrap();
+        assert_eq!(backend.load().unwrap().diff(&database), vec![]);
+    }
+}
@@ -0,0 +1,477 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::StorageBackend;
+use crate::{
+    database::{database_file::DatabaseFile, Database, DatabaseOp, Task, TaskId},
+    errors::{DatabaseReadError, DatabaseWriteError},
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS settings (id INTEGER PRIMARY KEY CHECK (id = 0), data TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS tasks (
+        id TEXT PRIMARY KEY,
+        archived INTEGER NOT NULL DEFAULT 0,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tasks_archived ON tasks (archived);
+    CREATE TABLE IF NOT EXISTS edges (
+        from_id TEXT NOT NULL,
+        to_id TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (from_id, to_id)
+    );
+    CREATE INDEX IF NOT EXISTS edges_to_id ON edges (to_id);
+";
+
+/// Stores the database in an `SQLite` file, with a row for every task and dependency.
+///
+/// Saving only writes the rows that changed since the database was last loaded or saved, so
+/// saving a small change to a large database is fast. The settings of the database are stored as
+/// a [`DatabaseFile`] without tasks, so they are versioned like a regular database file.
+///
+/// With [`Self::set_lazy_archive`], archived tasks are only loaded by
+/// [`StorageBackend::load_archived`], except those that share a dependency with an active task so
+/// no dependencies of active tasks are missing. Rows that were not loaded are never touched when
+/// saving, except the dependencies of tasks that are removed.
+///
+/// Tasks are always loaded whole. Leaving out their descriptions or work sessions until a task is
+/// opened would make searching, the time report, hooks and the audit log see incomplete tasks,
+/// since they all read those fields from every task. Archived tasks are what makes a database
+/// large over time, so they are what is left out until they are shown or searched.
+pub struct SqliteBackend {
+    path: PathBuf,
+    connection: Connection,
+    /// The database as it was last loaded or saved, to find the rows that changed.
+    stored: Option<Database>,
+    lazy_archive: bool,
+}
+
+/// Which task rows [`SqliteBackend::read_rows`] reads.
+#[derive(Clone, Copy)]
+enum TaskRows {
+    All,
+    /// The tasks that are not archived, and the archived tasks they share a dependency with.
+    Active,
+    Archived,
+}
+
+impl TaskRows {
+    fn condition(self) -> &'static str {
+        match self {
+            Self::All => "TRUE",
+            Self::Active => {
+                "archived = 0 OR id IN (
+                    SELECT edges.to_id FROM edges
+                    JOIN tasks AS active ON active.id = edges.from_id AND active.archived = 0
+                    UNION
+                    SELECT edges.from_id FROM edges
+                    JOIN tasks AS active ON active.id = edges.to_id AND active.archived = 0
+                )"
+            }
+            Self::Archived => "archived = 1",
+        }
+    }
+}
+
+impl SqliteBackend {
+    /// Opens the `SQLite` file at the given path, creating it if it does not exist yet.
+    pub fn open(path: &Path) -> Result<Self, DatabaseReadError> {
+        let sqlite_error = |source| DatabaseReadError::SqliteError {
+            path: path.to_owned(),
+            source,
+        };
+        let connection = Connection::open(path).map_err(sqlite_error)?;
+        connection.execute_batch(SCHEMA).map_err(sqlite_error)?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            connection,
+            stored: None,
+            lazy_archive: false,
+        })
+    }
+
+    /// Sets whether archived tasks are left out when loading. CRDT databases are always loaded
+    /// completely, since merging them needs every task.
+    pub fn set_lazy_archive(&mut self, lazy_archive: bool) {
+        self.lazy_archive = lazy_archive;
+    }
+
+    fn read(&self) -> Result<Database, DatabaseReadError> {
+        let settings: Option<String> = self
+            .connection
+            .query_row("SELECT data FROM settings", [], |row| row.get(0))
+            .optional()
+            .map_err(|source| DatabaseReadError::SqliteError {
+                path: self.path.clone(),
+                source,
+            })?;
+        let mut database: Database = match settings {
+            Some(settings) => DatabaseFile::parse(settings.as_bytes())?.try_into()?,
+            None => Database::default(),
+        };
+
+        let rows = match self.lazy_archive && !database.is_crdt() {
+            true => TaskRows::Active,
+            false => TaskRows::All,
+        };
+        let ops = self.read_rows(rows, &HashSet::new())?;
+        database.apply_ops(&ops, false);
+        Ok(database)
+    }
+
+    /// Reads the given tasks, except those in `skip`, as operations that add them. Their
+    /// dependencies on each other and on the tasks in `skip` are added as well.
+    fn read_rows(
+        &self,
+        rows: TaskRows,
+        skip: &HashSet<TaskId>,
+    ) -> Result<Vec<DatabaseOp>, DatabaseReadError> {
+        let sqlite_error = |source| DatabaseReadError::SqliteError {
+            path: self.path.clone(),
+            source,
+        };
+        let json_error = |source| DatabaseReadError::JsonError {
+            path: Some(self.path.clone()),
+            source,
+        };
+
+        let mut ops = vec![];
+        let mut ids = HashSet::new();
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "SELECT id, data FROM tasks WHERE {} ORDER BY rowid",
+                rows.condition()
+            ))
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sqlite_error)?;
+        for row in rows {
+            let (id, data) = row.map_err(sqlite_error)?;
+            let id = TaskId::from_raw(id);
+            if skip.contains(&id) {
+                continue;
+            }
+            let task: Task = serde_json::from_str(&data).map_err(json_error)?;
+            ids.insert(id.clone());
+            ops.push(DatabaseOp::SetTask {
+                id,
+                old: None,
+                new: Some(Box::new(task)),
+            });
+        }
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT from_id, to_id, data FROM edges ORDER BY rowid")
+            .map_err(sqlite_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(sqlite_error)?;
+        for row in rows {
+            let (from, to, data) = row.map_err(sqlite_error)?;
+            let (from, to) = (TaskId::from_raw(from), TaskId::from_raw(to));
+
+            // skip dependencies that were loaded before, or that involve tasks that are not loaded
+            let is_new = ids.contains(&from) || ids.contains(&to);
+            let is_loaded = |id: &TaskId| ids.contains(id) || skip.contains(id);
+            if !is_new || !is_loaded(&from) || !is_loaded(&to) {
+                continue;
+            }
+
+            ops.push(DatabaseOp::SetDependency {
+                from,
+                to,
+                old: None,
+                new: Some(serde_json::from_str(&data).map_err(json_error)?),
+            });
+        }
+
+        Ok(ops)
+    }
+
+    fn write(&mut self, database: &Database) -> Result<(), DatabaseWriteError> {
+        let sqlite_error = |source| DatabaseWriteError::SqliteError {
+            path: self.path.clone(),
+            source,
+        };
+        let json_error = |source| DatabaseWriteError::JsonError {
+            path: Some(self.path.clone()),
+            source,
+        };
+
+        let transaction = self.connection.transaction().map_err(sqlite_error)?;
+        // without a known previous version, everything is written again
+        let ops = match &self.stored {
+            Some(stored) => database.diff(stored),
+            None => {
+                transaction
+                    .execute_batch("DELETE FROM tasks; DELETE FROM edges; DELETE FROM settings;")
+                    .map_err(sqlite_error)?;
+                database.diff(&Database::default())
+            }
+        };
+
+        let mut settings_changed = false;
+        for op in &ops {
+            match op {
+                DatabaseOp::SetTask {
+                    id,
+                    new: Some(task),
+                    ..
+                } => {
+                    let data = serde_json::to_string(task).map_err(json_error)?;
+                    transaction
+                        .execute(
+                            "INSERT INTO tasks (id, archived, data) VALUES (?1, ?2, ?3)
+                             ON CONFLICT (id) DO UPDATE
+                             SET archived = excluded.archived, data = excluded.data",
+                            params![id.as_str(), task.time_archived.is_some(), data],
+                        )
+                        .map_err(sqlite_error)?;
+                }
+                DatabaseOp::SetTask { id, new: None, .. } => {
+                    // its dependencies on tasks that were not loaded are not in the diff
+                    transaction
+                        .execute(
+                            "DELETE FROM edges WHERE from_id = ?1 OR to_id = ?1",
+                            params![id.as_str()],
+                        )
+                        .map_err(sqlite_error)?;
+                    transaction
+                        .execute("DELETE FROM tasks WHERE id = ?1", params![id.as_str()])
+                        .map_err(sqlite_error)?;
+                }
+                DatabaseOp::SetDependency {
+                    from,
+                    to,
+                    new: Some(dependency),
+                    ..
+                } => {
+                    let data = serde_json::to_string(dependency).map_err(json_error)?;
+                    transaction
+                        .execute(
+                            "INSERT INTO edges (from_id, to_id, data) VALUES (?1, ?2, ?3)
+                             ON CONFLICT (from_id, to_id) DO UPDATE SET data = excluded.data",
+                            params![from.as_str(), to.as_str(), data],
+                        )
+                        .map_err(sqlite_error)?;
+                }
+                DatabaseOp::SetDependency {
+                    from,
+                    to,
+                    new: None,
+                    ..
+                } => {
+                    transaction
+                        .execute(
+                            "DELETE FROM edges WHERE from_id = ?1 AND to_id = ?2",
+                            params![from.as_str(), to.as_str()],
+                        )
+                        .map_err(sqlite_error)?;
+                }
+                DatabaseOp::SetArchivePolicy { .. }
+                | DatabaseOp::SetTagInfo { .. }
+                | DatabaseOp::SetDatabaseId { .. }
+                | DatabaseOp::SetIdNamespace { .. }
+                | DatabaseOp::SetCrdtClocks { .. }
+                | DatabaseOp::SetEncryption { .. } => settings_changed = true,
+            }
+        }
+
+        if settings_changed {
+            let settings = Database {
+                database_id: database.database_id.clone(),
+                id_namespace: database.id_namespace.clone(),
+                archive_policy: database.archive_policy,
+                tag_registry: database.tag_registry.clone(),
+                crdt: database.crdt.clone(),
+                encryption: database.encryption.clone(),
+                ..Default::default()
+            };
+            let data = serde_json::to_string(&DatabaseFile::from(&settings)).map_err(json_error)?;
+            transaction
+                .execute(
+                    "INSERT INTO settings (id, data) VALUES (0, ?1)
+                     ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+                    params![data],
+                )
+                .map_err(sqlite_error)?;
+        }
+
+        transaction.commit().map_err(sqlite_error)?;
+        self.stored = Some(database.clone());
+        Ok(())
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn load(&mut self) -> Result<Database, DatabaseReadError> {
+        let database = self.read()?;
+        self.stored = Some(database.clone());
+        Ok(database)
+    }
+
+    fn save(&mut self, database: &Database) -> Result<(), DatabaseWriteError> {
+        self.write(database)
+    }
+
+    fn load_archived(&mut self) -> Result<Vec<DatabaseOp>, DatabaseReadError> {
+        let Some(stored) = &self.stored else {return Ok(vec![]);};
+        if !self.lazy_archive || stored.is_crdt() {
+            return Ok(vec![]);
+        }
+
+        let loaded = stored.get_all_tasks().map(|t| t.id().clone()).collect();
+        let ops = self.read_rows(TaskRows::Archived, &loaded)?;
+        if let Some(stored) = &mut self.stored {
+            stored.apply_ops(&ops, false);
+        }
+        Ok(ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+    use crate::database::{ArchivePolicy, Task, TaskDependency};
+
+    #[test]
+    fn saves_changed_rows() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.sqlite");
+
+        let mut database = Database::default();
+        let (first, second) = (
+            Task::create_now("first".into()),
+            Task::create_now("second".into()),
+        );
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        database.add_task(first);
+        database.add_task(second);
+        database.add_dependency_with(
+            &first_id,
+            &second_id,
+            TaskDependency {
+                lag: Duration::days(1),
+                ..Default::default()
+            },
+        );
+        database.set_archive_policy(ArchivePolicy {
+            archive_completed_after: Some(Duration::days(7)),
+        });
+
+        let mut backend = SqliteBackend::open(&path).unwrap();
+        backend.save(&database).unwrap();
+
+        database[&first_id].title = "renamed".into();
+        database.remove_dependency(&first_id, &second_id);
+        backend.save(&database).unwrap();
+
+        let loaded = SqliteBackend::open(&path).unwrap().load().unwrap();
+        assert_eq!(loaded.diff(&database), vec![]);
+        assert_eq!(loaded[&first_id].title, "renamed");
+    }
+
+    /// Creates a database with an active task that depends on an archived task, which depends on
+    /// another archived task, and one more archived task without dependencies.
+    fn archived_chain() -> (Database, [TaskId; 4]) {
+        let mut database = Database::default();
+        let mut ids = vec![];
+        for (title, archived) in [
+            ("active", false),
+            ("archived", true),
+            ("nested", true),
+            ("unrelated", true),
+        ] {
+            let mut task = Task::create_now(title.into());
+            if archived {
+                task.time_archived = Some(task.time_created);
+            }
+            ids.push(task.id.clone());
+            database.add_task(task);
+        }
+        database.add_dependency(&ids[0], &ids[1]);
+        database.add_dependency(&ids[1], &ids[2]);
+        (database, ids.try_into().unwrap())
+    }
+
+    #[test]
+    fn loads_archive_lazily() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.sqlite");
+
+        let (mut database, [active_id, archived_id, ..]) = archived_chain();
+        SqliteBackend::open(&path).unwrap().save(&database).unwrap();
+
+        let mut backend = SqliteBackend::open(&path).unwrap();
+        backend.set_lazy_archive(true);
+        let mut loaded = backend.load().unwrap();
+        // the archived dependency of the active task is loaded, so it is still blocked
+        assert_eq!(loaded.get_all_tasks().count(), 2);
+        let dependencies = loaded.get_dependencies(&active_id).collect::<Vec<_>>();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].id(), &archived_id);
+
+        // saving does not remove the tasks that were not loaded
+        loaded[&active_id].title = "renamed".into();
+        backend.save(&loaded).unwrap();
+
+        let ops = backend.load_archived().unwrap();
+        loaded.apply_ops(&ops, false);
+        database[&active_id].title = "renamed".into();
+        assert_eq!(loaded.diff(&database), vec![]);
+        assert_eq!(backend.load_archived().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn removing_lazily_loaded_tasks_removes_all_their_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.sqlite");
+
+        let (mut database, [_, archived_id, ..]) = archived_chain();
+        SqliteBackend::open(&path).unwrap().save(&database).unwrap();
+
+        let mut backend = SqliteBackend::open(&path).unwrap();
+        backend.set_lazy_archive(true);
+        let mut loaded = backend.load().unwrap();
+        // the dependency on the nested task is not loaded, but has to be removed as well
+        loaded.remove_task(&archived_id);
+        backend.save(&loaded).unwrap();
+
+        let orphans: usize = backend
+            .connection
+            .query_row(
+                "SELECT COUNT(*) FROM edges
+                 WHERE from_id NOT IN (SELECT id FROM tasks) OR to_id NOT IN (SELECT id FROM tasks)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphans, 0);
+
+        database.remove_task(&archived_id);
+        let reloaded = SqliteBackend::open(&path).unwrap().load().unwrap();
+        assert_eq!(reloaded.diff(&database), vec![]);
+    }
+}
@@ -0,0 +1,452 @@
+//! Merging copies of a database as a conflict-free replicated data type (CRDT).
+//!
+//! Every task field and dependency carries the [`LamportTimestamp`] of its last change. When two
+//! copies are merged, the latest change to each field wins, so edits to different fields of the
+//! same task are all kept and the result does not depend on the order of merging. Removed tasks
+//! leave a tombstone behind, so copies that still have them do not bring them back unless they
+//! changed the task after it was removed.
+//!
+//! Changes are found by comparing a database to the version that was last stamped, see
+//! [`CrdtReplica`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+
+use super::find_task;
+use crate::database::{
+    CrdtClocks, Database, DependencyClock, LamportTimestamp, Task, TaskDependency, TaskId,
+};
+
+/// A copy of a database that is edited locally and merged with other copies.
+#[derive(Debug, Clone)]
+pub struct CrdtReplica {
+    /// A random id that distinguishes the changes made through this replica from others.
+    id: String,
+    /// The database as it was when its changes were last stamped.
+    base: Database,
+}
+
+impl CrdtReplica {
+    /// Starts tracking the changes made to the given database.
+    #[must_use]
+    pub fn new(database: &Database) -> Self {
+        Self {
+            id: nanoid::nanoid!(8),
+            base: database.clone(),
+        }
+    }
+
+    /// Gets a copy of `database` with timestamps for all changes made since the last time it was
+    /// stamped. This copy is what should be saved.
+    pub fn stamp(&mut self, database: &Database) -> Database {
+        let mut stamped = database.clone();
+        stamped.crdt = Some(stamp_changes(&self.base, database, &self.id));
+        self.base = stamped.clone();
+        stamped
+    }
+
+    /// Stamps the changes made to `local` and merges `remote` into it.
+    pub fn merge(&mut self, local: &Database, remote: &Database) -> Database {
+        let stamped = self.stamp(local);
+        let merged = merge_crdt(&stamped, remote);
+        self.base = merged.clone();
+        merged
+    }
+
+    /// Starts tracking changes from the given database instead, such as after reloading it.
+    pub fn reset(&mut self, database: &Database) {
+        self.base = database.clone();
+    }
+}
+
+/// Merges two stamped copies of a database. Changes that happened at the same time are resolved
+/// in favor of the replica with the highest id, so the result is the same in both directions.
+#[must_use]
+pub fn merge_crdt(local: &Database, remote: &Database) -> Database {
+    let empty = CrdtClocks::default();
+    let local_clocks = local.crdt.as_ref().unwrap_or(&empty);
+    let remote_clocks = remote.crdt.as_ref().unwrap_or(&empty);
+
+    let mut database = local.clone();
+    let mut clocks = CrdtClocks {
+        removed_tasks: local_clocks.removed_tasks.clone(),
+        ..Default::default()
+    };
+    for (id, time) in &remote_clocks.removed_tasks {
+        let removed = clocks.removed_tasks.entry(id.clone()).or_default();
+        *removed = time.clone().max(removed.clone());
+    }
+
+    let ids = local
+        .get_all_tasks()
+        .chain(remote.get_all_tasks())
+        .map(|task| task.id().clone())
+        .collect::<BTreeSet<_>>();
+    for id in &ids {
+        let local_task = find_task(local, id).map(|task| (task, local_clocks.tasks.get(id)));
+        let remote_task = find_task(remote, id).map(|task| (task, remote_clocks.tasks.get(id)));
+        let (task, task_clocks) = match (local_task, remote_task) {
+            (Some(local), Some(remote)) => merge_task(local, remote),
+            (Some((task, time)), None) | (None, Some((task, time))) => {
+                (task.clone(), time.cloned().unwrap_or_default())
+            }
+            (None, None) => unreachable!("task should exist in either database"),
+        };
+
+        let last_changed = task_clocks.values().max();
+        let alive = match clocks.removed_tasks.get(id) {
+            Some(removed) => last_changed.is_some_and(|changed| changed > removed),
+            None => true,
+        };
+
+        if !alive {
+            if find_task(&database, id).is_some() {
+                database.remove_task(id);
+            }
+            continue;
+        }
+
+        clocks.removed_tasks.remove(id);
+        if !task_clocks.is_empty() {
+            clocks.tasks.insert(id.clone(), task_clocks);
+        }
+        if find_task(&database, id).is_some() {
+            database[id] = task;
+        } else {
+            database.add_task(task);
+        }
+    }
+
+    merge_dependencies(&mut database, &mut clocks, local, remote);
+
+    database.crdt = Some(clocks);
+    database
+}
+
+/// Merges a task field by field, keeping the latest change to each field.
+fn merge_task(
+    (local, local_clocks): (&Task, Option<&BTreeMap<String, LamportTimestamp>>),
+    (remote, remote_clocks): (&Task, Option<&BTreeMap<String, LamportTimestamp>>),
+) -> (Task, BTreeMap<String, LamportTimestamp>) {
+    let local_fields = task_fields(local);
+    let remote_fields = task_fields(remote);
+    let no_time = LamportTimestamp::default();
+
+    let mut fields = Map::new();
+    let mut clocks = BTreeMap::new();
+    let keys = local_fields
+        .keys()
+        .chain(remote_fields.keys())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let local_time = local_clocks.and_then(|c| c.get(key)).unwrap_or(&no_time);
+        let remote_time = remote_clocks.and_then(|c| c.get(key)).unwrap_or(&no_time);
+        let (value, time) = if remote_time > local_time {
+            (remote_fields.get(key), remote_time)
+        } else {
+            (local_fields.get(key), local_time)
+        };
+
+        if let Some(value) = value {
+            fields.insert(key.clone(), value.clone());
+        }
+        if *time != no_time {
+            clocks.insert(key.clone(), time.clone());
+        }
+    }
+
+    let task = serde_json::from_value(Value::Object(fields)).unwrap_or_else(|_| local.clone());
+    (task, clocks)
+}
+
+/// Merges the dependencies of both databases into `database`, which is a copy of `local` whose
+/// tasks have already been merged.
+fn merge_dependencies(
+    database: &mut Database,
+    clocks: &mut CrdtClocks,
+    local: &Database,
+    remote: &Database,
+) {
+    let empty = CrdtClocks::default();
+    let local_clocks = local.crdt.as_ref().unwrap_or(&empty);
+    let remote_clocks = remote.crdt.as_ref().unwrap_or(&empty);
+    let local_dependencies = dependencies(local);
+    let remote_dependencies = dependencies(remote);
+
+    let clock_keys = |clocks: &CrdtClocks| {
+        clocks
+            .dependencies
+            .iter()
+            .flat_map(|(from, to)| to.keys().map(|to| (from.clone(), to.clone())))
+            .collect::<Vec<_>>()
+    };
+    let keys = local_dependencies
+        .keys()
+        .chain(remote_dependencies.keys())
+        .cloned()
+        .chain(clock_keys(local_clocks))
+        .chain(clock_keys(remote_clocks))
+        .collect::<BTreeSet<_>>();
+
+    for (from, to) in keys {
+        let local_clock = dependency_clock(local_clocks, &from, &to);
+        let remote_clock = dependency_clock(remote_clocks, &from, &to);
+        let remote_wins = remote_clock.map(|c| &c.time) > local_clock.map(|c| &c.time);
+        let (dependency, clock) = if remote_wins {
+            (
+                remote_dependencies.get(&(from.clone(), to.clone())),
+                remote_clock,
+            )
+        } else {
+            (
+                local_dependencies.get(&(from.clone(), to.clone())),
+                local_clock,
+            )
+        };
+
+        if let Some(clock) = clock {
+            clocks
+                .dependencies
+                .entry(from.clone())
+                .or_default()
+                .insert(to.clone(), clock.clone());
+        }
+
+        let both_exist = find_task(database, &from).is_some() && find_task(database, &to).is_some();
+        match dependency {
+            Some(dependency) if both_exist => match database.get_dependency_mut(&from, &to) {
+                Some(existing) => *existing = dependency.clone(),
+                None => database.add_dependency_with(&from, &to, dependency.clone()),
+            },
+            _ => {
+                database.remove_dependency(&from, &to);
+            }
+        }
+    }
+}
+
+/// Determines the clocks of `current` by comparing it to `base`, the version that was stamped
+/// last. Every change gets the same timestamp, which is later than all timestamps in `base`.
+fn stamp_changes(base: &Database, current: &Database, replica: &str) -> CrdtClocks {
+    let mut clocks = base.crdt.clone().unwrap_or_default();
+    let time = LamportTimestamp(max_counter(&clocks) + 1, replica.to_string());
+
+    for task in current.get_all_tasks() {
+        let fields = task_fields(task);
+        let base_fields = find_task(base, task.id())
+            .map(task_fields)
+            .unwrap_or_default();
+
+        let task_clocks = clocks.tasks.entry(task.id().clone()).or_default();
+        for key in fields.keys().chain(base_fields.keys()) {
+            if fields.get(key) != base_fields.get(key) {
+                task_clocks.insert(key.clone(), time.clone());
+            }
+        }
+        if task_clocks.is_empty() {
+            clocks.tasks.remove(task.id());
+        }
+
+        // the task was either added back, or merged in from a copy that changed it after removal
+        clocks.removed_tasks.remove(task.id());
+    }
+
+    for task in base.get_all_tasks() {
+        if find_task(current, task.id()).is_none() {
+            clocks.tasks.remove(task.id());
+            clocks.removed_tasks.insert(task.id().clone(), time.clone());
+        }
+    }
+
+    let current_dependencies = dependencies(current);
+    let base_dependencies = dependencies(base);
+    for ((from, to), dependency) in &current_dependencies {
+        if base_dependencies.get(&(from.clone(), to.clone())) != Some(dependency) {
+            let clock = DependencyClock {
+                time: time.clone(),
+                removed: false,
+            };
+            clocks
+                .dependencies
+                .entry(from.clone())
+                .or_default()
+                .insert(to.clone(), clock);
+        }
+    }
+    for (from, to) in base_dependencies.keys() {
+        if !current_dependencies.contains_key(&(from.clone(), to.clone())) {
+            let clock = DependencyClock {
+                time: time.clone(),
+                removed: true,
+            };
+            clocks
+                .dependencies
+                .entry(from.clone())
+                .or_default()
+                .insert(to.clone(), clock);
+        }
+    }
+
+    clocks
+}
+
+fn max_counter(clocks: &CrdtClocks) -> u64 {
+    let task_times = clocks.tasks.values().flat_map(BTreeMap::values);
+    let removed_times = clocks.removed_tasks.values();
+    let dependency_times = clocks
+        .dependencies
+        .values()
+        .flat_map(BTreeMap::values)
+        .map(|clock| &clock.time);
+
+    task_times
+        .chain(removed_times)
+        .chain(dependency_times)
+        .map(|time| time.0)
+        .max()
+        .unwrap_or(0)
+}
+
+fn dependency_clock<'a>(
+    clocks: &'a CrdtClocks,
+    from: &TaskId,
+    to: &TaskId,
+) -> Option<&'a DependencyClock> {
+    clocks.dependencies.get(from)?.get(to)
+}
+
+fn dependencies(database: &Database) -> BTreeMap<(TaskId, TaskId), TaskDependency> {
+    database
+        .get_all_tasks()
+        .flat_map(|task| {
+            database.get_dependencies(task.id()).map(|dependency| {
+                let value = database
+                    .get_dependency(task.id(), dependency.id())
+                    .cloned()
+                    .unwrap_or_default();
+                ((task.id().clone(), dependency.id().clone()), value)
+            })
+        })
+        .collect()
+}
+
+fn task_fields(task: &Task) -> Map<String, Value> {
+    match serde_json::to_value(task) {
+        Ok(Value::Object(fields)) => fields,
+        _ => unreachable!("tasks should serialize to an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_base() -> (Database, TaskId, TaskId) {
+        let mut db = Database::default();
+        db.enable_crdt();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let (id_1, id_2) = (task_1.id().clone(), task_2.id().clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        (db, id_1, id_2)
+    }
+
+    #[test]
+    fn crdt_mode_is_saved() {
+        let (base, id_1, _) = create_base();
+        let mut changed = base.clone();
+        changed[&id_1].title = "new title".into();
+        let stamped = CrdtReplica::new(&base).stamp(&changed);
+
+        let json = serde_json::to_string(&stamped).unwrap();
+        let loaded: Database = serde_json::from_str(&json).unwrap();
+        assert!(loaded.is_crdt());
+        assert_eq!(loaded.crdt, stamped.crdt);
+    }
+
+    #[test]
+    fn merges_changes_to_different_fields() {
+        let (base, id_1, _) = create_base();
+        let (mut replica_a, mut replica_b) = (CrdtReplica::new(&base), CrdtReplica::new(&base));
+        let (mut a, mut b) = (base.clone(), base.clone());
+        a[&id_1].title = "new title".into();
+        b[&id_1].tags.push("tag".into());
+        let new_task = Task::create_now("task 3".into());
+        let new_id = new_task.id().clone();
+        b.add_task(new_task);
+        b.add_dependency(&id_1, &new_id);
+        let (a, b) = (replica_a.stamp(&a), replica_b.stamp(&b));
+
+        for merged in [merge_crdt(&a, &b), merge_crdt(&b, &a)] {
+            assert_eq!(merged[&id_1].title, "new title");
+            assert_eq!(merged[&id_1].tags, vec!["tag"]);
+            assert_eq!(merged[&new_id].title, "task 3");
+            assert!(merged.get_dependency(&id_1, &new_id).is_some());
+        }
+    }
+
+    #[test]
+    fn latest_change_to_a_field_wins() {
+        let (base, id_1, _) = create_base();
+        let (mut replica_a, mut replica_b) = (CrdtReplica::new(&base), CrdtReplica::new(&base));
+        let mut a = base.clone();
+        a[&id_1].title = "first".into();
+        let a = replica_a.stamp(&a);
+
+        // b has seen the change from a, so its change is later
+        let mut b = replica_b.merge(&base, &a);
+        b[&id_1].title = "second".into();
+        let b = replica_b.stamp(&b);
+
+        assert_eq!(merge_crdt(&a, &b)[&id_1].title, "second");
+        assert_eq!(merge_crdt(&b, &a)[&id_1].title, "second");
+
+        // concurrent changes are resolved the same way in both directions
+        let mut c = base.clone();
+        c[&id_1].title = "concurrent".into();
+        let c = CrdtReplica::new(&base).stamp(&c);
+        assert_eq!(
+            merge_crdt(&a, &c)[&id_1].title,
+            merge_crdt(&c, &a)[&id_1].title
+        );
+    }
+
+    #[test]
+    fn removed_tasks_stay_removed_unless_added_back() {
+        let (mut base, id_1, id_2) = create_base();
+        base.add_dependency(&id_1, &id_2);
+        let (mut replica_a, mut replica_b) = (CrdtReplica::new(&base), CrdtReplica::new(&base));
+        let mut a = base.clone();
+        a.remove_task(&id_2);
+        let a = replica_a.stamp(&a);
+
+        // copies that did not change the task do not bring it back
+        let merged = merge_crdt(&replica_b.stamp(&base), &a);
+        assert_eq!(merged.get_all_tasks().count(), 1);
+        assert!(find_task(&merged, &id_2).is_none());
+
+        // adding it back after seeing the removal does
+        let mut b = replica_b.merge(&base, &a);
+        let mut task = Task::create_now("added back".into());
+        task.id = id_2.clone();
+        b.add_task(task);
+        let b = replica_b.stamp(&b);
+        assert_eq!(merge_crdt(&a, &b)[&id_2].title, "added back");
+    }
+
+    #[test]
+    fn merges_removed_dependencies() {
+        let (mut base, id_1, id_2) = create_base();
+        base.add_dependency(&id_1, &id_2);
+        let (mut replica_a, mut replica_b) = (CrdtReplica::new(&base), CrdtReplica::new(&base));
+        let mut a = base.clone();
+        a.remove_dependency(&id_1, &id_2);
+        let a = replica_a.stamp(&a);
+        let b = replica_b.stamp(&base);
+
+        assert!(merge_crdt(&a, &b).get_dependency(&id_1, &id_2).is_none());
+        assert!(merge_crdt(&b, &a).get_dependency(&id_1, &id_2).is_none());
+    }
+}
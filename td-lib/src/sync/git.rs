@@ -0,0 +1,275 @@
+//! Synchronizing a database through the git repository that contains it.
+//!
+//! This calls the `git` executable, so it uses the same configuration, credentials and remotes as
+//! the user does on the command line.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use super::{crdt::merge_crdt, describe_changes, merge_three_way, MergeOutcome};
+use crate::{
+    database::{database_file::DatabaseFile, Database, TaskId},
+    errors::SyncError,
+};
+
+/// A database file inside a git repository.
+#[derive(Debug, Clone)]
+pub struct GitSync {
+    /// The root of the working tree.
+    repo_dir: PathBuf,
+    /// The path of the database file, relative to the root of the working tree.
+    file: PathBuf,
+}
+
+/// The result of [`GitSync::pull`].
+#[derive(Debug, Clone, Default)]
+pub struct PullOutcome {
+    /// Whether the database file was changed by the pull.
+    pub updated: bool,
+    /// Tasks that were changed both locally and remotely. The local version was kept for these.
+    pub conflicts: Vec<TaskId>,
+}
+
+impl GitSync {
+    /// Finds the git repository that contains the given database file.
+    pub fn open(database_path: &Path) -> Result<Self, SyncError> {
+        let database_path = database_path
+            .canonicalize()
+            .map_err(|source| SyncError::IoError {
+                path: database_path.to_owned(),
+                source,
+            })?;
+        let dir = database_path.parent().unwrap_or(Path::new("/"));
+
+        let output = run_git(dir, &["rev-parse", "--show-toplevel"])?;
+        let repo_dir = PathBuf::from(output.trim_end());
+        let file = database_path
+            .strip_prefix(&repo_dir)
+            .unwrap_or(&database_path)
+            .to_owned();
+
+        Ok(Self { repo_dir, file })
+    }
+
+    /// Commits the database file if it changed since the last commit, with a message describing
+    /// the changes. Returns `false` if there was nothing to commit.
+    pub fn commit(&self, database: &Database) -> Result<bool, SyncError> {
+        let file = self.file_arg();
+        self.git(&["add", "--", &file])?;
+        if self.git_status(&["diff", "--cached", "--quiet", "--", &file])? {
+            return Ok(false);
+        }
+
+        let previous = self.read_revision("HEAD")?.unwrap_or_default();
+        let message = format!("td: {}", describe_changes(&previous, database));
+        self.git(&["commit", "--quiet", "-m", &message, "--", &file])?;
+        Ok(true)
+    }
+
+    /// Fetches and merges the upstream branch. If both sides changed the database file, the
+    /// changes are combined with [`merge_three_way`] instead of a textual merge, or with
+    /// [`merge_crdt`] if the database is a CRDT.
+    ///
+    /// Local changes to the database should be committed first.
+    pub fn pull(&self) -> Result<PullOutcome, SyncError> {
+        self.git(&["fetch", "--quiet"])?;
+
+        let upstream = "@{upstream}";
+        if self.git_status(&["merge-base", "--is-ancestor", upstream, "HEAD"])? {
+            return Ok(PullOutcome::default());
+        }
+
+        if self.git_status(&["merge-base", "--is-ancestor", "HEAD", upstream])? {
+            self.git(&["merge", "--quiet", "--ff-only", upstream])?;
+            return Ok(PullOutcome {
+                updated: true,
+                conflicts: vec![],
+            });
+        }
+
+        let merge_base = self.git(&["merge-base", "HEAD", upstream])?;
+        let base = self
+            .read_revision(merge_base.trim_end())?
+            .unwrap_or_default();
+        let local = self.read_revision("HEAD")?.unwrap_or_default();
+        let remote = self.read_revision(upstream)?.unwrap_or_default();
+
+        // let git merge the other files, a failure here is usually a conflict in our file
+        let merged = self.git(&["merge", "--quiet", "--no-ff", "--no-commit", upstream]);
+        let unmerged = self.git(&["diff", "--name-only", "--diff-filter=U"])?;
+        let file = self.file_arg();
+        if unmerged.lines().any(|path| path != file) {
+            _ = self.git(&["merge", "--abort"]);
+            return Err(SyncError::GitError {
+                command: format!("merge {upstream}"),
+                message: format!("files other than {file} have conflicts"),
+            });
+        }
+        // without a conflict, the merge did not start at all, such as when there are uncommitted
+        // changes it would overwrite
+        if let (Err(e), true) = (merged, unmerged.trim().is_empty()) {
+            return Err(e);
+        }
+
+        let outcome = if local.is_crdt() || remote.is_crdt() {
+            MergeOutcome {
+                database: merge_crdt(&local, &remote),
+                conflicts: vec![],
+            }
+        } else {
+            merge_three_way(&base, &local, &remote)
+        };
+        let path = self.repo_dir.join(&self.file);
+        DatabaseFile::write_database(&outcome.database, &path)?;
+        self.git(&["add", "--", &file])?;
+        self.git(&["commit", "--quiet", "--no-edit"])?;
+
+        Ok(PullOutcome {
+            updated: true,
+            conflicts: outcome.conflicts,
+        })
+    }
+
+    /// Pushes the current branch to its upstream.
+    pub fn push(&self) -> Result<(), SyncError> {
+        self.git(&["push", "--quiet"])?;
+        Ok(())
+    }
+
+    /// Counts the local commits that have not been pushed to the upstream branch yet, such as
+    /// commits made while the remote could not be reached.
+    pub fn unpushed_commits(&self) -> Result<usize, SyncError> {
+        let output = self.git(&["rev-list", "--count", "@{upstream}..HEAD"])?;
+        output.trim().parse().map_err(|_| SyncError::GitError {
+            command: "rev-list --count @{upstream}..HEAD".to_string(),
+            message: format!("unexpected output: {output}"),
+        })
+    }
+
+    /// Reads the database file as it was in the given revision. Returns `None` if the file did not
+    /// exist in that revision.
+    fn read_revision(&self, revision: &str) -> Result<Option<Database>, SyncError> {
+        let spec = format!("{revision}:{}", self.file_arg());
+        let output = self.command(&["show", &spec])?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let invalid = |source| SyncError::InvalidRevision {
+            revision: revision.to_string(),
+            source,
+        };
+        let database = DatabaseFile::parse(&output.stdout)
+            .map_err(invalid)?
+            .try_into()
+            .map_err(invalid)?;
+        Ok(Some(database))
+    }
+
+    /// The path of the database file as git expects it in arguments.
+    fn file_arg(&self) -> String {
+        self.file.to_string_lossy().replace('\\', "/")
+    }
+
+    fn command(&self, args: &[&str]) -> Result<Output, SyncError> {
+        Command::new("git")
+            .args(args)
+            .current_dir(&self.repo_dir)
+            .output()
+            .map_err(|source| SyncError::IoError {
+                path: self.repo_dir.clone(),
+                source,
+            })
+    }
+
+    /// Runs git and returns its output, failing if git returns an error.
+    fn git(&self, args: &[&str]) -> Result<String, SyncError> {
+        run_git(&self.repo_dir, args)
+    }
+
+    /// Runs git and returns whether it succeeded, for commands that report a yes/no answer through
+    /// their exit code.
+    fn git_status(&self, args: &[&str]) -> Result<bool, SyncError> {
+        Ok(self.command(args)?.status.success())
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, SyncError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|source| SyncError::IoError {
+            path: dir.to_owned(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(SyncError::GitError {
+            command: args.join(" "),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Task;
+
+    #[test]
+    fn pull_stops_if_git_can_not_merge() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let (local, other) = (dir.join("local"), dir.join("other"));
+        std::fs::create_dir_all(&local).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            run_git(dir, args).unwrap();
+        };
+        let write = |dir: &Path, title: &str| {
+            let mut database = Database::default();
+            database.add_task(Task::create_now(title.into()));
+            DatabaseFile::write_database(&database, &dir.join("todo.json")).unwrap();
+        };
+
+        git(dir, &["init", "--quiet", "--bare", "remote.git"]);
+        git(dir, &["clone", "--quiet", "remote.git", "local"]);
+        git(&local, &["config", "user.name", "td"]);
+        git(&local, &["config", "user.email", "td@example.com"]);
+        write(&local, "base");
+        std::fs::write(local.join("notes.txt"), "base").unwrap();
+        git(&local, &["add", "."]);
+        git(&local, &["commit", "--quiet", "-m", "base"]);
+        git(&local, &["push", "--quiet", "-u", "origin", "HEAD"]);
+
+        git(dir, &["clone", "--quiet", "remote.git", "other"]);
+        git(&other, &["config", "user.name", "td"]);
+        git(&other, &["config", "user.email", "td@example.com"]);
+        write(&other, "remote");
+        std::fs::write(other.join("notes.txt"), "remote").unwrap();
+        git(&other, &["commit", "--quiet", "-am", "remote"]);
+        git(&other, &["push", "--quiet"]);
+
+        // git refuses to merge over the uncommitted change to the other file
+        write(&local, "local");
+        let sync = GitSync::open(&local.join("todo.json")).unwrap();
+        sync.commit(&Database::default()).unwrap();
+        std::fs::write(local.join("notes.txt"), "uncommitted").unwrap();
+
+        let result = sync.pull();
+        let database: Database = DatabaseFile::read(&local.join("todo.json"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert!(matches!(result, Err(SyncError::GitError { .. })));
+        let titles = database
+            .get_all_tasks()
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["local"]);
+    }
+}
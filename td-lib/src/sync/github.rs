@@ -0,0 +1,424 @@
+//! Synchronizing tasks with the issues of a GitHub repository.
+//!
+//! Open issues are imported as tasks, which are linked to their issue through their sync state
+//! (see [`Task::mark_synced`]) with `owner/name#number` as the remote id. When the issues are
+//! synchronized again, the tasks of closed issues are completed and the tasks of reopened issues
+//! are reopened. The issues of tasks that were completed in td can be closed as well.
+//!
+//! Talking to GitHub needs the `github` feature. Without it, issues can still be applied with
+//! [`apply_issues`].
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::database::{Database, Task};
+#[cfg(feature = "github")]
+use crate::errors::SyncError;
+
+/// The name of the service in the sync state of tasks.
+pub const PROVIDER: &str = "github";
+
+/// The key of the [`Task::metadata`] that holds the issue of an imported task, as
+/// `owner/name#number`.
+pub const ISSUE_METADATA_KEY: &str = "github.issue";
+
+/// A GitHub repository, written as `owner/name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    /// The user or organization that owns the repository.
+    pub owner: String,
+    /// The name of the repository.
+    pub name: String,
+}
+
+impl FromStr for Repository {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self {
+                    owner: owner.to_string(),
+                    name: name.to_string(),
+                })
+            }
+            _ => Err(format!("invalid repository: {s}, expected owner/name")),
+        }
+    }
+}
+
+impl Display for Repository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.owner, self.name)
+    }
+}
+
+/// An issue of a GitHub repository, as returned by the GitHub API.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Issue {
+    /// The number of the issue in its repository.
+    pub number: u64,
+    /// The title of the issue.
+    pub title: String,
+    /// Whether the issue is open.
+    pub state: IssueState,
+    /// The labels of the issue, which become tags.
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// When the issue was closed, if it is.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub closed_at: Option<OffsetDateTime>,
+    /// Set if the issue is a pull request. Pull requests are not imported.
+    #[serde(default)]
+    pub pull_request: Option<serde_json::Value>,
+}
+
+/// Whether an [`Issue`] is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueState {
+    /// The issue is open.
+    Open,
+    /// The issue is closed.
+    Closed,
+}
+
+/// A label of an [`Issue`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Label {
+    /// The name of the label.
+    pub name: String,
+}
+
+/// What synchronizing with the issues of a repository changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IssueSync {
+    /// How many open issues were imported as new tasks.
+    pub imported: usize,
+    /// How many tasks were completed because their issue was closed.
+    pub completed: usize,
+    /// How many tasks were reopened because their issue was reopened.
+    pub reopened: usize,
+    /// The numbers of the open issues whose task was completed in td.
+    pub to_close: Vec<u64>,
+    /// How many issues were closed because their task was completed.
+    pub closed: usize,
+}
+
+impl Display for IssueSync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = [
+            (self.imported, "imported"),
+            (self.completed, "completed"),
+            (self.reopened, "reopened"),
+            (self.closed, "closed on GitHub"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, action)| format!("{count} {action}"))
+        .collect::<Vec<_>>();
+
+        if parts.is_empty() {
+            write!(f, "no changes")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Gets the remote id of the task that belongs to the given issue.
+fn remote_id(repository: &Repository, number: u64) -> String {
+    format!("{repository}#{number}")
+}
+
+/// Applies the issues of the given repository to the database. Open issues that were not imported
+/// yet become tasks, tagged with their labels. The tasks of issues that were closed are completed,
+/// and the tasks of issues that were reopened are reopened.
+///
+/// Tasks that were completed since their last sync while their issue is still open are left
+/// alone, and their issues are listed in [`IssueSync::to_close`].
+pub fn apply_issues(
+    database: &mut Database,
+    repository: &Repository,
+    issues: &[Issue],
+    now: OffsetDateTime,
+) -> IssueSync {
+    let mut sync = IssueSync::default();
+
+    for issue in issues.iter().filter(|issue| issue.pull_request.is_none()) {
+        let remote_id = remote_id(repository, issue.number);
+        let Some(task) = database.find_synced_task(PROVIDER, &remote_id) else {
+            if issue.state == IssueState::Open {
+                let mut task = Task::create_now(issue.title.clone());
+                task.time_created = now;
+                task.tags = issue
+                    .labels
+                    .iter()
+                    .map(|l| l.name.as_str().into())
+                    .collect();
+                task.metadata
+                    .insert(ISSUE_METADATA_KEY.to_string(), remote_id.clone());
+                task.mark_synced(PROVIDER, remote_id, now);
+                database.add_task(task);
+                sync.imported += 1;
+            }
+            continue;
+        };
+
+        let task_id = task.id().clone();
+        let last_synced = task.sync_state[PROVIDER].last_synced;
+        match (issue.state, task.time_completed) {
+            (IssueState::Closed, None) => {
+                database[&task_id].time_completed = Some(issue.closed_at.unwrap_or(now));
+                sync.completed += 1;
+            }
+            (IssueState::Open, Some(completed)) if completed > last_synced => {
+                // stays unsynced until the issue is closed, so it is not seen as reopened
+                sync.to_close.push(issue.number);
+                continue;
+            }
+            (IssueState::Open, Some(_)) => {
+                database[&task_id].time_completed = None;
+                sync.reopened += 1;
+            }
+            _ => (),
+        }
+        database[&task_id].mark_synced(PROVIDER, remote_id, now);
+    }
+
+    sync
+}
+
+/// Records that the given issue was closed, so its completed task is in sync again.
+pub fn mark_closed(
+    database: &mut Database,
+    repository: &Repository,
+    number: u64,
+    now: OffsetDateTime,
+) {
+    let remote_id = remote_id(repository, number);
+    if let Some(task) = database.find_synced_task(PROVIDER, &remote_id) {
+        let task_id = task.id().clone();
+        database[&task_id].mark_synced(PROVIDER, remote_id, now);
+    }
+}
+
+/// Talks to the GitHub API.
+///
+/// Requests are authenticated with the token in the `GITHUB_TOKEN` or `GH_TOKEN` environment
+/// variable, if there is one. A token is needed for private repositories and to close issues.
+#[cfg(feature = "github")]
+pub struct GitHubClient {
+    agent: ureq::Agent,
+    token: Option<String>,
+}
+
+#[cfg(feature = "github")]
+impl GitHubClient {
+    const API_URL: &'static str = "https://api.github.com";
+    const PAGE_SIZE: usize = 100;
+
+    /// Creates a client that uses the token from the environment, if there is one.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+            .into_iter()
+            .find_map(|name| std::env::var(name).ok())
+            .filter(|token| !token.is_empty());
+        Self {
+            agent: ureq::AgentBuilder::new().user_agent("td").build(),
+            token,
+        }
+    }
+
+    /// Fetches all open and closed issues of the repository.
+    pub fn fetch_issues(&self, repository: &Repository) -> Result<Vec<Issue>, SyncError> {
+        let url = format!("{}/repos/{repository}/issues", Self::API_URL);
+        let mut issues = vec![];
+        for page in 1.. {
+            let page_issues = self
+                .request("GET", &url)
+                .query("state", "all")
+                .query("per_page", &Self::PAGE_SIZE.to_string())
+                .query("page", &page.to_string())
+                .call()
+                .map_err(|e| github_error(&url, &e))?
+                .into_json::<Vec<Issue>>()
+                .map_err(|e| github_error(&url, &e))?;
+
+            let is_last_page = page_issues.len() < Self::PAGE_SIZE;
+            issues.extend(page_issues);
+            if is_last_page {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Closes the given issue.
+    pub fn close_issue(&self, repository: &Repository, number: u64) -> Result<(), SyncError> {
+        let url = format!("{}/repos/{repository}/issues/{number}", Self::API_URL);
+        self.request("PATCH", &url)
+            .send_json(serde_json::json!({ "state": "closed" }))
+            .map_err(|e| github_error(&url, &e))?;
+        Ok(())
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, url)
+            .set("Accept", "application/vnd.github+json");
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+            None => request,
+        }
+    }
+}
+
+#[cfg(feature = "github")]
+fn github_error(url: &str, error: &impl Display) -> SyncError {
+    SyncError::GitHubError {
+        url: url.to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// Fetches the issues of the repository and applies them with [`apply_issues`]. With
+/// `close_completed`, the issues of tasks that were completed in td are closed as well.
+#[cfg(feature = "github")]
+pub fn sync(
+    client: &GitHubClient,
+    database: &mut Database,
+    repository: &Repository,
+    close_completed: bool,
+    now: OffsetDateTime,
+) -> Result<IssueSync, SyncError> {
+    let issues = client.fetch_issues(repository)?;
+    let mut sync = apply_issues(database, repository, &issues, now);
+    if close_completed {
+        close_issues(client, database, repository, &mut sync, now)?;
+    }
+    Ok(sync)
+}
+
+/// Closes the issues in [`IssueSync::to_close`] and counts them in [`IssueSync::closed`]. Stops at
+/// the first issue that could not be closed, leaving it and the rest in the list.
+#[cfg(feature = "github")]
+pub fn close_issues(
+    client: &GitHubClient,
+    database: &mut Database,
+    repository: &Repository,
+    sync: &mut IssueSync,
+    now: OffsetDateTime,
+) -> Result<(), SyncError> {
+    while let Some(&number) = sync.to_close.first() {
+        client.close_issue(repository, number)?;
+        mark_closed(database, repository, number, now);
+        sync.to_close.remove(0);
+        sync.closed += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    fn issue(number: u64, state: IssueState) -> Issue {
+        Issue {
+            number,
+            title: format!("issue {number}"),
+            state,
+            labels: vec![Label { name: "bug".into() }],
+            closed_at: None,
+            pull_request: None,
+        }
+    }
+
+    fn task_of(database: &Database, number: u64) -> &Task {
+        database
+            .find_synced_task(PROVIDER, &format!("holly/td#{number}"))
+            .expect("issue should have a task")
+    }
+
+    #[test]
+    fn parses_repositories() {
+        let repository = "holly/td".parse::<Repository>().unwrap();
+        assert_eq!(repository.owner, "holly");
+        assert_eq!(repository.to_string(), "holly/td");
+        assert!("holly".parse::<Repository>().is_err());
+        assert!("holly/td/issues".parse::<Repository>().is_err());
+        assert!("/td".parse::<Repository>().is_err());
+    }
+
+    #[test]
+    fn parses_api_issues() {
+        let json = r#"[{
+            "number": 12, "title": "Crash", "state": "closed", "labels": [{ "name": "bug" }],
+            "closed_at": "2024-05-01T12:00:00Z", "html_url": "https://github.com/holly/td/issues/12"
+        }, {
+            "number": 13, "title": "Fix crash", "state": "open", "closed_at": null,
+            "pull_request": { "url": "https://api.github.com/repos/holly/td/pulls/13" }
+        }]"#;
+        let issues = serde_json::from_str::<Vec<Issue>>(json).unwrap();
+        assert_eq!(issues[0].state, IssueState::Closed);
+        assert_eq!(issues[0].closed_at, Some(datetime!(2024-05-01 12:00 UTC)));
+        assert!(issues[1].pull_request.is_some());
+    }
+
+    #[test]
+    fn imports_issues_and_follows_their_state() {
+        let repository = "holly/td".parse().unwrap();
+        let mut database = Database::default();
+        let now = datetime!(2024-05-01 12:00 UTC);
+
+        let mut pull_request = issue(3, IssueState::Open);
+        pull_request.pull_request = Some(serde_json::Value::Null);
+        let issues = [
+            issue(1, IssueState::Open),
+            issue(2, IssueState::Closed),
+            pull_request,
+        ];
+        let sync = apply_issues(&mut database, &repository, &issues, now);
+        assert_eq!(sync.imported, 1);
+        assert_eq!(database.get_all_tasks().count(), 1);
+        assert_eq!(task_of(&database, 1).tags, ["bug"]);
+        assert_eq!(
+            task_of(&database, 1).metadata[ISSUE_METADATA_KEY],
+            "holly/td#1"
+        );
+
+        // closed on GitHub
+        let now = now + Duration::hours(1);
+        let issues = [issue(1, IssueState::Closed)];
+        let sync = apply_issues(&mut database, &repository, &issues, now);
+        assert_eq!(sync.completed, 1);
+        assert_eq!(task_of(&database, 1).time_completed, Some(now));
+
+        // reopened on GitHub
+        let now = now + Duration::hours(1);
+        let issues = [issue(1, IssueState::Open)];
+        let sync = apply_issues(&mut database, &repository, &issues, now);
+        assert_eq!(sync.reopened, 1);
+        assert_eq!(task_of(&database, 1).time_completed, None);
+
+        // completed in td
+        let task_id = task_of(&database, 1).id().clone();
+        database[&task_id].time_completed = Some(now + Duration::minutes(5));
+        let now = now + Duration::hours(1);
+        let sync = apply_issues(&mut database, &repository, &issues, now);
+        assert_eq!(sync.to_close, [1]);
+        assert_eq!(sync.reopened, 0);
+        assert!(task_of(&database, 1).time_completed.is_some());
+
+        mark_closed(&mut database, &repository, 1, now);
+        let issues = [issue(1, IssueState::Closed)];
+        let sync = apply_issues(&mut database, &repository, &issues, now);
+        assert_eq!(sync, IssueSync::default());
+    }
+}
@@ -0,0 +1,293 @@
+//! Synchronizing a database with other copies of it.
+
+pub mod crdt;
+pub mod git;
+pub mod github;
+
+use std::collections::HashSet;
+
+use crate::database::{Database, Task, TaskId};
+
+/// The result of [`merge_three_way`].
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// The merged database.
+    pub database: Database,
+    /// Tasks that were changed in both copies. The local version was kept for these. Tasks that
+    /// got a dependency remotely that would create a cycle when merged are included as well,
+    /// without that dependency.
+    pub conflicts: Vec<TaskId>,
+}
+
+/// Merges the changes made in `local` and `remote` since their common ancestor `base`.
+///
+/// Changes are detected per task and per dependency, so edits to different tasks never conflict.
+/// If a task was changed differently in both copies, or changed in one and deleted in the other,
+/// the local version is kept and the task is reported as a conflict. Dependencies that were added
+/// in both copies can form a cycle together, the remote one is left out then.
+#[must_use]
+pub fn merge_three_way(base: &Database, local: &Database, remote: &Database) -> MergeOutcome {
+    let mut database = local.clone();
+    let mut conflicts = vec![];
+
+    for local_task in local.get_all_tasks() {
+        let id = local_task.id();
+        let base_task = find_task(base, id);
+        let remote_task = find_task(remote, id);
+
+        match (base_task, remote_task) {
+            (_, Some(remote_task)) if remote_task == local_task => (),
+            // only changed remotely
+            (Some(base_task), Some(remote_task)) if base_task == local_task => {
+                database[id] = remote_task.clone();
+            }
+            // only changed locally
+            (Some(base_task), Some(remote_task)) if base_task == remote_task => (),
+            // added or changed in both
+            (_, Some(_)) => conflicts.push(id.clone()),
+            // deleted remotely
+            (Some(base_task), None) if base_task == local_task => database.remove_task(id),
+            // deleted remotely but changed locally
+            (Some(_), None) => conflicts.push(id.clone()),
+            // added locally
+            (None, None) => (),
+        }
+    }
+
+    for remote_task in remote.get_all_tasks() {
+        let id = remote_task.id();
+        if find_task(local, id).is_some() {
+            continue;
+        }
+
+        match find_task(base, id) {
+            // added remotely
+            None => database.add_task(remote_task.clone()),
+            // deleted locally
+            Some(base_task) if base_task == remote_task => (),
+            // deleted locally but changed remotely, keep the deletion
+            Some(_) => conflicts.push(id.clone()),
+        }
+    }
+
+    conflicts.extend(merge_dependencies(&mut database, base, local, remote));
+
+    MergeOutcome {
+        database,
+        conflicts,
+    }
+}
+
+/// Applies the dependencies that were added, removed or changed in `remote` to `database`, which
+/// starts out as a copy of `local`. Returns the tasks whose added dependencies were left out
+/// because they would create a cycle.
+fn merge_dependencies(
+    database: &mut Database,
+    base: &Database,
+    local: &Database,
+    remote: &Database,
+) -> Vec<TaskId> {
+    let local_edges = dependency_edges(local);
+    let remote_edges = dependency_edges(remote);
+    let mut conflicts = vec![];
+
+    // removed first, so they can't make an added dependency look like it creates a cycle
+    for (from, to) in local_edges.difference(&remote_edges) {
+        let removed_remotely = base.get_dependency(from, to).is_some();
+        if removed_remotely {
+            database.remove_dependency(from, to);
+        }
+    }
+
+    for (from, to) in &remote_edges {
+        let remote_dependency = remote.get_dependency(from, to).cloned();
+        let base_dependency = base.get_dependency(from, to);
+        let local_dependency = local.get_dependency(from, to);
+
+        match (base_dependency, local_dependency) {
+            // added remotely
+            (None, None) => {
+                let both_exist = [from, to]
+                    .iter()
+                    .all(|id| find_task(database, id).is_some());
+                let creates_cycle = from == to
+                    || database
+                        .transitive_dependencies(to)
+                        .any(|task| task.id() == from);
+                if both_exist && creates_cycle {
+                    conflicts.push(from.clone());
+                } else if both_exist {
+                    let dependency = remote_dependency.unwrap_or_default();
+                    database.add_dependency_with(from, to, dependency);
+                }
+            }
+            // only changed remotely
+            (Some(base_dependency), Some(local_dependency))
+                if base_dependency == local_dependency =>
+            {
+                if let Some(dependency) = database.get_dependency_mut(from, to) {
+                    *dependency = remote_dependency.unwrap_or_default();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    conflicts
+}
+
+fn find_task<'a>(database: &'a Database, id: &TaskId) -> Option<&'a Task> {
+    let index = database.get_node_index(id)?;
+    Some(&database.graph[index])
+}
+
+fn dependency_edges(database: &Database) -> HashSet<(TaskId, TaskId)> {
+    database
+        .get_all_tasks()
+        .flat_map(|task| {
+            database
+                .get_dependencies(task.id())
+                .map(|dependency| (task.id().clone(), dependency.id().clone()))
+        })
+        .collect()
+}
+
+/// Describes the changes between two versions of a database in a single line, such as
+/// `2 tasks added, 1 task completed`.
+#[must_use]
+pub fn describe_changes(old: &Database, new: &Database) -> String {
+    let mut added = 0;
+    let mut completed = 0;
+    let mut changed = 0;
+    for task in new.get_all_tasks() {
+        match find_task(old, task.id()) {
+            None => added += 1,
+            Some(old_task)
+                if old_task.time_completed.is_none() && task.time_completed.is_some() =>
+            {
+                completed += 1;
+            }
+            Some(old_task) if old_task != task => changed += 1,
+            Some(_) => (),
+        }
+    }
+    let removed = old
+        .get_all_tasks()
+        .filter(|t| find_task(new, t.id()).is_none())
+        .count();
+
+    let parts = [
+        (added, "added"),
+        (completed, "completed"),
+        (changed, "changed"),
+        (removed, "removed"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, action)| {
+        let noun = if count == 1 { "task" } else { "tasks" };
+        format!("{count} {noun} {action}")
+    })
+    .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        "Update task dependencies".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_base() -> (Database, TaskId, TaskId) {
+        let mut db = Database::default();
+        let task_1 = Task::create_now("task 1".into());
+        let task_2 = Task::create_now("task 2".into());
+        let (id_1, id_2) = (task_1.id().clone(), task_2.id().clone());
+        db.add_task(task_1);
+        db.add_task(task_2);
+        (db, id_1, id_2)
+    }
+
+    #[test]
+    fn merges_changes_to_different_tasks() {
+        let (base, id_1, id_2) = create_base();
+        let mut local = base.clone();
+        let mut remote = base.clone();
+        local[&id_1].title = "local title".into();
+        remote[&id_2].title = "remote title".into();
+        let remote_task = Task::create_now("remote task".into());
+        let remote_id = remote_task.id().clone();
+        remote.add_task(remote_task);
+        remote.add_dependency(&id_1, &remote_id);
+
+        let outcome = merge_three_way(&base, &local, &remote);
+
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.database[&id_1].title, "local title");
+        assert_eq!(outcome.database[&id_2].title, "remote title");
+        assert_eq!(outcome.database[&remote_id].title, "remote task");
+        assert!(outcome.database.get_dependency(&id_1, &remote_id).is_some());
+    }
+
+    #[test]
+    fn keeps_local_version_on_conflict() {
+        let (base, id_1, id_2) = create_base();
+        let mut local = base.clone();
+        let mut remote = base.clone();
+        local[&id_1].title = "local title".into();
+        remote[&id_1].title = "remote title".into();
+        remote.remove_task(&id_2);
+
+        let outcome = merge_three_way(&base, &local, &remote);
+
+        assert_eq!(outcome.conflicts, vec![id_1.clone()]);
+        assert_eq!(outcome.database[&id_1].title, "local title");
+        assert_eq!(outcome.database.get_all_tasks().count(), 1);
+    }
+
+    #[test]
+    fn merges_removed_dependencies() {
+        let (mut base, id_1, id_2) = create_base();
+        base.add_dependency(&id_1, &id_2);
+        let local = base.clone();
+        let mut remote = base.clone();
+        remote.remove_dependency(&id_1, &id_2);
+
+        let outcome = merge_three_way(&base, &local, &remote);
+
+        assert_eq!(outcome.database.get_dependency(&id_1, &id_2), None);
+    }
+
+    #[test]
+    fn leaves_out_dependencies_that_create_a_cycle() {
+        let (base, id_1, id_2) = create_base();
+        let mut local = base.clone();
+        let mut remote = base.clone();
+        local.add_dependency(&id_1, &id_2);
+        remote.add_dependency(&id_2, &id_1);
+
+        let outcome = merge_three_way(&base, &local, &remote);
+
+        assert_eq!(outcome.conflicts, vec![id_2.clone()]);
+        assert!(outcome.database.get_dependency(&id_1, &id_2).is_some());
+        assert_eq!(outcome.database.get_dependency(&id_2, &id_1), None);
+    }
+
+    #[test]
+    fn describes_changes() {
+        let (old, id_1, id_2) = create_base();
+        let mut new = old.clone();
+        new[&id_1].time_completed = Some(new[&id_1].time_created);
+        new.remove_task(&id_2);
+        new.add_task(Task::create_now("task 3".into()));
+        new.add_task(Task::create_now("task 4".into()));
+
+        assert_eq!(
+            describe_changes(&old, &new),
+            "2 tasks added, 1 task completed, 1 task removed"
+        );
+    }
+}
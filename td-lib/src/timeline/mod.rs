@@ -0,0 +1,234 @@
+//! Laying tasks out on a timeline, so the order in which they have to be done can be seen at a
+//! glance.
+//!
+//! Every task gets a row with a bar that runs from when it was started to when it was completed.
+//! Tasks that are not done yet run until they are due, or until now if they have no due date.
+//! Tasks that were not started yet can start once their dependencies are done, so their bar starts
+//! where the bars of their dependencies end.
+//!
+//! Rows are ordered so that every task comes right after the tasks it depends on, which keeps
+//! chains of dependencies together and makes the arrows between them point down.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
+use time::OffsetDateTime;
+
+use crate::database::{Database, Task, TaskId};
+
+/// Tasks laid out on a timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    /// The rows, from top to bottom.
+    pub rows: Vec<TimelineRow>,
+    /// The start of the earliest bar.
+    pub start: OffsetDateTime,
+    /// The end of the latest bar.
+    pub end: OffsetDateTime,
+}
+
+/// A single task on a [`Timeline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineRow {
+    /// The task on this row.
+    pub task: TaskId,
+    /// The start of the bar of the task.
+    pub start: OffsetDateTime,
+    /// The end of the bar of the task.
+    pub end: OffsetDateTime,
+    /// Whether the end of the bar is only an estimate, because the task is not completed.
+    pub open_ended: bool,
+    /// The rows of the tasks this task depends on.
+    pub dependencies: Vec<usize>,
+}
+
+impl Timeline {
+    /// Lays out the tasks for which `include` returns `true`. Dependencies on tasks that are left
+    /// out are not shown.
+    #[must_use]
+    pub fn new(database: &Database, now: OffsetDateTime, include: impl Fn(&Task) -> bool) -> Self {
+        let graph = &database.graph;
+        let included = graph
+            .node_indices()
+            .filter(|index| include(&graph[*index]))
+            .collect::<HashSet<_>>();
+
+        // start from the tasks nothing depends on, so their dependencies are placed above them
+        let mut roots = included
+            .iter()
+            .copied()
+            .filter(|index| {
+                !graph
+                    .edges_directed(*index, Direction::Incoming)
+                    .any(|edge| included.contains(&edge.source()))
+            })
+            .collect::<Vec<_>>();
+        roots.sort_by_key(|index| (graph[*index].time_created, *index));
+        // tasks that are only part of cycles are not reachable from a root
+        let mut rest = included.iter().copied().collect::<Vec<_>>();
+        rest.sort_by_key(|index| (graph[*index].time_created, *index));
+
+        let mut order = Vec::with_capacity(included.len());
+        let mut visited = HashSet::new();
+        for index in roots.into_iter().chain(rest) {
+            place(database, &included, index, &mut visited, &mut order);
+        }
+
+        let mut rows = Vec::<TimelineRow>::with_capacity(order.len());
+        let mut row_of = HashMap::new();
+        for index in order {
+            let task = &graph[index];
+            let dependencies = graph
+                .edges_directed(index, Direction::Outgoing)
+                .filter_map(|edge| row_of.get(&edge.target()).copied())
+                .collect::<Vec<usize>>();
+
+            let start = match task.time_started {
+                Some(started) => started,
+                None => dependencies
+                    .iter()
+                    .map(|row| rows[*row].end)
+                    .fold(task.time_created, OffsetDateTime::max),
+            };
+            let (end, open_ended) = match (task.time_completed, task.time_due) {
+                (Some(completed), _) => (completed, false),
+                (None, Some(due)) => (due, true),
+                (None, None) => (now, true),
+            };
+
+            row_of.insert(index, rows.len());
+            rows.push(TimelineRow {
+                task: task.id().clone(),
+                start,
+                end: end.max(start),
+                open_ended,
+                dependencies,
+            });
+        }
+
+        Self {
+            start: rows.iter().map(|r| r.start).min().unwrap_or(now),
+            end: rows.iter().map(|r| r.end).max().unwrap_or(now),
+            rows,
+        }
+    }
+
+    /// Gets the column that the given time falls in, when the timeline is `width` columns wide.
+    #[must_use]
+    pub fn column(&self, time: OffsetDateTime, width: u16) -> u16 {
+        let total = (self.end - self.start).as_seconds_f64();
+        if total <= 0.0 || width == 0 {
+            return 0;
+        }
+        let fraction = (time - self.start).as_seconds_f64() / total;
+        ((fraction * f64::from(width - 1)).round() as u16).min(width - 1)
+    }
+}
+
+/// Places the given task after the tasks it depends on.
+fn place(
+    database: &Database,
+    included: &HashSet<NodeIndex>,
+    index: NodeIndex,
+    visited: &mut HashSet<NodeIndex>,
+    order: &mut Vec<NodeIndex>,
+) {
+    if !visited.insert(index) {
+        return;
+    }
+
+    let graph = &database.graph;
+    let mut dependencies = graph
+        .edges_directed(index, Direction::Outgoing)
+        .map(|edge| edge.target())
+        .filter(|dependency| included.contains(dependency))
+        .collect::<Vec<_>>();
+    dependencies.sort_by_key(|dependency| (graph[*dependency].time_created, *dependency));
+    for dependency in dependencies {
+        place(database, included, dependency, visited, order);
+    }
+
+    order.push(index);
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    #[test]
+    fn places_dependencies_first_and_starts_after_them() {
+        let mut db = Database::default();
+        let created = datetime!(2024-05-01 9:00 UTC);
+        let tasks = ["release", "build", "design", "unrelated"].map(|title| {
+            let mut task = Task::create_now(title.to_string());
+            task.time_created = created;
+            task
+        });
+        let [release, build, design, unrelated] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&release, &build);
+        db.add_dependency(&build, &design);
+        db.add_dependency(&release, &design);
+        db[&design].time_started = Some(created);
+        db[&design].time_completed = Some(created + Duration::days(2));
+        db[&build].time_due = Some(created + Duration::days(5));
+        db[&unrelated].time_created = created + Duration::days(1);
+
+        let now = created + Duration::days(3);
+        let timeline = Timeline::new(&db, now, |_| true);
+        let tasks = timeline.rows.iter().map(|r| &r.task).collect::<Vec<_>>();
+        assert_eq!(tasks, [&design, &build, &release, &unrelated]);
+
+        let rows = &timeline.rows;
+        assert_eq!(
+            (rows[0].end, rows[0].open_ended),
+            (created + Duration::days(2), false)
+        );
+        // build can only start once design is done, and is planned to end when it is due
+        assert_eq!(rows[1].start, created + Duration::days(2));
+        assert_eq!(
+            (rows[1].end, rows[1].open_ended),
+            (created + Duration::days(5), true)
+        );
+        assert_eq!(rows[1].dependencies, [0]);
+        // release has no due date, so it ends now, but not before it can start
+        assert_eq!(rows[2].start, created + Duration::days(5));
+        assert_eq!(rows[2].end, rows[2].start);
+        assert_eq!(rows[3].end, now);
+
+        assert_eq!(timeline.start, created);
+        assert_eq!(timeline.end, created + Duration::days(5));
+        assert_eq!(timeline.column(created, 11), 0);
+        assert_eq!(timeline.column(created + Duration::days(1), 11), 2);
+        assert_eq!(timeline.column(timeline.end, 11), 10);
+    }
+
+    #[test]
+    fn leaves_out_excluded_tasks_and_survives_cycles() {
+        let mut db = Database::default();
+        let tasks = ["a", "b", "hidden"].map(|title| Task::create_now(title.to_string()));
+        let [a, b, hidden] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&a, &hidden);
+        db.graph.add_edge(
+            db.get_node_index(&a).unwrap(),
+            db.get_node_index(&b).unwrap(),
+            Default::default(),
+        );
+        db.graph.add_edge(
+            db.get_node_index(&b).unwrap(),
+            db.get_node_index(&a).unwrap(),
+            Default::default(),
+        );
+
+        let timeline = Timeline::new(&db, OffsetDateTime::now_utc(), |t| t.title != "hidden");
+        assert_eq!(timeline.rows.len(), 2);
+        assert!(timeline.rows.iter().all(|r| r.task != hidden));
+    }
+}
@@ -0,0 +1,188 @@
+//! Saving the database automatically on a background thread, so the ui never waits for the disk.
+
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use td_lib::{
+    database::{database_file::DatabaseFile, Database},
+    errors::DatabaseWriteError,
+};
+
+/// When to save automatically. Nothing is saved automatically by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoSaveOptions {
+    /// Save changes once there was no input for this many seconds.
+    pub idle_secs: Option<u64>,
+    /// Save after every change.
+    pub on_change: bool,
+}
+
+/// The result of a save that ran in the background.
+pub struct FinishedSave {
+    /// The revision of the database that was saved.
+    pub revision: u64,
+    /// The database as it was written to disk.
+    pub disk_database: Database,
+    /// The version that was overwritten, if it was asked for.
+    pub previous: Option<Database>,
+    pub result: Result<(), DatabaseWriteError>,
+}
+
+/// Decides when to save automatically and keeps track of the save that is being written.
+pub struct SaveScheduler {
+    options: AutoSaveOptions,
+    last_input: Instant,
+    /// Where the result of the save that is being written arrives.
+    in_flight: Option<Receiver<FinishedSave>>,
+}
+
+impl SaveScheduler {
+    pub fn new(options: AutoSaveOptions, now: Instant) -> Self {
+        Self {
+            options,
+            last_input: now,
+            in_flight: None,
+        }
+    }
+
+    pub fn record_input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Checks if unsaved changes should be saved now. Only one save is written at a time.
+    pub fn is_due(&self, now: Instant, has_changes: bool) -> bool {
+        let idle = self
+            .options
+            .idle_secs
+            .is_some_and(|secs| now - self.last_input >= Duration::from_secs(secs));
+
+        has_changes && !self.is_saving() && (self.options.on_change || idle)
+    }
+
+    /// Whether a save is being written right now.
+    pub fn is_saving(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    /// Writes the database to the file at the given path on a background thread, using the same
+    /// atomic write as a normal save. With `read_previous`, the version that is overwritten is
+    /// read first so it can be compared with the new one.
+    pub fn start(
+        &mut self,
+        revision: u64,
+        disk_database: Database,
+        path: PathBuf,
+        read_previous: bool,
+    ) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let previous = read_previous
+                .then(|| DatabaseFile::read(&path).ok()?.try_into().ok())
+                .flatten();
+            let result = DatabaseFile::write_database(&disk_database, &path);
+            _ = sender.send(FinishedSave {
+                revision,
+                disk_database,
+                previous,
+                result,
+            });
+        });
+        self.in_flight = Some(receiver);
+    }
+
+    /// Takes the result of the save that is being written, if it finished.
+    pub fn poll(&mut self) -> Option<FinishedSave> {
+        let receiver = self.in_flight.as_ref()?;
+        match receiver.try_recv() {
+            Ok(finished) => {
+                self.in_flight = None;
+                Some(finished)
+            }
+            Err(TryRecvError::Empty) => None,
+            // the save panicked, don't wait for it forever
+            Err(TryRecvError::Disconnected) => {
+                self.in_flight = None;
+                None
+            }
+        }
+    }
+
+    /// Waits for the save that is being written to finish, so nothing else writes the file at the
+    /// same time.
+    pub fn wait(&mut self) -> Option<FinishedSave> {
+        let receiver = self.in_flight.take()?;
+        receiver.recv().ok()
+    }
+}
+
+impl Default for SaveScheduler {
+    fn default() -> Self {
+        Self::new(AutoSaveOptions::default(), Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use td_lib::database::Task;
+
+    use super::*;
+
+    #[test]
+    fn schedules_saves() {
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+
+        let disabled = SaveScheduler::new(AutoSaveOptions::default(), start);
+        assert!(!disabled.is_due(later(600), true));
+
+        let options = AutoSaveOptions {
+            idle_secs: Some(5),
+            on_change: false,
+        };
+        let mut scheduler = SaveScheduler::new(options, start);
+        scheduler.record_input(later(10));
+        assert!(!scheduler.is_due(later(12), true));
+        assert!(scheduler.is_due(later(15), true));
+        assert!(!scheduler.is_due(later(15), false));
+
+        let on_change = AutoSaveOptions {
+            on_change: true,
+            ..Default::default()
+        };
+        assert!(SaveScheduler::new(on_change, start).is_due(start, true));
+    }
+
+    #[test]
+    fn saves_in_the_background() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("todo.json");
+        let mut database = Database::default();
+        database.add_task(Task::create_now("saved in the background".into()));
+
+        let mut scheduler = SaveScheduler::new(
+            AutoSaveOptions {
+                on_change: true,
+                ..Default::default()
+            },
+            Instant::now(),
+        );
+        scheduler.start(7, database, path.clone(), true);
+        assert!(scheduler.is_saving());
+        assert!(!scheduler.is_due(Instant::now(), true));
+
+        let finished = scheduler.wait().unwrap();
+        assert!(!scheduler.is_saving());
+        assert_eq!(finished.revision, 7);
+        assert!(finished.result.is_ok());
+        assert!(finished.previous.is_none());
+
+        let loaded: Database = DatabaseFile::read(&path).unwrap().try_into().unwrap();
+        assert_eq!(loaded.get_all_tasks().count(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+//! Scheduling of automatic git syncs, so the database stays up to date without syncing after
+//! every change.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use td_lib::time::OffsetDateTime;
+
+/// When to sync automatically. Automatic syncs only happen if git sync is enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoSyncOptions {
+    /// Sync every this many seconds.
+    pub interval_secs: Option<u64>,
+    /// Sync after every save.
+    pub on_save: bool,
+    /// Sync saved changes once there was no input for this many seconds.
+    pub idle_secs: Option<u64>,
+}
+
+/// Decides when to sync automatically and keeps track of how the last sync went.
+pub struct SyncScheduler {
+    options: AutoSyncOptions,
+    started: Instant,
+    last_input: Instant,
+    last_attempt: Option<Instant>,
+    last_sync: Option<(Instant, OffsetDateTime)>,
+    /// Whether changes were saved since the last successful sync.
+    pending: bool,
+    /// How many commits are waiting to be pushed, as of the last check.
+    queued_commits: usize,
+    /// Whether the last sync failed.
+    failed: bool,
+}
+
+impl SyncScheduler {
+    /// The minimum time between automatic syncs, so a slow or unreachable remote is not retried
+    /// all the time.
+    const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(options: AutoSyncOptions, now: Instant) -> Self {
+        Self {
+            options,
+            started: now,
+            last_input: now,
+            last_attempt: None,
+            last_sync: None,
+            pending: false,
+            queued_commits: 0,
+            failed: false,
+        }
+    }
+
+    pub fn record_input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Records that changes were saved, which have to be synced.
+    pub fn record_save(&mut self) {
+        self.pending = true;
+    }
+
+    /// Records how many commits are waiting to be pushed. These are synced like saved changes, so
+    /// commits made while the remote could not be reached are pushed once it can be again, even
+    /// after restarting.
+    pub fn set_queued_commits(&mut self, count: usize) {
+        self.queued_commits = count;
+        if count > 0 {
+            self.pending = true;
+        }
+    }
+
+    /// Records the outcome of a sync, whether it was started automatically or not.
+    pub fn record_sync(&mut self, now: Instant, succeeded: bool) {
+        self.last_attempt = Some(now);
+        self.failed = !succeeded;
+        if succeeded {
+            let time = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+            self.last_sync = Some((now, time));
+            self.pending = false;
+        }
+    }
+
+    /// Checks if an automatic sync should happen now.
+    pub fn is_due(&self, now: Instant) -> bool {
+        if self
+            .last_attempt
+            .is_some_and(|attempt| now - attempt < Self::MIN_INTERVAL)
+        {
+            return false;
+        }
+
+        let last_sync = self.last_sync.map_or(self.started, |(instant, _)| instant);
+        let interval_elapsed = self
+            .options
+            .interval_secs
+            .is_some_and(|secs| now - last_sync >= Duration::from_secs(secs));
+        let idle = self
+            .options
+            .idle_secs
+            .is_some_and(|secs| now - self.last_input >= Duration::from_secs(secs));
+
+        interval_elapsed || (self.pending && (self.options.on_save || idle))
+    }
+
+    /// Describes the state of syncing for the status bar, if anything was synced or saved yet.
+    pub fn status(&self) -> Option<String> {
+        let format = td_lib::time::format_description::parse("[hour]:[minute]")
+            .expect("valid hardcoded time format");
+
+        if self.failed && self.queued_commits > 0 {
+            Some(format!(
+                "sync failed, {} commits queued",
+                self.queued_commits
+            ))
+        } else if self.failed {
+            Some("sync failed".to_string())
+        } else if self.pending {
+            Some("changes not synced".to_string())
+        } else {
+            let (_, time) = self.last_sync?;
+            Some(format!("synced {}", time.format(&format).ok()?))
+        }
+    }
+}
+
+impl Default for SyncScheduler {
+    fn default() -> Self {
+        Self::new(AutoSyncOptions::default(), Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_syncs() {
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+        let options = AutoSyncOptions {
+            interval_secs: Some(600),
+            on_save: false,
+            idle_secs: Some(60),
+        };
+        let mut scheduler = SyncScheduler::new(options, start);
+        assert!(!scheduler.is_due(later(10)));
+        assert!(scheduler.is_due(later(600)));
+
+        // saved changes are synced once the user is idle
+        scheduler.record_sync(later(600), true);
+        scheduler.record_save();
+        scheduler.record_input(later(630));
+        assert!(!scheduler.is_due(later(660)));
+        assert!(scheduler.is_due(later(690)));
+
+        // failed syncs are not retried right away
+        scheduler.record_sync(later(690), false);
+        assert!(!scheduler.is_due(later(700)));
+        assert!(scheduler.is_due(later(720)));
+        assert_eq!(scheduler.status().as_deref(), Some("sync failed"));
+    }
+
+    #[test]
+    fn replays_queued_commits() {
+        let start = Instant::now();
+        let options = AutoSyncOptions {
+            idle_secs: Some(60),
+            ..Default::default()
+        };
+        let mut scheduler = SyncScheduler::new(options, start);
+        scheduler.set_queued_commits(2);
+        assert!(scheduler.is_due(start + Duration::from_secs(60)));
+
+        scheduler.record_sync(start + Duration::from_secs(60), false);
+        assert_eq!(
+            scheduler.status().as_deref(),
+            Some("sync failed, 2 commits queued")
+        );
+    }
+}
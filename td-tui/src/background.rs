@@ -0,0 +1,152 @@
+//! Expensive computations that run on a background thread, so they never block input.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+/// A value that is computed on a background thread from some inputs, identified by a key.
+///
+/// When the key changes, the value is computed again. The value for the previous key stays
+/// available until then, and results for keys that are no longer requested are discarded.
+pub struct BackgroundJob<K, T> {
+    /// The key of the latest result, along with the result itself.
+    result: Option<(K, T)>,
+    /// The key of the computation that is currently running, and where its result arrives.
+    running: Option<(K, Receiver<T>)>,
+}
+
+impl<K: PartialEq, T: Send + 'static> BackgroundJob<K, T> {
+    /// Starts computing the value for the given key, unless it is already known or being computed.
+    pub fn request(&mut self, key: K, compute: impl FnOnce() -> T + Send + 'static) {
+        let is_known = self.result.as_ref().is_some_and(|(k, _)| *k == key);
+        let is_running = self.running.as_ref().is_some_and(|(k, _)| *k == key);
+        if is_known || is_running {
+            return;
+        }
+
+        // the result of a running computation for an older key is dropped when it arrives
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || _ = sender.send(compute()));
+        self.running = Some((key, receiver));
+    }
+
+    /// Takes the result of the running computation if it finished. Returns whether a new result
+    /// became available.
+    pub fn poll(&mut self) -> bool {
+        let Some((_, receiver)) = &self.running else {return false;};
+
+        match receiver.try_recv() {
+            Ok(value) => {
+                let (key, _) = self.running.take().expect("job is running");
+                self.result = Some((key, value));
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            // the computation panicked, don't wait for it forever
+            Err(TryRecvError::Disconnected) => {
+                self.running = None;
+                false
+            }
+        }
+    }
+
+    /// Blocks until the running computation finishes, for when its result is needed right away.
+    /// Returns whether a new result became available.
+    pub fn wait(&mut self) -> bool {
+        let Some((_, receiver)) = &self.running else {return false;};
+
+        let value = receiver.recv();
+        let (key, _) = self.running.take().expect("job is running");
+        // the computation panicked if nothing was sent
+        let Ok(value) = value else {return false;};
+        self.result = Some((key, value));
+        true
+    }
+
+    /// Checks if a computation is still running.
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Gets the latest result, which may be for an older key while a new one is being computed.
+    pub fn latest(&self) -> Option<&T> {
+        self.result.as_ref().map(|(_, value)| value)
+    }
+
+    /// Checks if the latest result is for the key that was requested last.
+    pub fn is_up_to_date(&self) -> bool {
+        self.running.is_none() && self.result.is_some()
+    }
+}
+
+impl<K, T> Default for BackgroundJob<K, T> {
+    fn default() -> Self {
+        Self {
+            result: None,
+            running: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn wait_for<K: PartialEq, T: Send + 'static>(job: &mut BackgroundJob<K, T>) {
+        let started = Instant::now();
+        while !job.poll() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "job never finished"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn computes_values_per_key() {
+        let mut job = BackgroundJob::default();
+        assert_eq!(job.latest(), None);
+
+        job.request(1, || 10);
+        wait_for(&mut job);
+        assert_eq!(job.latest(), Some(&10));
+        assert!(job.is_up_to_date());
+
+        // the same key is not computed again
+        job.request(1, || unreachable!());
+        assert!(job.is_up_to_date());
+
+        // the old value stays available while the new one is computed
+        let (sender, receiver) = mpsc::channel::<()>();
+        job.request(2, move || {
+            receiver.recv().unwrap();
+            20
+        });
+        assert!(!job.poll());
+        assert_eq!(job.latest(), Some(&10));
+        assert!(!job.is_up_to_date());
+
+        sender.send(()).unwrap();
+        wait_for(&mut job);
+        assert_eq!(job.latest(), Some(&20));
+    }
+
+    #[test]
+    fn waits_for_the_running_computation() {
+        let mut job = BackgroundJob::default();
+        assert!(!job.wait());
+
+        job.request(1, || {
+            thread::sleep(Duration::from_millis(10));
+            10
+        });
+        assert!(job.is_running());
+        assert!(job.wait());
+        assert!(!job.is_running());
+        assert_eq!(job.latest(), Some(&10));
+    }
+}
@@ -0,0 +1,278 @@
+//! Check scripts, which replay command line operations against a temporary database and check the
+//! result. They are meant for testing automation built on top of td against new versions.
+//!
+//! A script is a JSON file such as:
+//!
+//! ```json
+//! {
+//!     "database": "start.json",
+//!     "steps": [
+//!         { "run": ["--import-todotxt", "todo.txt"], "expect": { "output": "Imported 2 tasks" } },
+//!         { "expect": { "tasks": 2, "open": 1, "titles": ["Buy milk"], "tags": { "+home": 1 } } }
+//!     ]
+//! }
+//! ```
+//!
+//! Each step runs td with the given arguments, with `--db` pointing at a copy of `database`, or at
+//! an empty database if it is not given. Paths are relative to the working directory.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use td_lib::{database::Database, storage};
+
+use crate::cli::Args;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    /// The database to start with. It is copied, so it is never changed.
+    #[serde(default)]
+    database: Option<PathBuf>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    /// The arguments to run td with, without the database. Steps without arguments only check the
+    /// database.
+    #[serde(default)]
+    run: Vec<String>,
+    #[serde(default)]
+    expect: Expectations,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Expectations {
+    /// Whether running the step fails.
+    fails: bool,
+    /// Text that the output of the step contains, or the error if it failed.
+    output: Option<String>,
+    /// The amount of tasks in the database.
+    tasks: Option<usize>,
+    /// The amount of tasks that are not completed.
+    open: Option<usize>,
+    /// Titles of tasks that are in the database.
+    titles: Vec<String>,
+    /// The amount of tasks that have each tag.
+    tags: BTreeMap<String, usize>,
+}
+
+impl Expectations {
+    fn checks_database(&self) -> bool {
+        self.tasks.is_some()
+            || self.open.is_some()
+            || !self.titles.is_empty()
+            || !self.tags.is_empty()
+    }
+}
+
+/// The result of a single step of a script.
+#[derive(Debug)]
+struct StepResult {
+    name: String,
+    failures: Vec<String>,
+}
+
+/// Runs the check script at the given path, printing the result of each step. Returns whether all
+/// steps passed, or an error if the script could not be run at all.
+pub fn run(script_path: &Path) -> Result<bool, String> {
+    let script = std::fs::read_to_string(script_path)
+        .map_err(|e| format!("could not read {}: {e}", script_path.display()))?;
+    let script = serde_json::from_str(&script).map_err(|e| format!("invalid script: {e}"))?;
+
+    let dir = std::env::temp_dir().join(format!("td-check-{}", std::process::id()));
+    let results = run_in(&script, &dir);
+    _ = std::fs::remove_dir_all(&dir);
+    let results = results?;
+
+    for (number, result) in results.iter().enumerate() {
+        let status = if result.failures.is_empty() {
+            "ok  "
+        } else {
+            "FAIL"
+        };
+        println!("{status} {}: {}", number + 1, result.name);
+        for failure in &result.failures {
+            println!("       {failure}");
+        }
+    }
+    let passed = results.iter().filter(|r| r.failures.is_empty()).count();
+    println!("{passed} of {} steps passed", results.len());
+
+    Ok(passed == results.len())
+}
+
+/// Runs the script against a database in the given directory.
+fn run_in(script: &Script, dir: &Path) -> Result<Vec<StepResult>, String> {
+    let database_path = prepare_database(script.database.as_deref(), dir)
+        .map_err(|e| format!("could not create database: {e}"))?;
+
+    let results = script
+        .steps
+        .iter()
+        .map(|step| StepResult {
+            name: match step.run.is_empty() {
+                true => "(check database)".to_string(),
+                false => step.run.join(" "),
+            },
+            failures: run_step(step, &database_path),
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Creates the database that the script runs against in the given directory.
+fn prepare_database(
+    database: Option<&Path>,
+    dir: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+
+    match database {
+        Some(database) => {
+            let file_name = database.file_name().ok_or("the database is not a file")?;
+            let path = dir.join(file_name);
+            std::fs::copy(database, &path)?;
+            Ok(path)
+        }
+        None => {
+            let path = dir.join("tasks.json");
+            storage::open(&path)?.save(&Database::default())?;
+            Ok(path)
+        }
+    }
+}
+
+/// Runs a single step, returning what did not go as expected.
+fn run_step(step: &Step, database_path: &Path) -> Vec<String> {
+    let expect = &step.expect;
+    let mut failures = vec![];
+
+    if !step.run.is_empty() {
+        let mut args = vec![
+            "--db".to_string(),
+            database_path.to_string_lossy().into_owned(),
+        ];
+        args.extend(step.run.iter().cloned());
+
+        let result = Args::parse(args).and_then(|args| {
+            crate::run_command(&args)
+                .unwrap_or_else(|| Err("these arguments would start the UI".to_string()))
+        });
+        let output = match (&result, expect.fails) {
+            (Ok(output), false) | (Err(output), true) => output,
+            (Ok(output), true) => {
+                failures.push("expected the step to fail".to_string());
+                output
+            }
+            (Err(e), false) => {
+                failures.push(format!("failed: {e}"));
+                e
+            }
+        };
+
+        if let Some(expected) = &expect.output {
+            if !output.contains(expected) {
+                let output = output.trim_end();
+                failures.push(format!("expected output '{expected}', got '{output}'"));
+            }
+        }
+    } else if expect.output.is_some() || expect.fails {
+        failures.push("only steps that run something have output".to_string());
+    }
+
+    if expect.checks_database() {
+        match storage::open(database_path).and_then(|mut storage| storage.load()) {
+            Ok(database) => failures.extend(check_database(expect, &database)),
+            Err(e) => failures.push(format!("could not load database: {e}")),
+        }
+    }
+
+    failures
+}
+
+/// Checks the database against the expectations, returning what did not match.
+fn check_database(expect: &Expectations, database: &Database) -> Vec<String> {
+    let mut failures = vec![];
+    let tasks = database.get_all_tasks().collect::<Vec<_>>();
+
+    if let Some(expected) = expect.tasks {
+        if tasks.len() != expected {
+            failures.push(format!("expected {expected} tasks, found {}", tasks.len()));
+        }
+    }
+    if let Some(expected) = expect.open {
+        let open = tasks.iter().filter(|t| t.time_completed.is_none()).count();
+        if open != expected {
+            failures.push(format!("expected {expected} open tasks, found {open}"));
+        }
+    }
+    for title in &expect.titles {
+        if !tasks.iter().any(|t| t.title == *title) {
+            failures.push(format!("expected a task titled '{title}'"));
+        }
+    }
+    for (tag, &expected) in &expect.tags {
+        let count = tasks
+            .iter()
+            .filter(|t| t.tags.iter().any(|t| t == tag))
+            .count();
+        if count != expected {
+            failures.push(format!(
+                "expected {expected} tasks tagged '{tag}', found {count}"
+            ));
+        }
+    }
+
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_steps_and_checks_the_result() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let todo_path = dir.join("todo.txt");
+        std::fs::write(&todo_path, "Buy milk +home\nx Call mom\n").unwrap();
+
+        let script = serde_json::json!({
+            "steps": [
+                {
+                    "run": ["--import-todotxt", todo_path],
+                    "expect": { "output": "Imported 2 tasks" }
+                },
+                { "expect": { "tasks": 2, "open": 1, "titles": ["Buy milk"], "tags": { "+home": 1 } } },
+                { "expect": { "tasks": 3, "titles": ["Walk the dog"] } },
+                { "run": ["--export", "pdf"], "expect": { "fails": true } },
+                { "run": ["--export", "md"], "expect": { "output": "Buy milk" } },
+                { "run": [] , "expect": { "output": "anything" } },
+                { "run": ["--profile-startup"] },
+            ]
+        });
+        let script = serde_json::from_value(script).unwrap();
+        let results = run_in(&script, &dir.join("db")).unwrap();
+
+        let failures = results.iter().map(|r| r.failures.len()).collect::<Vec<_>>();
+        assert_eq!(failures, [0, 0, 2, 0, 0, 1, 1]);
+        assert_eq!(results[2].failures[0], "expected 3 tasks, found 2");
+        assert_eq!(
+            results[6].failures[0],
+            "failed: these arguments would start the UI"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let script = r#"{ "steps": [{ "expect": { "task": 1 } }] }"#;
+        assert!(serde_json::from_str::<Script>(script).is_err());
+    }
+}
@@ -0,0 +1,468 @@
+//! Parsing of command line arguments.
+
+use std::path::{Path, PathBuf};
+
+use td_lib::{
+    audit::AuditFilter,
+    database::Database,
+    export,
+    links::TaskLink,
+    sync::github::Repository,
+    time::{format_description, Date, OffsetDateTime},
+};
+
+use crate::paths;
+
+pub const USAGE: &str = "[--export md|html|taskwarrior] [--pick] [--crdt] [--import-todotxt <todo.txt>] \
+                         [--import-taskwarrior <export.json>] [--dry-run] [--convert <output>] \
+                         [--check] [--repair] [--audit] [--audit-task <id>] [--audit-user <user>] [--audit-since <yyyy-mm-dd>] \
+                         [--github <owner/repo> [--close-issues]] [--profile-startup] [--db <database.json>] \
+                         [<database.json>...] [td://<database>/<task>]";
+
+/// How to run a check script, see [`parse_check`].
+pub const CHECK_USAGE: &str = "check <script.json>";
+
+/// The parsed command line arguments.
+///
+/// If no database is given, the one at [`paths::default_database_path`] is used.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+    pub database_path: PathBuf,
+    /// More databases that can be switched to from the UI.
+    pub other_database_paths: Vec<PathBuf>,
+    pub export: Option<ExportFormat>,
+    /// A file to import into the database instead of starting the UI.
+    pub import: Option<(ImportFormat, PathBuf)>,
+    /// Show what an import, repair, conversion or sync would change without changing anything.
+    pub dry_run: bool,
+    /// Print a random actionable task instead of starting the UI.
+    pub pick: bool,
+    /// Write the database to another file, in the storage format that belongs to its extension.
+    pub convert: Option<PathBuf>,
+    /// Create the database as a CRDT, so copies of it can be merged without conflicts.
+    pub crdt: bool,
+    /// A link to the task that should be selected when the UI starts.
+    pub link: Option<TaskLink>,
+    /// Print the entries of the audit log that match this filter instead of starting the UI.
+    pub audit: Option<AuditFilter>,
+    /// Synchronize the tasks with the issues of this GitHub repository instead of starting the UI.
+    pub github: Option<Repository>,
+    /// Close the GitHub issues of tasks that were completed.
+    pub close_issues: bool,
+    /// Print how long each phase of starting up took after the UI exits.
+    pub profile_startup: bool,
+    /// Check the database for problems instead of starting the UI.
+    pub integrity: Option<IntegrityCheck>,
+}
+
+/// What to do with the problems in a database, as asked for with `--check` or `--repair`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityCheck {
+    /// Only print the problems.
+    Report,
+    /// Repair the problems that can be repaired and save the database.
+    Repair,
+}
+
+/// A format that the database can be exported to instead of starting the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    /// A printable status report.
+    Html,
+    Taskwarrior,
+}
+
+impl ExportFormat {
+    /// Parses the name of a format, as given to `--export`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            "taskwarrior" => Ok(Self::Taskwarrior),
+            _ => Err(format!("unknown export format: {name}")),
+        }
+    }
+
+    /// Gets the format that belongs to the extension of the given file.
+    pub fn for_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Ok(Self::Taskwarrior),
+            Some(extension) => Self::parse(extension),
+            None => Err(format!("no export format for {}", path.display())),
+        }
+    }
+
+    /// Exports the database in this format.
+    pub fn export(self, database: &Database, now: OffsetDateTime) -> String {
+        match self {
+            Self::Markdown => export::to_markdown(database),
+            Self::Html => export::to_html_report(database, now),
+            Self::Taskwarrior => export::taskwarrior::to_json(database),
+        }
+    }
+}
+
+/// Parses a day in `YYYY-MM-DD` format.
+pub fn parse_date(value: &str) -> Result<Date, String> {
+    let date_format =
+        format_description::parse("[year]-[month]-[day]").expect("valid hardcoded date format");
+    Date::parse(value, &date_format).map_err(|_| format!("invalid date: {value}"))
+}
+
+/// Parses `check <script.json>`, which runs a check script instead of starting the UI. Returns
+/// `None` if the arguments are not for a check script.
+pub fn parse_check(args: &[String]) -> Option<Result<PathBuf, String>> {
+    match args {
+        [command, rest @ ..] if command == "check" => Some(match rest {
+            [script] => Ok(script.into()),
+            _ => Err("check needs exactly one script".to_string()),
+        }),
+        _ => None,
+    }
+}
+
+/// A format that tasks can be imported from instead of starting the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    TodoTxt,
+    Taskwarrior,
+}
+
+impl Args {
+    /// Parses the given arguments, not including the executable name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        Self::parse_with_default(args, paths::default_database_path())
+    }
+
+    /// Parses the given arguments, using `default_path` as the database if none is given.
+    fn parse_with_default(
+        args: impl IntoIterator<Item = String>,
+        default_path: Option<PathBuf>,
+    ) -> Result<Self, String> {
+        let mut database_path = None;
+        let mut positional_paths = vec![];
+        let mut export = None;
+        let mut import = None;
+        let mut dry_run = false;
+        let mut pick = false;
+        let mut convert = None;
+        let mut crdt = false;
+        let mut link = None;
+        let mut audit = None::<AuditFilter>;
+        let mut github = None;
+        let mut close_issues = false;
+        let mut profile_startup = false;
+        let mut integrity = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--export" => {
+                    let format = args.next().ok_or("--export requires a format")?;
+                    export = Some(ExportFormat::parse(&format)?);
+                }
+                "--pick" => pick = true,
+                "--dry-run" => dry_run = true,
+                "--crdt" => crdt = true,
+                "--profile-startup" => profile_startup = true,
+                "--check" => _ = integrity.get_or_insert(IntegrityCheck::Report),
+                "--repair" => integrity = Some(IntegrityCheck::Repair),
+                "--close-issues" => close_issues = true,
+                "--github" => {
+                    let repository = args.next().ok_or("--github requires a repository")?;
+                    github = Some(repository.parse()?);
+                }
+                "--db" => {
+                    let path = args.next().ok_or("--db requires a database file")?;
+                    database_path = Some(PathBuf::from(path));
+                }
+                "--convert" => {
+                    let path = args.next().ok_or("--convert requires an output file")?;
+                    convert = Some(PathBuf::from(path));
+                }
+                "--import-todotxt" | "--import-taskwarrior" => {
+                    let format = match arg.as_str() {
+                        "--import-todotxt" => ImportFormat::TodoTxt,
+                        _ => ImportFormat::Taskwarrior,
+                    };
+                    let path = args.next().ok_or(format!("{arg} requires a file"))?;
+                    import = Some((format, PathBuf::from(path)));
+                }
+                "--audit" => _ = audit.get_or_insert_with(Default::default),
+                "--audit-task" | "--audit-user" | "--audit-since" => {
+                    let value = args.next().ok_or(format!("{arg} requires a value"))?;
+                    let filter = audit.get_or_insert_with(Default::default);
+                    match arg.as_str() {
+                        "--audit-task" => filter.task = Some(value),
+                        "--audit-user" => filter.user = Some(value),
+                        _ => filter.since = Some(parse_date(&value)?),
+                    }
+                }
+                _ if arg.starts_with("td://") => {
+                    link = Some(TaskLink::parse(&arg).ok_or(format!("invalid link: {arg}"))?);
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option: {arg}")),
+                _ => positional_paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let changes_files = import.is_some()
+            || integrity == Some(IntegrityCheck::Repair)
+            || convert.is_some()
+            || github.is_some();
+        if dry_run && !changes_files {
+            return Err(
+                "--dry-run can only be used with an import, --repair, --convert or --github".into(),
+            );
+        }
+        if dry_run && close_issues {
+            return Err("--close-issues can not be used with --dry-run".into());
+        }
+        if close_issues && github.is_none() {
+            return Err("--close-issues can only be used with --github".into());
+        }
+
+        // --db takes precedence, the other paths can still be switched to
+        let mut other_database_paths = positional_paths.into_iter();
+        let database_path = database_path
+            .or_else(|| other_database_paths.next())
+            .or(default_path)
+            .ok_or("no database path given and no default location found")?;
+        let other_database_paths = other_database_paths.collect();
+
+        Ok(Self {
+            database_path,
+            other_database_paths,
+            export,
+            import,
+            dry_run,
+            pick,
+            convert,
+            crdt,
+            link,
+            audit,
+            github,
+            close_issues,
+            profile_startup,
+            integrity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse_with_default(args.iter().map(|s| s.to_string()), None)
+    }
+
+    #[test]
+    fn parses_path_and_export() {
+        assert_eq!(
+            parse(&["todo.json"]),
+            Ok(Args {
+                database_path: "todo.json".into(),
+                other_database_paths: vec![],
+                export: None,
+                import: None,
+                dry_run: false,
+                pick: false,
+                convert: None,
+                crdt: false,
+                link: None,
+                audit: None,
+                github: None,
+                close_issues: false,
+                profile_startup: false,
+                integrity: None,
+            })
+        );
+        assert_eq!(
+            parse(&["--export", "md", "todo.json"]),
+            Ok(Args {
+                database_path: "todo.json".into(),
+                other_database_paths: vec![],
+                export: Some(ExportFormat::Markdown),
+                import: None,
+                dry_run: false,
+                pick: false,
+                convert: None,
+                crdt: false,
+                link: None,
+                audit: None,
+                github: None,
+                close_issues: false,
+                profile_startup: false,
+                integrity: None,
+            })
+        );
+        assert_eq!(
+            parse(&["todo.json", "--import-todotxt", "todo.txt"]),
+            Ok(Args {
+                database_path: "todo.json".into(),
+                other_database_paths: vec![],
+                export: None,
+                import: Some((ImportFormat::TodoTxt, "todo.txt".into())),
+                dry_run: false,
+                pick: false,
+                convert: None,
+                crdt: false,
+                link: None,
+                audit: None,
+                github: None,
+                close_issues: false,
+                profile_startup: false,
+                integrity: None,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_html_report() {
+        let args = parse(&["--export", "html", "todo.json"]).unwrap();
+        assert_eq!(args.export, Some(ExportFormat::Html));
+    }
+
+    #[test]
+    fn parses_convert() {
+        let args = parse(&["todo.json", "--convert", "todo.json.gz"]).unwrap();
+        assert_eq!(args.convert, Some("todo.json.gz".into()));
+        assert!(parse(&["todo.json", "--convert"]).is_err());
+    }
+
+    #[test]
+    fn parses_link() {
+        let args = parse(&["todo.json", "td://3f2a9c1e/k4bW7xnP"]).unwrap();
+        assert_eq!(args.link.unwrap().to_string(), "td://3f2a9c1e/k4bW7xnP");
+        assert!(parse(&["todo.json", "td://3f2a9c1e"]).is_err());
+    }
+
+    #[test]
+    fn parses_multiple_databases() {
+        let args = parse(&["a.json", "b.json", "c.json"]).unwrap();
+        assert_eq!(args.database_path, PathBuf::from("a.json"));
+        assert_eq!(
+            args.other_database_paths,
+            [PathBuf::from("b.json"), PathBuf::from("c.json")]
+        );
+    }
+
+    #[test]
+    fn uses_db_flag_or_default_path() {
+        let args = parse(&["--db", "a.json", "b.json"]).unwrap();
+        assert_eq!(args.database_path, PathBuf::from("a.json"));
+        assert_eq!(args.other_database_paths, [PathBuf::from("b.json")]);
+
+        let default_path = Some(PathBuf::from("/data/td/tasks.json"));
+        let args = Args::parse_with_default(["--pick".to_string()], default_path.clone()).unwrap();
+        assert_eq!(Some(args.database_path), default_path);
+        assert!(parse(&["--db"]).is_err());
+    }
+
+    #[test]
+    fn parses_dry_run() {
+        let args = parse(&["todo.json", "--import-todotxt", "todo.txt", "--dry-run"]).unwrap();
+        assert!(args.dry_run);
+        assert!(!parse(&["todo.json"]).unwrap().dry_run);
+        assert!(parse(&["todo.json", "--dry-run"]).is_err());
+        assert!(parse(&["todo.json", "--check", "--dry-run"]).is_err());
+
+        for args in [
+            ["todo.json", "--repair", "--dry-run"].as_slice(),
+            &["todo.json", "--convert", "todo.db", "--dry-run"],
+            &["todo.json", "--github", "owner/repo", "--dry-run"],
+        ] {
+            assert!(parse(args).unwrap().dry_run);
+        }
+        assert!(parse(&["--github", "owner/repo", "--close-issues", "--dry-run"]).is_err());
+    }
+
+    #[test]
+    fn parses_pick() {
+        assert!(parse(&["--pick", "todo.json"]).unwrap().pick);
+    }
+
+    #[test]
+    fn parses_crdt() {
+        assert!(parse(&["--crdt", "todo.json"]).unwrap().crdt);
+        assert!(!parse(&["todo.json"]).unwrap().crdt);
+    }
+
+    #[test]
+    fn parses_profile_startup() {
+        assert!(
+            parse(&["todo.json", "--profile-startup"])
+                .unwrap()
+                .profile_startup
+        );
+        assert!(!parse(&["todo.json"]).unwrap().profile_startup);
+    }
+
+    #[test]
+    fn parses_integrity_check() {
+        let integrity = |args: &[&str]| parse(args).unwrap().integrity;
+        assert_eq!(integrity(&["todo.json"]), None);
+        assert_eq!(
+            integrity(&["--check", "todo.json"]),
+            Some(IntegrityCheck::Report)
+        );
+        assert_eq!(
+            integrity(&["--repair", "--check", "todo.json"]),
+            Some(IntegrityCheck::Repair)
+        );
+    }
+
+    #[test]
+    fn parses_audit() {
+        assert_eq!(
+            parse(&["--audit", "todo.json"]).unwrap().audit,
+            Some(AuditFilter::default())
+        );
+
+        let filter = parse(&[
+            "todo.json",
+            "--audit-user",
+            "alice",
+            "--audit-since",
+            "2024-01-31",
+        ])
+        .unwrap()
+        .audit
+        .unwrap();
+        assert_eq!(filter.user.as_deref(), Some("alice"));
+        assert_eq!(filter.since.unwrap().to_string(), "2024-01-31");
+        assert!(parse(&["todo.json", "--audit-since", "yesterday"]).is_err());
+    }
+
+    #[test]
+    fn parses_github_sync() {
+        let args = parse(&["todo.json", "--github", "holly/td", "--close-issues"]).unwrap();
+        assert_eq!(args.github, Some("holly/td".parse().unwrap()));
+        assert!(args.close_issues);
+        assert!(parse(&["todo.json", "--github", "holly"]).is_err());
+        assert!(parse(&["todo.json", "--close-issues"]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--export"]).is_err());
+        assert!(parse(&["todo.json", "--import-todotxt"]).is_err());
+        assert!(parse(&["todo.json", "--import-taskwarrior"]).is_err());
+        assert!(parse(&["--export", "pdf", "todo.json"]).is_err());
+        assert!(parse(&["--unknown", "todo.json"]).is_err());
+    }
+
+    #[test]
+    fn parses_check_scripts() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_check(&args(&["check", "script.json"])),
+            Some(Ok("script.json".into()))
+        );
+        assert!(parse_check(&args(&["check"])).unwrap().is_err());
+        assert_eq!(parse_check(&args(&["check.json"])), None);
+    }
+}
@@ -0,0 +1,75 @@
+//! Copying text to the system clipboard.
+//!
+//! The clipboard is reached through the command line tool of the platform, such as `pbcopy` or
+//! `wl-copy`. If none of them work, for example over SSH, the text is sent to the terminal in an
+//! OSC 52 escape sequence instead, which most terminals copy to the clipboard of the user.
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Programs that copy their standard input to the clipboard, with their arguments and the
+/// environment variable that has to be set for them to work.
+const PROGRAMS: &[(&str, &[&str], Option<&str>)] = &[
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("clip.exe", &[], None),
+];
+
+/// Copies the given text to the clipboard.
+pub fn copy(text: &str) -> io::Result<()> {
+    let usable = PROGRAMS
+        .iter()
+        .filter(|(_, _, env)| env.is_none_or(|env| std::env::var_os(env).is_some()));
+    for (program, args, _) in usable {
+        if copy_with_program(program, args, text).is_ok() {
+            return Ok(());
+        }
+    }
+
+    let mut stdout = io::stdout();
+    write_osc52(&mut stdout, text)?;
+    stdout.flush()
+}
+
+fn copy_with_program(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // stdin is closed at the end of this statement, so the program knows the text is complete
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+
+    match child.wait()?.success() {
+        true => Ok(()),
+        false => Err(io::Error::other(format!("{program} failed"))),
+    }
+}
+
+/// Writes the escape sequence that asks the terminal to copy the given text.
+fn write_osc52(output: &mut impl Write, text: &str) -> io::Result<()> {
+    write!(output, "\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_contains_base64_text() {
+        let mut output = vec![];
+        write_osc52(&mut output, "abc123").unwrap();
+        assert_eq!(output, b"\x1b]52;c;YWJjMTIz\x07");
+    }
+}
@@ -0,0 +1,237 @@
+//! The commands that can be typed in the command line of the UI, such as `:add Buy milk +home`.
+//!
+//! Values are parsed the same way as the command line arguments, see [`crate::cli`].
+
+use std::path::PathBuf;
+
+use td_lib::{
+    sync::github::Repository,
+    time::{Date, Duration, Weekday},
+};
+
+use crate::cli::{self, ExportFormat};
+
+/// A command typed in the command line, without the leading `:`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// `:w`, saves the database.
+    Write,
+    /// `:q`, quits, asking to save unsaved changes first.
+    Quit,
+    /// `:q!`, quits without saving.
+    ForceQuit,
+    /// `:wq` or `:x`, saves and quits.
+    WriteQuit,
+    /// `:tag <name>`, adds a tag to the selected task.
+    Tag(String),
+    /// `:snooze [<time>]`, stops announcing that the selected task is due until the given time,
+    /// such as `+2h`. Snoozes for an hour if no time is given.
+    Snooze(String),
+    /// `:add <title> [+<tag>...] [due:<day>]`, adds a task.
+    Add(NewTask),
+    /// `:filter <query>`, searches the task list. Stops searching if the query is empty.
+    Filter(String),
+    /// `:export [<format>] <file>`, exports the database. The format is taken from the extension
+    /// of the file if it is not given.
+    Export(ExportFormat, PathBuf),
+    /// `:github [<owner/repo>]`, synchronizes the tasks with the issues of a GitHub repository, or
+    /// of the last one if none is given. `:github!` also closes the issues of completed tasks.
+    GitHub {
+        repository: Option<Repository>,
+        close_issues: bool,
+    },
+    /// `:check`, lists the problems in the database and offers to repair them.
+    Check,
+}
+
+/// A task described in an `:add` command.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NewTask {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due: Option<Date>,
+}
+
+impl Command {
+    /// Parses a command. Days such as `due:fri` are relative to `today`. Returns a message for the
+    /// user if it is not a valid command.
+    pub fn parse(text: &str, today: Date) -> Result<Self, String> {
+        let text = text.trim();
+        let (name, argument) = text
+            .split_once(char::is_whitespace)
+            .map_or((text, ""), |(name, arg)| (name, arg.trim()));
+
+        match (name, argument) {
+            ("w", "") => Ok(Self::Write),
+            ("q", "") => Ok(Self::Quit),
+            ("q!", "") => Ok(Self::ForceQuit),
+            ("wq" | "x", "") => Ok(Self::WriteQuit),
+            ("check", "") => Ok(Self::Check),
+            ("tag", "") => Err("Usage: :tag <name>".to_string()),
+            ("tag", tag) => Ok(Self::Tag(tag.to_string())),
+            ("snooze", "") => Ok(Self::Snooze("+1h".to_string())),
+            ("snooze", time) => Ok(Self::Snooze(time.to_string())),
+            ("add", text) => Ok(Self::Add(NewTask::parse(text, today)?)),
+            ("filter", query) => Ok(Self::Filter(query.to_string())),
+            ("export", arguments) => {
+                let arguments = arguments.split_whitespace().collect::<Vec<_>>();
+                match arguments[..] {
+                    [path] => Ok(Self::Export(
+                        ExportFormat::for_path(path.as_ref())?,
+                        path.into(),
+                    )),
+                    [format, path] => Ok(Self::Export(ExportFormat::parse(format)?, path.into())),
+                    _ => Err("Usage: :export [<format>] <file>".to_string()),
+                }
+            }
+            ("github" | "github!", repository) => Ok(Self::GitHub {
+                repository: match repository {
+                    "" => None,
+                    repository => Some(repository.parse()?),
+                },
+                close_issues: name == "github!",
+            }),
+            _ => Err(format!("Not a command: {text}")),
+        }
+    }
+}
+
+impl NewTask {
+    /// Parses the words after `:add`. Words starting with `+` are tags, `due:<day>` sets the due
+    /// date and the other words form the title.
+    fn parse(text: &str, today: Date) -> Result<Self, String> {
+        let mut title = vec![];
+        let mut tags = vec![];
+        let mut due = None;
+        for word in text.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('+').filter(|t| !t.is_empty()) {
+                tags.push(tag.to_string());
+            } else if let Some(day) = word.strip_prefix("due:") {
+                due = Some(parse_day(day, today)?);
+            } else {
+                title.push(word);
+            }
+        }
+
+        if title.is_empty() {
+            return Err("Usage: :add <title> [+<tag>...] [due:<day>]".to_string());
+        }
+
+        Ok(Self {
+            title: title.join(" "),
+            tags,
+            due,
+        })
+    }
+}
+
+/// Parses a day such as `today`, `tomorrow`, `fri` or `2024-05-01`. A weekday is the first one
+/// after `today`.
+fn parse_day(value: &str, today: Date) -> Result<Date, String> {
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Duration::DAY),
+        _ => (),
+    }
+
+    let weekdays = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+    let weekday = weekdays.into_iter().find(|weekday| {
+        let name = weekday.to_string().to_lowercase();
+        value.len() >= 3 && name.starts_with(&value)
+    });
+    match weekday {
+        Some(weekday) => Ok(today.next_occurrence(weekday)),
+        None => cli::parse_date(&value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use td_lib::time::Month;
+
+    use super::*;
+
+    // a wednesday
+    const TODAY: Date = date(Month::May, 1);
+
+    const fn date(month: Month, day: u8) -> Date {
+        match Date::from_calendar_date(2024, month, day) {
+            Ok(date) => date,
+            Err(_) => panic!("invalid date"),
+        }
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse("w", TODAY), Ok(Command::Write));
+        assert_eq!(Command::parse(" q! ", TODAY), Ok(Command::ForceQuit));
+        assert_eq!(Command::parse("x", TODAY), Ok(Command::WriteQuit));
+        assert_eq!(Command::parse("check", TODAY), Ok(Command::Check));
+        assert_eq!(
+            Command::parse("tag  needs review", TODAY),
+            Ok(Command::Tag("needs review".to_string()))
+        );
+        assert_eq!(
+            Command::parse("filter tag:work", TODAY),
+            Ok(Command::Filter("tag:work".to_string()))
+        );
+        assert_eq!(
+            Command::parse("github! holly/td", TODAY),
+            Ok(Command::GitHub {
+                repository: Some("holly/td".parse().unwrap()),
+                close_issues: true,
+            })
+        );
+        assert_eq!(
+            Command::parse("snooze", TODAY),
+            Ok(Command::Snooze("+1h".to_string()))
+        );
+        assert!(Command::parse("github holly", TODAY).is_err());
+        assert!(Command::parse("tag", TODAY).is_err());
+        assert!(Command::parse("w now", TODAY).is_err());
+        assert!(Command::parse("frobnicate", TODAY).is_err());
+    }
+
+    #[test]
+    fn parses_new_tasks() {
+        assert_eq!(
+            Command::parse("add Buy milk +home due:fri", TODAY),
+            Ok(Command::Add(NewTask {
+                title: "Buy milk".to_string(),
+                tags: vec!["home".to_string()],
+                due: Some(date(Month::May, 3)),
+            }))
+        );
+        assert_eq!(parse_day("wed", TODAY), Ok(date(Month::May, 8)));
+        assert_eq!(parse_day("tomorrow", TODAY), Ok(date(Month::May, 2)));
+        assert_eq!(parse_day("2024-06-01", TODAY), Ok(date(Month::June, 1)));
+        assert!(Command::parse("add +home", TODAY).is_err());
+        assert!(Command::parse("add milk due:someday", TODAY).is_err());
+    }
+
+    #[test]
+    fn parses_exports() {
+        assert_eq!(
+            Command::parse("export report.md", TODAY),
+            Ok(Command::Export(ExportFormat::Markdown, "report.md".into()))
+        );
+        assert_eq!(
+            Command::parse("export taskwarrior tasks.txt", TODAY),
+            Ok(Command::Export(
+                ExportFormat::Taskwarrior,
+                "tasks.txt".into()
+            ))
+        );
+        assert!(Command::parse("export report.pdf", TODAY).is_err());
+        assert!(Command::parse("export", TODAY).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Detection of changes made to the database file by other programs.
+
+use std::{
+    ffi::OsString,
+    path::Path,
+    sync::mpsc::{channel, Receiver},
+    time::SystemTime,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Watches a single file for changes.
+pub struct FileWatcher {
+    // the watcher stops when dropped, so it is kept around
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<()>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let file_name = path.file_name().map(OsString::from);
+        let (sender, receiver) = channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {return;};
+                if event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == file_name.as_deref())
+                {
+                    _ = sender.send(());
+                }
+            })?;
+
+        // editors often replace the file instead of writing to it, so watch the whole directory
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// Returns whether the file has been touched since the last call. This includes changes made
+    /// by this process.
+    pub fn has_events(&self) -> bool {
+        self.receiver.try_iter().count() > 0
+    }
+}
+
+/// Gets the last modification time of a file, if it exists.
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,202 @@
+//! Fuzzy string matching with skim-style scoring, used to search through lists of items.
+
+use ratatui::{style::Style, text::Span};
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_WORD_START: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_FIRST_CHAR: i64 = 4;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// The result of a successful fuzzy match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the pattern matched. Higher is better.
+    pub score: i64,
+    /// The char indices in the haystack that matched the pattern, in ascending order.
+    pub indices: Vec<usize>,
+}
+
+/// Tries to match all characters of `pattern` in order against `haystack`, ignoring case and
+/// whitespace in the pattern. Returns the best scoring match, or `None` if there is no match.
+pub fn fuzzy_match(pattern: &str, haystack: &str) -> Option<FuzzyMatch> {
+    let pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let haystack = haystack.chars().collect::<Vec<_>>();
+
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: vec![],
+        });
+    }
+    if pattern.len() > haystack.len() {
+        return None;
+    }
+
+    let haystack_lower = haystack
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+
+    // scores[i][j] holds the best score when pattern[i] is matched at haystack[j], and
+    // previous[i][j] holds where pattern[i - 1] was matched to get that score.
+    let mut scores = vec![vec![None::<i64>; haystack.len()]; pattern.len()];
+    let mut previous = vec![vec![0usize; haystack.len()]; pattern.len()];
+
+    for (i, pattern_char) in pattern.iter().enumerate() {
+        for j in i..haystack.len() {
+            if haystack_lower[j] != *pattern_char {
+                continue;
+            }
+
+            let char_score = SCORE_MATCH + position_bonus(&haystack, j);
+
+            if i == 0 {
+                let first_char_bonus = if j == 0 { BONUS_FIRST_CHAR } else { 0 };
+                scores[i][j] = Some(char_score + first_char_bonus);
+                continue;
+            }
+
+            let mut best = None::<(i64, usize)>;
+            for (k, prev_score) in scores[i - 1].iter().enumerate().take(j).skip(i - 1) {
+                let Some(prev_score) = *prev_score else {continue;};
+                let gap = j - k - 1;
+                let transition = if gap == 0 {
+                    BONUS_CONSECUTIVE
+                } else {
+                    -PENALTY_GAP_START - PENALTY_GAP_EXTENSION * (gap as i64 - 1)
+                };
+                let candidate = prev_score + transition;
+                if best.is_none_or(|(score, _)| candidate > score) {
+                    best = Some((candidate, k));
+                }
+            }
+
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + char_score);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    // find the best end position and walk back to collect the matched indices
+    let last = pattern.len() - 1;
+    let (mut index, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|s| (j, s)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+
+    let mut indices = vec![index];
+    for i in (1..=last).rev() {
+        index = previous[i][index];
+        indices.push(index);
+    }
+    indices.reverse();
+
+    Some(FuzzyMatch { score, indices })
+}
+
+/// Gives a bonus for matching chars that are at the start of a word.
+fn position_bonus(haystack: &[char], index: usize) -> i64 {
+    let Some(prev) = index.checked_sub(1).map(|i| haystack[i]) else {return BONUS_WORD_START;};
+    let current = haystack[index];
+
+    if !prev.is_alphanumeric() && current.is_alphanumeric() {
+        BONUS_WORD_START
+    } else if prev.is_lowercase() && current.is_uppercase() {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// Splits `text` into spans where the chars at `indices` use `highlight_style`.
+pub fn highlight_spans(
+    text: &str,
+    indices: &[usize],
+    style: Style,
+    highlight_style: Style,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut current = String::new();
+    let mut current_highlighted = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = indices.binary_search(&i).is_ok();
+        if highlighted != current_highlighted && !current.is_empty() {
+            let style = if current_highlighted {
+                style.patch(highlight_style)
+            } else {
+                style
+            };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_highlighted = highlighted;
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        let style = if current_highlighted {
+            style.patch(highlight_style)
+        } else {
+            style
+        };
+        spans.push(Span::styled(current, style));
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_pattern_matches() {
+        let result = fuzzy_match("", "anything").unwrap();
+        assert_eq!(result.indices, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(fuzzy_match("xyz", "fix login bug"), None);
+        assert_eq!(fuzzy_match("bugfix", "fix bug"), None);
+    }
+
+    #[test]
+    fn matches_subsequence() {
+        let result = fuzzy_match("flb", "fix login bug").unwrap();
+        assert_eq!(result.indices, vec![0, 4, 10]);
+    }
+
+    #[test]
+    fn ignores_case() {
+        let result = fuzzy_match("LOGIN", "fix login bug").unwrap();
+        assert_eq!(result.indices, vec![4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn prefers_consecutive_and_word_starts() {
+        // "log" should match the word "login", not the scattered chars in "lots of garbage"
+        let result = fuzzy_match("log", "lots of garbage login").unwrap();
+        assert_eq!(result.indices, vec![16, 17, 18]);
+
+        let consecutive = fuzzy_match("bug", "fix bug").unwrap();
+        let scattered = fuzzy_match("bug", "be useful, go").unwrap();
+        assert!(consecutive.score > scattered.score);
+    }
+
+    #[test]
+    fn highlight_splits_spans() {
+        let spans = highlight_spans("abcd", &[1, 2], Style::default(), Style::default());
+        let contents = spans.iter().map(|s| s.content.as_ref()).collect::<Vec<_>>();
+        assert_eq!(contents, vec!["a", "bc", "d"]);
+    }
+}
@@ -0,0 +1,275 @@
+//! Shell commands that run when tasks are created, completed or deleted, or when the database is
+//! saved. They are configured in the user's own configuration directory, in the file at
+//! [`paths::hooks_path`], for example:
+//!
+//! ```json
+//! {
+//!     "task_completed": ["notify-send \"Done: $TD_TASK_TITLE\""],
+//!     "database_saved": ["~/bin/backup-tasks \"$TD_DATABASE\""]
+//! }
+//! ```
+//!
+//! They are never read from a file next to the database, because anyone who can share a
+//! database with the user could then run commands on their machine.
+//!
+//! Commands get the event and the task in environment variables, and the task as JSON on their
+//! standard input. The titles and descriptions of confidential tasks are left out.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread::{self, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use td_lib::database::{Database, DatabaseOp, Task};
+
+use crate::paths;
+
+/// The commands to run for each event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    pub task_created: Vec<String>,
+    pub task_completed: Vec<String>,
+    pub task_deleted: Vec<String>,
+    pub database_saved: Vec<String>,
+}
+
+/// Something that happened to a database, which hooks can run on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookEvent {
+    TaskCreated(Task),
+    TaskCompleted(Task),
+    TaskDeleted(Task),
+    DatabaseSaved,
+}
+
+impl Hooks {
+    /// Loads the hooks from the user's configuration directory. There are no hooks if the file
+    /// does not exist.
+    pub fn load() -> Result<Self, String> {
+        match paths::hooks_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self, String> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+        serde_json::from_str(&json).map_err(|e| format!("Could not read {}: {e}", path.display()))
+    }
+
+    /// Checks if there are hooks for task events, which need the previous version of the
+    /// database to detect.
+    pub fn has_task_hooks(&self) -> bool {
+        !self.task_created.is_empty()
+            || !self.task_completed.is_empty()
+            || !self.task_deleted.is_empty()
+    }
+
+    fn commands(&self, event: &HookEvent) -> &[String] {
+        match event {
+            HookEvent::TaskCreated(_) => &self.task_created,
+            HookEvent::TaskCompleted(_) => &self.task_completed,
+            HookEvent::TaskDeleted(_) => &self.task_deleted,
+            HookEvent::DatabaseSaved => &self.database_saved,
+        }
+    }
+
+    /// Starts the hooks of the given events for the database at the given path, without waiting
+    /// for them to finish. Returns a message for the user if a hook could not be started.
+    pub fn run(&self, events: &[HookEvent], database_path: &Path) -> Result<(), String> {
+        let mut result = Ok(());
+        for event in events {
+            for command in self.commands(event) {
+                if let Err(e) = run_hook(command, event, database_path) {
+                    result = Err(format!("Could not run hook `{command}`: {e}"));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl HookEvent {
+    /// Finds the tasks that were created, completed or deleted between the old and new version of
+    /// a database.
+    pub fn between(old: &Database, new: &Database) -> Vec<Self> {
+        new.diff(old)
+            .into_iter()
+            .filter_map(|op| {
+                let DatabaseOp::SetTask {
+                    old: before,
+                    new: after,
+                    ..
+                } = op
+                else {return None;};
+                let event = match (before, after) {
+                    (None, Some(task)) => Self::TaskCreated(redact(new, *task)),
+                    (Some(task), None) => Self::TaskDeleted(redact(old, *task)),
+                    (Some(before), Some(after))
+                        if before.time_completed.is_none() && after.time_completed.is_some() =>
+                    {
+                        Self::TaskCompleted(redact(new, *after))
+                    }
+                    _ => return None,
+                };
+                Some(event)
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::TaskCreated(_) => "task_created",
+            Self::TaskCompleted(_) => "task_completed",
+            Self::TaskDeleted(_) => "task_deleted",
+            Self::DatabaseSaved => "database_saved",
+        }
+    }
+
+    fn task(&self) -> Option<&Task> {
+        match self {
+            Self::TaskCreated(task) | Self::TaskCompleted(task) | Self::TaskDeleted(task) => {
+                Some(task)
+            }
+            Self::DatabaseSaved => None,
+        }
+    }
+
+    /// Gets the environment variables that describe this event.
+    fn env(&self, database_path: &Path) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("TD_EVENT", self.name().to_string()),
+            ("TD_DATABASE", database_path.display().to_string()),
+        ];
+        if let Some(task) = self.task() {
+            let tags = task.tags.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+            env.extend([
+                ("TD_TASK_ID", task.id().as_str().to_string()),
+                ("TD_TASK_TITLE", task.title.clone()),
+                ("TD_TASK_TAGS", tags.join(",")),
+                ("TD_TASK_PROJECT", task.project.clone().unwrap_or_default()),
+                (
+                    "TD_TASK_ASSIGNEE",
+                    task.assignee.clone().unwrap_or_default(),
+                ),
+            ]);
+        }
+        env
+    }
+}
+
+/// Leaves out the title and description of the task if it is confidential in the database.
+fn redact(database: &Database, mut task: Task) -> Task {
+    if database.is_confidential(&task) {
+        task.title.clear();
+        task.description.clear();
+    }
+    task
+}
+
+/// Starts a single hook. The returned thread finishes when the hook does.
+fn run_hook(command: &str, event: &HookEvent, database_path: &Path) -> io::Result<JoinHandle<()>> {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .envs(event.env(database_path))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let input = match event.task() {
+        Some(task) => serde_json::to_string(task)?,
+        None => String::new(),
+    };
+    let stdin = child.stdin.take();
+    Ok(thread::spawn(move || {
+        // the hook does not have to read its input
+        if let Some(mut stdin) = stdin {
+            _ = stdin.write_all(input.as_bytes());
+        }
+        _ = child.wait();
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_hooks_from_a_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("hooks.json");
+        assert_eq!(Hooks::load_from(&path), Ok(Hooks::default()));
+
+        std::fs::write(&path, r#"{ "database_saved": ["backup"] }"#).unwrap();
+        let hooks = Hooks::load_from(&path);
+        assert_eq!(hooks.unwrap().database_saved, ["backup"]);
+    }
+
+    #[test]
+    fn finds_task_events() {
+        let mut old = Database::default();
+        let done = Task::create_now("done".into());
+        let removed = Task::create_now("removed".into());
+        let (done_id, removed_id) = (done.id().clone(), removed.id().clone());
+        old.add_task(done);
+        old.add_task(removed);
+
+        let mut new = old.clone();
+        new[&done_id].time_completed = Some(new[&done_id].time_created);
+        new.remove_task(&removed_id);
+        new.add_task(Task::create_now("added".into()));
+
+        let mut events = HookEvent::between(&old, &new)
+            .iter()
+            .map(|event| (event.name(), event.task().unwrap().title.clone()))
+            .collect::<Vec<_>>();
+        events.sort();
+        assert_eq!(
+            events,
+            [
+                ("task_completed", "done".to_string()),
+                ("task_created", "added".to_string()),
+                ("task_deleted", "removed".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_get_the_task() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let output = dir.join("output");
+
+        let mut task = Task::create_now("write tests".into());
+        task.tags.push("work".into());
+        let event = HookEvent::TaskCompleted(task.clone());
+        let command = format!(
+            "echo \"$TD_EVENT $TD_TASK_TITLE $TD_TASK_TAGS\" > {0}; cat >> {0}",
+            output.display()
+        );
+        run_hook(&command, &event, &dir.join("tasks.json"))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let output = std::fs::read_to_string(&output).unwrap();
+        let (env, json) = output.split_once('\n').unwrap();
+        assert_eq!(env, "task_completed write tests work");
+        assert_eq!(serde_json::from_str::<Task>(json).unwrap(), task);
+    }
+}
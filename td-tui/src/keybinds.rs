@@ -7,24 +7,140 @@ pub const KEYBIND_TASKPAGE_PANE_SETTINGS: &SimpleKeybind =
 pub const KEYBIND_TASKPAGE_PANE_TASKS: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Left, "Select tasks pane");
 
+pub const KEYBIND_TAGPAGE_PANE_TAGS: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Left, "Select tags pane");
+pub const KEYBIND_TAGPAGE_PANE_TASKS: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Right, "Select tasks pane");
+pub const KEYBIND_TAGPAGE_TOGGLE_ENCRYPTED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('E'), "Toggle encryption");
+pub const KEYBIND_TAGPAGE_REMOVE_TAG: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('x'), "Remove from all tasks");
+pub const KEYBIND_TAGPAGE_BURNDOWN: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('B'), "Burndown");
+pub const KEYBIND_TAGPAGE_SET_COLOR: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('c'), "Set color");
+
+pub const KEYBIND_TIMEPAGE_RANGE: &LeftRightKeybind = &LeftRightKeybind::new("Change range");
+pub const KEYBIND_TIMEPAGE_EXPORT: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('e'), "Export to CSV");
+
+pub const KEYBIND_CALENDAR_DAY: &LeftRightKeybind = &LeftRightKeybind::new("Change day");
+pub const KEYBIND_CALENDAR_WEEK: &UpDownKeybind = &UpDownKeybind::new("Change week");
+pub const KEYBIND_CALENDAR_MODE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('v'), "Month/week");
+pub const KEYBIND_CALENDAR_TODAY: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('t'), "Today");
+pub const KEYBIND_CALENDAR_SHOW_DAY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Enter, "Show tasks of this day");
+
+pub const KEYBIND_ACTIVITY_START_RECORDING: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('r'), "Start recording");
+pub const KEYBIND_ACTIVITY_STOP_RECORDING: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('r'), "Stop recording");
+
 pub const KEYBIND_TASK_MARK_STARTED: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Char(' '), "Mark as started");
 pub const KEYBIND_TASK_MARK_DONE: &SimpleKeybind =
-    &SimpleKeybind::new(KeyCode::Enter, "Mark as done");
+    &SimpleKeybind::new(KeyCode::Char('D'), "Mark as done");
+pub const KEYBIND_TASK_OPEN_DETAILS: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Enter, "Open details");
+pub const KEYBIND_DEPENDENCIES_TOGGLE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Enter, "Add/remove");
+pub const KEYBIND_DEPENDENCIES_SWITCH_COLUMN: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Tab, "Switch column");
+pub const KEYBIND_DEPENDENCIES_APPLY: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('s'), KeyModifiers::CONTROL, "Apply");
+
+pub const KEYBIND_COMPLETE_WITH_DEPENDENCIES: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('a'), "Also complete its dependencies");
+pub const KEYBIND_COMPLETE_GO_TO_DEPENDENCY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('g'), "Go to the first open dependency");
+pub const KEYBIND_COMPLETE_ANYWAY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('c'), "Complete anyway");
+pub const KEYBIND_TASK_CLOCK: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('c'), "Clock in/out");
 pub const KEYBIND_TASK_NEW: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('n'), "New task");
 pub const KEYBIND_TASK_DELETE: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('x'), "Delete");
 pub const KEYBIND_TASK_EDIT: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('e'), "Edit");
-pub const KEYBIND_TASK_ADD_TAG: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('t'), "Add tag");
+pub const KEYBIND_TASK_ADD_TAG: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('t'), KeyModifiers::NONE, "Add tag");
 pub const KEYBIND_TASK_ADD_DEPENDENCY: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Char('d'), "Add dependency");
+pub const KEYBIND_TASK_ADD_BLOCKED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('K'), "Add blocked task");
+pub const KEYBIND_TASK_EDIT_DEPENDENCIES: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('A'), "Edit dependencies");
+pub const KEYBIND_TASK_ADD_REMINDER: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('R'), "Add reminder");
+pub const KEYBIND_TASK_SHOW_LINK: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('L'), "Show link");
+pub const KEYBIND_TASK_COPY_ID: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('y'), "Copy ID");
+pub const KEYBIND_TASK_COPY_ID_AND_TITLE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('Y'), "Copy ID and title");
+pub const KEYBIND_TASK_TOGGLE_ENCRYPTED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('E'), "Toggle encryption");
+pub const KEYBIND_TASK_ARCHIVE: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('a'), "Archive");
+pub const KEYBIND_TASK_PURGE_ARCHIVED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('X'), "Purge archived");
+pub const KEYBIND_TASK_IMPORT_TODOTXT: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('I'), "Import todo.txt");
+pub const KEYBIND_TASK_SUGGEST: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('w'), KeyModifiers::NONE, "What next?");
+pub const KEYBIND_TASK_PICK_RANDOM: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('p'), "Pick for me");
+pub const KEYBIND_TASK_SWITCH_PROJECT: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('P'), "Switch project");
+pub const KEYBIND_TASK_SWITCH_ASSIGNEE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('W'), "Switch assignee");
+pub const KEYBIND_TASK_SHOW_UNBLOCKED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('b'), "Show what this unblocks");
+pub const KEYBIND_TASK_CLOSE_UNBLOCKED: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Esc, "Show all tasks");
+pub const KEYBIND_TASK_TOGGLE_SOMEDAY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('m'), "Someday/maybe");
+pub const KEYBIND_TASK_SHOW_SOMEDAY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('M'), "Show someday/maybe");
+pub const KEYBIND_TASK_HIDE_SOMEDAY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('M'), "Hide someday/maybe");
+pub const KEYBIND_TASK_SNOOZE: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('z'), "Snooze");
+pub const KEYBIND_SNOOZE_TOMORROW: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('t'), "Until tomorrow");
+pub const KEYBIND_SNOOZE_NEXT_WEEK: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('w'), "Until next week");
+pub const KEYBIND_SNOOZE_CUSTOM: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('c'), "Until another time");
+pub const KEYBIND_SNOOZE_WAKE: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('u'), "Unsnooze");
+pub const KEYBIND_TASK_DUPLICATE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('C'), "Duplicate");
+pub const KEYBIND_TASK_PROJECT_BURNDOWN: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('B'), "Project burndown");
+pub const KEYBIND_TASK_TIMELINE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('T'), "Timeline");
 pub const KEYBIND_TASK_RENAME: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('r'), "Rename");
+pub const KEYBIND_TASK_SET_ESTIMATE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('h'), "Set estimate");
 pub const KEYBIND_TASK_TOGGLE_SEARCH: &SimpleKeybind =
     &SimpleKeybind::new_mod(KeyCode::Char('s'), KeyModifiers::NONE, "Toggle search");
 pub const KEYBIND_TASK_CLOSE_SEARCH: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Esc, "Close search");
 
+pub const KEYBIND_DETAIL_EDIT: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Enter, "Edit");
+pub const KEYBIND_DETAIL_OPEN_DEPENDENCY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Enter, "Open dependency");
+pub const KEYBIND_DETAIL_REMOVE_DEPENDENCY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('x'), "Remove dependency");
+pub const KEYBIND_DETAIL_EDIT_DEPENDENCY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('e'), "Edit dependency");
+pub const KEYBIND_DEPENDENCY_CYCLE_RELATION: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Tab, "Change relation");
+
+pub const KEYBIND_VIEW_BACK: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Esc, "Back");
+
 pub const KEYBIND_TABS_NEXT: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Tab, "Next tab");
 pub const KEYBIND_TABS_PREV: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::BackTab);
+pub const KEYBIND_TABS_NEW_TASK_LIST: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('t'), KeyModifiers::CONTROL, "New task list");
+pub const KEYBIND_TABS_CLOSE: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('w'), KeyModifiers::CONTROL, "Close tab");
 
 pub const KEYBIND_CONTROLS_CHECKBOX_TOGGLE: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Char(' '), "Toggle");
@@ -35,17 +151,44 @@ pub const KEYBIND_CONTROLS_LIST_NAV_EXT: &UpDownExtendedKeybind =
 pub const KEYBIND_MODAL_SUBMIT: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Enter, "Submit");
 pub const KEYBIND_MODAL_SUBMITSELECT: &SimpleKeybind =
     &SimpleKeybind::new(KeyCode::Enter, "Select");
+pub const KEYBIND_MODAL_DISMISS: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Enter, "Dismiss");
 pub const KEYBIND_MODAL_CANCEL: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Esc, "Cancel");
 pub const KEYBIND_MODAL_LEFTRIGHT_OPTION: &LeftRightKeybind =
     &LeftRightKeybind::new("Choose option");
 
+pub const KEYBIND_EXTERNAL_CHANGE_RELOAD: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('r'), "Reload from disk");
+pub const KEYBIND_EXTERNAL_CHANGE_KEEP: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('k'), "Keep mine");
+pub const KEYBIND_EXTERNAL_CHANGE_MERGE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('m'), "Merge new tasks");
+
+pub const KEYBIND_DIAGNOSTICS_REPAIR: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('r'), "Repair");
+
 pub const KEYBIND_SAVE: &SimpleKeybind =
     &SimpleKeybind::new_mod(KeyCode::Char('s'), KeyModifiers::CONTROL, "Save");
+pub const KEYBIND_GIT_SYNC: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('S'), "Sync with git");
 pub const KEYBIND_UNDO: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('u'), "Undo");
 pub const KEYBIND_REDO: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('U'), "Redo");
+pub const KEYBIND_UNDO_HISTORY: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char('H'), "Undo history");
+pub const KEYBIND_SWITCH_DATABASE: &SimpleKeybind =
+    &SimpleKeybind::new_mod(KeyCode::Char('o'), KeyModifiers::CONTROL, "Switch database");
+pub const KEYBIND_COMMAND_LINE: &SimpleKeybind =
+    &SimpleKeybind::new(KeyCode::Char(':'), "Command line");
 pub const KEYBIND_QUIT: &SimpleKeybind = &SimpleKeybind::new(KeyCode::Char('q'), "Quit");
 pub const KEYBIND_QUIT_ALT: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Esc);
 
+// vim mode, see `ui::vim`. `g` and `d` have to be pressed twice.
+pub const KEYBIND_VIM_DOWN: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('j'));
+pub const KEYBIND_VIM_UP: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('k'));
+pub const KEYBIND_VIM_TOP: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('g'));
+pub const KEYBIND_VIM_BOTTOM: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('G'));
+pub const KEYBIND_VIM_DELETE: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('d'));
+pub const KEYBIND_VIM_SEARCH: &SimpleKeybind = &SimpleKeybind::new_hidden(KeyCode::Char('/'));
+
 pub trait Keybind {
     fn is_match(&self, key: KeyEvent) -> bool;
     fn key_hint(&self) -> Cow<'static, str>;
@@ -104,12 +247,28 @@ impl SimpleKeybind {
         }
     }
 
+    /// Creates a keybind with a description that is only known at runtime.
+    pub fn new_owned(code: KeyCode, description: String) -> Self {
+        Self {
+            key_combo: KeyCombo(code, None),
+            description: Some(Cow::Owned(description)),
+        }
+    }
+
     pub const fn new_hidden(code: KeyCode) -> Self {
         Self {
             key_combo: KeyCombo(code, None),
             description: None,
         }
     }
+
+    /// Creates a key event that matches this keybind, to act as if it was pressed.
+    pub fn key_event(&self) -> KeyEvent {
+        KeyEvent::new(
+            self.key_combo.0,
+            self.key_combo.1.unwrap_or(KeyModifiers::NONE),
+        )
+    }
 }
 
 impl Keybind for SimpleKeybind {
@@ -4,44 +4,370 @@
     clippy::cloned_instead_of_copied
 )]
 
+mod autosave;
+mod autosync;
+mod background;
+mod check;
+mod cli;
+mod clipboard;
+mod command;
+mod file_watcher;
+mod fuzzy;
+mod hooks;
 mod keybinds;
+mod notifications;
+mod paths;
+mod session;
+mod startup;
 mod ui;
+mod undo_history;
 mod utils;
 
-use std::{error::Error, path::PathBuf};
+use std::{error::Error, path::Path};
+
+use cli::{Args, ExportFormat, ImportFormat, IntegrityCheck};
 
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use hooks::{HookEvent, Hooks};
 use ratatui::{backend::CrosstermBackend, Terminal};
+use session::SessionState;
+use td_lib::{
+    audit,
+    database::{Database, DiffSummary},
+    errors,
+    import::{taskwarrior, todotxt},
+    storage,
+    sync::github::{IssueSync, Repository},
+    time::OffsetDateTime,
+};
 use ui::AppState;
 
 fn main() {
-    let args = std::env::args().skip(1).collect::<Vec<_>>();
-    if args.is_empty() {
-        let name = std::env::args()
-            .next()
-            .expect("There should always be 1 item");
-        println!("Usage: {name} <database.json>");
+    let raw_args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(script) = cli::parse_check(&raw_args) {
+        let passed = script.and_then(|script| check::run(&script));
+        match passed {
+            Ok(true) => (),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error while running check script: {e}");
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    let args = match Args::parse(raw_args) {
+        Ok(args) => args,
+        Err(e) => {
+            let name = std::env::args()
+                .next()
+                .expect("There should always be 1 item");
+            println!("Error: {e}");
+            println!("Usage: {name} {}", cli::USAGE);
+            println!("       {name} {}", cli::CHECK_USAGE);
+            return;
+        }
+    };
+
+    if let Some(result) = run_command(&args) {
+        match result {
+            Ok(output) => print!("{output}"),
+            Err(e) => eprintln!("{e}"),
+        }
         return;
     }
 
-    let path = PathBuf::from(&args[0]);
-    let app = match AppState::create(path) {
+    // the default database is created on first run, along with the directory it is in
+    if !args.database_path.exists() {
+        if let Some(parent) = args.database_path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                println!("Error while creating database directory: {e}");
+                return;
+            }
+        }
+    }
+
+    if args.crdt {
+        if args.database_path.exists() {
+            println!("Error: --crdt can only be used when creating a new database");
+            return;
+        }
+
+        let mut database = Database::default();
+        database.enable_crdt();
+        let result = storage::open(&args.database_path)
+            .map_err(errors::Error::from)
+            .and_then(|mut storage| Ok(storage.save(&database)?));
+        if let Err(e) = result {
+            println!("Error while creating database: {e}");
+            return;
+        }
+    }
+
+    let mut databases = vec![args.database_path.clone()];
+    databases.extend(args.other_database_paths);
+    let mut app = match AppState::create(args.database_path) {
         Ok(app) => app,
         Err(e) => {
             println!("Error while loading database: {e}");
             return;
         }
     };
+    app.set_databases(databases);
 
-    if let Err(e) = run_app(app) {
+    if let Some(link) = args.link {
+        if !link.belongs_to(&app.database) {
+            println!("Error: {link} does not belong to this database");
+            return;
+        }
+        if !app.open_task(link.task.clone()) {
+            app.show_toast(format!("The task of {link} does not exist anymore"));
+        }
+    }
+
+    let mut apps = vec![app];
+    if let Err(e) = run_app(&mut apps) {
         println!("Error while running app: {e}");
     }
+
+    if args.profile_startup {
+        print!("{}", apps[0].startup_profile());
+    }
 }
 
-fn run_app(mut app: AppState) -> Result<(), Box<dyn Error>> {
+/// Runs the modes that do not start the UI, such as exporting or importing. Returns what to print,
+/// or `None` if the arguments are for the UI.
+fn run_command(args: &Args) -> Option<Result<String, String>> {
+    if let Some(format) = args.export {
+        return Some(
+            export_database(args, format)
+                .map_err(|e| format!("Error while exporting database: {e}")),
+        );
+    }
+
+    if let Some(filter) = &args.audit {
+        let result = audit::read(&audit::log_path(&args.database_path)).map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| filter.matches(entry))
+                .map(|entry| format!("{entry}\n"))
+                .collect()
+        });
+        return Some(result.map_err(|e| format!("Error while reading the audit log: {e}")));
+    }
+
+    if let Some(mode) = args.integrity {
+        return Some(
+            check_integrity(args, mode).map_err(|e| format!("Error while checking database: {e}")),
+        );
+    }
+
+    if args.pick {
+        return Some(match pick_task(args) {
+            Ok(Some(picked)) => Ok(format!("{picked}\n")),
+            Ok(None) => Ok("There are no actionable tasks\n".to_string()),
+            Err(e) => Err(format!("Error while loading database: {e}")),
+        });
+    }
+
+    if let Some(output_path) = &args.convert {
+        return Some(match convert_database(args, output_path) {
+            Ok(Some(summary)) => Ok(format!(
+                "Would write database to {}: {summary}\n",
+                output_path.display()
+            )),
+            Ok(None) => Ok(format!("Wrote database to {}\n", output_path.display())),
+            Err(e) => Err(format!("Error while converting database: {e}")),
+        });
+    }
+
+    if let Some((format, import_path)) = &args.import {
+        return Some(match import_tasks(args, *format, import_path) {
+            Ok((count, summary)) if args.dry_run => {
+                Ok(format!("Would import {count} tasks: {summary}\n"))
+            }
+            Ok((count, summary)) => Ok(format!("Imported {count} tasks: {summary}\n")),
+            Err(e) => Err(format!("Error while importing tasks: {e}")),
+        });
+    }
+
+    if let Some(repository) = &args.github {
+        return Some(match sync_github(args, repository) {
+            Ok((_, summary)) if args.dry_run => {
+                Ok(format!("Would sync with {repository}: {summary}\n"))
+            }
+            Ok((sync, _)) => Ok(format!("Synced with {repository}: {sync}\n")),
+            Err(e) => Err(format!("Error while syncing with GitHub: {e}")),
+        });
+    }
+
+    None
+}
+
+/// Synchronizes the database with the issues of the given repository and saves it. Returns what
+/// was synced and what that changed. With `--dry-run`, the database is left untouched.
+#[cfg(feature = "github")]
+fn sync_github(
+    args: &Args,
+    repository: &Repository,
+) -> Result<(IssueSync, DiffSummary), Box<dyn Error>> {
+    use td_lib::sync::github::{sync, GitHubClient};
+
+    let mut storage = storage::open(&args.database_path)?;
+    let mut database = storage.load()?;
+    let previous = database.clone();
+    let client = GitHubClient::from_env();
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    let result = sync(&client, &mut database, repository, args.close_issues, now);
+
+    let summary = DiffSummary::of(&database.diff(&previous));
+    if args.dry_run {
+        return Ok((result?, summary));
+    }
+
+    // closing an issue can fail after others were imported, which should be kept
+    storage.save(&database)?;
+    Ok((result?, summary))
+}
+
+#[cfg(not(feature = "github"))]
+fn sync_github(
+    _args: &Args,
+    _repository: &Repository,
+) -> Result<(IssueSync, DiffSummary), Box<dyn Error>> {
+    Err("td was built without the github feature".into())
+}
+
+/// Writes the database to the given file, in the storage format that belongs to its extension.
+/// With `--dry-run`, nothing is written and how the contents of that file would change is
+/// returned instead.
+fn convert_database(args: &Args, output_path: &Path) -> Result<Option<DiffSummary>, errors::Error> {
+    let database = storage::open(&args.database_path)?.load()?;
+    if args.dry_run {
+        // opening a file that does not exist yet can create it
+        let existing = match output_path.exists() {
+            true => storage::open(output_path)?.load()?,
+            false => Database::default(),
+        };
+        return Ok(Some(DiffSummary::of(&database.diff(&existing))));
+    }
+
+    storage::open(output_path)?.save(&database)?;
+    Ok(None)
+}
+
+fn export_database(args: &Args, format: ExportFormat) -> Result<String, errors::Error> {
+    let database = storage::open(&args.database_path)?.load()?;
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    Ok(format.export(&database, now))
+}
+
+/// Lists the problems in the database. With [`IntegrityCheck::Repair`], the ones that can be
+/// repaired are repaired and the database is saved, unless this is a dry run.
+fn check_integrity(args: &Args, mode: IntegrityCheck) -> Result<String, errors::Error> {
+    let mut storage = storage::open(&args.database_path)?;
+    let mut database = storage.load()?;
+    let report = database.validate();
+    if report.is_ok() {
+        return Ok("No problems found\n".to_string());
+    }
+
+    let mut output = report
+        .issues
+        .iter()
+        .map(|issue| format!("- {}\n", issue.describe(&database)))
+        .collect::<String>();
+    match mode {
+        IntegrityCheck::Report => match report.repairable_count() {
+            0 => (),
+            count => output.push_str(&format!("{count} problems can be repaired with --repair\n")),
+        },
+        IntegrityCheck::Repair if args.dry_run => {
+            let previous = database.clone();
+            let repaired = database.repair();
+            let summary = DiffSummary::of(&database.diff(&previous));
+            output.push_str(&format!(
+                "Would repair {} problems: {summary}\n",
+                repaired.len()
+            ));
+        }
+        IntegrityCheck::Repair => {
+            let repaired = database.repair();
+            if !repaired.is_empty() {
+                storage.save(&database)?;
+            }
+            output.push_str(&format!("Repaired {} problems\n", repaired.len()));
+        }
+    }
+    Ok(output)
+}
+
+fn pick_task(args: &Args) -> Result<Option<String>, errors::Error> {
+    let database = storage::open(&args.database_path)?.load()?;
+    let picked = database.pick_random_task(&mut rand::thread_rng(), |_| true);
+    Ok(picked.map(|task| format!("{} ({})", task.title, task.id().as_str())))
+}
+
+/// Imports the tasks in the given file into the database. Returns how many tasks were imported
+/// and what that changed. With `--dry-run`, the database is left untouched.
+fn import_tasks(
+    args: &Args,
+    format: ImportFormat,
+    import_path: &Path,
+) -> Result<(usize, DiffSummary), Box<dyn Error>> {
+    let mut storage = storage::open(&args.database_path)?;
+    let mut database = storage.load()?;
+    let previous = database.clone();
+
+    let contents = std::fs::read_to_string(import_path)?;
+    let count = match format {
+        ImportFormat::TodoTxt => {
+            let tasks = todotxt::parse(&contents);
+            let count = tasks.len();
+            for task in tasks {
+                database.add_task(task);
+            }
+            count
+        }
+        ImportFormat::Taskwarrior => {
+            let imported = taskwarrior::parse(&contents)?;
+            database.merge(&imported);
+            imported.get_all_tasks().count()
+        }
+    };
+
+    let summary = DiffSummary::of(&database.diff(&previous));
+    if args.dry_run {
+        return Ok((count, summary));
+    }
+
+    storage.save(&database)?;
+
+    let session = SessionState::load(&args.database_path);
+    if session.audit_log {
+        let entries = audit::changes(
+            &previous,
+            &database,
+            &audit::current_user(),
+            OffsetDateTime::now_utc(),
+        );
+        audit::append(&audit::log_path(&args.database_path), &entries)?;
+    }
+
+    let mut events = HookEvent::between(&previous, &database);
+    events.push(HookEvent::DatabaseSaved);
+    Hooks::load()?.run(&events, &args.database_path)?;
+
+    Ok((count, summary))
+}
+
+/// Runs the ui of the first database. The databases that are switched to are opened when needed
+/// and added to `apps`, so their state is kept when switching back to them.
+fn run_app(apps: &mut Vec<AppState>) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
 
@@ -50,7 +376,27 @@ fn run_app(mut app: AppState) -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    app.run_loop(&mut terminal)?;
+    let mut current = 0;
+    loop {
+        apps[current].run_loop(&mut terminal)?;
+        let Some(path) = apps[current].take_switch_request() else {break;};
+
+        if let Some(index) = apps.iter().position(|app| app.path == path) {
+            current = index;
+            continue;
+        }
+        match AppState::create(path.clone()) {
+            Ok(mut app) => {
+                app.set_databases(apps[current].databases().to_vec());
+                apps.push(app);
+                current = apps.len() - 1;
+            }
+            Err(e) => {
+                let message = format!("Could not open {}: {e}", path.display());
+                apps[current].show_toast(message);
+            }
+        }
+    }
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
@@ -0,0 +1,29 @@
+//! Desktop notifications for tasks that become due while td is running.
+
+use td_lib::reminders::DueState;
+
+/// Gets the text of the notification for a task that became due soon or overdue.
+pub fn message(title: &str, state: DueState) -> String {
+    match state {
+        DueState::DueSoon => format!("Due soon: {title}"),
+        DueState::Overdue => format!("Overdue: {title}"),
+    }
+}
+
+/// Shows a desktop notification with the given text.
+#[cfg(feature = "notifications")]
+pub fn send(text: &str) -> Result<(), String> {
+    notify_rust::Notification::new()
+        .appname("td")
+        .summary("td")
+        .body(text)
+        .show()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Shows a desktop notification with the given text.
+#[cfg(not(feature = "notifications"))]
+pub fn send(_text: &str) -> Result<(), String> {
+    Err("td was built without desktop notifications".to_string())
+}
@@ -0,0 +1,101 @@
+//! Finding the default location of the database, for when no path is given, and of the user's
+//! configuration.
+
+use std::path::PathBuf;
+
+/// Gets the path of the database that is used when none is given, such as
+/// `$XDG_DATA_HOME/td/tasks.json` on Linux. Returns `None` if the data directory of the user can
+/// not be found.
+pub fn default_database_path() -> Option<PathBuf> {
+    let data_dir = data_dir(|name| std::env::var_os(name).map(PathBuf::from))?;
+    Some(data_dir.join("td").join("tasks.json"))
+}
+
+/// Gets the path of the file with the user's hooks, such as `$XDG_CONFIG_HOME/td/hooks.json` on
+/// Linux. Returns `None` if the configuration directory of the user can not be found.
+pub fn hooks_path() -> Option<PathBuf> {
+    let config_dir = config_dir(|name| std::env::var_os(name).map(PathBuf::from))?;
+    Some(config_dir.join("td").join("hooks.json"))
+}
+
+/// Gets the directory the user's application data is stored in on this platform, looking up
+/// environment variables with `var`.
+fn data_dir(var: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    // relative paths are not allowed by the XDG spec and must be ignored
+    let absolute = |path: PathBuf| path.is_absolute().then_some(path);
+
+    if cfg!(windows) {
+        var("APPDATA").and_then(absolute)
+    } else if cfg!(target_os = "macos") {
+        var("HOME")
+            .and_then(absolute)
+            .map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_DATA_HOME")
+            .and_then(absolute)
+            .or_else(|| Some(absolute(var("HOME")?)?.join(".local").join("share")))
+    }
+}
+
+/// Gets the directory the user's configuration is stored in on this platform, looking up
+/// environment variables with `var`.
+fn config_dir(var: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    let absolute = |path: PathBuf| path.is_absolute().then_some(path);
+
+    if cfg!(windows) || cfg!(target_os = "macos") {
+        data_dir(var)
+    } else {
+        var("XDG_CONFIG_HOME")
+            .and_then(absolute)
+            .or_else(|| Some(absolute(var("HOME")?)?.join(".config")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn vars(
+        xdg: Option<&'static str>,
+        home: Option<&'static str>,
+    ) -> impl Fn(&str) -> Option<PathBuf> {
+        move |name| match name {
+            "XDG_DATA_HOME" | "XDG_CONFIG_HOME" => xdg.map(PathBuf::from),
+            "HOME" => home.map(PathBuf::from),
+            _ => None,
+        }
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn uses_xdg_data_home() {
+        assert_eq!(
+            data_dir(vars(Some("/data"), Some("/home/me"))),
+            Some("/data".into())
+        );
+        assert_eq!(
+            data_dir(vars(None, Some("/home/me"))),
+            Some("/home/me/.local/share".into())
+        );
+        assert_eq!(
+            data_dir(vars(Some("data"), Some("/home/me"))),
+            Some("/home/me/.local/share".into())
+        );
+        assert_eq!(data_dir(vars(None, None)), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn uses_xdg_config_home() {
+        assert_eq!(
+            config_dir(vars(Some("/config"), Some("/home/me"))),
+            Some("/config".into())
+        );
+        assert_eq!(
+            config_dir(vars(None, Some("/home/me"))),
+            Some("/home/me/.config".into())
+        );
+        assert_eq!(config_dir(vars(None, None)), None);
+    }
+}
@@ -0,0 +1,168 @@
+//! State that is kept between sessions, stored next to the database file.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use td_lib::{database::TaskId, rollover::RolloverOptions, time::OffsetDateTime};
+
+use crate::{autosave::AutoSaveOptions, autosync::AutoSyncOptions, ui::TaskFilters};
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// When the daily rollover last ran.
+    pub last_rollover: Option<OffsetDateTime>,
+    /// Which actions the daily rollover should run.
+    pub rollover: RolloverOptions,
+    /// Until when reminders have been shown to the user.
+    pub last_reminder_check: Option<OffsetDateTime>,
+    /// Whether the database is committed to the git repository it is in when saving.
+    pub git_sync: bool,
+    /// The GitHub repository that `:github` synchronizes with if none is given, which is the one
+    /// it synchronized with last.
+    pub github_repository: Option<String>,
+    /// When to sync with git automatically, if git sync is enabled.
+    pub autosync: AutoSyncOptions,
+    /// When to save automatically.
+    pub autosave: AutoSaveOptions,
+    /// How many changes can be undone at most. Uses [`Self::DEFAULT_UNDO_LIMIT`] if not set.
+    pub undo_limit: Option<usize>,
+    /// Whether the undo history is stored next to the database, so changes can still be undone
+    /// after restarting.
+    pub persist_undo: bool,
+    /// Whether tasks that become due while the ui runs are announced with a desktop
+    /// notification. Enabled if not set.
+    pub desktop_notifications: Option<bool>,
+    /// The tasks whose due notifications are snoozed, with the time until when.
+    pub snoozed_notifications: BTreeMap<TaskId, OffsetDateTime>,
+    /// Whether every saved change is appended to the audit log next to the database.
+    pub audit_log: bool,
+    /// Whether cancelling a text input asks before throwing away what was typed. Enabled if not
+    /// set.
+    pub confirm_discard: Option<bool>,
+    /// Actions that change more tasks than this at once have to be confirmed by typing the amount
+    /// of tasks. Uses [`Self::DEFAULT_BULK_CONFIRMATION_THRESHOLD`] if not set.
+    pub bulk_confirmation_threshold: Option<usize>,
+    /// How tasks are announced when the last of their dependencies is completed.
+    pub ready_tasks: ReadyTaskAlert,
+    /// Whether vim-style keys like `j`, `k`, `gg` and `dd` can be used.
+    pub vim_mode: bool,
+    /// How the ui looked when the last session ended.
+    pub ui: UiSettings,
+}
+
+/// How tasks that become ready to work on are announced, because the last of their dependencies
+/// was completed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadyTaskAlert {
+    /// They are not announced.
+    #[default]
+    Off,
+    /// They are highlighted in the task list.
+    Highlight,
+    /// They are highlighted, and a notification is shown.
+    Notify,
+}
+
+impl ReadyTaskAlert {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Highlight,
+            Self::Highlight => Self::Notify,
+            Self::Notify => Self::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Highlight => "highlight",
+            Self::Notify => "notify",
+        }
+    }
+}
+
+/// The ui settings that are restored when the database is opened again.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    /// The filters of the main task list.
+    #[serde(flatten)]
+    pub list: TaskFilters,
+    /// The filters of the task lists that were opened in extra tabs.
+    pub extra_task_lists: Vec<TaskFilters>,
+    /// The index of the selected tab.
+    pub selected_tab: usize,
+    /// The task that was selected last. It is selected again if it is still visible.
+    pub selected_task: Option<TaskId>,
+}
+
+impl SessionState {
+    pub const DEFAULT_UNDO_LIMIT: usize = 1000;
+    pub const DEFAULT_BULK_CONFIRMATION_THRESHOLD: usize = 10;
+
+    /// Gets the path of the session file that belongs to the given database.
+    pub fn path_for(database_path: &Path) -> PathBuf {
+        database_path.with_extension("session.json")
+    }
+
+    /// Loads the session state for the given database. A missing or unreadable session file
+    /// results in the default state.
+    pub fn load(database_path: &Path) -> Self {
+        std::fs::read_to_string(Self::path_for(database_path))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, database_path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path_for(database_path), json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_ui_settings_use_defaults() {
+        let session: SessionState =
+            serde_json::from_str(r#"{ "git_sync": true, "ui": { "sort_oldest_first": true } }"#)
+                .unwrap();
+        assert!(session.git_sync);
+        assert!(session.ui.list.sort_oldest_first);
+        assert!(session.ui.list.filter_completed);
+        assert!(session.ui.list.filter_archived);
+        assert!(session.ui.extra_task_lists.is_empty());
+        assert_eq!(session.ui.selected_tab, 0);
+    }
+
+    #[test]
+    fn extra_task_lists_are_stored_next_to_the_main_list() {
+        let ui = UiSettings {
+            list: TaskFilters {
+                project: Some("work".into()),
+                filter_search: true,
+                ..TaskFilters::default()
+            },
+            extra_task_lists: vec![TaskFilters {
+                assignee: Some("alex".into()),
+                ..TaskFilters::default()
+            }],
+            ..UiSettings::default()
+        };
+        let json = serde_json::to_value(&ui).unwrap();
+        assert_eq!(json["project"], "work");
+        assert_eq!(json["extra_task_lists"][0]["assignee"], "alex");
+
+        let ui: UiSettings = serde_json::from_value(json).unwrap();
+        assert_eq!(ui.list.project.as_deref(), Some("work"));
+        assert!(!ui.list.filter_search);
+        assert_eq!(ui.extra_task_lists[0].assignee.as_deref(), Some("alex"));
+    }
+}
@@ -0,0 +1,80 @@
+//! Measures how long each phase of starting up takes, so slow startups can be diagnosed with
+//! `--profile-startup`.
+
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+pub struct StartupProfile {
+    started: Instant,
+    /// When the last phase ended.
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupProfile {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last: now,
+            phases: vec![],
+        }
+    }
+
+    /// Records that the phase with the given name ended now. It started when the previous phase
+    /// ended.
+    pub fn mark(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+}
+
+impl Default for StartupProfile {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl Display for StartupProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.phases.iter().map(|(name, _)| name.len()).max();
+        let width = width.unwrap_or_default().max("total".len());
+        for (name, duration) in &self.phases {
+            writeln!(
+                f,
+                "{name:<width$} {:>9.2} ms",
+                duration.as_secs_f64() * 1000.
+            )?;
+        }
+        let total = self.last - self.started;
+        writeln!(
+            f,
+            "{:<width$} {:>9.2} ms",
+            "total",
+            total.as_secs_f64() * 1000.
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_phases_and_total() {
+        let mut profile = StartupProfile::start();
+        profile.mark("load database");
+        profile.mark("first frame");
+
+        let text = profile.to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("load database "));
+        assert!(lines[1].starts_with("first frame   "));
+        assert!(lines[2].starts_with("total         "));
+        assert!(lines.iter().all(|line| line.ends_with(" ms")));
+    }
+}
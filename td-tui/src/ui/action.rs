@@ -0,0 +1,669 @@
+//! Changes that components ask the app to make, so what a keybind does is decided separately from
+//! which keybind was pressed.
+
+use td_lib::{
+    database::{DependencyRelation, TagColor, Task, TaskDependency, TaskId},
+    time::{format_description, Duration, OffsetDateTime},
+};
+
+use super::AppState;
+use crate::utils::format_duration;
+
+/// A change to the app state or the database, created by a component in response to input.
+///
+/// Actions are applied with [`AppState::dispatch`]. Changes to the database are stored as a
+/// single undo step, labeled after the action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Creates a task with the given title in the project that is being looked at.
+    NewTask(String),
+    /// Adds the given task to the project that is being looked at.
+    AddTask(Box<Task>),
+    RenameTask(TaskId, String),
+    /// Adds a copy of the task, optionally depending on the same tasks.
+    DuplicateTask {
+        task: TaskId,
+        with_dependencies: bool,
+    },
+    DeleteTask(TaskId),
+    ToggleStarted(TaskId),
+    ToggleDone(TaskId),
+    /// Marks the task as done, along with all open tasks it directly or indirectly depends on.
+    CompleteWithDependencies(TaskId),
+    ToggleClockedIn(TaskId),
+    ToggleArchived(TaskId),
+    /// Marks the task as started, if it was not yet.
+    StartTask(TaskId),
+    AddTag(TaskId, String),
+    /// Removes the tag from all tasks that have it.
+    RemoveTag(String),
+    /// Sets the color a tag is shown in, or removes it.
+    SetTagColor(String, Option<TagColor>),
+    AddReminder(TaskId, OffsetDateTime),
+    AddDependency {
+        task: TaskId,
+        dependency: TaskId,
+    },
+    RemoveDependency {
+        task: TaskId,
+        dependency: TaskId,
+    },
+    /// Replaces all dependencies of the task with the given ones.
+    SetDependencies {
+        task: TaskId,
+        dependencies: Vec<TaskId>,
+    },
+    /// Changes the label and relation of an existing dependency.
+    EditDependency {
+        task: TaskId,
+        dependency: TaskId,
+        label: Option<String>,
+        relation: DependencyRelation,
+    },
+    ImportTasks(Vec<Task>),
+    /// Only shows the tasks of the given project, or of all projects if `None`.
+    SwitchProject(Option<String>),
+    /// Only shows the tasks assigned to the given person, or the tasks of everyone if `None`.
+    SwitchAssignee(Option<String>),
+    /// Only shows the tasks that the given task unblocks, or all tasks again if `None`.
+    ShowUnblockedBy(Option<TaskId>),
+    /// Moves the task into or out of the someday/maybe bucket.
+    ToggleSomeday(TaskId),
+    /// Only shows the tasks in the someday/maybe bucket, or hides them again.
+    ShowSomeday(bool),
+    /// Hides the task until the given time, or shows it again if no time is given.
+    Snooze(TaskId, Option<OffsetDateTime>),
+    /// Sets how long the task is expected to take, or clears the estimate.
+    SetEstimate(TaskId, Option<Duration>),
+    Undo,
+    Redo,
+    Save,
+}
+
+impl AppState {
+    /// Applies the given action.
+    pub fn dispatch(&mut self, action: Action) {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        self.dispatch_at(action, now);
+    }
+
+    /// Applies the given action as if it is the given time.
+    fn dispatch_at(&mut self, action: Action, now: OffsetDateTime) {
+        match action {
+            Action::NewTask(title) => {
+                let task = Task::create_now(title);
+                self.dispatch_at(Action::AddTask(Box::new(task)), now);
+            }
+            Action::AddTask(task) => {
+                let mut task = *task;
+                task.project = self.filters().project.clone();
+                task.assignee = self.filters().assignee.clone();
+                let label = format!("Add task '{}'", task.title);
+                self.database.modify(label, |db| db.add_task(task));
+            }
+            Action::RenameTask(task_id, title) => {
+                let label = format!("Rename '{}' to '{title}'", self.database[&task_id].title);
+                self.database.modify(label, |db| db[&task_id].title = title);
+            }
+            Action::DuplicateTask {
+                task,
+                with_dependencies,
+            } => {
+                let label = format!("Duplicate '{}'", self.database[&task].title);
+                self.database.modify(label, |db| {
+                    _ = db.duplicate_task(&task, with_dependencies, now);
+                });
+            }
+            Action::DeleteTask(task_id) => {
+                let title = self.database[&task_id].title.clone();
+                let label = format!("Delete task '{title}'");
+                self.database.modify(label, |db| db.remove_task(&task_id));
+                self.show_undo_toast(format!("Deleted '{title}'"));
+            }
+            Action::ToggleStarted(task_id) => {
+                let label = format!("Toggle started '{}'", self.database[&task_id].title);
+                self.database.modify(label, |db| {
+                    let task = &mut db[&task_id];
+                    task.time_started = task.time_started.xor(Some(now));
+                });
+            }
+            Action::ToggleDone(task_id) => {
+                let label = format!("Toggle done '{}'", self.database[&task_id].title);
+                self.database.modify(label, |db| {
+                    let task = &mut db[&task_id];
+                    task.time_completed = task.time_completed.xor(Some(now));
+                });
+                let task = &self.database[&task_id];
+                if task.time_completed.is_some() {
+                    self.show_undo_toast(format!("Completed '{}'", task.title));
+                    self.announce_ready_tasks(&[task_id]);
+                }
+            }
+            Action::CompleteWithDependencies(task_id) => {
+                let title = self.database[&task_id].title.clone();
+                let dependencies = self
+                    .database
+                    .open_dependencies(&task_id)
+                    .into_iter()
+                    .map(|task| task.id().clone())
+                    .collect::<Vec<_>>();
+                let label = format!("Complete '{title}' and its dependencies");
+                self.database.modify(label, |db| {
+                    for id in dependencies.iter().chain([&task_id]) {
+                        db[id].time_completed.get_or_insert(now);
+                    }
+                });
+                self.show_undo_toast(format!(
+                    "Completed '{title}' and {} dependencies",
+                    dependencies.len()
+                ));
+                let mut completed = dependencies;
+                completed.push(task_id);
+                self.announce_ready_tasks(&completed);
+            }
+            Action::ToggleClockedIn(task_id) => {
+                let label = format!("Clock in/out '{}'", self.database[&task_id].title);
+                self.database.modify(label, |db| {
+                    let task = &mut db[&task_id];
+                    if task.is_clocked_in() {
+                        task.clock_out(now);
+                    } else {
+                        task.clock_in(now);
+                    }
+                });
+            }
+            Action::ToggleArchived(task_id) => {
+                let label = format!("Toggle archived '{}'", self.database[&task_id].title);
+                self.database.modify(label, |db| {
+                    let task = &mut db[&task_id];
+                    task.time_archived = task.time_archived.xor(Some(now));
+                });
+                let task = &self.database[&task_id];
+                if task.time_archived.is_some() {
+                    self.show_undo_toast(format!("Archived '{}'", task.title));
+                }
+            }
+            Action::StartTask(task_id) => {
+                if self.database[&task_id].time_started.is_none() {
+                    let label = format!("Start '{}'", self.database[&task_id].title);
+                    self.database
+                        .modify(label, |db| db[&task_id].time_started = Some(now));
+                }
+            }
+            Action::AddTag(task_id, tag) => {
+                let label = format!("Add tag '{tag}' to '{}'", self.database[&task_id].title);
+                self.database
+                    .modify(label, |db| db[&task_id].tags.push(tag.into()));
+            }
+            Action::RemoveTag(tag) => {
+                let label = format!("Remove tag '{tag}'");
+                self.database.modify(label, |db| _ = db.remove_tag(&tag));
+            }
+            Action::SetTagColor(tag, color) => {
+                let label = match color {
+                    Some(color) => format!("Color tag '{tag}' {}", color.name()),
+                    None => format!("Remove color of tag '{tag}'"),
+                };
+                self.database
+                    .modify(label, |db| db.set_tag_color(&tag, color));
+            }
+            Action::AddReminder(task_id, time) => {
+                self.database.modify("Add reminder", |db| {
+                    let task = &mut db[&task_id];
+                    task.reminders.push(time);
+                    task.reminders.sort();
+                });
+            }
+            Action::AddDependency { task, dependency } => {
+                let label = format!("Add dependency to '{}'", self.database[&task].title);
+                self.database.modify(label, |db| {
+                    db.add_dependency_with(&task, &dependency, Self::new_dependency(now));
+                });
+            }
+            Action::RemoveDependency { task, dependency } => {
+                let label = format!("Remove dependency from '{}'", self.database[&task].title);
+                self.database
+                    .modify(label, |db| _ = db.remove_dependency(&task, &dependency));
+            }
+            Action::SetDependencies { task, dependencies } => {
+                let current = self
+                    .database
+                    .get_dependencies(&task)
+                    .map(|dependency| dependency.id().clone())
+                    .collect::<Vec<_>>();
+                let removed = current
+                    .iter()
+                    .filter(|id| !dependencies.contains(id))
+                    .collect::<Vec<_>>();
+                let added = dependencies
+                    .iter()
+                    .filter(|id| !current.contains(id))
+                    .collect::<Vec<_>>();
+                if removed.is_empty() && added.is_empty() {
+                    return;
+                }
+
+                let label = format!("Edit dependencies of '{}'", self.database[&task].title);
+                self.database.modify(label, |db| {
+                    for dependency in removed {
+                        db.remove_dependency(&task, dependency);
+                    }
+                    for dependency in added {
+                        db.add_dependency_with(&task, dependency, Self::new_dependency(now));
+                    }
+                });
+            }
+            Action::EditDependency {
+                task,
+                dependency,
+                label,
+                relation,
+            } => {
+                let Some(current) = self.database.get_dependency(&task, &dependency) else {return;};
+                if current.label == label && current.relation == relation {
+                    return;
+                }
+
+                let undo_label = format!(
+                    "Edit dependency of '{}' on '{}'",
+                    self.database[&task].title, self.database[&dependency].title
+                );
+                self.database.modify(undo_label, |db| {
+                    let edge = db.get_dependency_mut(&task, &dependency).unwrap();
+                    edge.label = label;
+                    edge.relation = relation;
+                });
+            }
+            Action::ImportTasks(tasks) => {
+                let label = format!("Import {} tasks", tasks.len());
+                self.database.modify(label, |db| {
+                    for task in tasks {
+                        db.add_task(task);
+                    }
+                });
+            }
+            Action::SwitchProject(project) => self.filters_mut().project = project,
+            Action::SwitchAssignee(assignee) => self.filters_mut().assignee = assignee,
+            Action::ShowUnblockedBy(task_id) => self.filters_mut().unblocks_filter = task_id,
+            Action::ToggleSomeday(task_id) => {
+                let task = &self.database[&task_id];
+                let label = match task.is_someday() {
+                    true => format!("Move '{}' out of someday/maybe", task.title),
+                    false => format!("Move '{}' to someday/maybe", task.title),
+                };
+                self.database.modify(label, |db| {
+                    let task = &mut db[&task_id];
+                    task.set_someday(!task.is_someday());
+                });
+            }
+            Action::ShowSomeday(show) => self.filters_mut().show_someday = show,
+            Action::Snooze(task_id, until) => {
+                let title = self.database[&task_id].title.clone();
+                let label = match until {
+                    Some(_) => format!("Snooze '{title}'"),
+                    None => format!("Unsnooze '{title}'"),
+                };
+                self.database
+                    .modify(label, |db| db[&task_id].snoozed_until = until);
+                if let Some(until) = until {
+                    let format = format_description::parse("[year]-[month]-[day] [hour]:[minute]")
+                        .expect("valid hardcoded format");
+                    let until = until.format(&format).unwrap_or_default();
+                    self.show_undo_toast(format!("Snoozed '{title}' until {until}"));
+                }
+            }
+            Action::SetEstimate(task_id, estimate) => {
+                let title = self.database[&task_id].title.clone();
+                let label = match estimate {
+                    Some(estimate) => {
+                        format!("Estimate '{title}' at {}", format_duration(estimate))
+                    }
+                    None => format!("Clear estimate of '{title}'"),
+                };
+                self.database
+                    .modify(label, |db| db[&task_id].estimate = estimate);
+            }
+            Action::Undo => {
+                _ = self.database.undo();
+                // the toasts would offer to undo a change that is already undone
+                self.toasts.dismiss_undo_offers();
+            }
+            Action::Redo => _ = self.database.redo(),
+            Action::Save => _ = self.save(),
+        }
+    }
+
+    /// Creates the properties of a dependency that is added at the given time.
+    fn new_dependency(now: OffsetDateTime) -> TaskDependency {
+        TaskDependency {
+            created: Some(now),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::ReadyTaskAlert;
+
+    fn add_task(state: &mut AppState, title: &str) -> TaskId {
+        state.dispatch(Action::NewTask(title.into()));
+        let task = state.database.get_all_tasks().find(|t| t.title == title);
+        task.unwrap().id().clone()
+    }
+
+    #[test]
+    fn toggles_task_state() {
+        let mut state = AppState::default();
+        let now = OffsetDateTime::now_utc();
+        let task_id = add_task(&mut state, "task");
+
+        state.dispatch_at(Action::ToggleDone(task_id.clone()), now);
+        assert_eq!(state.database[&task_id].time_completed, Some(now));
+        assert_eq!(state.toasts.waiting(), 0);
+        assert!(state.toasts.current().is_some());
+        state.dispatch(Action::Undo);
+        assert!(state.toasts.current().is_none());
+        state.dispatch(Action::Redo);
+        state.dispatch_at(Action::ToggleDone(task_id.clone()), now);
+        assert_eq!(state.database[&task_id].time_completed, None);
+
+        state.dispatch_at(Action::StartTask(task_id.clone()), now);
+        state.dispatch_at(Action::StartTask(task_id.clone()), now);
+        assert_eq!(state.database[&task_id].time_started, Some(now));
+        assert_eq!(state.database.steps().last().unwrap().label, "Start 'task'");
+
+        state.dispatch(Action::Undo);
+        assert_eq!(state.database[&task_id].time_started, None);
+    }
+
+    #[test]
+    fn snoozed_tasks_are_hidden_until_their_time() {
+        let mut state = AppState::default();
+        let task_id = add_task(&mut state, "task");
+        let is_visible = |state: &AppState| {
+            let predicate = state.get_task_filter_predicate();
+            predicates::Predicate::eval(&predicate, &state.database[&task_id])
+        };
+
+        let now = OffsetDateTime::now_utc();
+        state.dispatch(Action::Snooze(
+            task_id.clone(),
+            Some(now + Duration::hours(1)),
+        ));
+        assert!(!is_visible(&state));
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Snooze 'task'"
+        );
+
+        state.dispatch(Action::Snooze(
+            task_id.clone(),
+            Some(now - Duration::hours(1)),
+        ));
+        assert!(is_visible(&state));
+
+        state.dispatch(Action::Snooze(task_id.clone(), None));
+        assert_eq!(state.database[&task_id].snoozed_until, None);
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Unsnooze 'task'"
+        );
+    }
+
+    #[test]
+    fn sets_and_clears_estimates() {
+        let mut state = AppState::default();
+        let task_id = add_task(&mut state, "task");
+
+        state.dispatch(Action::SetEstimate(
+            task_id.clone(),
+            Some(Duration::minutes(150)),
+        ));
+        assert_eq!(
+            state.database[&task_id].estimate,
+            Some(Duration::minutes(150))
+        );
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Estimate 'task' at 2h 30m"
+        );
+
+        state.dispatch(Action::SetEstimate(task_id.clone(), None));
+        assert_eq!(state.database[&task_id].estimate, None);
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Clear estimate of 'task'"
+        );
+    }
+
+    #[test]
+    fn someday_tasks_are_only_shown_in_their_bucket() {
+        let mut state = AppState::default();
+        let task_id = add_task(&mut state, "task");
+        let is_visible = |state: &AppState| {
+            let predicate = state.get_task_filter_predicate();
+            predicates::Predicate::eval(&predicate, &state.database[&task_id])
+        };
+        assert!(is_visible(&state));
+
+        state.dispatch(Action::ToggleSomeday(task_id.clone()));
+        assert!(state.database[&task_id].is_someday());
+        assert!(!is_visible(&state));
+
+        state.dispatch(Action::ShowSomeday(true));
+        assert!(is_visible(&state));
+
+        state.dispatch(Action::ToggleSomeday(task_id.clone()));
+        assert!(!is_visible(&state));
+    }
+
+    #[test]
+    fn new_tasks_go_into_current_project() {
+        let mut state = AppState::default();
+        state.dispatch(Action::SwitchProject(Some("work".into())));
+        let task_id = add_task(&mut state, "task");
+        assert_eq!(state.database[&task_id].project.as_deref(), Some("work"));
+
+        let dependency = add_task(&mut state, "dependency");
+        state.dispatch(Action::AddDependency {
+            task: task_id.clone(),
+            dependency: dependency.clone(),
+        });
+        assert!(!state.database.is_actionable(&task_id));
+
+        state.dispatch(Action::DuplicateTask {
+            task: task_id.clone(),
+            with_dependencies: true,
+        });
+        let copy = state
+            .database
+            .get_all_tasks()
+            .find(|t| t.id() != &task_id && t.title == "task");
+        assert!(!state.database.is_actionable(copy.unwrap().id()));
+
+        state.dispatch(Action::DeleteTask(dependency));
+        assert!(state.database.is_actionable(&task_id));
+    }
+
+    #[test]
+    fn sets_tag_colors() {
+        let mut state = AppState::default();
+        state.dispatch(Action::SetTagColor("work".into(), Some(TagColor::Blue)));
+        assert_eq!(state.database.tag_color("work"), Some(TagColor::Blue));
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Color tag 'work' blue"
+        );
+
+        state.dispatch(Action::SetTagColor("work".into(), None));
+        assert_eq!(state.database.tag_color("work"), None);
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Remove color of tag 'work'"
+        );
+    }
+
+    #[test]
+    fn filters_by_assignee() {
+        let mut state = AppState::default();
+        let unassigned = add_task(&mut state, "unassigned");
+        state.dispatch(Action::SwitchAssignee(Some("alex".into())));
+        let assigned = add_task(&mut state, "assigned");
+        assert_eq!(state.database[&assigned].assignee.as_deref(), Some("alex"));
+
+        let visible = |state: &AppState| {
+            let predicate = state.get_task_filter_predicate();
+            let mut titles = state
+                .database
+                .get_all_tasks()
+                .filter(|t| predicates::Predicate::eval(&predicate, t))
+                .map(|t| t.title.clone())
+                .collect::<Vec<_>>();
+            titles.sort();
+            titles
+        };
+        assert_eq!(visible(&state), ["assigned"]);
+
+        state.dispatch(Action::SwitchAssignee(None));
+        assert_eq!(visible(&state), ["assigned", "unassigned"]);
+        assert_eq!(state.database[&unassigned].assignee, None);
+    }
+
+    #[test]
+    fn task_lists_have_their_own_filters() {
+        let mut state = AppState::default();
+        state.dispatch(Action::SwitchProject(Some("work".into())));
+        state.dispatch(Action::ShowSomeday(true));
+
+        let list_id = state.add_task_list(state.filters().duplicate());
+        assert_eq!(state.filters().project.as_deref(), Some("work"));
+        assert!(!state.filters().show_someday);
+        state.dispatch(Action::SwitchProject(Some("home".into())));
+        let task_id = add_task(&mut state, "task");
+        assert_eq!(state.database[&task_id].project.as_deref(), Some("home"));
+
+        state.activate_task_list(0);
+        assert_eq!(state.filters().project.as_deref(), Some("work"));
+        assert!(state.filters().show_someday);
+
+        state.activate_task_list(list_id);
+        state.remove_task_list(list_id);
+        assert_eq!(state.extra_task_lists().count(), 0);
+        assert_eq!(state.filters().project.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn completes_open_dependencies_in_one_step() {
+        let mut state = AppState::default();
+        let task_id = add_task(&mut state, "task");
+        let dependency = add_task(&mut state, "dependency");
+        let nested = add_task(&mut state, "nested");
+        state.dispatch(Action::AddDependency {
+            task: task_id.clone(),
+            dependency: dependency.clone(),
+        });
+        state.dispatch(Action::AddDependency {
+            task: dependency.clone(),
+            dependency: nested.clone(),
+        });
+
+        state.dispatch(Action::CompleteWithDependencies(task_id.clone()));
+        for id in [&task_id, &dependency, &nested] {
+            assert!(state.database[id].time_completed.is_some());
+        }
+
+        state.dispatch(Action::Undo);
+        for id in [&task_id, &dependency, &nested] {
+            assert!(state.database[id].time_completed.is_none());
+        }
+    }
+
+    #[test]
+    fn highlights_tasks_that_become_ready() {
+        let mut state = AppState {
+            ready_task_alert: ReadyTaskAlert::Highlight,
+            ..Default::default()
+        };
+        let task_id = add_task(&mut state, "task");
+        let dependency = add_task(&mut state, "dependency");
+        state.dispatch(Action::AddDependency {
+            task: task_id.clone(),
+            dependency: dependency.clone(),
+        });
+        assert!(!state.is_newly_ready(&state.database[&task_id]));
+
+        state.dispatch(Action::ToggleDone(dependency));
+        assert!(state.is_newly_ready(&state.database[&task_id]));
+
+        state.dispatch(Action::StartTask(task_id.clone()));
+        assert!(!state.is_newly_ready(&state.database[&task_id]));
+    }
+
+    #[test]
+    fn sets_all_dependencies_in_one_step() {
+        let mut state = AppState::default();
+        let task_id = add_task(&mut state, "task");
+        let [kept, removed, added] = ["kept", "removed", "added"].map(|t| add_task(&mut state, t));
+        for dependency in [&kept, &removed] {
+            state.dispatch(Action::AddDependency {
+                task: task_id.clone(),
+                dependency: dependency.clone(),
+            });
+        }
+        let steps = state.database.steps().len();
+
+        state.dispatch(Action::SetDependencies {
+            task: task_id.clone(),
+            dependencies: vec![kept.clone(), added.clone()],
+        });
+        let mut dependencies = state
+            .database
+            .get_dependencies(&task_id)
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>();
+        dependencies.sort_unstable();
+        assert_eq!(dependencies, ["added", "kept"]);
+        assert_eq!(state.database.steps().len(), steps + 1);
+
+        state.dispatch(Action::Undo);
+        assert!(state.database.get_dependency(&task_id, &removed).is_some());
+        assert!(state.database.get_dependency(&task_id, &added).is_none());
+    }
+
+    #[test]
+    fn edits_dependency_properties() {
+        let mut state = AppState::default();
+        let now = OffsetDateTime::now_utc();
+        let [task, dependency] = ["task", "dependency"].map(|t| add_task(&mut state, t));
+        state.dispatch_at(
+            Action::AddDependency {
+                task: task.clone(),
+                dependency: dependency.clone(),
+            },
+            now,
+        );
+        let edge = state.database.get_dependency(&task, &dependency).unwrap();
+        assert_eq!(edge.created, Some(now));
+
+        let edit = Action::EditDependency {
+            task: task.clone(),
+            dependency: dependency.clone(),
+            label: Some("same bug".into()),
+            relation: DependencyRelation::Duplicates,
+        };
+        state.dispatch(edit.clone());
+        let steps = state.database.steps().len();
+        state.dispatch(edit);
+        assert_eq!(state.database.steps().len(), steps);
+
+        let edge = state.database.get_dependency(&task, &dependency).unwrap();
+        assert_eq!(edge.label.as_deref(), Some("same bug"));
+        assert_eq!(edge.relation, DependencyRelation::Duplicates);
+        assert_eq!(
+            state.database.steps().last().unwrap().label,
+            "Edit dependency of 'task' on 'dependency'"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use std::{cell::RefCell, time::SystemTime};
+
+use ratatui::{
+    layout::Rect,
+    widgets::{List, ListItem, ListState, Paragraph, Wrap},
+};
+use td_lib::{
+    audit::{self, AuditEntry},
+    database::TaskId,
+};
+
+use super::{
+    constants::{FG_DIM, FG_WHITE, LIST_HIGHLIGHT_STYLE, LIST_STYLE},
+    tasks::TaskDetailPage,
+    AppState, Component, FrameLocalStorage,
+};
+use crate::{background::BackgroundJob, file_watcher::modified_time, keybinds::*, utils::RectExt};
+
+/// The entries of the activity log, newest first, or why they could not be read.
+type Entries = Result<Vec<AuditEntry>, String>;
+
+/// A page that shows the activity log of the database, newest changes first.
+pub struct ActivityPage {
+    selected: usize,
+    /// The entries of the log, which are read again when the log changes.
+    entries: RefCell<BackgroundJob<Option<SystemTime>, Entries>>,
+}
+
+impl ActivityPage {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            entries: RefCell::default(),
+        }
+    }
+
+    /// Starts reading the log again, if it changed since it was last read.
+    fn request_entries(&self, state: &AppState) {
+        let path = audit::log_path(&state.path);
+        let mut entries = self.entries.borrow_mut();
+        entries.poll();
+        entries.request(modified_time(&path), move || {
+            audit::read(&path)
+                .map(|mut entries| {
+                    entries.reverse();
+                    entries
+                })
+                .map_err(|e| e.to_string())
+        });
+    }
+
+    /// Gets the task that the selected entry is about, if it still exists.
+    fn selected_task(&self, state: &AppState) -> Option<TaskId> {
+        let entries = self.entries.borrow();
+        let Some(Ok(entries)) = entries.latest() else {return None;};
+        let task = entries.get(self.selected)?.change.task()?;
+        state.database.get_task(task).map(|task| task.id().clone())
+    }
+}
+
+impl Component for ActivityPage {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        self.request_entries(global_state);
+
+        let count = match self.entries.borrow().latest() {
+            Some(Ok(entries)) => entries.len(),
+            _ => 0,
+        };
+        let selected_task = self.selected_task(global_state);
+        frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, count >= 2);
+        frame_storage.register_keybind(KEYBIND_TASK_OPEN_DETAILS, selected_task.is_some());
+        frame_storage.register_keybind(
+            match global_state.audit_log_enabled() {
+                true => KEYBIND_ACTIVITY_STOP_RECORDING,
+                false => KEYBIND_ACTIVITY_START_RECORDING,
+            },
+            true,
+        );
+        frame_storage.selected_task_id = selected_task;
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: Rect,
+        state: &AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) {
+        let (header_area, list_area) = area.split_y(1);
+        let header = match state.audit_log_enabled() {
+            true => " Recording changes to the activity log",
+            false => " Not recording changes to the activity log",
+        };
+        frame.render_widget(Paragraph::new(header).style(FG_WHITE), header_area);
+
+        let job = self.entries.borrow();
+        let entries = match job.latest() {
+            None => {
+                frame.render_widget(Paragraph::new("Reading...").style(FG_DIM), list_area);
+                return;
+            }
+            Some(Err(e)) => {
+                let message = format!("Could not read the activity log: {e}");
+                let paragraph = Paragraph::new(message).wrap(Wrap { trim: false });
+                frame.render_widget(paragraph, list_area);
+                return;
+            }
+            Some(Ok(entries)) if entries.is_empty() => {
+                let paragraph = Paragraph::new("No activity was recorded yet").style(FG_DIM);
+                frame.render_widget(paragraph, list_area);
+                return;
+            }
+            Some(Ok(entries)) => entries,
+        };
+
+        let items = entries
+            .iter()
+            .map(|entry| ListItem::new(entry.to_string()))
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .highlight_style(LIST_HIGHLIGHT_STYLE)
+            .style(LIST_STYLE);
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.selected.min(entries.len() - 1)));
+        frame.render_stateful_widget(list, list_area, &mut list_state);
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        let count = match self.entries.borrow().latest() {
+            Some(Ok(entries)) => entries.len(),
+            _ => 0,
+        };
+        self.selected = self.selected.min(count.saturating_sub(1));
+
+        if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            self.selected = match key {
+                UpDownKey::Up => self.selected.saturating_sub(1),
+                UpDownKey::Down => (self.selected + 1).min(count.saturating_sub(1)),
+            };
+            true
+        } else if KEYBIND_TASK_OPEN_DETAILS.is_match(key) {
+            if let Some(task) = self.selected_task(state) {
+                state.push_view(TaskDetailPage::new(task));
+            }
+            true
+        } else if KEYBIND_ACTIVITY_START_RECORDING.is_match(key) {
+            let enabled = !state.audit_log_enabled();
+            state.set_audit_log(enabled);
+            state.show_toast(
+                match enabled {
+                    true => "Recording changes to the activity log",
+                    false => "Stopped recording changes to the activity log",
+                }
+                .to_string(),
+            );
+            true
+        } else {
+            false
+        }
+    }
+}
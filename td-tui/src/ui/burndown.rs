@@ -0,0 +1,130 @@
+use ratatui::{
+    layout::Rect,
+    symbols::Marker,
+    text::Span,
+    widgets::{Axis, Chart, Dataset, GraphType, Paragraph},
+    Frame,
+};
+use td_lib::{
+    database::Task,
+    stats::Burndown,
+    time::{Duration, OffsetDateTime, UtcOffset},
+};
+
+use super::{
+    constants::{ACCENT_COLOR, FG_DIM, FG_WHITE},
+    AppState, Component, FrameLocalStorage, View,
+};
+
+/// The tasks that a [`BurndownView`] shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BurndownScope {
+    Tag(String),
+    Project(String),
+}
+
+impl BurndownScope {
+    fn contains(&self, task: &Task) -> bool {
+        match self {
+            Self::Tag(tag) => task.tags.iter().any(|t| t == tag),
+            Self::Project(project) => task.project.as_ref() == Some(project),
+        }
+    }
+}
+
+/// A full-screen view with a chart of the amount of open tasks with a tag or in a project over
+/// time, from the day the first of them was created until today.
+pub struct BurndownView {
+    scope: BurndownScope,
+}
+
+impl BurndownView {
+    pub fn new(scope: BurndownScope) -> Self {
+        Self { scope }
+    }
+
+    fn burndown(&self, state: &AppState) -> Burndown {
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let today = OffsetDateTime::now_utc().to_offset(offset).date();
+
+        let tasks = state
+            .database
+            .get_all_tasks()
+            .filter(|t| self.scope.contains(t))
+            .collect::<Vec<_>>();
+        let first = tasks
+            .iter()
+            .map(|t| t.time_created.to_offset(offset).date())
+            .min()
+            .unwrap_or(today);
+        // start the day before, so the chart starts at zero
+        let from = first
+            .previous_day()
+            .unwrap_or(first)
+            .min(today - Duration::DAY);
+        Burndown::new(tasks, from, today, offset)
+    }
+}
+
+impl View for BurndownView {
+    fn title(&self, _state: &AppState) -> String {
+        match &self.scope {
+            BurndownScope::Tag(tag) => format!("Burndown of {tag}"),
+            BurndownScope::Project(project) => format!("Burndown of project {project}"),
+        }
+    }
+}
+
+impl Component for BurndownView {
+    fn pre_render(&self, _global_state: &AppState, _frame_storage: &mut FrameLocalStorage) {}
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) {
+        let burndown = self.burndown(state);
+        let (Some((first, _)), Some((last, open))) = (burndown.days.first(), burndown.days.last())
+        else {
+            frame.render_widget(Paragraph::new("There are no tasks to show"), area);
+            return;
+        };
+
+        let points = burndown
+            .days
+            .iter()
+            .enumerate()
+            .map(|(day, (_, open))| (day as f64, *open as f64))
+            .collect::<Vec<_>>();
+        let dataset = Dataset::default()
+            .name(format!("{open} open"))
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(ACCENT_COLOR)
+            .data(&points);
+
+        let max = burndown.max().max(1);
+        let chart = Chart::new(vec![dataset])
+            .x_axis(
+                Axis::default()
+                    .style(FG_DIM)
+                    .bounds([0.0, (points.len() - 1).max(1) as f64])
+                    .labels(vec![
+                        Span::styled(first.to_string(), FG_WHITE),
+                        Span::styled(last.to_string(), FG_WHITE),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(FG_DIM)
+                    .bounds([0.0, max as f64])
+                    .labels(vec![
+                        Span::styled("0", FG_WHITE),
+                        Span::styled(max.to_string(), FG_WHITE),
+                    ]),
+            );
+        frame.render_widget(chart, area);
+    }
+}
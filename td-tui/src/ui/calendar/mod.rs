@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use td_lib::{
+    database::Database,
+    time::{Date, Duration, OffsetDateTime, UtcOffset},
+};
+
+use super::{
+    constants::{BOLD, COMPLETED_TASK, FG_DIM, FG_RED, FG_WHITE, LIST_HIGHLIGHT_STYLE},
+    AppState, Component,
+};
+use crate::{keybinds::*, utils::RectExt};
+
+/// How much of the calendar a [`CalendarPage`] shows at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalendarMode {
+    Month,
+    Week,
+}
+
+/// A page that shows the tasks on a calendar, by the day they are due or were completed on.
+pub struct CalendarPage {
+    mode: CalendarMode,
+    selected: Date,
+}
+
+/// A task shown on a day of the calendar.
+struct Entry<'db> {
+    title: &'db str,
+    completed: bool,
+}
+
+impl CalendarPage {
+    pub fn new() -> Self {
+        Self {
+            mode: CalendarMode::Month,
+            selected: today(),
+        }
+    }
+
+    /// Gets the days shown in the calendar, which are whole weeks starting on monday.
+    fn shown_days(&self) -> (Date, usize) {
+        match self.mode {
+            CalendarMode::Month => {
+                let first = self
+                    .selected
+                    .replace_day(1)
+                    .expect("every month has a first day");
+                (start_of_week(first), 6 * 7)
+            }
+            CalendarMode::Week => (start_of_week(self.selected), 7),
+        }
+    }
+
+    fn move_selection(&mut self, days: i64) {
+        if let Some(date) = self.selected.checked_add(Duration::days(days)) {
+            self.selected = date;
+        }
+    }
+
+    fn render_day(&self, frame: &mut ratatui::Frame, area: Rect, date: Date, entries: &[Entry]) {
+        let today = today();
+        let outside_month =
+            self.mode == CalendarMode::Month && date.month() != self.selected.month();
+        let header_style = if date == self.selected {
+            LIST_HIGHLIGHT_STYLE
+        } else if date == today {
+            BOLD
+        } else if outside_month {
+            FG_DIM
+        } else {
+            FG_WHITE
+        };
+        let header = match self.mode {
+            CalendarMode::Month => format!("{:>2}", date.day()),
+            CalendarMode::Week => format!("{} {}", date.weekday(), date),
+        };
+
+        let mut lines = vec![Line::from(Span::styled(header, header_style))];
+        let room = usize::from(area.height.saturating_sub(1));
+        let shown = match entries.len() > room {
+            true => room.saturating_sub(1),
+            false => entries.len(),
+        };
+        lines.extend(entries[..shown].iter().map(|entry| {
+            let style = match (entry.completed, date < today) {
+                (true, _) => COMPLETED_TASK,
+                (false, true) => FG_RED,
+                (false, false) => FG_WHITE,
+            };
+            Line::from(Span::styled(entry.title, style))
+        }));
+        if shown < entries.len() {
+            lines.push(Line::from(Span::styled(
+                format!("+{} more", entries.len() - shown),
+                FG_DIM,
+            )));
+        }
+
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+}
+
+impl Component for CalendarPage {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut super::FrameLocalStorage) {
+        frame_storage.register_keybind(KEYBIND_CALENDAR_DAY, true);
+        frame_storage.register_keybind(KEYBIND_CALENDAR_WEEK, true);
+        frame_storage.register_keybind(KEYBIND_CALENDAR_MODE, true);
+        frame_storage.register_keybind(KEYBIND_CALENDAR_TODAY, true);
+        frame_storage.register_keybind(KEYBIND_CALENDAR_SHOW_DAY, true);
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: Rect,
+        state: &AppState,
+        _frame_storage: &super::FrameLocalStorage,
+    ) {
+        let (header_area, calendar_area) = area.split_y(1);
+        let header = match self.mode {
+            CalendarMode::Month => {
+                format!(" {} {}", self.selected.month(), self.selected.year())
+            }
+            CalendarMode::Week => format!(
+                " Week {} of {}",
+                self.selected.iso_week(),
+                self.selected.year()
+            ),
+        };
+        frame.render_widget(Paragraph::new(header).style(FG_WHITE), header_area);
+
+        let (first, count) = self.shown_days();
+        let last = first + Duration::days(count as i64 - 1);
+        let mut entries = entries(&state.database, first, last);
+
+        let (weekdays_area, days_area) = match self.mode {
+            CalendarMode::Month => calendar_area.split_y(1),
+            CalendarMode::Week => (Rect::default(), calendar_area),
+        };
+        let column_width = days_area.width / 7;
+        let rows = (count / 7) as u16;
+        let row_height = days_area.height / rows;
+
+        for (index, date) in (0..count).map(|i| (i, first + Duration::days(i as i64))) {
+            let (column, row) = ((index % 7) as u16, (index / 7) as u16);
+            if row == 0 && self.mode == CalendarMode::Month {
+                let weekday = date.weekday().to_string();
+                let weekday_area = Rect {
+                    x: weekdays_area.x + column * column_width,
+                    width: column_width,
+                    ..weekdays_area
+                };
+                frame.render_widget(Paragraph::new(&weekday[..3]).style(BOLD), weekday_area);
+            }
+
+            let day_area = Rect::new(
+                days_area.x + column * column_width,
+                days_area.y + row * row_height,
+                column_width.saturating_sub(1),
+                row_height,
+            );
+            let day_entries = entries.remove(&date).unwrap_or_default();
+            self.render_day(frame, day_area, date, &day_entries);
+        }
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        _frame_storage: &super::FrameLocalStorage,
+    ) -> bool {
+        if let Some(key) = KEYBIND_CALENDAR_DAY.get_match(key) {
+            self.move_selection(match key {
+                LeftRightKey::Left => -1,
+                LeftRightKey::Right => 1,
+            });
+            true
+        } else if let Some(key) = KEYBIND_CALENDAR_WEEK.get_match(key) {
+            self.move_selection(match key {
+                UpDownKey::Up => -7,
+                UpDownKey::Down => 7,
+            });
+            true
+        } else if KEYBIND_CALENDAR_MODE.is_match(key) {
+            self.mode = match self.mode {
+                CalendarMode::Month => CalendarMode::Week,
+                CalendarMode::Week => CalendarMode::Month,
+            };
+            true
+        } else if KEYBIND_CALENDAR_TODAY.is_match(key) {
+            self.selected = today();
+            true
+        } else if KEYBIND_CALENDAR_SHOW_DAY.is_match(key) {
+            state.request_search(format!("include:completed on:{}", self.selected));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn today() -> Date {
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    OffsetDateTime::now_utc().to_offset(offset).date()
+}
+
+fn start_of_week(date: Date) -> Date {
+    date - Duration::days(date.weekday().number_days_from_monday().into())
+}
+
+/// Gets the tasks to show on each day from `first` up to and including `last`. Open tasks are shown
+/// on the day they are due, completed tasks on the day they were completed.
+fn entries(database: &Database, first: Date, last: Date) -> BTreeMap<Date, Vec<Entry<'_>>> {
+    let mut entries = BTreeMap::<_, Vec<_>>::new();
+    for task in database.get_all_tasks() {
+        let (time, completed) = match task.time_completed {
+            Some(completed) => (completed, true),
+            None => match task.time_due {
+                Some(due) => (due, false),
+                None => continue,
+            },
+        };
+        if (first..=last).contains(&time.date()) {
+            entries.entry(time.date()).or_default().push((
+                time,
+                Entry {
+                    title: &task.title,
+                    completed,
+                },
+            ));
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(date, mut day)| {
+            day.sort_by_key(|(time, _)| *time);
+            (date, day.into_iter().map(|(_, entry)| entry).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use td_lib::{database::Task, time::Month};
+
+    use super::*;
+
+    #[test]
+    fn shows_open_tasks_when_due_and_completed_tasks_when_completed() {
+        let mut database = Database::default();
+        let day = |day| Date::from_calendar_date(2024, Month::May, day).unwrap();
+        let at = |date: Date| date.midnight().assume_utc();
+
+        let mut due = Task::create_now("due".into());
+        due.time_due = Some(at(day(3)));
+        let mut done = Task::create_now("done".into());
+        done.time_due = Some(at(day(3)));
+        done.time_completed = Some(at(day(2)));
+        let mut later = Task::create_now("later".into());
+        later.time_due = Some(at(day(20)));
+        database.add_task(due);
+        database.add_task(done);
+        database.add_task(later);
+        database.add_task(Task::create_now("no date".into()));
+
+        let entries = entries(&database, day(1), day(7));
+        let titles = entries
+            .iter()
+            .map(|(date, day)| (date.day(), day.iter().map(|e| e.title).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(titles, [(2, vec!["done"]), (3, vec!["due"])]);
+    }
+}
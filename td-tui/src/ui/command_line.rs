@@ -0,0 +1,77 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    keybinds::*,
+    ui::{input::TextBoxComponent, AppState, Component, FrameLocalStorage},
+    utils::RectExt,
+};
+
+/// A single line at the bottom of the screen to type commands in, opened with `:`.
+#[derive(Default)]
+pub struct CommandLine {
+    input: Option<TextBoxComponent>,
+}
+
+impl CommandLine {
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn open(&mut self) {
+        self.input = Some(TextBoxComponent::new_focused().with_background(true));
+    }
+
+    /// Closes the command line, returning the command that was typed.
+    pub fn close(&mut self) -> Option<String> {
+        self.input.take().map(|input| input.text().to_string())
+    }
+}
+
+impl Component for CommandLine {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if self.is_open() {
+            frame_storage.register_keybind(KEYBIND_MODAL_SUBMIT, true);
+            frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+            frame_storage.lock_keybinds();
+        }
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let Some(input) = &self.input else {return;};
+
+        // drawn over the keybind list
+        let area = area.take_last_y(TextBoxComponent::HEIGHT);
+        let (area_prompt, area_input) = area.split_x(1);
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(":"), area_prompt);
+        input.render(frame, area_input, state, frame_storage);
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        let Some(input) = &mut self.input else {return false;};
+
+        if KEYBIND_MODAL_CANCEL.is_match(key) {
+            self.input = None;
+            return true;
+        }
+
+        _ = input.process_input(key, state, frame_storage);
+        true
+    }
+}
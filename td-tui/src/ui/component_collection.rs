@@ -12,10 +12,7 @@ pub struct CollectionKey<T: Component> {
 
 impl<T: Component> Clone for CollectionKey<T> {
     fn clone(&self) -> Self {
-        Self {
-            index: self.index,
-            data: self.data,
-        }
+        *self
     }
 }
 impl<T: Component> Copy for CollectionKey<T> {}
@@ -33,7 +30,7 @@ impl ComponentCollection {
         self.components.push(my_box);
         CollectionKey {
             index: self.components.len() - 1,
-            data: PhantomData::default(),
+            data: PhantomData,
         }
     }
 }
@@ -93,6 +90,12 @@ impl Component for ComponentCollection {
 
         false
     }
+
+    fn on_tick(&mut self, state: &mut crate::ui::AppState) -> bool {
+        self.components
+            .iter_mut()
+            .fold(false, |changed, m| m.on_tick(state) | changed)
+    }
 }
 
 #[cfg(test)]
@@ -141,5 +144,6 @@ mod tests {
             &mut app_state,
             &frame_storage,
         );
+        collection.on_tick(&mut app_state);
     }
 }
@@ -1,4 +1,5 @@
 use ratatui::style::{Color, Modifier, Style};
+use td_lib::database::TagColor;
 
 /// The minimum width a modal window can be
 pub const MIN_MODAL_WIDTH: u16 = 32;
@@ -69,6 +70,26 @@ pub const ITALIC: Style = Style {
     underline_color: None,
 };
 
+/// Gets the style a tag is shown in, in its color if it has one.
+pub const fn tag_style(color: Option<TagColor>) -> Style {
+    let fg = match color {
+        None => Color::DarkGray,
+        Some(TagColor::Red) => Color::Red,
+        Some(TagColor::Green) => Color::Green,
+        Some(TagColor::Yellow) => Color::Yellow,
+        Some(TagColor::Blue) => Color::Blue,
+        Some(TagColor::Magenta) => Color::Magenta,
+        Some(TagColor::Cyan) => Color::Cyan,
+    };
+    Style {
+        fg: Some(fg),
+        bg: None,
+        add_modifier: Modifier::ITALIC,
+        sub_modifier: Modifier::empty(),
+        underline_color: None,
+    }
+}
+
 pub const TEXT: Style = Style {
     fg: Some(Color::White),
     bg: Some(Color::Black),
@@ -101,6 +122,15 @@ pub const STARTED_TASK: Style = Style {
     underline_color: None,
 };
 
+/// The style for tasks that became ready to work on because their dependencies were completed.
+pub const READY_TASK: Style = Style {
+    fg: Some(Color::Cyan),
+    bg: None,
+    add_modifier: Modifier::BOLD,
+    sub_modifier: Modifier::empty(),
+    underline_color: None,
+};
+
 pub const COMPLETED_TASK: Style = Style {
     fg: Some(Color::DarkGray),
     bg: None,
@@ -109,6 +139,14 @@ pub const COMPLETED_TASK: Style = Style {
     underline_color: None,
 };
 
+pub const ARCHIVED_TASK: Style = Style {
+    fg: Some(Color::DarkGray),
+    bg: None,
+    add_modifier: Modifier::ITALIC,
+    sub_modifier: Modifier::empty(),
+    underline_color: None,
+};
+
 /// The style for unselected list items
 pub const LIST_STYLE: Style = Style {
     fg: Some(Color::Gray),
@@ -134,6 +172,15 @@ pub const LIST_HIGHLIGHT_STYLE_DISABLED: Style = Style {
     underline_color: None,
 };
 
+/// The style for characters that matched a search query
+pub const SEARCH_MATCH_STYLE: Style = Style {
+    fg: Some(ACCENT_COLOR),
+    bg: None,
+    add_modifier: Modifier::UNDERLINED,
+    sub_modifier: Modifier::empty(),
+    underline_color: None,
+};
+
 /// The style for unselected tabs
 pub const TAB_STYLE: Style = Style {
     fg: Some(Color::DarkGray),
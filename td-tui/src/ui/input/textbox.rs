@@ -40,7 +40,6 @@ impl TextBoxComponent {
     }
 
     #[must_use]
-    #[allow(unused)]
     pub fn with_text(mut self, text: String) -> Self {
         self.input = Input::from(text);
         self
@@ -51,6 +50,10 @@ impl TextBoxComponent {
         self.input.value()
     }
 
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
     pub fn set_focus(&mut self, value: bool) {
         self.focused = value;
     }
@@ -67,6 +70,12 @@ impl Default for TextBoxComponent {
 }
 
 impl Component for TextBoxComponent {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if self.focused {
+            frame_storage.text_input_focused = true;
+        }
+    }
+
     fn render(
         &self,
         frame: &mut ratatui::Frame,
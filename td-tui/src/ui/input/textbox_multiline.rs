@@ -51,10 +51,7 @@ impl MultilineTextBoxComponent {
 
     fn get_text_position(naive_cursor_pos: usize, text_wrapped: &[String]) -> (u16, u16) {
         let (mut cursor_x, mut cursor_y) = (naive_cursor_pos, 0);
-        loop {
-            let Some(line) = text_wrapped.get(cursor_y) else {
-                break;
-            };
+        while let Some(line) = text_wrapped.get(cursor_y) {
             let line_len = line.len();
             if cursor_x <= line_len {
                 break;
@@ -1,4 +1,13 @@
-use ratatui::{symbols, text::Span, widgets::Paragraph};
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell},
+};
+
+use ratatui::{
+    symbols,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
 
 use super::{
     constants::{
@@ -8,10 +17,22 @@ use super::{
 };
 use crate::utils::wrap_spans;
 
-pub struct KeybindList;
+#[derive(Default)]
+pub struct KeybindList {
+    /// The wrapped keybind hints of the last frame. The same keybinds are usually registered frame
+    /// after frame, so they don't need to be built again.
+    cache: RefCell<Option<WrappedKeybinds>>,
+}
+
+struct WrappedKeybinds {
+    /// The keybinds and width the lines were made for.
+    keybinds: Vec<(Cow<'static, str>, Cow<'static, str>, bool)>,
+    width: u16,
+    lines: Vec<Line<'static>>,
+}
 
 impl KeybindList {
-    pub fn get_spans(frame_storage: &super::FrameLocalStorage) -> Vec<Span> {
+    pub fn get_spans(frame_storage: &super::FrameLocalStorage) -> Vec<Span<'static>> {
         let keybinds = &frame_storage.current_keybinds;
         let mut spans = vec![];
 
@@ -33,9 +54,9 @@ impl KeybindList {
             } else {
                 KEYBINDS_CHAR_INACTIVE
             };
-            spans.push(Span::styled(description.to_string(), style_text));
+            spans.push(Span::styled(description.clone(), style_text));
             spans.push(Span::styled(" [", style_text));
-            spans.push(Span::styled(char.to_string(), style_keybind));
+            spans.push(Span::styled(char.clone(), style_keybind));
             spans.push(Span::styled("]", style_text));
 
             is_first = false;
@@ -43,6 +64,38 @@ impl KeybindList {
 
         spans
     }
+
+    /// Gets the amount of lines the keybind hints take up at the given width.
+    pub fn height(&self, frame_storage: &super::FrameLocalStorage, width: u16) -> u16 {
+        self.wrapped(frame_storage, width).len() as u16
+    }
+
+    /// Gets the keybind hints wrapped to the given width, building them only if the keybinds or
+    /// the width changed since the last call.
+    fn wrapped(
+        &self,
+        frame_storage: &super::FrameLocalStorage,
+        width: u16,
+    ) -> Ref<'_, [Line<'static>]> {
+        let is_cached = self.cache.borrow().as_ref().is_some_and(|cached| {
+            cached.width == width && cached.keybinds == frame_storage.current_keybinds
+        });
+        if !is_cached {
+            *self.cache.borrow_mut() = Some(WrappedKeybinds {
+                keybinds: frame_storage.current_keybinds.clone(),
+                width,
+                lines: wrap_spans(Self::get_spans(frame_storage), width),
+            });
+        }
+
+        Ref::map(self.cache.borrow(), |cached| {
+            cached
+                .as_ref()
+                .expect("cache was just filled")
+                .lines
+                .as_slice()
+        })
+    }
 }
 
 impl Component for KeybindList {
@@ -53,8 +106,19 @@ impl Component for KeybindList {
         _state: &super::AppState,
         frame_storage: &super::FrameLocalStorage,
     ) {
-        let spans = wrap_spans(Self::get_spans(frame_storage), area.width);
-        let paragraph = Paragraph::new(spans);
+        // borrow the text from the cache instead of copying it
+        let lines = self.wrapped(frame_storage, area.width);
+        let lines = lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| Span::styled(span.content.as_ref(), span.style))
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect::<Vec<Line>>();
+        let paragraph = Paragraph::new(lines);
         frame.render_widget(paragraph, area);
     }
 }
@@ -1,85 +1,408 @@
-use std::{borrow::Cow, collections::HashSet, error::Error, io::Stdout, path::PathBuf};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    io::Stdout,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use crossterm::event::{self, Event, KeyEvent};
 use downcast_rs::{impl_downcast, Downcast};
-use predicates::{
-    prelude::{predicate, PredicateBooleanExt},
-    BoxPredicate, PredicateBoxExt,
-};
+use predicates::{prelude::Predicate, BoxPredicate};
 use ratatui::{backend::CrosstermBackend, layout::Rect, Frame, Terminal};
 use td_lib::{
-    database::{database_file::DatabaseFile, Database, Task, TaskId},
-    errors::DatabaseReadError,
+    audit,
+    database::{
+        database_file::{DatabaseFile, DatabaseLock},
+        ArchivePolicy, Database, DatabaseOp, DiffSummary, Task, TaskId,
+    },
+    encryption::EncryptionKey,
+    errors::{self, DatabaseLockError, DatabaseReadError, DatabaseWriteError},
+    query::HiddenTasks,
+    reminders::{self, DueReminder},
+    rollover::{self, RolloverReport},
+    storage::{self, StorageBackend},
+    sync::{
+        crdt::CrdtReplica,
+        git::{GitSync, PullOutcome},
+        github::{IssueSync, Repository},
+    },
+    time::OffsetDateTime,
 };
 use td_util::undo::UndoWrapper;
 
 use self::{
-    keybind_list::KeybindList, modal::ConfirmationModal, tab_layout::TabLayout, tasks::TaskPage,
+    activity::ActivityPage,
+    calendar::CalendarPage,
+    command_line::CommandLine,
+    keybind_list::KeybindList,
+    modal::{ConfirmationModal, KeybindSelectModal, ListSearchModal, MessageModal},
+    tab_layout::TabLayout,
+    tags::TagPage,
+    tasks::TaskPage,
+    time::TimePage,
+    toast::{Toast, ToastDisplay, ToastQueue},
+    view_stack::ViewStack,
+    vim::VimKeys,
 };
 use crate::{
+    autosave::{self, SaveScheduler},
+    autosync::SyncScheduler,
+    background::BackgroundJob,
+    command::Command,
+    file_watcher::{modified_time, FileWatcher},
+    hooks::{HookEvent, Hooks},
     keybinds::*,
-    utils::{wrap_spans, MapPredicate, RectExt},
+    notifications,
+    session::{ReadyTaskAlert, SessionState, UiSettings},
+    startup::StartupProfile,
+    undo_history,
+    utils::RectExt,
 };
 
+mod action;
+mod activity;
+mod burndown;
+mod calendar;
+mod command_line;
 mod component_collection;
 mod constants;
 mod dirty_indicator;
 mod input;
 mod keybind_list;
 mod modal;
+mod sync_status;
 mod tab_layout;
+mod tags;
+mod task_filters;
 mod tasks;
+mod time;
+mod toast;
+mod view_stack;
+mod vim;
+
+pub use action::Action;
+pub use task_filters::{TaskFilters, TaskListId};
+pub use view_stack::View;
 
 #[cfg_attr(test, derive(Default))]
 pub struct AppState {
     pub database: UndoWrapper<Database>,
+    /// A copy of the database that can be shared with background threads, along with the revision
+    /// it was made at.
+    snapshot: RefCell<Option<(u64, Arc<Database>)>>,
     pub path: PathBuf,
+    /// Where the database is loaded from and saved to.
+    storage: Box<dyn StorageBackend>,
+    /// Whether the archived tasks that the storage left out have been loaded.
+    archive_loaded: bool,
+
+    /// The lock on the database file, released when the app exits.
+    lock: Option<DatabaseLock>,
+    /// Set if another instance had the database open when this one started.
+    lock_conflict: Option<DatabaseLockError>,
+
+    /// The result of the daily rollover, if it ran when this session started.
+    rollover_report: Option<RolloverReport>,
+
+    /// How long starting up took so far, for `--profile-startup`.
+    startup_profile: StartupProfile,
+    /// Whether the work that is deferred until after the first frame has been done.
+    startup_finished: bool,
+
+    /// Watches the database file for changes. Created once startup has finished.
+    file_watcher: Option<FileWatcher>,
+    /// The modification time of the database file when it was last read or written by us.
+    disk_modified: Option<SystemTime>,
+    /// Whether the database file was changed by another program and the user has not decided
+    /// what to do about it yet.
+    pub external_change: bool,
+
+    /// Until when reminders have been checked. Reminders before this are not shown anymore.
+    last_reminder_check: Option<OffsetDateTime>,
+    /// Reminders that went off and have not been shown to the user yet.
+    pub due_reminders: Vec<DueReminder>,
+    /// Until when tasks that became due have been checked. Starts when the ui starts, so only
+    /// tasks that become due while it runs are announced.
+    last_due_check: Option<OffsetDateTime>,
+    /// Whether tasks that become due are announced with a desktop notification.
+    desktop_notifications: bool,
+    /// The tasks whose due notifications are snoozed, with the time until when.
+    snoozed_notifications: BTreeMap<TaskId, OffsetDateTime>,
+
+    /// Tracks the changes made in this session, if the database is merged as a CRDT.
+    crdt: Option<CrdtReplica>,
+
+    /// The git repository the database is committed to on save, if git sync is enabled.
+    git_sync: Option<GitSync>,
+    /// Decides when to sync with git automatically.
+    autosync: SyncScheduler,
+    /// The git sync that runs in the background, keyed by how many syncs were started.
+    git_sync_job: BackgroundJob<u64, GitSyncResult>,
+    /// Decides when to save automatically, and writes those saves in the background.
+    autosave: SaveScheduler,
+
+    /// Short messages shown on top of the ui one after another, such as the result of an automatic
+    /// action.
+    toasts: ToastQueue,
+
+    /// The task to select when the ui starts, such as the target of a link.
+    open_task: Option<TaskId>,
+    /// The tab to select when the ui starts.
+    open_tab: usize,
+
+    /// Views to push on the [`ViewStack`] before the next frame.
+    pending_views: Vec<Box<dyn View>>,
+    /// A query to search the task list for before the next frame.
+    pending_search: Option<String>,
+
+    /// Whether the undo history is stored next to the database when saving.
+    persist_undo: bool,
+    /// Whether saved changes are appended to the audit log.
+    audit_log: bool,
+    /// The shell commands to run when tasks change or the database is saved.
+    hooks: Hooks,
+    /// Whether cancelling a text input asks before throwing away what was typed.
+    confirm_discard: bool,
+    /// How many tasks an action can change before it has to be confirmed by typing the amount.
+    bulk_confirmation_threshold: usize,
+    /// Whether vim-style keys are translated into the regular keybinds.
+    vim_mode: bool,
+    /// How tasks are announced when the last of their dependencies is completed.
+    ready_task_alert: ReadyTaskAlert,
+    /// The tasks that became ready to work on during this session, which are highlighted.
+    ready_tasks: HashSet<TaskId>,
+
+    /// The passphrase from [`Self::PASSPHRASE_VARIABLE`], used to enable encryption.
+    passphrase: Option<String>,
+    /// The key confidential tasks are encrypted with, if it is known.
+    encryption_key: Option<EncryptionKey>,
 
     should_exit: bool,
 
-    pub sort_oldest_first: bool,
-    pub filter_completed: bool,
-    pub filter_unactionable: bool,
-    pub filter_search: bool,
+    /// All databases that can be switched to, including this one.
+    databases: Vec<PathBuf>,
+    /// The database to switch to once the ui of this one has exited.
+    switch_request: Option<PathBuf>,
+
+    /// The filters of the main task list, which is always open.
+    task_list: TaskFilters,
+    /// The filters of the task lists that were opened in extra tabs.
+    extra_task_lists: BTreeMap<TaskListId, TaskFilters>,
+    /// The task list that was selected last, whose filters are used.
+    active_list: TaskListId,
 }
 
 impl AppState {
-    pub fn create(path: PathBuf) -> Result<Self, DatabaseReadError> {
-        let db_info = if !path.exists() {
-            println!("The given database file ({path:?}) does not exist, creating a new one.");
+    /// How often components are ticked and the database file is checked while waiting for input.
+    const TICK_INTERVAL: Duration = Duration::from_millis(500);
+    /// How often the screen is redrawn without any changes, so relative times stay up to date.
+    const REDRAW_INTERVAL: Duration = Duration::from_secs(10);
+    /// The environment variable that holds the passphrase for confidential tasks.
+    const PASSPHRASE_VARIABLE: &'static str = "TD_PASSPHRASE";
 
-            let db_info = DatabaseFile::default();
-            db_info.write(&path)?;
-            db_info
-        } else {
-            DatabaseFile::read(&path)?
+    /// Loads the database at the given path. Work that is not needed to show the first frame is
+    /// deferred until [`Self::finish_startup`].
+    pub fn create(path: PathBuf) -> Result<Self, errors::Error> {
+        let mut profile = StartupProfile::start();
+        let (lock, lock_conflict) = match DatabaseFile::lock(&path) {
+            Ok(lock) => (Some(lock), None),
+            Err(e @ DatabaseLockError::AlreadyLocked { .. }) => (None, Some(e)),
+            Err(e) => return Err(e.into()),
         };
+        profile.mark("lock database");
+
+        let exists = path.exists();
+        let mut storage = storage::open_lazy(&path)?;
+        if !exists {
+            println!("The given database file ({path:?}) does not exist, creating a new one.");
+            storage.save(&Database::default())?;
+        }
 
-        let mut database: UndoWrapper<Database> = UndoWrapper::new(db_info.try_into()?);
+        let mut session = SessionState::load(&path);
+        let mut database = storage.load()?;
+        profile.mark("load database");
+
+        let passphrase = std::env::var(Self::PASSPHRASE_VARIABLE).ok();
+        let mut encryption_error = None;
+        let encryption_key = passphrase
+            .as_deref()
+            .filter(|_| database.is_encryption_enabled())
+            .and_then(|passphrase| {
+                let key = database.unlock(passphrase).and_then(|key| {
+                    database.decrypt_confidential_tasks(&key)?;
+                    Ok(key)
+                });
+                key.map_err(|e| encryption_error = Some(e)).ok()
+            });
+        profile.mark("decrypt tasks");
+
+        let mut database = match session.persist_undo {
+            true => undo_history::load(&path, database, modified_time(&path)),
+            false => UndoWrapper::new(database),
+        };
+        database.set_max_depth(Some(
+            session
+                .undo_limit
+                .unwrap_or(SessionState::DEFAULT_UNDO_LIMIT),
+        ));
         database.mark_clean();
+        let crdt = database.is_crdt().then(|| CrdtReplica::new(&database));
+        profile.mark("undo history");
+
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let last_reminder_check = session.last_reminder_check.or(Some(now));
+        let git_sync = session.git_sync.then(|| GitSync::open(&path));
+        let rollover_report = rollover::should_run(session.last_rollover, now).then(|| {
+            let report =
+                rollover::rollover(&database, session.last_rollover, now, &session.rollover);
+            session.last_rollover = Some(now);
+            // failing to save only means that the rollover runs again next time
+            _ = session.save(&path);
+            report
+        });
+        if let Some(report) = rollover_report.as_ref().filter(|r| !r.unsnoozed.is_empty()) {
+            database.modify("Clear ended snoozes", |db| report.clear_snoozes(db));
+        }
+        profile.mark("rollover");
+
+        let disk_modified = modified_time(&path);
 
-        Ok(Self {
+        let mut state = Self {
             database,
+            snapshot: RefCell::default(),
             path,
+            storage,
+            archive_loaded: false,
+            lock,
+            lock_conflict,
+            rollover_report,
+            startup_profile: profile,
+            startup_finished: false,
+            file_watcher: None,
+            disk_modified,
+            external_change: false,
+            last_reminder_check,
+            due_reminders: vec![],
+            last_due_check: Some(now),
+            desktop_notifications: session.desktop_notifications.unwrap_or(true),
+            snoozed_notifications: session.snoozed_notifications.clone(),
+            crdt,
+            git_sync: None,
+            autosync: SyncScheduler::new(session.autosync, Instant::now()),
+            git_sync_job: BackgroundJob::default(),
+            autosave: SaveScheduler::new(session.autosave, Instant::now()),
+            toasts: ToastQueue::default(),
+            open_task: session.ui.selected_task.clone(),
+            open_tab: session.ui.selected_tab,
+            pending_views: vec![],
+            pending_search: None,
+            persist_undo: session.persist_undo,
+            audit_log: session.audit_log,
+            hooks: Hooks::default(),
+            confirm_discard: session.confirm_discard.unwrap_or(true),
+            bulk_confirmation_threshold: session
+                .bulk_confirmation_threshold
+                .unwrap_or(SessionState::DEFAULT_BULK_CONFIRMATION_THRESHOLD),
+            vim_mode: session.vim_mode,
+            ready_task_alert: session.ready_tasks,
+            ready_tasks: HashSet::new(),
+            passphrase,
+            encryption_key,
             should_exit: false,
-            sort_oldest_first: false,
-            filter_completed: true,
-            filter_unactionable: false,
-            filter_search: false,
-        })
+            databases: vec![],
+            switch_request: None,
+            task_list: session.ui.list.clone(),
+            extra_task_lists: (1..)
+                .zip(session.ui.extra_task_lists.iter().cloned())
+                .collect(),
+            active_list: 0,
+        };
+        match git_sync {
+            Some(Ok(git_sync)) => {
+                state.git_sync = Some(git_sync);
+                state.update_queued_commits();
+            }
+            Some(Err(e)) => state.show_toast(format!("Git sync is disabled: {e}")),
+            None => (),
+        }
+        if let Some(e) = encryption_error {
+            state.show_toast(format!("Encrypted tasks stay locked: {e}"));
+        }
+        match Hooks::load() {
+            Ok(hooks) => state.hooks = hooks,
+            Err(e) => state.show_toast(format!("Hooks are disabled: {e}")),
+        }
+        state.startup_profile.mark("git sync");
+
+        Ok(state)
+    }
+
+    /// Does the work that was deferred so the first frame could be shown sooner.
+    fn finish_startup(&mut self) {
+        // not being able to watch the file is not fatal, we still check before writing
+        self.file_watcher = FileWatcher::new(&self.path).ok();
+        self.startup_profile.mark("file watcher");
+
+        self.apply_archive_policy();
+        self.startup_profile.mark("archive policy");
+        self.check_reminders();
+        self.startup_profile.mark("reminders");
+
+        self.startup_finished = true;
+    }
+
+    /// Whether the app is still starting up. Expensive information that is not essential, such as
+    /// statistics, is not shown until it has finished.
+    pub fn is_starting(&self) -> bool {
+        !self.startup_finished
+    }
+
+    /// Gets how long each phase of starting up took.
+    pub fn startup_profile(&self) -> &StartupProfile {
+        &self.startup_profile
     }
 
     pub fn run_loop(
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     ) -> Result<(), Box<dyn Error>> {
-        let mut root_component = LayoutRoot::new();
+        let mut root_component = LayoutRoot::new(self);
+        // the rollover is only shown the first time, not after switching back to this database
+        self.rollover_report = None;
 
         'main_loop: loop {
+            root_component.update(self);
+
             let mut frame_storage = FrameLocalStorage::default();
             root_component.pre_render(self, &mut frame_storage);
 
             terminal.draw(|f| root_component.render(f, f.size(), self, &frame_storage))?;
+            let drawn_at = Instant::now();
+
+            if !self.startup_finished {
+                self.startup_profile.mark("first frame");
+                self.finish_startup();
+                continue 'main_loop;
+            }
+
+            // wait for input, ticking in the meantime and redrawing if anything changed
+            while !event::poll(Self::TICK_INTERVAL)? {
+                if self.check_external_change()
+                    | self.check_reminders()
+                    | self.check_due_tasks()
+                    | self.check_autosync()
+                    | self.check_autosave()
+                    | self.expire_toast()
+                    | root_component.on_tick(self)
+                    || drawn_at.elapsed() >= Self::REDRAW_INTERVAL
+                {
+                    continue 'main_loop;
+                }
+            }
 
             // while loop so we only check for key-down events, not key-up
             while let Event::Key(key) = event::read()? {
@@ -90,9 +413,15 @@ impl AppState {
                     event::KeyEventKind::Release => continue,
                 };
 
+                self.autosync.record_input(Instant::now());
+                self.autosave.record_input(Instant::now());
                 _ = root_component.process_input(key, self, &frame_storage);
 
                 if self.should_exit {
+                    // restore the selection if the ui starts again after switching databases
+                    self.open_tab = root_component.tabs.index();
+                    self.open_task = frame_storage.selected_task_id.clone();
+                    self.save_ui_settings(self.open_tab, self.open_task.clone());
                     break 'main_loop;
                 }
 
@@ -101,53 +430,1023 @@ impl AppState {
             }
         }
 
-        Ok(())
+        self.save_reminder_check();
+        // the process may exit right after this, which would cut off the save or the sync
+        if let Some(finished) = self.autosave.wait() {
+            self.finish_autosave(finished);
+        }
+        if self.git_sync_job.wait() {
+            self.finish_git_sync();
+        }
+
+        Ok(())
+    }
+
+    /// Selects the given task when the ui starts, turning off the filters that would hide it.
+    /// Returns `false` if the task does not exist.
+    pub fn open_task(&mut self, task_id: TaskId) -> bool {
+        if !self.database.get_all_tasks().any(|t| t.id() == &task_id) {
+            self.load_archive();
+        }
+        let Some(task) = self
+            .database
+            .get_all_tasks()
+            .find(|t| t.id() == &task_id)
+            .cloned()
+        else {return false;};
+
+        let filters = self.filters_mut();
+        if task.time_completed.is_some() {
+            filters.filter_completed = false;
+        }
+        if task.time_archived.is_some() {
+            filters.filter_archived = false;
+        }
+        if !self.get_task_filter_predicate().eval(&task) {
+            self.filters_mut().filter_unactionable = false;
+        }
+
+        self.open_task = Some(task_id);
+        true
+    }
+
+    /// Gets a copy of the database that can be sent to a background thread. The same copy is
+    /// returned until the database changes.
+    pub fn database_snapshot(&self) -> Arc<Database> {
+        let revision = self.database.revision();
+        let mut snapshot = self.snapshot.borrow_mut();
+        match &*snapshot {
+            Some((snapshot_revision, database)) if *snapshot_revision == revision => {
+                database.clone()
+            }
+            _ => {
+                let database = Arc::new((*self.database).clone());
+                *snapshot = Some((revision, database.clone()));
+                database
+            }
+        }
+    }
+
+    /// Summarizes what the given change would do to the database, without making it.
+    pub fn preview_changes(&self, change: impl FnOnce(&mut Database)) -> DiffSummary {
+        let mut preview = (*self.database).clone();
+        change(&mut preview);
+        DiffSummary::of(&preview.diff(&self.database))
+    }
+
+    /// Whether an action that changes the given amount of tasks should be confirmed by typing the
+    /// amount, instead of with a simple yes or no.
+    pub fn needs_typed_confirmation(&self, task_count: usize) -> bool {
+        task_count > self.bulk_confirmation_threshold
+    }
+
+    pub fn request_exit(&mut self) {
+        self.should_exit = true;
+    }
+
+    /// Sets the databases that can be switched to with [`Self::request_switch`].
+    pub fn set_databases(&mut self, databases: Vec<PathBuf>) {
+        self.databases = databases;
+    }
+
+    /// Gets all databases that can be switched to, including this one.
+    pub fn databases(&self) -> &[PathBuf] {
+        &self.databases
+    }
+
+    /// Exits the ui of this database, so the one at the given path can be shown instead.
+    pub fn request_switch(&mut self, path: PathBuf) {
+        self.switch_request = Some(path);
+        self.should_exit = true;
+    }
+
+    /// Gets the database that should be shown after the ui of this one exited, if any. The ui can
+    /// be started again afterwards.
+    pub fn take_switch_request(&mut self) -> Option<PathBuf> {
+        let path = self.switch_request.take()?;
+        self.should_exit = false;
+        Some(path)
+    }
+
+    /// Shows a view on top of the tabs, or on top of the view that is currently shown. Pressing
+    /// Esc goes back to the previous one.
+    pub fn push_view(&mut self, view: impl View + 'static) {
+        self.pending_views.push(Box::new(view));
+    }
+
+    /// Shows the task list, searching it for the given query.
+    pub fn request_search(&mut self, query: String) {
+        self.pending_search = Some(query);
+    }
+
+    /// Takes over the lock on the database file from the other instance that holds it.
+    pub fn force_lock(&mut self) -> Result<(), errors::Error> {
+        self.lock = Some(DatabaseFile::lock_forced(&self.path)?);
+        self.lock_conflict = None;
+        Ok(())
+    }
+
+    /// Collects the reminders that went off since the last check into [`Self::due_reminders`].
+    /// Returns `true` if any were found.
+    fn check_reminders(&mut self) -> bool {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let since = self.last_reminder_check.unwrap_or(now);
+        let due = reminders::due_reminders(&self.database, since, now);
+        self.last_reminder_check = Some(now);
+        if due.is_empty() {
+            return false;
+        }
+
+        // remember that these were shown, so they don't show up again next session
+        self.save_reminder_check();
+
+        self.due_reminders.extend(due);
+        true
+    }
+
+    /// Announces the tasks that became due soon or overdue since the last check, with a desktop
+    /// notification or a toast if that is not possible. Returns `true` if a toast was shown.
+    fn check_due_tasks(&mut self) -> bool {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let since = self.last_due_check.replace(now).unwrap_or(now);
+        let notices =
+            reminders::due_notices(&self.database, since, now, &self.snoozed_notifications);
+
+        let mut toast = None;
+        for notice in notices {
+            let task = &self.database[&notice.task];
+            let title = match self.database.is_confidential(task) {
+                true => "(confidential task)",
+                false => &task.title,
+            };
+            let message = notifications::message(title, notice.state);
+            let sent = self.desktop_notifications && notifications::send(&message).is_ok();
+            if !sent {
+                toast = Some(message);
+            }
+        }
+
+        match toast {
+            Some(message) => {
+                self.show_toast(message);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Announces the tasks that can be worked on now that the given tasks are completed, as set
+    /// by [`Self::ready_task_alert`].
+    fn announce_ready_tasks(&mut self, completed: &[TaskId]) {
+        if self.ready_task_alert == ReadyTaskAlert::Off {
+            return;
+        }
+
+        let ready = completed
+            .iter()
+            .flat_map(|id| self.database.newly_actionable_after(id))
+            .filter(|task| !completed.contains(task.id()))
+            .map(|task| task.id().clone())
+            .collect::<Vec<_>>();
+        for task_id in ready {
+            if !self.ready_tasks.insert(task_id.clone())
+                || self.ready_task_alert != ReadyTaskAlert::Notify
+            {
+                continue;
+            }
+
+            let task = &self.database[&task_id];
+            let message = match self.database.is_confidential(task) {
+                true => "A confidential task is ready to start".to_string(),
+                false => format!("Ready to start: {}", task.title),
+            };
+            let sent = self.desktop_notifications && notifications::send(&message).is_ok();
+            if !sent {
+                self.show_toast(message);
+            }
+        }
+    }
+
+    /// Checks if the task became ready to work on during this session and is not started yet, so
+    /// it should be highlighted.
+    pub fn is_newly_ready(&self, task: &Task) -> bool {
+        self.ready_tasks.contains(task.id())
+            && task.time_started.is_none()
+            && self.database.is_actionable(task.id())
+    }
+
+    pub fn ready_task_alert(&self) -> ReadyTaskAlert {
+        self.ready_task_alert
+    }
+
+    /// Changes how tasks that become ready are announced, and remembers this for the next session.
+    pub fn set_ready_task_alert(&mut self, alert: ReadyTaskAlert) {
+        self.ready_task_alert = alert;
+        if alert == ReadyTaskAlert::Off {
+            self.ready_tasks.clear();
+        }
+
+        let mut session = SessionState::load(&self.path);
+        session.ready_tasks = alert;
+        _ = session.save(&self.path);
+    }
+
+    /// Stops announcing that the task is due until the given time, and remembers this for the
+    /// next session.
+    pub fn snooze_notifications(&mut self, task_id: TaskId, until: OffsetDateTime) {
+        // the ones that ran out before the last check are not needed to announce the task again
+        let last_check = self.last_due_check;
+        self.snoozed_notifications
+            .retain(|_, time| last_check.is_none_or(|check| *time > check));
+        self.snoozed_notifications.insert(task_id, until);
+
+        let mut session = SessionState::load(&self.path);
+        session.snoozed_notifications = self.snoozed_notifications.clone();
+        // failing to save only means that the snooze is forgotten next session
+        _ = session.save(&self.path);
+    }
+
+    /// Stores the ui settings in the session file, so they are restored next session.
+    fn save_ui_settings(&self, selected_tab: usize, selected_task: Option<TaskId>) {
+        let mut session = SessionState::load(&self.path);
+        session.ui = UiSettings {
+            list: self.task_list.clone(),
+            extra_task_lists: self.extra_task_lists.values().cloned().collect(),
+            selected_tab,
+            selected_task,
+        };
+        // losing these is harmless, the defaults are used next time
+        _ = session.save(&self.path);
+    }
+
+    /// Stores until when reminders have been checked in the session file.
+    fn save_reminder_check(&self) {
+        let mut session = SessionState::load(&self.path);
+        session.last_reminder_check = self.last_reminder_check;
+        // failing to save only means that reminders may show up again next time
+        _ = session.save(&self.path);
+    }
+
+    pub fn show_toast(&mut self, text: String) {
+        self.toasts.push(Toast::new(text));
+    }
+
+    /// Shows a toast about a change that tells how to undo it.
+    pub fn show_undo_toast(&mut self, text: String) {
+        self.toasts.push(Toast::with_undo(text));
+    }
+
+    /// Moves on to the next toast if the current one has been shown long enough. Returns `true` if
+    /// the toast that is shown changed.
+    fn expire_toast(&mut self) -> bool {
+        self.toasts.expire()
+    }
+
+    /// Changes the archive policy of the database and applies it right away. Both can be undone.
+    pub fn set_archive_policy(&mut self, policy: ArchivePolicy) {
+        self.database
+            .modify("Change archive policy", |db| db.set_archive_policy(policy));
+        self.apply_archive_policy();
+    }
+
+    /// Archives the tasks that should be archived according to the archive policy of the
+    /// database, as a single undoable change.
+    fn apply_archive_policy(&mut self) {
+        let now = OffsetDateTime::now_utc();
+        if self.database.tasks_to_auto_archive(now).is_empty() {
+            return;
+        }
+
+        let mut archived = vec![];
+        self.database.modify("Auto-archive tasks", |db| {
+            archived = db.apply_archive_policy(now);
+        });
+        self.show_undo_toast(format!("Auto-archived {} completed tasks", archived.len()));
+    }
+
+    /// Checks if the database file was modified by another program since we last read or wrote
+    /// it, and sets [`Self::external_change`] if so. Returns `true` if a new change was found.
+    ///
+    /// If the database is a CRDT, the changes are merged right away instead.
+    fn check_external_change(&mut self) -> bool {
+        let Some(watcher) = &self.file_watcher else {return false;};
+        // our own automatic save changes the file too, it is checked once that finished
+        if self.autosave.is_saving() || !watcher.has_events() || self.external_change {
+            return false;
+        }
+
+        if modified_time(&self.path) == self.disk_modified {
+            return false;
+        }
+
+        let before = self.crdt.is_some().then(|| (*self.database).clone());
+        if let Some(before) = before.filter(|_| self.merge_from_disk().is_ok()) {
+            let text = self.describe_remote_changes(&before);
+            self.show_toast(text);
+        } else {
+            self.external_change = true;
+        }
+        true
+    }
+
+    /// Describes the tasks that were changed by merging in the changes of another program, so
+    /// they can be announced while editing the same database together.
+    fn describe_remote_changes(&self, before: &Database) -> String {
+        let changed = self
+            .database
+            .diff(before)
+            .into_iter()
+            .filter_map(|op| match op {
+                DatabaseOp::SetTask { old, new, .. } => new.or(old),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        match changed.as_slice() {
+            [] => "Merged changes made by another program".to_string(),
+            [task] if task.is_locked() => "Another program changed a confidential task".to_string(),
+            [task] => format!("Another program changed '{}'", task.title),
+            tasks => format!("Another program changed {} tasks", tasks.len()),
+        }
+    }
+
+    /// Saves the database to disk and marks it as clean. If the file was changed by another
+    /// program, nothing is written and [`Self::external_change`] is set instead so the user can
+    /// decide what to do first. CRDT databases merge those changes and save anyway. Other
+    /// problems, such as a full disk, are shown as a toast.
+    ///
+    /// Returns whether the database was saved.
+    pub fn save(&mut self) -> bool {
+        match self.try_save() {
+            Ok(()) => true,
+            // the user is already asked what to do about the external change
+            Err(errors::Error::Write(DatabaseWriteError::ChangedOnDisk { .. })) => false,
+            Err(e) => {
+                self.show_toast(format!("Could not save: {e}"));
+                false
+            }
+        }
+    }
+
+    /// Saves the database like [`Self::save`], returning why it was not saved.
+    fn try_save(&mut self) -> Result<(), errors::Error> {
+        // an automatic save or a git pull may be writing the same file
+        if let Some(finished) = self.autosave.wait() {
+            self.finish_autosave(finished);
+        }
+        if self.git_sync_job.wait() {
+            self.finish_git_sync();
+        }
+
+        if !self.prepare_save() {
+            return Err(DatabaseWriteError::ChangedOnDisk {
+                path: self.path.clone(),
+            }
+            .into());
+        }
+
+        // the previous version is read before it is overwritten, to log what changed
+        let previous = (self.audit_log || self.hooks.has_task_hooks())
+            .then(|| self.load_from_storage().ok())
+            .flatten();
+
+        let disk_database = self.disk_database();
+        let written = disk_database.as_ref().unwrap_or(&self.database);
+        self.storage.save(written)?;
+        self.finish_save(disk_database.as_ref(), previous.as_ref());
+        self.database.mark_clean();
+
+        Ok(())
+    }
+
+    /// Checks that the database can be saved and applies the archive policy before it is.
+    /// Returns `false` if the file was changed by another program. See [`Self::save`].
+    fn prepare_save(&mut self) -> bool {
+        let changed_on_disk = modified_time(&self.path) != self.disk_modified;
+        if changed_on_disk && (self.crdt.is_none() || self.merge_from_disk().is_err()) {
+            self.external_change = true;
+            return false;
+        }
+
+        self.apply_archive_policy();
+        true
+    }
+
+    /// Gets the database as it should be written to disk, if that is different from the database
+    /// itself because it is a CRDT or its confidential tasks are encrypted.
+    fn disk_database(&mut self) -> Option<Database> {
+        let mut disk_database = match &mut self.crdt {
+            Some(crdt) => Cow::Owned(crdt.stamp(&self.database)),
+            None => Cow::Borrowed(&*self.database),
+        };
+        if let Some(key) = &self.encryption_key {
+            disk_database.to_mut().encrypt_confidential_tasks(key);
+        }
+        match disk_database {
+            Cow::Owned(disk_database) => Some(disk_database),
+            Cow::Borrowed(_) => None,
+        }
+    }
+
+    /// Does everything that happens after the database was written to disk, such as logging the
+    /// changes, running hooks and committing to git. `disk_database` is what was written, if it
+    /// differs from the database itself. See [`Self::save`].
+    fn finish_save(&mut self, disk_database: Option<&Database>, previous: Option<&Database>) {
+        let disk_database = disk_database.unwrap_or(&self.database);
+        self.disk_modified = modified_time(&self.path);
+
+        if let (true, Some(previous)) = (self.audit_log, previous) {
+            let now = OffsetDateTime::now_utc();
+            let entries = audit::changes(previous, disk_database, &audit::current_user(), now);
+            if let Err(e) = audit::append(&audit::log_path(&self.path), &entries) {
+                self.toasts
+                    .push(Toast::new(format!("Could not write to the audit log: {e}")));
+            }
+        }
+
+        let mut events = match previous {
+            // the database may have been changed since it was written
+            Some(previous) if self.hooks.has_task_hooks() => {
+                HookEvent::between(previous, disk_database)
+            }
+            _ => vec![],
+        };
+        events.push(HookEvent::DatabaseSaved);
+        if let Err(e) = self.hooks.run(&events, &self.path) {
+            self.toasts.push(Toast::new(e));
+        }
+
+        // the undo history contains the titles of confidential tasks, so it is not stored
+        let persist_undo = self.persist_undo && !self.database.is_encryption_enabled();
+        if let (true, Some(disk_modified)) = (persist_undo, self.disk_modified) {
+            // losing the undo history is not worth bothering the user about
+            _ = undo_history::save(&self.path, &self.database, disk_modified);
+        }
+
+        if let Some(git_sync) = &self.git_sync {
+            if let Err(e) = git_sync.commit(disk_database) {
+                self.toasts
+                    .push(Toast::new(format!("Could not commit: {e}")));
+            }
+            self.autosync.record_save();
+        }
+
+        if self.encryption_key.is_none() && self.database.has_unencrypted_confidential_tasks() {
+            self.show_toast(format!(
+                "Some confidential tasks were saved unencrypted, set {} to encrypt them",
+                Self::PASSPHRASE_VARIABLE
+            ));
+        }
+    }
+
+    /// Whether an automatic save is being written in the background.
+    pub fn is_autosaving(&self) -> bool {
+        self.autosave.is_saving()
+    }
+
+    /// Finishes an automatic save that was written, or starts one if [`Self::autosave`] says it
+    /// is time to. Returns `true` if either happened.
+    ///
+    /// Only databases stored in a single file are saved in the background. Others are saved like
+    /// normal, which blocks until they are written.
+    fn check_autosave(&mut self) -> bool {
+        if let Some(finished) = self.autosave.poll() {
+            self.finish_autosave(finished);
+            return true;
+        }
+
+        let blocked =
+            self.external_change || self.lock_conflict.is_some() || self.git_sync_job.is_running();
+        if blocked
+            || !self
+                .autosave
+                .is_due(Instant::now(), self.database.is_dirty())
+        {
+            return false;
+        }
+        if storage::is_sqlite_path(&self.path) {
+            return self.save();
+        }
+
+        if !self.prepare_save() {
+            return true;
+        }
+        // the thread needs its own copy, the database may be changed while it is written
+        let disk_database = self
+            .disk_database()
+            .unwrap_or_else(|| (*self.database).clone());
+        let read_previous = self.audit_log || self.hooks.has_task_hooks();
+        let revision = self.database.revision();
+        self.autosave
+            .start(revision, disk_database, self.path.clone(), read_previous);
+        true
+    }
+
+    /// Handles an automatic save that was written. The database is only marked as clean if it
+    /// was not changed while the save was written, otherwise those changes are saved next time.
+    fn finish_autosave(&mut self, finished: autosave::FinishedSave) {
+        if let Err(e) = finished.result {
+            self.show_toast(format!("Could not save automatically: {e}"));
+            return;
+        }
+
+        self.finish_save(Some(&finished.disk_database), finished.previous.as_ref());
+        if self.database.revision() == finished.revision {
+            self.database.mark_clean();
+        }
+    }
+
+    pub fn vim_mode_enabled(&self) -> bool {
+        self.vim_mode
+    }
+
+    /// Enables or disables vim-style keys, and remembers this for the next session.
+    pub fn set_vim_mode(&mut self, enabled: bool) {
+        self.vim_mode = enabled;
+
+        let mut session = SessionState::load(&self.path);
+        session.vim_mode = enabled;
+        _ = session.save(&self.path);
+    }
+
+    pub fn audit_log_enabled(&self) -> bool {
+        self.audit_log
+    }
+
+    /// Starts or stops appending saved changes to the audit log, and remembers this for the next
+    /// session.
+    pub fn set_audit_log(&mut self, enabled: bool) {
+        self.audit_log = enabled;
+
+        let mut session = SessionState::load(&self.path);
+        session.audit_log = enabled;
+        _ = session.save(&self.path);
+    }
+
+    pub fn git_sync_enabled(&self) -> bool {
+        self.git_sync.is_some()
+    }
+
+    /// Enables or disables committing the database to its git repository, and remembers this for
+    /// the next session.
+    pub fn set_git_sync(&mut self, enabled: bool) {
+        self.git_sync = None;
+        if enabled {
+            match GitSync::open(&self.path) {
+                Ok(git_sync) => {
+                    self.git_sync = Some(git_sync);
+                    self.update_queued_commits();
+                }
+                Err(e) => {
+                    self.show_toast(format!("Could not enable git sync: {e}"));
+                    return;
+                }
+            }
+        }
+
+        let mut session = SessionState::load(&self.path);
+        session.git_sync = enabled;
+        _ = session.save(&self.path);
+    }
+
+    /// Commits the database file, merges the changes from the upstream branch and pushes the
+    /// result. This runs in the background, and only syncs what was saved.
+    pub fn sync_with_git(&mut self) {
+        if self.git_sync_job.is_running() {
+            self.show_toast("Already syncing with git".to_string());
+            return;
+        }
+        self.start_git_sync(true);
+    }
+
+    /// Synchronizes the tasks with the issues of the given GitHub repository, or of the one that
+    /// was synchronized with last. With `close_issues`, the issues of tasks that were completed are
+    /// closed.
+    pub fn sync_with_github(&mut self, repository: Option<Repository>, close_issues: bool) {
+        let mut session = SessionState::load(&self.path);
+        let repository = repository.or_else(|| session.github_repository.as_deref()?.parse().ok());
+        let Some(repository) = repository else {
+            self.show_toast("Usage: :github <owner/repo>".to_string());
+            return;
+        };
+        session.github_repository = Some(repository.to_string());
+        _ = session.save(&self.path);
+
+        let message = match self.try_github_sync(&repository, close_issues) {
+            Ok(sync) => format!("Synced with {repository}: {sync}"),
+            Err(e) => format!("GitHub sync failed: {e}"),
+        };
+        self.show_toast(message);
+    }
+
+    #[cfg(feature = "github")]
+    fn try_github_sync(
+        &mut self,
+        repository: &Repository,
+        close_issues: bool,
+    ) -> Result<IssueSync, String> {
+        use td_lib::sync::github::{self, GitHubClient};
+
+        let client = GitHubClient::from_env();
+        let issues = client.fetch_issues(repository).map_err(|e| e.to_string())?;
+
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let mut sync = IssueSync::default();
+        let mut closed = Ok(());
+        self.database
+            .modify(format!("Sync with {repository}"), |db| {
+                sync = github::apply_issues(db, repository, &issues, now);
+                if close_issues {
+                    closed = github::close_issues(&client, db, repository, &mut sync, now);
+                }
+            });
+        closed.map_err(|e| e.to_string())?;
+        Ok(sync)
+    }
+
+    #[cfg(not(feature = "github"))]
+    fn try_github_sync(
+        &mut self,
+        _repository: &Repository,
+        _close_issues: bool,
+    ) -> Result<IssueSync, String> {
+        Err("td was built without the github feature".to_string())
+    }
+
+    /// Finishes the git sync that runs in the background, or starts one if [`Self::autosync`] says
+    /// it is time to. Returns `true` if either happened.
+    fn check_autosync(&mut self) -> bool {
+        if self.git_sync_job.poll() {
+            self.finish_git_sync();
+            return true;
+        }
+
+        // a save that is being written would be committed halfway
+        let blocked = self.external_change
+            || self.lock_conflict.is_some()
+            || self.autosave.is_saving()
+            || self.git_sync_job.is_running();
+        if self.git_sync.is_none() || blocked || !self.autosync.is_due(Instant::now()) {
+            return false;
+        }
+
+        self.start_git_sync(false);
+        true
+    }
+
+    /// Starts syncing the database file with git on a background thread. The result of a `manual`
+    /// sync is always shown, otherwise only problems are.
+    fn start_git_sync(&mut self, manual: bool) {
+        let Some(git_sync) = self.git_sync.clone() else {return;};
+        let path = self.path.clone();
+        let count = self.git_sync_job.latest().map_or(0, |r| r.count) + 1;
+        self.git_sync_job.request(count, move || GitSyncResult {
+            count,
+            manual,
+            outcome: sync_file_with_git(&git_sync, &path),
+            // without an upstream branch there is nothing to push to
+            queued_commits: git_sync.unpushed_commits().ok(),
+        });
+    }
+
+    /// Handles the result of the git sync that ran in the background. If the pull changed the
+    /// file, it is reloaded. When there are unsaved changes, the user is asked what to do instead,
+    /// like for any other program that changes the file.
+    fn finish_git_sync(&mut self) {
+        let Some(finished) = self.git_sync_job.latest() else {return;};
+        let (manual, outcome) = (finished.manual, finished.outcome.clone());
+        self.autosync
+            .set_queued_commits(finished.queued_commits.unwrap_or(0));
+        self.autosync.record_sync(Instant::now(), outcome.is_ok());
+
+        let result = outcome.and_then(|outcome| {
+            if outcome.updated && self.database.is_dirty() {
+                self.external_change = true;
+            } else if outcome.updated {
+                self.reload_from_disk()
+                    .map_err(|e| format!("could not load the merged database: {e}"))?;
+            }
+            Ok(outcome.conflicts.len())
+        });
+
+        // successful syncs show up in the status bar, only problems need attention
+        if manual || result != Ok(0) {
+            self.show_sync_result(result);
+        }
+    }
+
+    fn show_sync_result(&mut self, result: Result<usize, String>) {
+        self.show_toast(match result {
+            Ok(0) => "Synced with git".to_string(),
+            Ok(n) => format!("Synced with git, kept the local version of {n} conflicting tasks"),
+            Err(e) => format!("Sync failed: {e}"),
+        });
+    }
+
+    /// Checks how many commits could not be pushed yet, so they are synced later.
+    fn update_queued_commits(&mut self) {
+        // without an upstream branch there is nothing to push to
+        let queued = self
+            .git_sync
+            .as_ref()
+            .and_then(|g| g.unpushed_commits().ok());
+        self.autosync.set_queued_commits(queued.unwrap_or(0));
+    }
+
+    /// Describes the state of automatic syncing, if git sync is enabled.
+    pub fn sync_status(&self) -> Option<String> {
+        self.git_sync.as_ref().and(self.autosync.status())
+    }
+
+    /// Gets the id that identifies the database in links to its tasks. The first time, the id is
+    /// created and the database is saved right away, so the links keep working even if nothing
+    /// else is saved.
+    pub fn database_id(&mut self) -> String {
+        if let Some(id) = self.database.database_id() {
+            return id.to_string();
+        }
+
+        self.database
+            .modify_untracked(|db| _ = db.get_or_create_database_id());
+        self.save();
+        self.database
+            .database_id()
+            .expect("the id was just created")
+            .to_string()
+    }
+
+    /// Replaces the database with the version on disk. This can be undone.
+    pub fn reload_from_disk(&mut self) -> Result<(), errors::Error> {
+        let disk_database = self.read_disk_database()?;
+        if let Some(crdt) = &mut self.crdt {
+            crdt.reset(&disk_database);
+        }
+        self.database
+            .modify("Reload from disk", |db| db.replace(disk_database));
+        self.database.mark_clean();
+        self.disk_modified = modified_time(&self.path);
+        self.external_change = false;
+        Ok(())
+    }
+
+    /// Adds the tasks that only exist in the version on disk to the database, or merges all
+    /// changes if the database is a CRDT. The next save will overwrite the file with the merged
+    /// database.
+    pub fn merge_from_disk(&mut self) -> Result<(), errors::Error> {
+        let disk_database = self.read_disk_database()?;
+        match &mut self.crdt {
+            Some(crdt) => {
+                let merged = crdt.merge(&self.database, &disk_database);
+                self.database
+                    .modify("Merge from disk", |db| db.replace(merged));
+            }
+            None => self.database.modify("Merge from disk", |db| {
+                db.merge_new_tasks(&disk_database);
+            }),
+        }
+        self.keep_local_changes();
+        Ok(())
+    }
+
+    /// Reads the version of the database on disk, decrypting confidential tasks if possible.
+    fn read_disk_database(&mut self) -> Result<Database, errors::Error> {
+        let mut database = self.load_from_storage()?;
+        if let Some(key) = &self.encryption_key {
+            database.decrypt_confidential_tasks(key)?;
+        }
+        Ok(database)
+    }
+
+    /// Loads the database from storage, including the archived tasks if they were loaded before.
+    fn load_from_storage(&mut self) -> Result<Database, errors::Error> {
+        let mut database = self.storage.load()?;
+        if self.archive_loaded {
+            database.apply_ops(&self.storage.load_archived()?, false);
+        }
+        Ok(database)
+    }
+
+    /// Loads the archived tasks that the storage left out when starting, so they can be shown.
+    pub fn load_archive(&mut self) {
+        if let Err(e) = self.try_load_archive() {
+            self.show_toast(format!("Could not load archived tasks: {e}"));
+        }
+    }
+
+    fn try_load_archive(&mut self) -> Result<(), errors::Error> {
+        if self.archive_loaded {
+            return Ok(());
+        }
+
+        let ops = self.storage.load_archived()?;
+        let key = &self.encryption_key;
+        self.database.modify_untracked(|db| {
+            db.apply_ops(&ops, false);
+            if let Some(key) = key {
+                // tasks that can not be decrypted stay locked, like when starting
+                _ = db.decrypt_confidential_tasks(key);
+            }
+        });
+        self.archive_loaded = true;
+        Ok(())
+    }
+
+    /// Marks the task as confidential or not, see [`Self::ensure_encryption_key`].
+    pub fn toggle_task_encrypted(&mut self, task_id: &TaskId) {
+        if !self.ensure_encryption_key() {
+            return;
+        }
+
+        self.database.modify("Toggle task encryption", |db| {
+            let task = &mut db[task_id];
+            task.encrypted = !task.encrypted;
+        });
+    }
+
+    /// Makes all tasks with the given tag confidential or not, see
+    /// [`Self::ensure_encryption_key`].
+    pub fn toggle_tag_encrypted(&mut self, tag: &str) {
+        if !self.ensure_encryption_key() {
+            return;
+        }
+
+        let mut tags = self.database.encrypted_tags().to_vec();
+        match tags.iter().position(|t| t == tag) {
+            Some(index) => _ = tags.remove(index),
+            None => tags.push(tag.to_string()),
+        }
+        self.database
+            .modify(format!("Toggle encryption of tag '{tag}'"), |db| {
+                db.set_encrypted_tags(tags).expect("encryption is enabled");
+            });
+    }
+
+    /// Checks if confidential tasks can be encrypted, enabling encryption with the passphrase
+    /// from [`Self::PASSPHRASE_VARIABLE`] the first time. Shows a toast if they can't.
+    fn ensure_encryption_key(&mut self) -> bool {
+        if self.encryption_key.is_some() {
+            return true;
+        }
+
+        match self.passphrase.clone() {
+            Some(passphrase) if !self.database.is_encryption_enabled() => {
+                let mut key = None;
+                self.database.modify("Enable encryption", |db| {
+                    key = db.enable_encryption(&passphrase).ok();
+                });
+                self.encryption_key = key;
+                true
+            }
+            _ => {
+                self.show_toast(format!(
+                    "Set {} to the passphrase of the database to encrypt tasks",
+                    Self::PASSPHRASE_VARIABLE
+                ));
+                false
+            }
+        }
+    }
+
+    /// Ignores the changes made to the database file, so the next save overwrites them.
+    pub fn keep_local_changes(&mut self) {
+        self.disk_modified = modified_time(&self.path);
+        self.external_change = false;
+    }
+
+    /// Moves all archived tasks to the archive side file and saves the database. This clears the
+    /// undo history, since undoing would duplicate the tasks that are now in the archive. If the
+    /// database can not be saved, the archive side file is restored and nothing changes.
+    ///
+    /// Returns the amount of tasks that were purged.
+    pub fn purge_archived(&mut self) -> Result<usize, errors::Error> {
+        // archived tasks that the storage left out would stay behind otherwise
+        self.try_load_archive()?;
+
+        let archive_path = DatabaseFile::archive_path(&self.path);
+        let previous_archive = match std::fs::read(&archive_path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(source) => {
+                return Err(DatabaseReadError::IoError {
+                    path: archive_path,
+                    source,
+                }
+                .into())
+            }
+        };
+        let mut archive: Database = match &previous_archive {
+            Some(contents) => DatabaseFile::parse(contents)?.try_into()?,
+            None => Database::default(),
+        };
+
+        let mut database = (*self.database).clone();
+        let mut archived = database.take_archived();
+        if let Some(key) = &self.encryption_key {
+            archived.encrypt_confidential_tasks(key);
+        }
+        archive.merge(&archived);
+        DatabaseFile::from(&archive).write(&archive_path)?;
+
+        let max_depth = self.database.max_depth();
+        let previous_database = std::mem::replace(&mut self.database, UndoWrapper::new(database));
+        self.database.set_max_depth(max_depth);
+        // if the file was changed externally the user is asked what to do, and can purge after
+        if let Err(e) = self.try_save() {
+            self.database = previous_database;
+            let restored = match previous_archive {
+                Some(contents) => std::fs::write(&archive_path, contents),
+                None => std::fs::remove_file(&archive_path),
+            };
+            restored.map_err(|source| DatabaseWriteError::IoError {
+                path: archive_path,
+                source,
+            })?;
+            return Err(e);
+        }
+
+        Ok(archived.get_all_tasks().count())
     }
 
-    pub fn request_exit(&mut self) {
-        self.should_exit = true;
+    /// Gets the filters of the active task list.
+    pub fn filters(&self) -> &TaskFilters {
+        self.extra_task_lists
+            .get(&self.active_list)
+            .unwrap_or(&self.task_list)
     }
 
-    /// Saves the database to disk and marks it as clean.
-    pub fn save(&mut self) {
-        // TODO: error handling. show popup on failure to save?
-        let db_info: DatabaseFile = (&*self.database).into();
-        db_info.write(&self.path).unwrap();
-        self.database.mark_clean();
+    pub fn filters_mut(&mut self) -> &mut TaskFilters {
+        match self.extra_task_lists.get_mut(&self.active_list) {
+            Some(filters) => filters,
+            None => &mut self.task_list,
+        }
     }
 
-    pub fn get_task_filter_predicate(&self) -> BoxPredicate<Task> {
-        let mut predicate = predicate::always().boxed();
+    /// Gets the ids of the task lists in extra tabs, in the order they were opened.
+    pub fn extra_task_lists(&self) -> impl Iterator<Item = TaskListId> + '_ {
+        self.extra_task_lists.keys().copied()
+    }
+
+    /// Opens a new task list with the given filters, and makes it the active one.
+    pub fn add_task_list(&mut self, filters: TaskFilters) -> TaskListId {
+        let id = self.extra_task_lists.keys().last().map_or(1, |id| id + 1);
+        self.extra_task_lists.insert(id, filters);
+        self.active_list = id;
+        id
+    }
 
-        if self.filter_completed {
-            predicate = predicate
-                .and(predicate::function(|x: &Task| x.time_completed.is_none()))
-                .boxed();
+    /// Closes a task list, making the main list the active one if it was active. The main list
+    /// cannot be closed.
+    pub fn remove_task_list(&mut self, id: TaskListId) {
+        self.extra_task_lists.remove(&id);
+        if self.active_list == id {
+            self.active_list = 0;
         }
+    }
 
-        if self.filter_unactionable {
-            let tasks_with_uncompleted_dependencies = self
-                .database
-                .get_all_tasks()
-                .filter(|t| {
-                    self.database
-                        .get_dependencies(t.id())
-                        .any(|dep| dep.time_completed.is_none())
-                })
-                .map(|t| t.id().clone())
-                .collect::<HashSet<_>>();
+    /// Makes the filters of the given task list the ones that are used, if it is open.
+    pub fn activate_task_list(&mut self, id: TaskListId) {
+        if id == 0 || self.extra_task_lists.contains_key(&id) {
+            self.active_list = id;
+        }
+    }
 
-            let has_uncompleted_dependencies =
-                predicate::in_hash(tasks_with_uncompleted_dependencies);
+    pub fn get_task_filter_predicate(&self) -> BoxPredicate<Task> {
+        self.get_search_filter_predicate(|_| false)
+    }
 
-            let has_uncompleted_dependencies =
-                MapPredicate::new(has_uncompleted_dependencies, |task: &Task| task.id());
+    /// Gets the predicate for the filters while searching, which does not hide the tasks that the
+    /// search includes.
+    pub fn get_search_filter_predicate(
+        &self,
+        includes: impl Fn(HiddenTasks) -> bool,
+    ) -> BoxPredicate<Task> {
+        self.filters().predicate(&self.database, includes)
+    }
+}
 
-            predicate = predicate.and(has_uncompleted_dependencies.not()).boxed();
-        }
+/// The result of a git sync that ran in the background.
+struct GitSyncResult {
+    /// How many syncs were started, including this one.
+    count: u64,
+    /// Whether the user started the sync, so the result is shown even if nothing went wrong.
+    manual: bool,
+    outcome: Result<PullOutcome, String>,
+    /// How many commits could not be pushed yet, if that could be checked.
+    queued_commits: Option<usize>,
+}
 
-        predicate
-    }
+/// Commits the database file as it was saved, merges the changes from the upstream branch and
+/// pushes the result.
+fn sync_file_with_git(git_sync: &GitSync, path: &Path) -> Result<PullOutcome, String> {
+    // the saved file is committed, it is only read to describe the changes
+    let saved = DatabaseFile::read(path)
+        .and_then(TryInto::try_into)
+        .map_err(|e| e.to_string())?;
+    git_sync
+        .commit(&saved)
+        .and_then(|_| git_sync.pull())
+        .and_then(|outcome| git_sync.push().map(|_| outcome))
+        .map_err(|e| e.to_string())
 }
 
 /// Global storage for the current frame. Can be populated during [Component::pre_render] and read
@@ -157,6 +1456,8 @@ pub struct FrameLocalStorage {
     /// A map of keybind to action for the currently rendering frame
     current_keybinds: Vec<(Cow<'static, str>, Cow<'static, str>, bool)>,
     keybinds_locked: bool,
+    /// Whether a text input outside of a modal has focus, so typed keys are text and not vim keys.
+    text_input_focused: bool,
 
     /// The currently selected/focused task
     selected_task_id: Option<TaskId>,
@@ -212,64 +1513,323 @@ pub trait Component: Downcast {
     ) -> bool {
         false
     }
+
+    /// Executed periodically while waiting for input, so components can update without a key
+    /// press. Returns whether anything changed and the screen should be redrawn.
+    fn on_tick(&mut self, _state: &mut AppState) -> bool {
+        false
+    }
 }
 
 impl_downcast!(Component);
 
 struct LayoutRoot {
     tabs: TabLayout,
+    views: ViewStack,
     save_unsaved_confirmation: ConfirmationModal,
+    rollover_message: MessageModal,
+    external_change_modal: KeybindSelectModal,
+    lock_confirmation: ConfirmationModal,
+    reminder_message: MessageModal,
+    /// Lists the steps in the undo history, keyed by the undo count to jump to.
+    undo_history_modal: ListSearchModal<usize>,
+    database_switcher: ListSearchModal<PathBuf>,
+    /// Asks to save the changes before switching to [`Self::pending_switch`].
+    save_before_switch_confirmation: ConfirmationModal,
+    pending_switch: Option<PathBuf>,
+    /// Lists the problems found by `:check`, offering to repair them.
+    diagnostics_modal: KeybindSelectModal,
+    vim_keys: VimKeys,
+    command_line: CommandLine,
+    keybind_list: KeybindList,
 }
 
 impl LayoutRoot {
-    fn new() -> Self {
+    fn new(state: &AppState) -> Self {
+        let mut rollover_message = MessageModal::new("Good morning!".into());
+        if let Some(report) = state.rollover_report.as_ref().filter(|r| !r.is_empty()) {
+            let mut text = String::new();
+            let sections = [
+                ("tasks have become due", &report.newly_due),
+                ("snoozed tasks are back", &report.unsnoozed),
+            ];
+            for (description, task_ids) in sections.into_iter().filter(|(_, ids)| !ids.is_empty()) {
+                if !text.is_empty() {
+                    text.push_str("\n\n");
+                }
+                text.push_str(&format!("{} {description}:", task_ids.len()));
+                for task_id in task_ids {
+                    text.push_str(&format!("\n- {}", state.database[task_id].title));
+                }
+            }
+            rollover_message.open(text);
+        }
+
+        let mut lock_confirmation =
+            ConfirmationModal::new(String::new()).with_title("Database already opened".into());
+        if let Some(conflict) = &state.lock_conflict {
+            lock_confirmation.set_text(format!(
+                "Could not lock the database: {conflict}.\n\nChanges made in multiple instances \
+                 can overwrite each other. Open it anyway?"
+            ));
+            lock_confirmation.open(false);
+        }
+
+        let mut task_page = TaskPage::new(0);
+        if let Some(task_id) = &state.open_task {
+            task_page.select_task(state, task_id);
+        }
+
+        let mut tabs = TabLayout::new([
+            ("Tasks", Box::new(task_page) as Box<dyn Component>),
+            ("Tags", Box::new(TagPage::new())),
+            ("Time", Box::new(TimePage::new())),
+            ("Calendar", Box::new(CalendarPage::new())),
+            ("Activity", Box::new(ActivityPage::new())),
+        ]);
+        for list_id in state.extra_task_lists() {
+            tabs.push(
+                Self::task_list_title(list_id),
+                Box::new(TaskPage::new(list_id)),
+            );
+        }
+
         Self {
-            tabs: TabLayout::new([("Tasks", Box::new(TaskPage::new()) as Box<dyn Component>)]),
+            tabs: tabs.with_index(state.open_tab),
+            views: ViewStack::default(),
             save_unsaved_confirmation: ConfirmationModal::new(
                 "There are unsaved changes. Do you want to save before quitting?".into(),
             )
             .with_title("Save before quitting?".into()),
+            rollover_message,
+            external_change_modal: KeybindSelectModal::new(
+                "The database file was changed by another program".into(),
+            ),
+            lock_confirmation,
+            reminder_message: MessageModal::new("Reminders".into()),
+            undo_history_modal: ListSearchModal::new("Undo history".into()),
+            database_switcher: ListSearchModal::new("Switch database".into()),
+            save_before_switch_confirmation: ConfirmationModal::new(String::new())
+                .with_title("Save before switching?".into()),
+            pending_switch: None,
+            diagnostics_modal: KeybindSelectModal::new("Database check".into()),
+            vim_keys: VimKeys::default(),
+            command_line: CommandLine::default(),
+            keybind_list: KeybindList::default(),
         }
     }
-}
 
-impl Component for LayoutRoot {
-    fn pre_render(&self, state: &AppState, frame_storage: &mut FrameLocalStorage) {
-        self.save_unsaved_confirmation
-            .pre_render(state, frame_storage);
-        self.tabs.pre_render(state, frame_storage);
+    fn task_list_title(list_id: TaskListId) -> String {
+        format!("Tasks {}", list_id + 1)
+    }
 
-        frame_storage.register_keybind(KEYBIND_SAVE, state.database.is_dirty());
-        frame_storage.register_keybind(KEYBIND_UNDO, state.database.undo_count() > 0);
-        frame_storage.register_keybind(KEYBIND_REDO, state.database.redo_count() > 0);
-        frame_storage.register_keybind(KEYBIND_QUIT, true);
-        frame_storage.register_keybind(KEYBIND_QUIT_ALT, true);
+    /// Opens a new task list tab that starts out with the filters of the active list.
+    fn open_task_list(&mut self, state: &mut AppState) {
+        let list_id = state.add_task_list(state.filters().duplicate());
+        self.tabs.push(
+            Self::task_list_title(list_id),
+            Box::new(TaskPage::new(list_id)),
+        );
+        self.tabs.select_last();
     }
 
-    fn render(
-        &self,
-        frame: &mut Frame,
-        area: Rect,
-        state: &AppState,
-        frame_storage: &FrameLocalStorage,
-    ) {
-        let height = wrap_spans(KeybindList::get_spans(frame_storage), area.width).len() as u16;
+    /// Gets the task list of the selected tab, if it is one that can be closed.
+    fn closable_task_list(&self) -> Option<TaskListId> {
+        let list_id = self.tabs.selected::<TaskPage>()?.list_id();
+        (list_id != 0).then_some(list_id)
+    }
 
-        let (area_tabs, area_keybinds) = area.split_last_y(height);
-        self.tabs.render(frame, area_tabs, state, frame_storage);
+    /// Uses the filters of the task list in the selected tab, if it has one. Other tabs keep
+    /// using the filters of the task list that was selected last.
+    fn sync_active_task_list(&self, state: &mut AppState) {
+        if let Some(page) = self.tabs.selected::<TaskPage>() {
+            state.activate_task_list(page.list_id());
+        }
+    }
 
-        KeybindList.render(frame, area_keybinds, state, frame_storage);
+    /// Opens a list of the steps in the undo history, newest first. Selecting a step jumps to the
+    /// state right after it.
+    fn open_undo_history(&mut self, state: &AppState) {
+        let undo_count = state.database.undo_count();
+        let mut items = state
+            .database
+            .steps()
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let label = match index + 1 {
+                    n if n == undo_count => format!("{} (current)", step.label),
+                    n if n > undo_count => format!("{} (undone)", step.label),
+                    _ => step.label.clone(),
+                };
+                (index + 1, label)
+            })
+            .rev()
+            .collect::<Vec<_>>();
+        items.push(match undo_count {
+            0 => (0, "Initial state (current)".to_string()),
+            _ => (0, "Initial state".to_string()),
+        });
+        self.undo_history_modal.open(items);
+    }
 
-        self.save_unsaved_confirmation
-            .render(frame, area, state, frame_storage);
+    /// Opens a list of the databases that can be switched to.
+    fn open_database_switcher(&mut self, state: &AppState) {
+        let items = state
+            .databases()
+            .iter()
+            .map(|path| {
+                let label = match *path == state.path {
+                    true => format!("{} (current)", path.display()),
+                    false => path.display().to_string(),
+                };
+                (path.clone(), label)
+            })
+            .collect();
+        self.database_switcher.open(items);
     }
 
-    fn process_input(
+    /// Switches to the database at the given path, asking to save the changes to the current
+    /// one first. Switching is cancelled if the user does not want to save them.
+    fn switch_database(&mut self, state: &mut AppState, path: PathBuf) {
+        if path == state.path {
+            return;
+        }
+
+        if state.database.is_dirty() {
+            self.save_before_switch_confirmation.set_text(format!(
+                "There are unsaved changes. Do you want to save them and switch to {}?",
+                path.display()
+            ));
+            self.save_before_switch_confirmation.open(true);
+            self.pending_switch = Some(path);
+        } else {
+            state.request_switch(path);
+        }
+    }
+
+    /// Lists the problems in the database, offering to repair the ones that can be repaired.
+    fn open_diagnostics(&mut self, state: &AppState) {
+        let report = state.database.validate();
+        if report.is_ok() {
+            self.diagnostics_modal
+                .open_with_text("No problems found".to_string(), vec![]);
+            return;
+        }
+
+        let mut text = report
+            .issues
+            .iter()
+            .map(|issue| format!("- {}", issue.describe(&state.database)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let keybinds = match report.repairable_count() {
+            0 => vec![],
+            count => {
+                text.push_str(&format!("\n\n{count} of these problems can be repaired."));
+                vec![KEYBIND_DIAGNOSTICS_REPAIR.clone()]
+            }
+        };
+        self.diagnostics_modal.open_with_text(text, keybinds);
+    }
+
+    /// Shows the task list, searching it for the given query.
+    fn search(&mut self, state: &mut AppState, query: &str) {
+        self.views.clear();
+        if let Some(task_page) = self.tabs.select::<TaskPage>() {
+            state.activate_task_list(task_page.list_id());
+            task_page.search(state, query);
+        }
+    }
+
+    /// Quits, asking to save the unsaved changes first.
+    fn quit(&mut self, state: &mut AppState) {
+        if state.database.is_dirty() {
+            self.save_unsaved_confirmation.open(true);
+        } else {
+            state.request_exit();
+        }
+    }
+
+    /// Runs a command typed in the command line.
+    fn run_command(&mut self, state: &mut AppState, frame_storage: &FrameLocalStorage, text: &str) {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        match Command::parse(text, now.date()) {
+            Ok(Command::Write) => state.dispatch(Action::Save),
+            Ok(Command::Quit) => self.quit(state),
+            Ok(Command::ForceQuit) => state.request_exit(),
+            Ok(Command::WriteQuit) => {
+                // if saving fails because of external changes, stay so they can be resolved
+                if state.save() {
+                    state.request_exit();
+                }
+            }
+            Ok(Command::Tag(tag)) => match &frame_storage.selected_task_id {
+                Some(task_id) => state.dispatch(Action::AddTag(task_id.clone(), tag)),
+                None => state.show_toast("No task is selected".to_string()),
+            },
+            Ok(Command::Snooze(time)) => {
+                let until = reminders::parse_time(&time, now);
+                match (&frame_storage.selected_task_id, until) {
+                    (Some(task_id), Some(until)) => {
+                        state.snooze_notifications(task_id.clone(), until);
+                        state.show_toast(format!("Snoozed until {time}"));
+                    }
+                    (None, _) => state.show_toast("No task is selected".to_string()),
+                    (_, None) => state.show_toast(format!("Invalid snooze time: {time}")),
+                }
+            }
+            Ok(Command::Add(new_task)) => {
+                let mut task = Task::create_now(new_task.title);
+                task.tags = new_task.tags.into_iter().map(Into::into).collect();
+                task.time_due = new_task
+                    .due
+                    .map(|day| day.midnight().assume_offset(now.offset()));
+                state.dispatch(Action::AddTask(Box::new(task)));
+            }
+            Ok(Command::Filter(query)) => self.search(state, &query),
+            Ok(Command::Check) => self.open_diagnostics(state),
+            Ok(Command::GitHub {
+                repository,
+                close_issues,
+            }) => state.sync_with_github(repository, close_issues),
+            Ok(Command::Export(format, path)) => {
+                let contents = format.export(&state.database, now);
+                match std::fs::write(&path, contents) {
+                    Ok(()) => state.show_toast(format!("Exported to {}", path.display())),
+                    Err(e) => state.show_toast(format!("Could not write {}: {e}", path.display())),
+                }
+            }
+            Err(message) => state.show_toast(message),
+        }
+    }
+
+    /// Handles a key as if vim mode is disabled.
+    fn handle_key(
         &mut self,
         key: KeyEvent,
         state: &mut AppState,
         frame_storage: &FrameLocalStorage,
     ) -> bool {
+        if self.lock_confirmation.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) || KEYBIND_MODAL_CANCEL.is_match(key) {
+                let selected = self.lock_confirmation.close();
+                let open_anyway = KEYBIND_MODAL_SUBMIT.is_match(key) && selected;
+                if open_anyway {
+                    // the database stays unlocked, so autosaving stays off as well
+                    if let Err(e) = state.force_lock() {
+                        state.show_toast(format!("Could not lock the database: {e}"));
+                    }
+                } else {
+                    state.request_exit();
+                }
+                return true;
+            }
+            return self
+                .lock_confirmation
+                .process_input(key, state, frame_storage);
+        }
+
         if self
             .save_unsaved_confirmation
             .process_input(key, state, frame_storage)
@@ -277,40 +1837,481 @@ impl Component for LayoutRoot {
             return true;
         }
 
+        if self
+            .reminder_message
+            .process_input(key, state, frame_storage)
+        {
+            return true;
+        }
+
+        if self
+            .rollover_message
+            .process_input(key, state, frame_storage)
+        {
+            return true;
+        }
+
+        if self.external_change_modal.is_open() {
+            _ = self
+                .external_change_modal
+                .process_input(key, state, frame_storage);
+            if let Some(selected) = self.external_change_modal.take_selected_keybind() {
+                // the database in memory is kept if the version on disk can not be read
+                let result = match selected {
+                    _ if selected == *KEYBIND_EXTERNAL_CHANGE_RELOAD => state.reload_from_disk(),
+                    _ if selected == *KEYBIND_EXTERNAL_CHANGE_KEEP => {
+                        state.keep_local_changes();
+                        Ok(())
+                    }
+                    _ if selected == *KEYBIND_EXTERNAL_CHANGE_MERGE => state.merge_from_disk(),
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    state.show_toast(format!("Could not read the database: {e}"));
+                }
+            }
+            // the modal must block all other input
+            return true;
+        }
+
+        if self.diagnostics_modal.is_open() {
+            _ = self
+                .diagnostics_modal
+                .process_input(key, state, frame_storage);
+            if self.diagnostics_modal.take_selected_keybind().is_some() {
+                let mut repaired = 0;
+                state
+                    .database
+                    .modify("Repair database", |db| repaired = db.repair().len());
+                state.show_undo_toast(format!("Repaired {repaired} problems"));
+            }
+            // the modal must block all other input
+            return true;
+        }
+
         if self.save_unsaved_confirmation.is_open() {
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
-                if self.save_unsaved_confirmation.close() {
-                    state.save();
+                // if saving fails because of external changes, stay open so they can be resolved
+                if !self.save_unsaved_confirmation.close() || state.save() {
+                    state.request_exit();
                 }
-                state.request_exit();
                 return true;
             } else {
                 return false;
             }
         }
 
-        if self.tabs.process_input(key, state, frame_storage) {
+        if self.undo_history_modal.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(undo_count) = self.undo_history_modal.close() {
+                    state.database.jump_to(undo_count);
+                }
+            } else {
+                _ = self
+                    .undo_history_modal
+                    .process_input(key, state, frame_storage);
+            }
+            // the modal must block all other input
+            return true;
+        }
+
+        if self.database_switcher.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(path) = self.database_switcher.close() {
+                    self.switch_database(state, path);
+                }
+            } else {
+                _ = self
+                    .database_switcher
+                    .process_input(key, state, frame_storage);
+            }
+            return true;
+        }
+
+        if self.save_before_switch_confirmation.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                let pending_switch = self.pending_switch.take();
+                // if saving fails because of external changes, stay so they can be resolved
+                if self.save_before_switch_confirmation.close() && state.save() {
+                    if let Some(path) = pending_switch {
+                        state.request_switch(path);
+                    }
+                }
+            } else {
+                _ = self
+                    .save_before_switch_confirmation
+                    .process_input(key, state, frame_storage);
+            }
+            return true;
+        }
+
+        let handled = match self.views.is_empty() {
+            true => self.tabs.process_input(key, state, frame_storage),
+            false => self.views.process_input(key, state, frame_storage),
+        };
+        if handled {
             return true;
         }
 
         if KEYBIND_SAVE.is_match(key) {
-            state.save();
+            state.dispatch(Action::Save);
+            true
+        } else if KEYBIND_GIT_SYNC.is_match(key) && state.git_sync_enabled() {
+            state.sync_with_git();
             true
         } else if KEYBIND_UNDO.is_match(key) && state.database.undo_count() > 0 {
-            state.database.undo();
+            state.dispatch(Action::Undo);
             true
         } else if KEYBIND_REDO.is_match(key) && state.database.redo_count() > 0 {
-            state.database.redo();
+            state.dispatch(Action::Redo);
+            true
+        } else if KEYBIND_UNDO_HISTORY.is_match(key) && !state.database.steps().is_empty() {
+            self.open_undo_history(state);
+            true
+        } else if KEYBIND_SWITCH_DATABASE.is_match(key) && state.databases().len() > 1 {
+            self.open_database_switcher(state);
+            true
+        } else if KEYBIND_TABS_NEW_TASK_LIST.is_match(key) && self.views.is_empty() {
+            self.open_task_list(state);
+            true
+        } else if KEYBIND_TABS_CLOSE.is_match(key) && self.views.is_empty() {
+            let Some(list_id) = self.closable_task_list() else {return false;};
+            self.tabs.remove_selected();
+            state.remove_task_list(list_id);
+            self.sync_active_task_list(state);
+            true
+        } else if KEYBIND_COMMAND_LINE.is_match(key) {
+            self.command_line.open();
             true
         } else if KEYBIND_QUIT.is_match(key) || KEYBIND_QUIT_ALT.is_match(key) {
-            if state.database.is_dirty() {
-                self.save_unsaved_confirmation.open(true);
-            } else {
-                state.request_exit();
-            }
+            self.quit(state);
             true
         } else {
             false
         }
     }
+
+    /// Updates the component based on changes in the state that did not come from user input.
+    fn update(&mut self, state: &mut AppState) {
+        self.sync_active_task_list(state);
+        self.views.push_pending(state);
+        if let Some(query) = state.pending_search.take() {
+            self.search(state, &query);
+        }
+
+        if !state.due_reminders.is_empty() && !self.reminder_message.is_open() {
+            let text = std::mem::take(&mut state.due_reminders)
+                .into_iter()
+                .map(|reminder| format!("- {}", state.database[&reminder.task].title))
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.reminder_message.open(text);
+        }
+
+        if state.external_change && !self.external_change_modal.is_open() {
+            self.external_change_modal.open(vec![
+                KEYBIND_EXTERNAL_CHANGE_RELOAD.clone(),
+                KEYBIND_EXTERNAL_CHANGE_KEEP.clone(),
+                KEYBIND_EXTERNAL_CHANGE_MERGE.clone(),
+            ]);
+        }
+    }
+}
+
+impl Component for LayoutRoot {
+    fn pre_render(&self, state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        self.command_line.pre_render(state, frame_storage);
+        self.lock_confirmation.pre_render(state, frame_storage);
+        self.save_unsaved_confirmation
+            .pre_render(state, frame_storage);
+        self.rollover_message.pre_render(state, frame_storage);
+        self.reminder_message.pre_render(state, frame_storage);
+        self.external_change_modal.pre_render(state, frame_storage);
+        self.diagnostics_modal.pre_render(state, frame_storage);
+        self.undo_history_modal.pre_render(state, frame_storage);
+        self.database_switcher.pre_render(state, frame_storage);
+        self.save_before_switch_confirmation
+            .pre_render(state, frame_storage);
+        match self.views.is_empty() {
+            true => self.tabs.pre_render(state, frame_storage),
+            false => self.views.pre_render(state, frame_storage),
+        }
+
+        frame_storage.register_keybind(KEYBIND_SAVE, state.database.is_dirty());
+        if state.git_sync_enabled() {
+            frame_storage.register_keybind(KEYBIND_GIT_SYNC, true);
+        }
+        frame_storage.register_keybind(KEYBIND_UNDO, state.database.undo_count() > 0);
+        frame_storage.register_keybind(KEYBIND_REDO, state.database.redo_count() > 0);
+        frame_storage.register_keybind(KEYBIND_UNDO_HISTORY, !state.database.steps().is_empty());
+        frame_storage.register_keybind(KEYBIND_SWITCH_DATABASE, state.databases().len() > 1);
+        frame_storage.register_keybind(KEYBIND_TABS_NEW_TASK_LIST, self.views.is_empty());
+        frame_storage.register_keybind(
+            KEYBIND_TABS_CLOSE,
+            self.views.is_empty() && self.closable_task_list().is_some(),
+        );
+        frame_storage.register_keybind(KEYBIND_QUIT, true);
+        frame_storage.register_keybind(KEYBIND_QUIT_ALT, self.views.is_empty());
+        frame_storage.register_keybind(KEYBIND_COMMAND_LINE, true);
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let height = self.keybind_list.height(frame_storage, area.width);
+
+        let (area_tabs, area_keybinds) = area.split_last_y(height);
+        match self.views.is_empty() {
+            true => self.tabs.render(frame, area_tabs, state, frame_storage),
+            false => self.views.render(frame, area_tabs, state, frame_storage),
+        }
+        ToastDisplay.render(frame, area_tabs, state, frame_storage);
+
+        self.keybind_list
+            .render(frame, area_keybinds, state, frame_storage);
+
+        self.save_unsaved_confirmation
+            .render(frame, area, state, frame_storage);
+        self.rollover_message
+            .render(frame, area, state, frame_storage);
+        self.reminder_message
+            .render(frame, area, state, frame_storage);
+        self.external_change_modal
+            .render(frame, area, state, frame_storage);
+        self.diagnostics_modal
+            .render(frame, area, state, frame_storage);
+        self.undo_history_modal
+            .render(frame, area, state, frame_storage);
+        self.database_switcher
+            .render(frame, area, state, frame_storage);
+        self.save_before_switch_confirmation
+            .render(frame, area, state, frame_storage);
+        self.lock_confirmation
+            .render(frame, area, state, frame_storage);
+        self.command_line.render(frame, area, state, frame_storage);
+    }
+
+    fn on_tick(&mut self, state: &mut AppState) -> bool {
+        self.tabs.on_tick(state) | self.views.on_tick(state)
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if self.command_line.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(text) = self.command_line.close() {
+                    self.run_command(state, frame_storage, &text);
+                }
+                return true;
+            }
+            return self.command_line.process_input(key, state, frame_storage);
+        }
+
+        // vim keys are not used while typing or while a modal is open
+        let typing = frame_storage.keybinds_locked || frame_storage.text_input_focused;
+        if !state.vim_mode || typing {
+            return self.handle_key(key, state, frame_storage);
+        }
+
+        // a key that starts a sequence is handled by waiting for the next one
+        let mut handled = true;
+        for key in self.vim_keys.translate(key) {
+            handled = self.handle_key(key, state, frame_storage);
+        }
+        handled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use td_lib::database::Task;
+
+    use super::*;
+
+    #[test]
+    fn tab_keybinds_work_while_a_task_is_selected() {
+        let mut state = AppState::default();
+        state.dispatch(Action::NewTask("task".into()));
+        let mut root = LayoutRoot::new(&state);
+        fn press(root: &mut LayoutRoot, state: &mut AppState, keybind: &SimpleKeybind) -> bool {
+            root.update(state);
+            let mut frame_storage = FrameLocalStorage::default();
+            root.pre_render(state, &mut frame_storage);
+            assert!(frame_storage.selected_task_id.is_some());
+            root.process_input(keybind.key_event(), state, &frame_storage)
+        }
+
+        assert!(press(&mut root, &mut state, KEYBIND_TABS_NEW_TASK_LIST));
+        assert_eq!(root.closable_task_list(), Some(1));
+        assert!(root.views.is_empty());
+
+        assert!(press(&mut root, &mut state, KEYBIND_TABS_CLOSE));
+        assert_eq!(root.closable_task_list(), None);
+        assert_eq!(state.extra_task_lists().count(), 0);
+    }
+
+    #[test]
+    fn keeps_the_database_if_the_changed_file_can_not_be_read() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+        DatabaseFile::write_database(&Database::default(), &path).unwrap();
+
+        let mut state = AppState::create(path.clone()).unwrap();
+        state.dispatch(Action::NewTask("task".into()));
+        std::fs::write(&path, "not a database").unwrap();
+        state.external_change = true;
+
+        let mut root = LayoutRoot::new(&state);
+        for keybind in [
+            KEYBIND_EXTERNAL_CHANGE_RELOAD,
+            KEYBIND_EXTERNAL_CHANGE_MERGE,
+        ] {
+            root.update(&mut state);
+            let frame_storage = FrameLocalStorage::default();
+            assert!(root.process_input(keybind.key_event(), &mut state, &frame_storage));
+            assert_eq!(state.database.get_all_tasks().count(), 1);
+            assert!(state.toasts.current().is_some());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hooks_only_see_the_tasks_that_were_saved() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let output = dir.join("output");
+
+        let mut state = AppState::default();
+        state.hooks.task_created = vec![format!("echo \"$TD_TASK_TITLE\" >> {}", output.display())];
+        state.dispatch(Action::NewTask("saved".into()));
+        let disk_database = (*state.database).clone();
+        state.dispatch(Action::NewTask("not saved yet".into()));
+
+        state.finish_autosave(autosave::FinishedSave {
+            revision: 0,
+            disk_database,
+            previous: Some(Database::default()),
+            result: Ok(()),
+        });
+
+        // the hooks run in the background
+        for _ in 0..100 {
+            if output.exists() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        let output = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(output, "saved\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn git_sync_only_pushes_what_was_saved() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let work = dir.join("work");
+        std::fs::create_dir_all(&work).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let path = work.join("todo.json");
+        DatabaseFile::write_database(&Database::default(), &path).unwrap();
+        git(&work, &["init", "--quiet"]);
+        git(&work, &["config", "user.name", "td"]);
+        git(&work, &["config", "user.email", "td@example.com"]);
+        git(&work, &["add", "todo.json"]);
+        git(&work, &["commit", "--quiet", "-m", "initial"]);
+        git(dir, &["init", "--quiet", "--bare", "remote.git"]);
+        git(&work, &["remote", "add", "origin", "../remote.git"]);
+        git(&work, &["push", "--quiet", "-u", "origin", "HEAD"]);
+        // saved, but not committed yet
+        let mut database = Database::default();
+        database.add_task(Task::create_now("saved".into()));
+        DatabaseFile::write_database(&database, &path).unwrap();
+
+        let mut state = AppState::create(path.clone()).unwrap();
+        state.set_git_sync(true);
+        state.dispatch(Action::NewTask("not saved".into()));
+        state.sync_with_git();
+        assert!(state.git_sync_job.wait());
+        state.finish_git_sync();
+
+        assert!(state.database.is_dirty());
+        assert_eq!(state.database.get_all_tasks().count(), 2);
+        let pushed = git(&dir.join("remote.git"), &["show", "HEAD:todo.json"]);
+        assert!(pushed.contains("saved") && !pushed.contains("not saved"));
+    }
+
+    #[test]
+    fn purging_keeps_the_archive_unchanged_if_saving_fails() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+
+        let mut database = Database::default();
+        let mut task = Task::create_now("archived".into());
+        task.time_archived = Some(task.time_created);
+        database.add_task(task);
+        DatabaseFile::write_database(&database, &path).unwrap();
+
+        let mut state = AppState::create(path.clone()).unwrap();
+        // another program changes the file, so the purged database can not be saved
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+
+        assert!(state.purge_archived().is_err());
+        assert!(!DatabaseFile::archive_path(&path).exists());
+        assert_eq!(state.database.get_all_tasks().count(), 1);
+    }
+
+    #[test]
+    fn shows_failed_saves_as_a_toast() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+        DatabaseFile::write_database(&Database::default(), &path).unwrap();
+
+        let mut state = AppState::create(path.clone()).unwrap();
+        state.dispatch(Action::NewTask("task".into()));
+        // the temporary file that is written first can not be created
+        std::fs::create_dir(dir.join("todo.json.tmp")).unwrap();
+
+        assert!(!state.save());
+        assert!(state.database.is_dirty());
+        assert!(state.toasts.current().is_some());
+    }
+
+    #[test]
+    fn saves_the_database_id_when_it_is_created() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+        DatabaseFile::write_database(&Database::default(), &path).unwrap();
+
+        let mut state = AppState::create(path.clone()).unwrap();
+        let database_id = state.database_id();
+        assert_eq!(state.database_id(), database_id);
+        assert_eq!(state.database.undo_count(), 0);
+
+        let saved: Database = DatabaseFile::read(&path).unwrap().try_into().unwrap();
+        assert_eq!(saved.database_id(), Some(database_id.as_str()));
+    }
 }
@@ -33,6 +33,10 @@ impl ConfirmationModal {
         self
     }
 
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
     pub fn is_open(&self) -> bool {
         self.selected_value.is_some()
     }
@@ -90,9 +94,11 @@ impl Component for ConfirmationModal {
             .max(BUTTONS_LEN as u16);
         let block_width = inner_width + 2;
 
-        // wrap the text inside the inner width
-        let wrapped_text = wrap_text(&self.text, inner_width)
-            .into_iter()
+        // wrap the text inside the inner width, keeping explicit line breaks
+        let wrapped_text = self
+            .text
+            .lines()
+            .flat_map(|line| wrap_text(line, inner_width))
             .map(|str| Line::from(Span::from(str)))
             .collect::<Vec<_>>();
         let inner_height = wrapped_text.len() as u16 + 2;
@@ -13,6 +13,8 @@ use crate::{
 
 pub struct KeybindSelectModal {
     title: String,
+    /// Explains the choice, shown above the keybinds.
+    text: Option<String>,
     keybinds: Option<Vec<SimpleKeybind>>,
     selected_keybind: Option<SimpleKeybind>,
 }
@@ -21,6 +23,7 @@ impl KeybindSelectModal {
     pub fn new(title: String) -> Self {
         Self {
             title,
+            text: None,
             keybinds: None,
             selected_keybind: None,
         }
@@ -31,10 +34,17 @@ impl KeybindSelectModal {
     }
 
     pub fn open(&mut self, keybinds: Vec<SimpleKeybind>) {
+        self.text = None;
         self.keybinds = Some(keybinds);
         self.selected_keybind = None;
     }
 
+    /// Opens the modal with a text that explains the choice between the keybinds.
+    pub fn open_with_text(&mut self, text: String, keybinds: Vec<SimpleKeybind>) {
+        self.open(keybinds);
+        self.text = Some(text);
+    }
+
     pub fn take_selected_keybind(&mut self) -> Option<SimpleKeybind> {
         let taken = self.selected_keybind.take();
         if taken.is_some() {
@@ -76,17 +86,18 @@ impl Component for KeybindSelectModal {
             .title(self.title.clone())
             .borders(Borders::ALL);
 
-        let line = keybinds
-            .iter()
-            .map(|k| {
-                Line::from(vec![
-                    Span::raw("["),
-                    Span::styled(k.key_hint(), KEYBINDS_CHAR_ACTIVE),
-                    Span::raw("] "),
-                    Span::raw(k.description().cloned().unwrap_or(Cow::Borrowed(""))),
-                ])
-            })
-            .collect::<Vec<_>>();
+        let mut line = match &self.text {
+            Some(text) => text.lines().map(Line::raw).chain([Line::raw("")]).collect(),
+            None => vec![],
+        };
+        line.extend(keybinds.iter().map(|k| {
+            Line::from(vec![
+                Span::raw("["),
+                Span::styled(k.key_hint(), KEYBINDS_CHAR_ACTIVE),
+                Span::raw("] "),
+                Span::raw(k.description().cloned().unwrap_or(Cow::Borrowed(""))),
+            ])
+        }));
 
         let inner_width = (line
             .iter()
@@ -98,7 +109,11 @@ impl Component for KeybindSelectModal {
 
         let paragraph = Paragraph::new(line);
 
-        let block_area = area.center_rect(inner_width + 2, inner_height + 2);
+        // the text can be longer than the keybinds, so keep the modal on the screen
+        let block_area = area.center_rect(
+            (inner_width + 2).min(area.width),
+            (inner_height + 2).min(area.height),
+        );
         let block_area_inner = block.inner(block_area);
 
         frame.render_widget(Clear, block_area);
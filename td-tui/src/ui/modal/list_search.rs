@@ -1,9 +1,14 @@
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use ratatui::{
+    style::Style,
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+};
 
 use crate::{
+    fuzzy::{fuzzy_match, highlight_spans, FuzzyMatch},
     keybinds::*,
     ui::{
-        constants::{LIST_HIGHLIGHT_STYLE, LIST_STYLE, MIN_MODAL_WIDTH},
+        constants::{LIST_HIGHLIGHT_STYLE, LIST_STYLE, MIN_MODAL_WIDTH, SEARCH_MATCH_STYLE},
         input::TextBoxComponent,
         Component,
     },
@@ -38,20 +43,26 @@ impl<TKey: Eq + Clone> ListSearchModal<TKey> {
     }
 
     pub fn close(&mut self) -> Option<TKey> {
-        let ret = self.get_seach_results().nth(self.index).cloned();
+        let ret = self
+            .get_search_results()
+            .into_iter()
+            .nth(self.index)
+            .map(|(item, _)| item.0.clone());
         self.items = None;
-        ret.map(|x| x.0)
+        ret
     }
 
-    fn get_seach_results(&self) -> Box<dyn Iterator<Item = &(TKey, String)> + '_> {
-        let search_query = self.filter_box.text().to_lowercase();
-        match &self.items {
-            Some(x) => Box::new(
-                x.iter()
-                    .filter(move |(_, x)| x.to_lowercase().contains(&search_query)),
-            ),
-            None => Box::new(std::iter::empty()),
-        }
+    /// Gets all items that match the search query, with the best matches first.
+    fn get_search_results(&self) -> Vec<(&(TKey, String), FuzzyMatch)> {
+        let search_query = self.filter_box.text();
+        let Some(items) = &self.items else {return vec![];};
+
+        let mut results = items
+            .iter()
+            .filter_map(|item| fuzzy_match(search_query, &item.1).map(|m| (item, m)))
+            .collect::<Vec<_>>();
+        results.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
+        results
     }
 }
 
@@ -64,9 +75,9 @@ impl<TKey: Eq + Clone + 'static> Component for ListSearchModal<TKey> {
         if self.is_open() {
             self.filter_box.pre_render(global_state, frame_storage);
 
-            let mut results = self.get_seach_results();
-            let at_least_1_result = results.next().is_some();
-            let at_least_2_results = results.next().is_some();
+            let result_count = self.get_search_results().len();
+            let at_least_1_result = result_count >= 1;
+            let at_least_2_results = result_count >= 2;
             frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, at_least_2_results);
             frame_storage.register_keybind(KEYBIND_MODAL_SUBMITSELECT, at_least_1_result);
             frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
@@ -87,13 +98,20 @@ impl<TKey: Eq + Clone + 'static> Component for ListSearchModal<TKey> {
             .title(self.title.clone())
             .borders(Borders::ALL);
 
-        let filtered_items = self.get_seach_results().collect::<Vec<_>>();
+        let filtered_items = self.get_search_results();
 
         let (list, mut list_state) = {
             let list = List::new(
                 filtered_items
                     .iter()
-                    .map(|item| ListItem::new(item.1.clone()))
+                    .map(|(item, search_match)| {
+                        ListItem::new(Line::from(highlight_spans(
+                            &item.1,
+                            &search_match.indices,
+                            Style::default(),
+                            SEARCH_MATCH_STYLE,
+                        )))
+                    })
                     .collect::<Vec<_>>(),
             )
             .style(LIST_STYLE)
@@ -139,7 +157,7 @@ impl<TKey: Eq + Clone + 'static> Component for ListSearchModal<TKey> {
         }
 
         let Some(_items) = &self.items else {return false;};
-        let filtered_item_count = self.get_seach_results().count();
+        let filtered_item_count = self.get_search_results().len();
 
         // NOTE: could abstract list into a component and have consistent list navigation everywhere
         if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
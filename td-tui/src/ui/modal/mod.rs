@@ -1,9 +1,13 @@
 mod confirmation;
 mod keybind_select;
 mod list_search;
+mod message;
 mod text_input;
+mod typed_confirmation;
 
 pub use confirmation::ConfirmationModal;
 pub use keybind_select::KeybindSelectModal;
 pub use list_search::ListSearchModal;
+pub use message::MessageModal;
 pub use text_input::TextInputModal;
+pub use typed_confirmation::TypedConfirmationModal;
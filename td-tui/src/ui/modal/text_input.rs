@@ -5,20 +5,36 @@ use ratatui::{
     Frame,
 };
 
+use super::ConfirmationModal;
 use crate::{
     keybinds::*,
-    ui::{constants::MIN_MODAL_WIDTH, input::MultilineTextBoxComponent, AppState, Component},
+    ui::{
+        constants::MIN_MODAL_WIDTH, input::MultilineTextBoxComponent, AppState, Component,
+        FrameLocalStorage,
+    },
     utils::RectExt,
 };
 
 pub struct TextInputModal {
     title: String,
     input: Option<MultilineTextBoxComponent>,
+    /// The text the modal was opened with, to tell if anything was typed.
+    initial_text: String,
+    /// Asks whether the typed text should be thrown away when the modal is cancelled.
+    discard_confirmation: ConfirmationModal,
 }
 
 impl TextInputModal {
     pub fn new(title: String) -> Self {
-        Self { title, input: None }
+        Self {
+            title,
+            input: None,
+            initial_text: String::new(),
+            discard_confirmation: ConfirmationModal::new(
+                "Do you want to discard what you typed?".into(),
+            )
+            .with_title("Discard input?".into()),
+        }
     }
 
     pub fn is_open(&self) -> bool {
@@ -26,10 +42,11 @@ impl TextInputModal {
     }
 
     pub fn open(&mut self) {
-        self.input = Some(MultilineTextBoxComponent::new_focused().with_background(false));
+        self.open_with_text(String::new());
     }
 
     pub fn open_with_text(&mut self, input: String) {
+        self.initial_text = input.clone();
         self.input = Some(
             MultilineTextBoxComponent::new_focused()
                 .with_background(false)
@@ -37,8 +54,16 @@ impl TextInputModal {
         );
     }
     pub fn close(&mut self) -> Option<String> {
+        self.discard_confirmation.close();
         self.input.take().map(|input| input.text().to_string())
     }
+
+    /// Checks if the text was changed since the modal was opened.
+    fn has_changes(&self) -> bool {
+        self.input
+            .as_ref()
+            .is_some_and(|input| input.text() != self.initial_text)
+    }
 }
 
 impl Component for TextInputModal {
@@ -47,7 +72,10 @@ impl Component for TextInputModal {
         global_state: &AppState,
         frame_storage: &mut crate::ui::FrameLocalStorage,
     ) {
-        if let Some(input) = &self.input {
+        if self.discard_confirmation.is_open() {
+            self.discard_confirmation
+                .pre_render(global_state, frame_storage);
+        } else if let Some(input) = &self.input {
             debug_assert!(self.is_open());
             input.pre_render(global_state, frame_storage);
 
@@ -82,17 +110,38 @@ impl Component for TextInputModal {
         frame.render_widget(Clear, block_area);
         frame.render_widget(block, block_area);
         textbox.render(frame, block_area_inner, state, frame_storage);
+
+        self.discard_confirmation
+            .render(frame, area, state, frame_storage);
     }
 
     fn process_input(
         &mut self,
         key: KeyEvent,
         state: &mut AppState,
-        frame_storage: &crate::ui::FrameLocalStorage,
+        frame_storage: &FrameLocalStorage,
     ) -> bool {
-        // always close with Esc
+        // the text stays open until the question is answered
+        if self.discard_confirmation.is_open() {
+            if KEYBIND_MODAL_SUBMITSELECT.is_match(key) {
+                if self.discard_confirmation.close() {
+                    self.close();
+                }
+            } else {
+                _ = self
+                    .discard_confirmation
+                    .process_input(key, state, frame_storage);
+            }
+            return true;
+        }
+
+        // close with Esc, asking first if that would throw away typed text
         if self.is_open() && KEYBIND_MODAL_CANCEL.is_match(key) {
-            self.close();
+            if state.confirm_discard && self.has_changes() {
+                self.discard_confirmation.open(false);
+            } else {
+                self.close();
+            }
             return true;
         }
 
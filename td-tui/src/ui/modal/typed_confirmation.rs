@@ -0,0 +1,129 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    keybinds::*,
+    ui::{
+        constants::MIN_MODAL_WIDTH, input::TextBoxComponent, AppState, Component, FrameLocalStorage,
+    },
+    utils::{wrap_text, RectExt},
+};
+
+/// A confirmation for actions that are hard to take back, where the user has to type a given
+/// text to confirm instead of only pressing Enter.
+pub struct TypedConfirmationModal {
+    title: String,
+    text: String,
+    /// The text that has to be typed, set while the modal is open.
+    expected: Option<String>,
+    input: TextBoxComponent,
+}
+
+impl TypedConfirmationModal {
+    pub fn new(title: String) -> Self {
+        Self {
+            title,
+            text: String::new(),
+            expected: None,
+            input: TextBoxComponent::new_focused(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.expected.is_some()
+    }
+
+    /// Opens the modal with the given question. It can only be confirmed by typing `expected`.
+    pub fn open(&mut self, text: String, expected: String) {
+        self.text = format!("{text}\n\nType {expected} to confirm.");
+        self.expected = Some(expected);
+        self.input = TextBoxComponent::new_focused().with_background(true);
+    }
+
+    /// Closes the modal. Returns `true` if the expected text was typed.
+    pub fn close(&mut self) -> bool {
+        self.expected
+            .take()
+            .is_some_and(|expected| self.input.text().trim() == expected)
+    }
+
+    fn is_confirmed(&self) -> bool {
+        self.expected
+            .as_ref()
+            .is_some_and(|expected| self.input.text().trim() == expected)
+    }
+}
+
+impl Component for TypedConfirmationModal {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if self.is_open() {
+            frame_storage.register_keybind(KEYBIND_MODAL_SUBMIT, self.is_confirmed());
+            frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+            frame_storage.lock_keybinds();
+        }
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        if !self.is_open() {
+            return;
+        }
+
+        let block = Block::default()
+            .title(self.title.clone())
+            .borders(Borders::ALL);
+
+        let inner_width = MIN_MODAL_WIDTH.max(self.title.len() as u16);
+        let wrapped_text = self
+            .text
+            .lines()
+            .flat_map(|line| wrap_text(line, inner_width))
+            .map(|str| Line::from(Span::from(str)))
+            .collect::<Vec<_>>();
+        let inner_height = wrapped_text.len() as u16 + 1 + TextBoxComponent::HEIGHT;
+
+        let block_area = area.center_rect(inner_width + 2, inner_height + 2);
+        let block_area_inner = block.inner(block_area);
+
+        frame.render_widget(Clear, block_area);
+        frame.render_widget(block, block_area);
+
+        let (area_text, area_input) = block_area_inner.split_last_y(TextBoxComponent::HEIGHT);
+        frame.render_widget(Paragraph::new(wrapped_text), area_text);
+        self.input.render(frame, area_input, state, frame_storage);
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+
+        if KEYBIND_MODAL_CANCEL.is_match(key) {
+            self.expected = None;
+            return true;
+        }
+
+        // submitting is left to the owner, but only once the expected text is typed
+        if KEYBIND_MODAL_SUBMIT.is_match(key) {
+            return !self.is_confirmed();
+        }
+
+        _ = self.input.process_input(key, state, frame_storage);
+        true
+    }
+}
@@ -0,0 +1,28 @@
+use ratatui::widgets::Paragraph;
+
+use super::{constants::FG_DIM, Component};
+use crate::utils::RectExt;
+
+/// Shows that an automatic save is being written, or otherwise how the last automatic sync went,
+/// see [`super::AppState::sync_status`].
+pub struct SyncStatusDisplay;
+
+impl Component for SyncStatusDisplay {
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &super::AppState,
+        _frame_storage: &super::FrameLocalStorage,
+    ) {
+        let status = match state.is_autosaving() {
+            true => Some("saving...".to_string()),
+            false => state.sync_status(),
+        };
+        let Some(status) = status else {return;};
+
+        let text = format!(" {status} ");
+        let width = (text.chars().count() as u16).min(area.width);
+        frame.render_widget(Paragraph::new(text).style(FG_DIM), area.take_last_x(width));
+    }
+}
@@ -4,13 +4,14 @@ use ratatui::{symbols, text::Line, widgets::Tabs};
 use super::{
     constants::{TAB_HIGHLIGHT_STYLE, TAB_STYLE},
     dirty_indicator::DirtyIndicator,
+    sync_status::SyncStatusDisplay,
     Component,
 };
 use crate::{keybinds::*, utils::RectExt};
 
 pub struct TabLayout {
     items: Vec<Box<dyn Component>>,
-    titles: Vec<&'static str>,
+    titles: Vec<String>,
     index: usize,
 }
 
@@ -18,12 +19,57 @@ impl TabLayout {
     pub fn new(items: impl IntoIterator<Item = (&'static str, Box<dyn Component>)>) -> Self {
         let (titles, items): (Vec<_>, Vec<_>) = items.into_iter().unzip();
         Self {
-            titles,
+            titles: titles.into_iter().map(String::from).collect(),
             items,
             index: 0,
         }
     }
 
+    /// Selects the tab at the given index, if it exists.
+    pub fn with_index(mut self, index: usize) -> Self {
+        if index < self.items.len() {
+            self.index = index;
+        }
+        self
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Adds a tab after the existing ones, without selecting it.
+    pub fn push(&mut self, title: String, item: Box<dyn Component>) {
+        self.titles.push(title);
+        self.items.push(item);
+    }
+
+    pub fn select_last(&mut self) {
+        self.index = self.items.len().saturating_sub(1);
+    }
+
+    /// Removes the selected tab and selects the one before it. The last remaining tab cannot be
+    /// removed.
+    pub fn remove_selected(&mut self) {
+        if self.items.len() < 2 {
+            return;
+        }
+        self.titles.remove(self.index);
+        self.items.remove(self.index);
+        self.index = self.index.saturating_sub(1);
+    }
+
+    /// Gets the component of the selected tab, if it has the given type.
+    pub fn selected<T: Component>(&self) -> Option<&T> {
+        self.get_selected_component()?.downcast_ref()
+    }
+
+    /// Selects the first tab with a component of the given type, and returns that component.
+    pub fn select<T: Component>(&mut self) -> Option<&mut T> {
+        let index = self.items.iter().position(|item| item.is::<T>())?;
+        self.index = index;
+        self.items[index].downcast_mut()
+    }
+
     fn get_selected_component(&self) -> Option<&dyn Component> {
         self.items.get(self.index).map(|x| x.as_ref())
     }
@@ -72,12 +118,20 @@ impl Component for TabLayout {
             .divider(symbols::DOT);
 
         frame.render_widget(tabs, area_tabs);
+        SyncStatusDisplay.render(frame, area_tabs, state, frame_storage);
 
         if let Some(content) = self.get_selected_component() {
             content.render(frame, area_content, state, frame_storage);
         }
     }
 
+    fn on_tick(&mut self, state: &mut super::AppState) -> bool {
+        // background tabs are ticked too, so they are up to date when switching to them
+        self.items
+            .iter_mut()
+            .fold(false, |changed, item| item.on_tick(state) | changed)
+    }
+
     fn process_input(
         &mut self,
         key: crossterm::event::KeyEvent,
@@ -0,0 +1,128 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+use td_lib::database::TagColor;
+
+use crate::{
+    keybinds::*,
+    ui::{
+        constants::{tag_style, LIST_HIGHLIGHT_STYLE, LIST_STYLE, MIN_MODAL_WIDTH},
+        Action, AppState, Component, FrameLocalStorage,
+    },
+    utils::RectExt,
+};
+
+/// A modal that picks the color of a tag, showing every color as tags look in it.
+pub struct TagColorModal {
+    /// The tag whose color is picked, if the modal is open.
+    tag: Option<String>,
+    index: usize,
+}
+
+impl TagColorModal {
+    pub fn new() -> Self {
+        Self {
+            tag: None,
+            index: 0,
+        }
+    }
+
+    /// Gets the colors to choose from, starting with no color.
+    fn choices() -> impl Iterator<Item = Option<TagColor>> {
+        std::iter::once(None).chain(TagColor::ALL.map(Some))
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.tag.is_some()
+    }
+
+    /// Opens the modal for the given tag, with its current color selected.
+    pub fn open(&mut self, state: &AppState, tag: String) {
+        let color = state.database.tag_color(&tag);
+        self.index = Self::choices().position(|c| c == color).unwrap_or(0);
+        self.tag = Some(tag);
+    }
+
+    /// Closes the modal, returning the action that applies the selected color.
+    pub fn close(&mut self) -> Option<Action> {
+        let tag = self.tag.take()?;
+        let color = Self::choices().nth(self.index).flatten();
+        Some(Action::SetTagColor(tag, color))
+    }
+}
+
+impl Component for TagColorModal {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if !self.is_open() {
+            return;
+        }
+
+        frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, true);
+        frame_storage.register_keybind(KEYBIND_MODAL_SUBMIT, true);
+        frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+        frame_storage.lock_keybinds();
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        _state: &AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) {
+        let Some(tag) = &self.tag else {return;};
+
+        let title = format!("Color of '{tag}'");
+        let block = Block::default().title(title.clone()).borders(Borders::ALL);
+        let block_width = (title.chars().count() as u16)
+            .max(MIN_MODAL_WIDTH)
+            .min(area.width.saturating_sub(2));
+        let block_area = area.center_rect(block_width + 2, Self::choices().count() as u16 + 2);
+        frame.render_widget(Clear, block_area);
+
+        let items = Self::choices()
+            .map(|color| {
+                let name = color.map_or("no color", TagColor::name);
+                ListItem::new(Line::from(vec![
+                    Span::styled(tag.clone(), tag_style(color)),
+                    Span::raw(format!(" ({name})")),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(block)
+            .style(LIST_STYLE)
+            .highlight_style(LIST_HIGHLIGHT_STYLE);
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.index));
+        frame.render_stateful_widget(list, block_area, &mut list_state);
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        _state: &mut AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+
+        if KEYBIND_MODAL_CANCEL.is_match(key) {
+            self.tag = None;
+            true
+        } else if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            self.index = match key {
+                UpDownKey::Up => self.index.saturating_sub(1),
+                UpDownKey::Down => (self.index + 1).min(Self::choices().count() - 1),
+            };
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use predicates::Predicate;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+};
+mod color_picker;
+
+use td_lib::database::Task;
+
+use self::color_picker::TagColorModal;
+use super::{
+    burndown::{BurndownScope, BurndownView},
+    constants::{
+        tag_style, COMPLETED_TASK, FG_DIM, FG_WHITE, LIST_HIGHLIGHT_STYLE,
+        LIST_HIGHLIGHT_STYLE_DISABLED, LIST_STYLE, NO_STYLE, STARTED_TASK,
+    },
+    modal::{ConfirmationModal, TypedConfirmationModal},
+    Action, AppState, Component,
+};
+use crate::keybinds::*;
+
+/// A page that lists all tags in the database, and the tasks that belong to the selected tag.
+pub struct TagPage {
+    tag_index: usize,
+    task_index: usize,
+    selection_index: usize,
+    remove_tag_modal: ConfirmationModal,
+    /// Replaces [`Self::remove_tag_modal`] when the tag is used by many tasks.
+    remove_tag_typed_modal: TypedConfirmationModal,
+    /// The tag that is removed when the removal is confirmed.
+    tag_to_remove: Option<String>,
+    color_modal: TagColorModal,
+}
+
+impl TagPage {
+    pub fn new() -> Self {
+        Self {
+            tag_index: 0,
+            task_index: 0,
+            selection_index: 0,
+            remove_tag_modal: ConfirmationModal::new(String::new())
+                .with_title("Remove Tag".to_string()),
+            remove_tag_typed_modal: TypedConfirmationModal::new("Remove Tag".to_string()),
+            tag_to_remove: None,
+            color_modal: TagColorModal::new(),
+        }
+    }
+
+    /// Asks whether the given tag should be removed from all tasks that have it.
+    fn open_remove_tag(&mut self, state: &AppState, tag: String, count: usize) {
+        let summary = state.preview_changes(|db| _ = db.remove_tag(&tag));
+        let text = format!("Do you want to remove the tag '{tag}' from all tasks?\n\n{summary}");
+        if state.needs_typed_confirmation(count) {
+            self.remove_tag_typed_modal.open(text, count.to_string());
+        } else {
+            self.remove_tag_modal.set_text(text);
+            self.remove_tag_modal.open(false);
+        }
+        self.tag_to_remove = Some(tag);
+    }
+
+    fn get_tags(state: &AppState) -> Vec<(String, usize)> {
+        state
+            .database
+            .all_tags()
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect()
+    }
+
+    fn get_task_list(&self, state: &AppState) -> Vec<Task> {
+        let tags = Self::get_tags(state);
+        let Some((selected_tag, _)) = tags.get(self.tag_index) else {return vec![];};
+
+        let mut tasks = state
+            .database
+            .tasks_with_tag(selected_tag)
+            .into_iter()
+            .filter(|t| t.tags.iter().any(|tag| tag == selected_tag))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        tasks.sort_by_key(|t| t.time_created);
+        if !state.filters().sort_oldest_first {
+            tasks.reverse();
+        }
+
+        let predicate = state.get_task_filter_predicate();
+        tasks.retain(|t| predicate.eval(t));
+
+        tasks
+    }
+}
+
+impl Component for TagPage {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut super::FrameLocalStorage) {
+        self.remove_tag_modal
+            .pre_render(global_state, frame_storage);
+        self.remove_tag_typed_modal
+            .pre_render(global_state, frame_storage);
+        self.color_modal.pre_render(global_state, frame_storage);
+
+        if self.selection_index == 0 {
+            let tag_count = Self::get_tags(global_state).len();
+            frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, tag_count >= 2);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_PANE_TASKS, true);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_TOGGLE_ENCRYPTED, tag_count > 0);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_REMOVE_TAG, tag_count > 0);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_BURNDOWN, tag_count > 0);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_SET_COLOR, tag_count > 0);
+        }
+        if self.selection_index == 1 {
+            let task_list = self.get_task_list(global_state);
+            frame_storage.selected_task_id = task_list.get(self.task_index).map(|x| x.id().clone());
+
+            frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, task_list.len() >= 2);
+            frame_storage.register_keybind(KEYBIND_TAGPAGE_PANE_TAGS, true);
+        }
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppState,
+        frame_storage: &super::FrameLocalStorage,
+    ) {
+        let layout = Layout::default()
+            .constraints([Constraint::Percentage(33), Constraint::Percentage(67)])
+            .direction(Direction::Horizontal)
+            .split(area);
+
+        let tags_area = layout[0];
+        let tasks_area = layout[1];
+
+        // render tag list
+        let tags = Self::get_tags(state);
+        let tags_block = Block::default()
+            .title("Tags")
+            .style(if self.selection_index == 0 {
+                FG_WHITE
+            } else {
+                FG_DIM
+            })
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let tag_items = tags
+            .iter()
+            .map(|(tag, count)| {
+                let style = match state.database.tag_color(tag) {
+                    Some(color) => tag_style(Some(color)),
+                    None => NO_STYLE,
+                };
+                let mut spans = vec![
+                    Span::raw(format!("{count:>3} ")),
+                    Span::styled(tag.clone(), style),
+                ];
+                if state.database.encrypted_tags().contains(tag) {
+                    spans.push(Span::raw(" (encrypted)"));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+        let tag_list = List::new(tag_items)
+            .block(tags_block)
+            .highlight_style(if self.selection_index == 0 {
+                LIST_HIGHLIGHT_STYLE
+            } else {
+                LIST_HIGHLIGHT_STYLE_DISABLED
+            })
+            .style(LIST_STYLE);
+        let mut tag_list_state = ListState::default();
+        tag_list_state.select((!tags.is_empty()).then_some(self.tag_index));
+        frame.render_stateful_widget(tag_list, tags_area, &mut tag_list_state);
+
+        // render tasks for the selected tag
+        let tasks = self.get_task_list(state);
+        let tasks_block = Block::default()
+            .title(match tags.get(self.tag_index) {
+                Some((tag, _)) => format!("Tasks tagged '{tag}'"),
+                None => "Tasks".to_string(),
+            })
+            .style(if self.selection_index == 1 {
+                FG_WHITE
+            } else {
+                FG_DIM
+            })
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let task_items = tasks
+            .iter()
+            .map(|task| {
+                let style = if task.time_completed.is_some() {
+                    LIST_STYLE.patch(COMPLETED_TASK)
+                } else if task.time_started.is_some() {
+                    LIST_STYLE.patch(STARTED_TASK)
+                } else {
+                    LIST_STYLE
+                };
+                ListItem::new(Line::from(Span::styled(task.title.clone(), style)))
+            })
+            .collect::<Vec<_>>();
+        let task_list = List::new(task_items)
+            .block(tasks_block)
+            .highlight_style(if self.selection_index == 1 {
+                LIST_HIGHLIGHT_STYLE
+            } else {
+                LIST_HIGHLIGHT_STYLE_DISABLED
+            })
+            .style(LIST_STYLE);
+        let mut task_list_state = ListState::default();
+        task_list_state
+            .select((self.selection_index == 1 && !tasks.is_empty()).then_some(self.task_index));
+        frame.render_stateful_widget(task_list, tasks_area, &mut task_list_state);
+
+        self.remove_tag_modal
+            .render(frame, area, state, frame_storage);
+        self.remove_tag_typed_modal
+            .render(frame, area, state, frame_storage);
+        self.color_modal.render(frame, area, state, frame_storage);
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        frame_storage: &super::FrameLocalStorage,
+    ) -> bool {
+        if self.remove_tag_modal.is_open() || self.remove_tag_typed_modal.is_open() {
+            let handled = self
+                .remove_tag_modal
+                .process_input(key, state, frame_storage)
+                || self
+                    .remove_tag_typed_modal
+                    .process_input(key, state, frame_storage);
+            if !handled && KEYBIND_MODAL_SUBMIT.is_match(key) {
+                let confirmed = match self.remove_tag_modal.is_open() {
+                    true => self.remove_tag_modal.close(),
+                    false => self.remove_tag_typed_modal.close(),
+                };
+                if let Some(tag) = self.tag_to_remove.take().filter(|_| confirmed) {
+                    state.dispatch(Action::RemoveTag(tag));
+                }
+            }
+            // the modal must block all other input
+            return true;
+        }
+        if self.color_modal.is_open() {
+            let handled = self.color_modal.process_input(key, state, frame_storage);
+            if !handled && KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(action) = self.color_modal.close() {
+                    state.dispatch(action);
+                }
+            }
+            return true;
+        }
+
+        // safety checks, tags and tasks may have disappeared since the last frame
+        self.tag_index = self
+            .tag_index
+            .min(Self::get_tags(state).len().saturating_sub(1));
+        self.task_index = self
+            .task_index
+            .min(self.get_task_list(state).len().saturating_sub(1));
+
+        if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            let (index, count) = if self.selection_index == 0 {
+                (&mut self.tag_index, Self::get_tags(state).len())
+            } else {
+                let count = self.get_task_list(state).len();
+                (&mut self.task_index, count)
+            };
+
+            match key {
+                UpDownKey::Up => *index = index.saturating_sub(1),
+                UpDownKey::Down => {
+                    if count != 0 && *index < count - 1 {
+                        *index += 1;
+                    }
+                }
+            }
+
+            // a different tag means a different task list
+            if self.selection_index == 0 {
+                self.task_index = 0;
+            }
+
+            true
+        } else if self.selection_index == 0 && KEYBIND_TAGPAGE_TOGGLE_ENCRYPTED.is_match(key) {
+            if let Some((tag, _)) = Self::get_tags(state).get(self.tag_index) {
+                state.toggle_tag_encrypted(tag);
+            }
+            true
+        } else if self.selection_index == 0 && KEYBIND_TAGPAGE_REMOVE_TAG.is_match(key) {
+            if let Some((tag, count)) = Self::get_tags(state).get(self.tag_index).cloned() {
+                self.open_remove_tag(state, tag, count);
+            }
+            true
+        } else if self.selection_index == 0 && KEYBIND_TAGPAGE_BURNDOWN.is_match(key) {
+            if let Some((tag, _)) = Self::get_tags(state).get(self.tag_index).cloned() {
+                state.push_view(BurndownView::new(BurndownScope::Tag(tag)));
+            }
+            true
+        } else if self.selection_index == 0 && KEYBIND_TAGPAGE_SET_COLOR.is_match(key) {
+            if let Some((tag, _)) = Self::get_tags(state).get(self.tag_index).cloned() {
+                self.color_modal.open(state, tag);
+            }
+            true
+        } else if KEYBIND_TAGPAGE_PANE_TAGS.is_match(key) {
+            self.selection_index = 0;
+            true
+        } else if KEYBIND_TAGPAGE_PANE_TASKS.is_match(key) {
+            self.selection_index = 1;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use predicates::{
+    prelude::{predicate, PredicateBooleanExt},
+    BoxPredicate, PredicateBoxExt,
+};
+use serde::{Deserialize, Serialize};
+use td_lib::{
+    database::{Database, Task, TaskId},
+    query::HiddenTasks,
+    sort::SortMode,
+    time::OffsetDateTime,
+};
+
+use crate::utils::MapPredicate;
+
+/// Identifies one of the task lists in the [`AppState`](super::AppState). The main list, which
+/// is always open, has id 0.
+pub type TaskListId = usize;
+
+/// How a task list filters and sorts the tasks. Every task list tab has its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskFilters {
+    pub sort_mode: SortMode,
+    /// Whether older tasks come first, among tasks that are equal in [`Self::sort_mode`].
+    pub sort_oldest_first: bool,
+    pub filter_completed: bool,
+    pub filter_archived: bool,
+    pub filter_unactionable: bool,
+    #[serde(skip)]
+    pub filter_search: bool,
+    /// Whether searching also covers the tasks that are hidden by [`Self::filter_completed`] and
+    /// [`Self::filter_archived`].
+    pub search_hidden: bool,
+    /// The project whose tasks are shown, or `None` to show the tasks of all projects.
+    pub project: Option<String>,
+    /// The person whose tasks are shown, or `None` to show the tasks of everyone.
+    pub assignee: Option<String>,
+    /// When set, only the tasks that directly or indirectly depend on this task are shown, to see
+    /// what finishing it unblocks. This is not kept between sessions.
+    #[serde(skip)]
+    pub unblocks_filter: Option<TaskId>,
+    /// Whether only the tasks in the someday/maybe bucket are shown, instead of hiding them.
+    #[serde(skip)]
+    pub show_someday: bool,
+}
+
+impl TaskFilters {
+    /// Gets the predicate for the tasks that pass these filters in the given database. The hidden
+    /// tasks that `includes` returns `true` for are not filtered out, for searching.
+    pub fn predicate(
+        &self,
+        database: &Database,
+        includes: impl Fn(HiddenTasks) -> bool,
+    ) -> BoxPredicate<Task> {
+        let mut predicate = predicate::always().boxed();
+
+        if self.filter_completed && !includes(HiddenTasks::Completed) {
+            predicate = predicate
+                .and(predicate::function(|x: &Task| x.time_completed.is_none()))
+                .boxed();
+        }
+
+        if self.filter_archived && !includes(HiddenTasks::Archived) {
+            predicate = predicate
+                .and(predicate::function(|x: &Task| x.time_archived.is_none()))
+                .boxed();
+        }
+
+        if self.show_someday {
+            predicate = predicate
+                .and(predicate::function(|x: &Task| x.is_someday()))
+                .boxed();
+        } else if !includes(HiddenTasks::Someday) {
+            predicate = predicate
+                .and(predicate::function(|x: &Task| !x.is_someday()))
+                .boxed();
+        }
+
+        // snoozed tasks come back on their own once their time arrives
+        if !includes(HiddenTasks::Snoozed) {
+            let now = OffsetDateTime::now_utc();
+            predicate = predicate
+                .and(predicate::function(move |x: &Task| !x.is_snoozed(now)))
+                .boxed();
+        }
+
+        if let Some(project) = self.project.clone() {
+            predicate = predicate
+                .and(predicate::function(move |x: &Task| {
+                    x.project.as_ref() == Some(&project)
+                }))
+                .boxed();
+        }
+
+        if let Some(assignee) = self.assignee.clone() {
+            predicate = predicate
+                .and(predicate::function(move |x: &Task| {
+                    x.assignee.as_ref() == Some(&assignee)
+                }))
+                .boxed();
+        }
+
+        if let Some(task_id) = &self.unblocks_filter {
+            let dependents = database
+                .transitive_dependents(task_id)
+                .map(|t| t.id().clone())
+                .collect::<HashSet<_>>();
+            let is_dependent =
+                MapPredicate::new(predicate::in_hash(dependents), |task: &Task| task.id());
+            predicate = predicate.and(is_dependent).boxed();
+        }
+
+        if self.filter_unactionable {
+            let tasks_with_uncompleted_dependencies = database
+                .dependency_stats()
+                .blocked_tasks()
+                .cloned()
+                .collect::<HashSet<_>>();
+
+            let has_uncompleted_dependencies =
+                predicate::in_hash(tasks_with_uncompleted_dependencies);
+
+            let has_uncompleted_dependencies =
+                MapPredicate::new(has_uncompleted_dependencies, |task: &Task| task.id());
+
+            predicate = predicate.and(has_uncompleted_dependencies.not()).boxed();
+        }
+
+        predicate
+    }
+
+    /// Gets a copy of these filters for a new task list, without the ones that only make sense
+    /// for what this list is doing right now.
+    pub fn duplicate(&self) -> Self {
+        Self {
+            filter_search: false,
+            unblocks_filter: None,
+            show_someday: false,
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for TaskFilters {
+    fn default() -> Self {
+        Self {
+            sort_mode: SortMode::Created,
+            sort_oldest_first: false,
+            filter_completed: true,
+            filter_archived: true,
+            filter_unactionable: false,
+            filter_search: false,
+            search_hidden: false,
+            project: None,
+            assignee: None,
+            unblocks_filter: None,
+            show_someday: false,
+        }
+    }
+}
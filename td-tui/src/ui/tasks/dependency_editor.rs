@@ -0,0 +1,303 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState},
+};
+use td_lib::database::{Task, TaskId};
+
+use crate::{
+    fuzzy::fuzzy_match,
+    keybinds::*,
+    ui::{
+        constants::{
+            COMPLETED_TASK, FG_DIM, FG_WHITE, LIST_HIGHLIGHT_STYLE, LIST_HIGHLIGHT_STYLE_DISABLED,
+            LIST_STYLE,
+        },
+        input::TextBoxComponent,
+        AppState, Component, FrameLocalStorage,
+    },
+    utils::RectExt,
+};
+
+/// A column of a [`DependencyEditModal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    /// The tasks the task depends on.
+    Dependencies,
+    /// The tasks that can be added as dependencies.
+    Others,
+}
+
+/// A modal that edits all dependencies of a task at once. The tasks it depends on are shown next
+/// to the other tasks, and tasks can be moved between them before the changes are applied.
+pub struct DependencyEditModal {
+    /// The task whose dependencies are edited, if the modal is open.
+    task: Option<TaskId>,
+    /// The dependencies the task has when the changes are applied.
+    dependencies: Vec<TaskId>,
+    /// Narrows down the other tasks.
+    filter_box: TextBoxComponent,
+    column: Column,
+    dependency_index: usize,
+    other_index: usize,
+}
+
+/// A task in the column of other tasks.
+struct Candidate<'a> {
+    task: &'a Task,
+    /// Whether the task depends on the edited task, so depending on it would create a cycle.
+    creates_cycle: bool,
+}
+
+impl DependencyEditModal {
+    pub fn new() -> Self {
+        Self {
+            task: None,
+            dependencies: vec![],
+            filter_box: TextBoxComponent::default(),
+            column: Column::Others,
+            dependency_index: 0,
+            other_index: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.task.is_some()
+    }
+
+    pub fn open(&mut self, state: &AppState, task_id: &TaskId) {
+        self.task = Some(task_id.clone());
+        self.dependencies = state
+            .database
+            .get_dependencies(task_id)
+            .map(|task| task.id().clone())
+            .collect();
+        self.filter_box = TextBoxComponent::new_focused().with_background(true);
+        self.column = Column::Others;
+        self.dependency_index = 0;
+        self.other_index = 0;
+    }
+
+    /// Closes the modal, returning the task and the dependencies it should have.
+    pub fn close(&mut self) -> Option<(TaskId, Vec<TaskId>)> {
+        let task = self.task.take()?;
+        Some((task, std::mem::take(&mut self.dependencies)))
+    }
+
+    /// Gets the tasks that the task will depend on, sorted by title.
+    fn dependency_rows<'a>(&self, state: &'a AppState) -> Vec<&'a Task> {
+        let mut rows = self
+            .dependencies
+            .iter()
+            .filter_map(|id| state.database.get_task(id))
+            .collect::<Vec<_>>();
+        rows.sort_by_cached_key(|task| task.title.to_lowercase());
+        rows
+    }
+
+    /// Gets the tasks that match the filter and can be added as dependencies, best matches and
+    /// open tasks first.
+    fn other_rows<'a>(&self, state: &'a AppState) -> Vec<Candidate<'a>> {
+        let Some(task_id) = &self.task else {return vec![];};
+        let dependents = state
+            .database
+            .transitive_dependents(task_id)
+            .map(|task| task.id().clone())
+            .collect::<HashSet<_>>();
+
+        let mut rows = state
+            .database
+            .get_all_tasks()
+            .filter(|task| task.id() != task_id && task.time_archived.is_none())
+            .filter(|task| !self.dependencies.contains(task.id()))
+            .filter_map(|task| {
+                let score = fuzzy_match(self.filter_box.text(), &task.title)?.score;
+                Some((score, task))
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_cached_key(|(score, task)| {
+            (
+                Reverse(*score),
+                task.time_completed.is_some(),
+                task.title.to_lowercase(),
+            )
+        });
+        rows.into_iter()
+            .map(|(_, task)| Candidate {
+                task,
+                creates_cycle: dependents.contains(task.id()),
+            })
+            .collect()
+    }
+
+    /// Moves the selected task to the other column, unless that would create a cycle.
+    fn toggle_selected(&mut self, state: &AppState) {
+        match self.column {
+            Column::Dependencies => {
+                let rows = self.dependency_rows(state);
+                let Some(task) = rows.get(self.dependency_index) else {return;};
+                let id = task.id().clone();
+                self.dependencies.retain(|dependency| *dependency != id);
+                self.dependency_index = self.dependency_index.min(rows.len().saturating_sub(2));
+            }
+            Column::Others => {
+                let rows = self.other_rows(state);
+                let Some(candidate) = rows.get(self.other_index) else {return;};
+                if candidate.creates_cycle {
+                    return;
+                }
+                self.dependencies.push(candidate.task.id().clone());
+                self.other_index = self.other_index.min(rows.len().saturating_sub(2));
+            }
+        }
+    }
+}
+
+impl Component for DependencyEditModal {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if !self.is_open() {
+            return;
+        }
+
+        self.filter_box.pre_render(global_state, frame_storage);
+        let rows = match self.column {
+            Column::Dependencies => self.dependency_rows(global_state).len(),
+            Column::Others => self.other_rows(global_state).len(),
+        };
+        frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, rows >= 2);
+        frame_storage.register_keybind(KEYBIND_DEPENDENCIES_TOGGLE, rows >= 1);
+        frame_storage.register_keybind(KEYBIND_DEPENDENCIES_SWITCH_COLUMN, true);
+        frame_storage.register_keybind(KEYBIND_DEPENDENCIES_APPLY, true);
+        frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+        frame_storage.lock_keybinds();
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let Some(task_id) = &self.task else {return;};
+
+        let title = state
+            .database
+            .get_task(task_id)
+            .map_or_else(String::new, |task| task.title.clone());
+        let block = Block::default()
+            .title(format!("Dependencies of '{title}'"))
+            .borders(Borders::ALL);
+        let block_area = area.center_rect(area.width * 4 / 5, area.height * 4 / 5);
+        let block_area_inner = block.inner(block_area);
+        frame.render_widget(Clear, block_area);
+        frame.render_widget(block, block_area);
+
+        let (filter_area, columns_area) = block_area_inner.split_y(TextBoxComponent::HEIGHT);
+        self.filter_box
+            .render(frame, filter_area, state, frame_storage);
+
+        let layout = Layout::default()
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .direction(Direction::Horizontal)
+            .split(columns_area);
+
+        let column = |column: Column, title: String, items: Vec<ListItem<'static>>| {
+            let active = self.column == column;
+            let block = Block::default()
+                .title(title)
+                .style(if active { FG_WHITE } else { FG_DIM })
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded);
+            List::new(items)
+                .block(block)
+                .style(LIST_STYLE)
+                .highlight_style(match active {
+                    true => LIST_HIGHLIGHT_STYLE,
+                    false => LIST_HIGHLIGHT_STYLE_DISABLED,
+                })
+        };
+        let item = |task: &Task, note: Option<&'static str>| {
+            let style = match task.time_completed {
+                Some(_) => LIST_STYLE.patch(COMPLETED_TASK),
+                None => LIST_STYLE,
+            };
+            let mut spans = vec![Span::styled(task.title.clone(), style)];
+            spans.extend(note.map(|note| Span::styled(note, FG_DIM)));
+            ListItem::new(Line::from(spans))
+        };
+
+        let dependencies = self.dependency_rows(state);
+        let list = column(
+            Column::Dependencies,
+            format!("Depends on ({})", dependencies.len()),
+            dependencies.iter().map(|task| item(task, None)).collect(),
+        );
+        let mut list_state = ListState::default();
+        list_state.select((!dependencies.is_empty()).then_some(self.dependency_index));
+        frame.render_stateful_widget(list, layout[0], &mut list_state);
+
+        let others = self.other_rows(state);
+        let list = column(
+            Column::Others,
+            "Other tasks".to_string(),
+            others
+                .iter()
+                .map(|c| item(c.task, c.creates_cycle.then_some(" (depends on this task)")))
+                .collect(),
+        );
+        let mut list_state = ListState::default();
+        list_state.select((!others.is_empty()).then_some(self.other_index));
+        frame.render_stateful_widget(list, layout[1], &mut list_state);
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+        if KEYBIND_MODAL_CANCEL.is_match(key) {
+            self.task = None;
+            return true;
+        }
+        // the parent applies the changes, and the filter box would take it as a character
+        if KEYBIND_DEPENDENCIES_APPLY.is_match(key) {
+            return false;
+        }
+
+        if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            let (index, rows) = match self.column {
+                Column::Dependencies => (&mut self.dependency_index, self.dependencies.len()),
+                Column::Others => {
+                    let rows = self.other_rows(state).len();
+                    (&mut self.other_index, rows)
+                }
+            };
+            *index = match key {
+                UpDownKey::Up => index.saturating_sub(1),
+                UpDownKey::Down => (*index + 1).min(rows.saturating_sub(1)),
+            };
+            true
+        } else if KEYBIND_DEPENDENCIES_SWITCH_COLUMN.is_match(key) {
+            self.column = match self.column {
+                Column::Dependencies => Column::Others,
+                Column::Others => Column::Dependencies,
+            };
+            true
+        } else if KEYBIND_DEPENDENCIES_TOGGLE.is_match(key) {
+            self.toggle_selected(state);
+            true
+        } else if self.filter_box.process_input(key, state, frame_storage) {
+            self.other_index = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use td_lib::database::{DependencyRelation, TaskId};
+
+use crate::{
+    keybinds::*,
+    ui::{
+        constants::{BOLD, MIN_MODAL_WIDTH},
+        input::TextBoxComponent,
+        Action, AppState, Component, FrameLocalStorage,
+    },
+    utils::RectExt,
+};
+
+/// A modal that edits the label and relation of a single dependency.
+pub struct DependencyPropertiesModal {
+    /// The dependent task and its dependency, if the modal is open.
+    edge: Option<(TaskId, TaskId)>,
+    relation: DependencyRelation,
+    label_box: TextBoxComponent,
+}
+
+impl DependencyPropertiesModal {
+    pub fn new() -> Self {
+        Self {
+            edge: None,
+            relation: DependencyRelation::default(),
+            label_box: TextBoxComponent::default(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.edge.is_some()
+    }
+
+    pub fn open(&mut self, state: &AppState, task: TaskId, dependency: TaskId) {
+        let Some(properties) = state.database.get_dependency(&task, &dependency) else {return;};
+        self.relation = properties.relation;
+        self.label_box = TextBoxComponent::new_focused()
+            .with_background(true)
+            .with_text(properties.label.clone().unwrap_or_default());
+        self.edge = Some((task, dependency));
+    }
+
+    /// Closes the modal, returning the action that applies the changes.
+    pub fn close(&mut self) -> Option<Action> {
+        let (task, dependency) = self.edge.take()?;
+        let label = Some(self.label_box.text().trim().to_string()).filter(|l| !l.is_empty());
+        Some(Action::EditDependency {
+            task,
+            dependency,
+            label,
+            relation: self.relation,
+        })
+    }
+}
+
+impl Component for DependencyPropertiesModal {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if !self.is_open() {
+            return;
+        }
+
+        self.label_box.pre_render(global_state, frame_storage);
+        frame_storage.register_keybind(KEYBIND_DEPENDENCY_CYCLE_RELATION, true);
+        frame_storage.register_keybind(KEYBIND_MODAL_SUBMIT, true);
+        frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+        frame_storage.lock_keybinds();
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let Some((task, dependency)) = &self.edge else {return;};
+        let (Some(task), Some(dependency)) = (
+            state.database.get_task(task),
+            state.database.get_task(dependency),
+        ) else {return;};
+
+        let title = format!("'{}' depends on '{}'", task.title, dependency.title);
+        let block = Block::default().title(title.clone()).borders(Borders::ALL);
+        let block_width = (title.chars().count() as u16)
+            .max(MIN_MODAL_WIDTH)
+            .min(area.width.saturating_sub(2));
+        let block_area = area.center_rect(block_width + 2, 4);
+        let block_area_inner = block.inner(block_area);
+        frame.render_widget(Clear, block_area);
+        frame.render_widget(block, block_area);
+
+        let (relation_area, label_area) = block_area_inner.split_y(1);
+        let relation = Line::from(vec![
+            Span::styled("Relation: ", BOLD),
+            Span::raw(self.relation.name()),
+        ]);
+        frame.render_widget(Paragraph::new(relation), relation_area);
+
+        let (label_title_area, label_box_area) = label_area.split_x(7);
+        frame.render_widget(
+            Paragraph::new(Span::styled("Label: ", BOLD)),
+            label_title_area,
+        );
+        self.label_box
+            .render(frame, label_box_area, state, frame_storage);
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if !self.is_open() {
+            return false;
+        }
+
+        if KEYBIND_MODAL_CANCEL.is_match(key) {
+            self.edge = None;
+            true
+        } else if KEYBIND_DEPENDENCY_CYCLE_RELATION.is_match(key) {
+            self.relation = self.relation.next();
+            true
+        } else {
+            self.label_box.process_input(key, state, frame_storage)
+        }
+    }
+}
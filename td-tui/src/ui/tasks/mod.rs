@@ -3,32 +3,59 @@ use ratatui::{
     widgets::{Block, BorderType, Borders},
 };
 
+use td_lib::database::TaskId;
+
 use self::{task_info::TaskInfoDisplay, task_list::TaskList, task_list_settings::TaskListSettings};
 use super::{
     constants::{FG_DIM, FG_LIGHT, FG_WHITE},
-    Component,
+    AppState, Component, TaskListId,
 };
 use crate::{keybinds::*, utils::RectExt};
 
+mod dependency_editor;
+mod dependency_properties;
+mod task_detail;
 mod task_info;
 mod task_list;
 mod task_list_settings;
 mod task_search;
+mod timeline;
+
+pub use task_detail::TaskDetailPage;
 
 pub struct TaskPage {
+    /// The task list in the [`AppState`] whose filters this page uses.
+    list_id: TaskListId,
     list: TaskList,
     settings: TaskListSettings,
     selection_index: usize,
 }
 
 impl TaskPage {
-    pub fn new() -> Self {
+    pub fn new(list_id: TaskListId) -> Self {
         Self {
+            list_id,
             list: TaskList::new(),
             selection_index: 0,
             settings: TaskListSettings::default(),
         }
     }
+
+    pub fn list_id(&self) -> TaskListId {
+        self.list_id
+    }
+
+    /// Searches the task list for the given query, or stops searching if it is empty.
+    pub fn search(&mut self, state: &mut AppState, query: &str) {
+        self.selection_index = 0;
+        self.list.search(state, query);
+    }
+
+    /// Selects the given task in the task list, if it is visible.
+    pub fn select_task(&mut self, state: &AppState, task_id: &TaskId) {
+        self.selection_index = 0;
+        self.list.select_task(state, task_id);
+    }
 }
 
 impl Component for TaskPage {
@@ -63,8 +90,26 @@ impl Component for TaskPage {
         let info_area = layout[1];
 
         // render task list
+        let filters = state.filters();
+        let unblocked_by = filters
+            .unblocks_filter
+            .as_ref()
+            .and_then(|task_id| state.database.get_task(task_id));
+        let title = match (unblocked_by, &filters.project) {
+            (Some(task), _) => format!("Tasks unblocked by '{}'", task.title),
+            (None, Some(project)) => format!("Tasks in {project}"),
+            (None, None) => "Tasks".to_string(),
+        };
+        let title = match &filters.assignee {
+            Some(assignee) => format!("{title} assigned to {assignee}"),
+            None => title,
+        };
+        let title = match filters.show_someday {
+            true => format!("{title} (someday/maybe)"),
+            false => title,
+        };
         let list_block = Block::default()
-            .title("Tasks")
+            .title(title)
             .style(if self.selection_index == 0 {
                 FG_WHITE
             } else {
@@ -107,6 +152,10 @@ impl Component for TaskPage {
         TaskInfoDisplay.render(frame, inner_task_info_area, state, frame_storage);
     }
 
+    fn on_tick(&mut self, state: &mut super::AppState) -> bool {
+        self.list.on_tick(state) | self.settings.on_tick(state)
+    }
+
     fn process_input(
         &mut self,
         key: crossterm::event::KeyEvent,
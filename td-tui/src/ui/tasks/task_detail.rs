@@ -0,0 +1,597 @@
+use std::collections::HashSet;
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use td_lib::{
+    database::{Tag, Task, TaskId},
+    reminders,
+    time::{format_description, OffsetDateTime, UtcOffset},
+};
+
+use super::{dependency_properties::DependencyPropertiesModal, task_info::dependency_note};
+use crate::{
+    keybinds::*,
+    ui::{
+        constants::*,
+        input::{MultilineTextBoxComponent, TextBoxComponent},
+        modal::ListSearchModal,
+        Action, AppState, Component, FrameLocalStorage, View,
+    },
+    utils::{format_duration, parse_duration, wrap_text},
+};
+
+/// A full-screen view of a single task, where each of its fields can be edited in place.
+pub struct TaskDetailPage {
+    task_id: TaskId,
+    focus: usize,
+    editor: Option<FieldEditor>,
+    /// Why the last edit could not be saved.
+    error: Option<&'static str>,
+    search_box_depend_on: ListSearchModal<TaskId>,
+    dependency_modal: DependencyPropertiesModal,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DetailField {
+    Title,
+    Description,
+    Tags,
+    Project,
+    Assignee,
+    Started,
+    Completed,
+    Due,
+    Snoozed,
+    Estimate,
+    Dependency(usize),
+}
+
+enum FieldEditor {
+    Line(TextBoxComponent),
+    Multiline(MultilineTextBoxComponent),
+}
+
+impl FieldEditor {
+    fn text(&self) -> &str {
+        match self {
+            Self::Line(textbox) => textbox.text(),
+            Self::Multiline(textbox) => textbox.text(),
+        }
+    }
+
+    fn height(&self, width: u16) -> u16 {
+        match self {
+            Self::Line(_) => TextBoxComponent::HEIGHT,
+            Self::Multiline(textbox) => textbox.text_wrapped(width).len().max(1) as u16,
+        }
+    }
+
+    fn component_mut(&mut self) -> &mut dyn Component {
+        match self {
+            Self::Line(textbox) => textbox,
+            Self::Multiline(textbox) => textbox,
+        }
+    }
+
+    fn component(&self) -> &dyn Component {
+        match self {
+            Self::Line(textbox) => textbox,
+            Self::Multiline(textbox) => textbox,
+        }
+    }
+}
+
+impl DetailField {
+    const EDITABLE: [Self; 10] = [
+        Self::Title,
+        Self::Description,
+        Self::Tags,
+        Self::Project,
+        Self::Assignee,
+        Self::Started,
+        Self::Completed,
+        Self::Due,
+        Self::Snoozed,
+        Self::Estimate,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Description => "Description",
+            Self::Tags => "Tags",
+            Self::Project => "Project",
+            Self::Assignee => "Assignee",
+            Self::Started => "Started",
+            Self::Completed => "Completed",
+            Self::Due => "Due",
+            Self::Snoozed => "Snoozed",
+            Self::Estimate => "Estimate",
+            Self::Dependency(0) => "Depends on",
+            Self::Dependency(_) => "",
+        }
+    }
+
+    fn undo_label(self, task: &Task) -> String {
+        match self {
+            Self::Title => format!("Rename '{}'", task.title),
+            Self::Description => format!("Edit description of '{}'", task.title),
+            Self::Tags => format!("Edit tags of '{}'", task.title),
+            Self::Project => format!("Move '{}' to another project", task.title),
+            Self::Assignee => format!("Assign '{}'", task.title),
+            Self::Started => format!("Edit start time of '{}'", task.title),
+            Self::Completed => format!("Edit completion time of '{}'", task.title),
+            Self::Due => format!("Edit due date of '{}'", task.title),
+            Self::Snoozed => format!("Snooze '{}'", task.title),
+            Self::Estimate => format!("Edit estimate of '{}'", task.title),
+            Self::Dependency(_) => unreachable!("dependencies are not edited as text"),
+        }
+    }
+}
+
+impl TaskDetailPage {
+    const LABEL_WIDTH: u16 = 13;
+    const DATE_FORMAT: &'static str = "[year]-[month]-[day] [hour]:[minute]";
+
+    pub fn new(task_id: TaskId) -> Self {
+        Self {
+            task_id,
+            focus: 0,
+            editor: None,
+            error: None,
+            search_box_depend_on: ListSearchModal::new(
+                "Choose which task to depend on".to_string(),
+            ),
+            dependency_modal: DependencyPropertiesModal::new(),
+        }
+    }
+
+    fn fields(&self, state: &AppState) -> Vec<DetailField> {
+        let dependency_count = state.database.get_dependencies(&self.task_id).count();
+        DetailField::EDITABLE
+            .into_iter()
+            .chain((0..dependency_count).map(DetailField::Dependency))
+            .collect()
+    }
+
+    fn focused_field(&self, state: &AppState) -> DetailField {
+        let fields = self.fields(state);
+        fields[self.focus.min(fields.len() - 1)]
+    }
+
+    fn get_dependency(&self, state: &AppState, index: usize) -> Option<TaskId> {
+        state
+            .database
+            .get_dependencies(&self.task_id)
+            .nth(index)
+            .map(|t| t.id().clone())
+    }
+
+    fn format_time(time: Option<OffsetDateTime>) -> String {
+        let format = format_description::parse(Self::DATE_FORMAT).expect("valid hardcoded format");
+        time.map(|time| {
+            time.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
+                .format(&format)
+                .unwrap()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Gets the text to edit a field with.
+    fn field_text(task: &Task, field: DetailField) -> String {
+        match field {
+            DetailField::Title => task.title.clone(),
+            DetailField::Description => task.description.clone(),
+            DetailField::Tags => task.tags.join(" "),
+            DetailField::Project => task.project.clone().unwrap_or_default(),
+            DetailField::Assignee => task.assignee.clone().unwrap_or_default(),
+            DetailField::Started => Self::format_time(task.time_started),
+            DetailField::Completed => Self::format_time(task.time_completed),
+            DetailField::Due => Self::format_time(task.time_due),
+            DetailField::Snoozed => Self::format_time(task.snoozed_until),
+            DetailField::Estimate => task.estimate.map(format_duration).unwrap_or_default(),
+            DetailField::Dependency(_) => unreachable!("dependencies are not edited as text"),
+        }
+    }
+
+    fn start_editing(&mut self, state: &AppState, field: DetailField) {
+        let Some(task) = state.database.get_task(&self.task_id) else {return;};
+
+        // an encrypted title can not be edited without overwriting it
+        if task.is_locked() && matches!(field, DetailField::Title | DetailField::Description) {
+            return;
+        }
+
+        let text = Self::field_text(task, field);
+        self.editor = Some(match field {
+            DetailField::Description => {
+                FieldEditor::Multiline(MultilineTextBoxComponent::new_focused().with_text(text))
+            }
+            _ => FieldEditor::Line(
+                TextBoxComponent::new_focused()
+                    .with_background(true)
+                    .with_text(text),
+            ),
+        });
+        self.error = None;
+    }
+
+    /// Saves the text in the editor to the focused field.
+    fn commit(&self, state: &mut AppState, field: DetailField) -> Result<(), &'static str> {
+        let Some(editor) = &self.editor else {return Ok(());};
+        let Some(task) = state.database.get_task(&self.task_id) else {return Ok(());};
+        let text = editor.text();
+
+        // nothing changed, so don't create an undo step
+        if text == Self::field_text(task, field) {
+            return Ok(());
+        }
+
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let parse_time = |text: &str| match text.trim() {
+            "" => Ok(None),
+            text => reminders::parse_time(text, now)
+                .map(Some)
+                .ok_or("Expected +30m, +2h, +1d or YYYY-MM-DD HH:MM"),
+        };
+
+        let label = field.undo_label(task);
+        let task_id = &self.task_id;
+        match field {
+            DetailField::Title => {
+                let title = text.trim();
+                if title.is_empty() {
+                    return Err("The title can not be empty");
+                }
+                state
+                    .database
+                    .modify(label, |db| db[task_id].title = title.to_string());
+            }
+            DetailField::Description => {
+                let description = text.trim();
+                state.database.modify(label, |db| {
+                    db[task_id].description = description.to_string();
+                });
+            }
+            DetailField::Tags => {
+                let mut seen = HashSet::new();
+                let tags = text
+                    .split_whitespace()
+                    .filter(|tag| seen.insert(*tag))
+                    .map(Tag::from)
+                    .collect();
+                state.database.modify(label, |db| db[task_id].tags = tags);
+            }
+            DetailField::Project => {
+                let project = Some(text.trim().to_string()).filter(|p| !p.is_empty());
+                state
+                    .database
+                    .modify(label, |db| db[task_id].project = project);
+            }
+            DetailField::Assignee => {
+                let assignee = Some(text.trim().to_string()).filter(|a| !a.is_empty());
+                state
+                    .database
+                    .modify(label, |db| db[task_id].assignee = assignee);
+            }
+            DetailField::Started => {
+                let time = parse_time(text)?;
+                state
+                    .database
+                    .modify(label, |db| db[task_id].time_started = time);
+            }
+            DetailField::Completed => {
+                let time = parse_time(text)?;
+                state
+                    .database
+                    .modify(label, |db| db[task_id].time_completed = time);
+            }
+            DetailField::Due => {
+                let time = parse_time(text)?;
+                state
+                    .database
+                    .modify(label, |db| db[task_id].time_due = time);
+            }
+            DetailField::Snoozed => {
+                let time = parse_time(text)?;
+                state
+                    .database
+                    .modify(label, |db| db[task_id].snoozed_until = time);
+            }
+            DetailField::Estimate => {
+                let estimate = match text.trim() {
+                    "" => None,
+                    text => Some(parse_duration(text).ok_or("Expected a duration such as 2h 30m")?),
+                };
+                state
+                    .database
+                    .modify(label, |db| db[task_id].estimate = estimate);
+            }
+            DetailField::Dependency(_) => unreachable!("dependencies are not edited as text"),
+        }
+
+        Ok(())
+    }
+
+    fn open_add_dependency_dialog(&mut self, state: &AppState) {
+        let existing_dependency_ids = state
+            .database
+            .get_dependencies(&self.task_id)
+            .map(|x| x.id().clone())
+            .collect::<HashSet<_>>();
+        let candidate_tasks = state
+            .database
+            .get_all_tasks()
+            .filter(|t| t.id() != &self.task_id)
+            .filter(|candidate| !existing_dependency_ids.contains(candidate.id()))
+            .map(|t| (t.id().clone(), t.title.clone()))
+            .collect();
+        self.search_box_depend_on.open(candidate_tasks);
+    }
+
+    fn field_value(&self, state: &AppState, task: &Task, field: DetailField) -> Vec<Span<'_>> {
+        let text = match field {
+            DetailField::Description if task.is_locked() => String::new(),
+            DetailField::Dependency(index) => {
+                return match self.get_dependency(state, index) {
+                    Some(dependency_id) => {
+                        let dependency = &state.database[&dependency_id];
+                        let note = state
+                            .database
+                            .get_dependency(&self.task_id, &dependency_id)
+                            .map(dependency_note)
+                            .unwrap_or_default();
+                        vec![
+                            if dependency.time_completed.is_some() {
+                                Span::styled(dependency.title.clone(), COMPLETED_TASK)
+                            } else {
+                                Span::raw(dependency.title.clone())
+                            },
+                            Span::styled(note, FG_DIM),
+                        ]
+                    }
+                    None => vec![],
+                };
+            }
+            _ => Self::field_text(task, field),
+        };
+
+        if text.is_empty() {
+            vec![Span::styled("none", FG_DIM.patch(ITALIC))]
+        } else {
+            vec![Span::raw(text)]
+        }
+    }
+}
+
+impl View for TaskDetailPage {
+    fn title(&self, state: &AppState) -> String {
+        match state.database.get_task(&self.task_id) {
+            Some(task) => task.title.clone(),
+            None => "Deleted task".to_string(),
+        }
+    }
+}
+
+impl Component for TaskDetailPage {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        frame_storage.selected_task_id = Some(self.task_id.clone());
+
+        if self.search_box_depend_on.is_open() {
+            self.search_box_depend_on
+                .pre_render(global_state, frame_storage);
+            return;
+        }
+
+        if self.dependency_modal.is_open() {
+            self.dependency_modal
+                .pre_render(global_state, frame_storage);
+            return;
+        }
+
+        if self.editor.is_some() {
+            frame_storage.register_keybind(KEYBIND_MODAL_SUBMIT, true);
+            frame_storage.register_keybind(KEYBIND_MODAL_CANCEL, true);
+            frame_storage.lock_keybinds();
+            return;
+        }
+
+        if global_state.database.get_task(&self.task_id).is_some() {
+            let is_dependency =
+                matches!(self.focused_field(global_state), DetailField::Dependency(_));
+            frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, true);
+            frame_storage.register_keybind(KEYBIND_DETAIL_EDIT, !is_dependency);
+            frame_storage.register_keybind(KEYBIND_DETAIL_OPEN_DEPENDENCY, is_dependency);
+            frame_storage.register_keybind(KEYBIND_DETAIL_REMOVE_DEPENDENCY, is_dependency);
+            frame_storage.register_keybind(KEYBIND_DETAIL_EDIT_DEPENDENCY, is_dependency);
+            frame_storage.register_keybind(KEYBIND_TASK_ADD_DEPENDENCY, true);
+        }
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let Some(task) = state.database.get_task(&self.task_id) else {
+            frame.render_widget(Paragraph::new("This task no longer exists"), area);
+            return;
+        };
+
+        let fields = self.fields(state);
+        let focus = self.focus.min(fields.len() - 1);
+        let value_width = area.width.saturating_sub(Self::LABEL_WIDTH);
+
+        let mut y = area.y;
+        for (index, field) in fields.into_iter().enumerate() {
+            // leave some room between the fields of the task and its dependencies
+            if field == DetailField::Dependency(0) {
+                y += 1;
+            }
+            if y >= area.bottom() {
+                break;
+            }
+
+            let is_focused = index == focus;
+            let editor = self.editor.as_ref().filter(|_| is_focused);
+            let value_lines = match (editor, field) {
+                (Some(_), _) => vec![],
+                (None, DetailField::Description)
+                    if !task.description.is_empty() && !task.is_locked() =>
+                {
+                    wrap_text(&task.description, value_width)
+                        .into_iter()
+                        .map(Line::raw)
+                        .collect()
+                }
+                (None, _) => vec![Line::from(self.field_value(state, task, field))],
+            };
+            let height = match editor {
+                Some(editor) => editor.height(value_width),
+                None => value_lines.len().max(1) as u16,
+            }
+            .min(area.bottom() - y);
+
+            let label_area = Rect::new(area.x, y, Self::LABEL_WIDTH.min(area.width), 1);
+            let value_area = Rect::new(area.x + label_area.width, y, value_width, height);
+
+            let label_style = if is_focused {
+                LIST_HIGHLIGHT_STYLE
+            } else {
+                BOLD
+            };
+            let label = match field.label() {
+                "" => String::new(),
+                label => format!("{label}:"),
+            };
+            frame.render_widget(Paragraph::new(Span::styled(label, label_style)), label_area);
+
+            match editor {
+                Some(editor) => editor
+                    .component()
+                    .render(frame, value_area, state, frame_storage),
+                None => frame.render_widget(Paragraph::new(value_lines), value_area),
+            }
+            y += height;
+
+            if let Some(error) = self.error.filter(|_| is_focused && y < area.bottom()) {
+                let error_area = Rect::new(value_area.x, y, value_width, 1);
+                frame.render_widget(Paragraph::new(Span::styled(error, FG_RED)), error_area);
+                y += 1;
+            }
+        }
+
+        // if needed, render popups
+        self.search_box_depend_on
+            .render(frame, frame.size(), state, frame_storage);
+        self.dependency_modal
+            .render(frame, frame.size(), state, frame_storage);
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        if self
+            .search_box_depend_on
+            .process_input(key, state, frame_storage)
+        {
+            return true;
+        }
+
+        if self.search_box_depend_on.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(dependency) = self.search_box_depend_on.close() {
+                    state.dispatch(Action::AddDependency {
+                        task: self.task_id.clone(),
+                        dependency,
+                    });
+                }
+                return true;
+            }
+            return false;
+        }
+
+        if self.dependency_modal.is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(action) = self.dependency_modal.close() {
+                    state.dispatch(action);
+                }
+                return true;
+            }
+            return self
+                .dependency_modal
+                .process_input(key, state, frame_storage);
+        }
+
+        if state.database.get_task(&self.task_id).is_none() {
+            return false;
+        }
+
+        let field = self.focused_field(state);
+        if let Some(editor) = &mut self.editor {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                match self.commit(state, field) {
+                    Ok(()) => {
+                        self.editor = None;
+                        self.error = None;
+                    }
+                    Err(error) => self.error = Some(error),
+                }
+                return true;
+            } else if KEYBIND_MODAL_CANCEL.is_match(key) {
+                self.editor = None;
+                self.error = None;
+                return true;
+            }
+
+            return editor
+                .component_mut()
+                .process_input(key, state, frame_storage);
+        }
+
+        if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            let field_count = self.fields(state).len();
+            self.focus = match key {
+                UpDownKey::Up => self.focus.min(field_count - 1).saturating_sub(1),
+                UpDownKey::Down => (self.focus + 1).min(field_count - 1),
+            };
+            true
+        } else if KEYBIND_TASK_ADD_DEPENDENCY.is_match(key) {
+            self.open_add_dependency_dialog(state);
+            true
+        } else if let DetailField::Dependency(index) = field {
+            let Some(dependency_id) = self.get_dependency(state, index) else {return false;};
+            if KEYBIND_DETAIL_OPEN_DEPENDENCY.is_match(key) {
+                state.push_view(Self::new(dependency_id));
+                true
+            } else if KEYBIND_DETAIL_REMOVE_DEPENDENCY.is_match(key) {
+                state.dispatch(Action::RemoveDependency {
+                    task: self.task_id.clone(),
+                    dependency: dependency_id,
+                });
+                true
+            } else if KEYBIND_DETAIL_EDIT_DEPENDENCY.is_match(key) {
+                let task_id = self.task_id.clone();
+                self.dependency_modal.open(state, task_id, dependency_id);
+                true
+            } else {
+                false
+            }
+        } else if KEYBIND_DETAIL_EDIT.is_match(key) {
+            self.start_editing(state, field);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -2,15 +2,102 @@ use ratatui::{
     text::{Line, Span},
     widgets::Paragraph,
 };
-use td_lib::time::{format_description, UtcOffset};
+use td_lib::{
+    database::{Database, DependencyRelation, TaskDependency, TaskId},
+    stats::TaskStats,
+    time::{format_description, OffsetDateTime, UtcOffset},
+};
 
-use crate::ui::{
-    constants::{BOLD, COMPLETED_TASK},
-    AppState, Component, FrameLocalStorage,
+use crate::{
+    ui::{
+        constants::{tag_style, BOLD, COMPLETED_TASK, FG_DIM, FG_GREEN, FG_RED, NO_STYLE},
+        AppState, Component, FrameLocalStorage,
+    },
+    utils::{format_duration, wrap_text},
 };
 
 pub struct TaskInfoDisplay;
 
+/// Describes in one sentence what is keeping the given task from being worked on, such as
+/// "Blocked by 2 tasks (nearest: 'set up CI')". The nearest task is an open direct dependency,
+/// preferring one that can be worked on right away. Returns `None` for completed tasks.
+fn blocked_by_sentence(database: &Database, task_id: &TaskId) -> Option<(String, bool)> {
+    if database[task_id].time_completed.is_some() {
+        return None;
+    }
+
+    let blocked_by = database
+        .transitive_dependencies(task_id)
+        .filter(|t| t.time_completed.is_none())
+        .count();
+    if blocked_by == 0 {
+        return Some(("Ready to work on".to_string(), false));
+    }
+
+    let open_dependencies = database
+        .get_dependencies(task_id)
+        .filter(|t| t.time_completed.is_none())
+        .collect::<Vec<_>>();
+    let nearest = open_dependencies
+        .iter()
+        .find(|t| database.is_actionable(t.id()))
+        .or(open_dependencies.first());
+    let tasks = match blocked_by {
+        1 => "1 task".to_string(),
+        n => format!("{n} tasks"),
+    };
+    let sentence = match nearest {
+        Some(nearest) => format!("Blocked by {tasks} (nearest: '{}')", nearest.title),
+        None => format!("Blocked by {tasks}"),
+    };
+    Some((sentence, true))
+}
+
+/// Describes how much work is left in the open tasks the given task directly or indirectly
+/// depends on, such as "5h 30m in 3 tasks (1 without estimate)". Returns `None` when all of them
+/// are completed.
+fn remaining_work_sentence(database: &Database, task_id: &TaskId) -> Option<String> {
+    let stats = TaskStats::from_tasks(database.transitive_dependencies(task_id));
+    let open = stats.todo + stats.started;
+    let tasks = match open {
+        0 => return None,
+        1 => "1 task".to_string(),
+        n => format!("{n} tasks"),
+    };
+
+    let sentence = match stats.unestimated {
+        0 => format!("{} in {tasks}", format_duration(stats.estimate_remaining)),
+        n if n == open => format!("{tasks} without estimate"),
+        n => format!(
+            "{} in {tasks} ({n} without estimate)",
+            format_duration(stats.estimate_remaining)
+        ),
+    };
+    Some(sentence)
+}
+
+/// Describes the properties of a dependency that are not the default, such as
+/// " (relates to: same area, added 2024-01-01)". Returns an empty string for plain dependencies.
+pub(super) fn dependency_note(dependency: &TaskDependency) -> String {
+    let date_format = format_description::parse("[year]-[month]-[day]").expect("valid format");
+    let mut parts = vec![];
+    match (dependency.relation, &dependency.label) {
+        (DependencyRelation::Blocks, None) => {}
+        (DependencyRelation::Blocks, Some(label)) => parts.push(label.clone()),
+        (relation, None) => parts.push(relation.name().to_string()),
+        (relation, Some(label)) => parts.push(format!("{}: {label}", relation.name())),
+    }
+    if let Some(created) = dependency.created {
+        let local = created.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+        parts.push(format!("added {}", local.format(&date_format).unwrap()));
+    }
+
+    match parts.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", parts.join(", ")),
+    }
+}
+
 impl Component for TaskInfoDisplay {
     fn render(
         &self,
@@ -33,14 +120,37 @@ impl Component for TaskInfoDisplay {
             .time_created
             .to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
 
-        // show useful info
-        let mut spans = vec![
+        // show useful info, starting with whether the task can be worked on
+        let mut spans = vec![];
+        if let Some((sentence, is_blocked)) = blocked_by_sentence(&state.database, &task_id) {
+            let style = if is_blocked { FG_RED } else { FG_GREEN };
+            spans.extend([Line::styled(sentence, style), Line::default()]);
+        }
+        spans.extend([
             Line::from(vec![Span::styled("Name: ", BOLD), Span::raw(&task.title)]),
+            Line::from(vec![
+                Span::styled("ID: ", BOLD),
+                Span::raw(state.database.short_id(&task_id)),
+            ]),
             Line::from(vec![
                 Span::styled("Created: ", BOLD),
                 Span::raw(time_local.format(&date_format).unwrap()),
             ]),
-        ];
+        ]);
+
+        if let Some(project) = &task.project {
+            spans.push(Line::from(vec![
+                Span::styled("Project: ", BOLD),
+                Span::raw(project),
+            ]));
+        }
+
+        if let Some(assignee) = &task.assignee {
+            spans.push(Line::from(vec![
+                Span::styled("Assignee: ", BOLD),
+                Span::raw(assignee),
+            ]));
+        }
 
         if let Some(started_at) = &task.time_started {
             let time_local =
@@ -60,15 +170,110 @@ impl Component for TaskInfoDisplay {
             ]));
         }
 
+        if let Some(archived_at) = &task.time_archived {
+            let time_local =
+                archived_at.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+            spans.push(Line::from(vec![
+                Span::styled("Archived: ", BOLD),
+                Span::raw(time_local.format(&date_format).unwrap()),
+            ]));
+        }
+
+        if let Some(due_at) = &task.time_due {
+            let time_local =
+                due_at.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+            spans.push(Line::from(vec![
+                Span::styled("Due: ", BOLD),
+                Span::raw(time_local.format(&date_format).unwrap()),
+            ]));
+        }
+
+        let now = OffsetDateTime::now_utc();
+        if let Some(snoozed_until) = task.snoozed_until.filter(|_| task.is_snoozed(now)) {
+            let time_local = snoozed_until
+                .to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+            spans.push(Line::from(vec![
+                Span::styled("Snoozed until: ", BOLD),
+                Span::raw(time_local.format(&date_format).unwrap()),
+            ]));
+        }
+
+        if let Some(reminder) = task.reminders.iter().find(|time| **time > now) {
+            let time_local =
+                reminder.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+            spans.push(Line::from(vec![
+                Span::styled("Next reminder: ", BOLD),
+                Span::raw(time_local.format(&date_format).unwrap()),
+            ]));
+        }
+
+        if let Some(estimate) = task.estimate {
+            spans.push(Line::from(vec![
+                Span::styled("Estimate: ", BOLD),
+                Span::raw(format_duration(estimate)),
+            ]));
+        }
+
+        // gauge how big the work behind this task really is
+        if let Some(sentence) = remaining_work_sentence(&state.database, &task_id) {
+            spans.push(Line::from(vec![
+                Span::styled("Remaining in dependencies: ", BOLD),
+                Span::raw(sentence),
+            ]));
+        }
+
+        if !task.work_sessions.is_empty() {
+            let mut text = format_duration(task.time_worked(now));
+            if task.is_clocked_in() {
+                text.push_str(" (clocked in)");
+            }
+            spans.push(Line::from(vec![
+                Span::styled("Time worked: ", BOLD),
+                Span::raw(text),
+            ]));
+        }
+
+        if state.database.is_confidential(task) {
+            spans.push(Line::from(vec![
+                Span::styled("Encrypted: ", BOLD),
+                Span::raw(match task.is_locked() {
+                    true => "yes, locked",
+                    false => "yes",
+                }),
+            ]));
+        }
+
+        // add description
+        if !task.description.is_empty() && !task.is_locked() {
+            spans.push(Line::default());
+            spans.extend(
+                wrap_text(&task.description, area.width)
+                    .into_iter()
+                    .map(Line::raw),
+            );
+        }
+
         // add tags
         if !task.tags.is_empty() {
             spans.extend([Line::default(), Line::from(Span::styled("Tags:", BOLD))]);
 
-            spans.extend(
-                task.tags
-                    .iter()
-                    .map(|tag| Line::from(vec![Span::raw("- "), Span::raw(tag)])),
-            );
+            spans.extend(task.tags.iter().map(|tag| {
+                // uncolored tags are shown as plain text here, they don't need to stand out
+                let style = match state.database.tag_color(tag) {
+                    Some(color) => tag_style(Some(color)),
+                    None => NO_STYLE,
+                };
+                Line::from(vec![Span::raw("- "), Span::styled(tag.as_str(), style)])
+            }));
+        }
+
+        // add metadata
+        if !task.metadata.is_empty() {
+            spans.extend([Line::default(), Line::from(Span::styled("Metadata:", BOLD))]);
+
+            spans.extend(task.metadata.iter().map(|(key, value)| {
+                Line::from(vec![Span::raw(format!("- {key}: ")), Span::raw(value)])
+            }));
         }
 
         // add dependencies
@@ -80,6 +285,11 @@ impl Component for TaskInfoDisplay {
             ]);
 
             spans.extend(dependencies.map(|task| {
+                let note = state
+                    .database
+                    .get_dependency(&task_id, task.id())
+                    .map(dependency_note)
+                    .unwrap_or_default();
                 Line::from(vec![
                     Span::raw("- "),
                     if task.time_completed.is_some() {
@@ -87,6 +297,7 @@ impl Component for TaskInfoDisplay {
                     } else {
                         Span::raw(&task.title)
                     },
+                    Span::styled(note, FG_DIM),
                 ])
             }));
         }
@@ -111,6 +322,128 @@ impl Component for TaskInfoDisplay {
             }));
         }
 
+        // show what this task is holding up, and what is holding it up the longest
+        let blocked_count = state
+            .database
+            .transitive_dependents(&task_id)
+            .filter(|task| task.time_completed.is_none())
+            .count();
+        if blocked_count > 0 {
+            spans.extend([
+                Line::default(),
+                Line::from(vec![
+                    Span::styled("Blocks: ", BOLD),
+                    Span::raw(format!("{blocked_count} tasks transitively")),
+                ]),
+            ]);
+        }
+
+        let critical_path = state.database.critical_path(&task_id);
+        if critical_path.len() >= 2 {
+            spans.extend([
+                Line::default(),
+                Line::from(Span::styled("Longest blocking chain:", BOLD)),
+            ]);
+            spans.extend(
+                critical_path
+                    .iter()
+                    .enumerate()
+                    .map(|(i, task)| Line::raw(format!("{}. {}", i + 1, task.title))),
+            );
+        }
+
         frame.render_widget(Paragraph::new(spans), area);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use td_lib::{database::Task, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn describes_what_blocks_a_task() {
+        let mut db = Database::default();
+        let tasks = ["release", "tests", "set up CI", "done"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [release, tests, ci, done] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&release, &tests);
+        db.add_dependency(&release, &ci);
+        db.add_dependency(&release, &done);
+        db.add_dependency(&tests, &ci);
+        db[&done].time_completed = Some(db[&done].time_created);
+
+        let sentence = blocked_by_sentence(&db, &release);
+        assert_eq!(
+            sentence,
+            Some((
+                "Blocked by 2 tasks (nearest: 'set up CI')".to_string(),
+                true
+            ))
+        );
+        assert_eq!(
+            blocked_by_sentence(&db, &ci),
+            Some(("Ready to work on".to_string(), false))
+        );
+        assert_eq!(blocked_by_sentence(&db, &done), None);
+    }
+
+    #[test]
+    fn sums_remaining_work_of_dependencies() {
+        let mut db = Database::default();
+        let tasks = ["goal", "design", "build", "polish", "done"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [goal, design, build, polish, done] = tasks.each_ref().map(|t| t.id().clone());
+        for task in tasks {
+            db.add_task(task);
+        }
+        db.add_dependency(&goal, &build);
+        db.add_dependency(&goal, &done);
+        db.add_dependency(&build, &design);
+        db[&goal].estimate = Some(Duration::hours(8));
+        db[&design].estimate = Some(Duration::hours(2));
+        db[&build].estimate = Some(Duration::minutes(90));
+        db[&done].estimate = Some(Duration::hours(4));
+        db[&done].time_completed = Some(db[&done].time_created);
+
+        assert_eq!(
+            remaining_work_sentence(&db, &goal).as_deref(),
+            Some("3h 30m in 2 tasks")
+        );
+        assert_eq!(remaining_work_sentence(&db, &design), None);
+
+        db.add_dependency(&build, &polish);
+        assert_eq!(
+            remaining_work_sentence(&db, &goal).as_deref(),
+            Some("3h 30m in 3 tasks (1 without estimate)")
+        );
+        db[&design].estimate = None;
+        db[&build].estimate = None;
+        assert_eq!(
+            remaining_work_sentence(&db, &goal).as_deref(),
+            Some("3 tasks without estimate")
+        );
+    }
+
+    #[test]
+    fn describes_dependency_properties() {
+        assert_eq!(dependency_note(&TaskDependency::default()), "");
+
+        let mut dependency = TaskDependency {
+            label: Some("same area".into()),
+            ..Default::default()
+        };
+        assert_eq!(dependency_note(&dependency), " (same area)");
+
+        dependency.relation = DependencyRelation::RelatesTo;
+        assert_eq!(dependency_note(&dependency), " (relates to: same area)");
+
+        dependency.label = None;
+        dependency.relation = DependencyRelation::Duplicates;
+        assert_eq!(dependency_note(&dependency), " (duplicates)");
+    }
+}
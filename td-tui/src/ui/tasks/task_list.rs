@@ -1,94 +1,262 @@
-use std::collections::HashSet;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+};
 
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 use predicates::prelude::*;
 use ratatui::{
     layout::Rect,
     text::{Line, Span},
-    widgets::{List, ListItem, ListState},
+    widgets::{List, ListItem, ListState, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 use td_lib::{
-    database::{Task, TaskId},
+    database::{DependencyCounts, Task, TaskId},
+    import::todotxt,
+    links::TaskLink,
+    reminders,
+    sort::SortMode,
     time::OffsetDateTime,
 };
 
-use super::task_search::TaskSearchBarComponent;
+use super::{
+    dependency_editor::DependencyEditModal, task_detail::TaskDetailPage,
+    task_search::TaskSearchBarComponent, timeline::TimelineView,
+};
 use crate::{
+    clipboard,
+    fuzzy::highlight_spans,
     keybinds::*,
     ui::{
+        burndown::{BurndownScope, BurndownView},
         component_collection::{CollectionKey, ComponentCollection},
         constants::*,
         modal::*,
-        AppState, Component, FrameLocalStorage,
+        Action, AppState, Component, FrameLocalStorage, TaskFilters,
     },
-    utils::RectExt,
+    utils::{format_duration, parse_duration, render_qr_code, scroll_offset, RectExt},
 };
 
 pub struct TaskList {
     focus: TaskListFocus,
+    /// The selected task. It stays selected when the list changes around it.
+    selected_id: RefCell<Option<TaskId>>,
+    /// Where the selected task was in the list when it was last found.
+    selected_index: Cell<usize>,
+    /// The tasks around the selected task when it was last found, nearest first. If the selected
+    /// task disappears, the nearest of these that is still visible is selected instead.
+    neighbors: RefCell<Vec<TaskId>>,
+    /// The index of the first visible task, as of the last render.
+    offset: Cell<usize>,
+    /// How many tasks fit in the list, as of the last render.
+    viewport_height: Cell<usize>,
+    /// The visible tasks as of the last time they were sorted and filtered.
+    cached_list: RefCell<Option<CachedList>>,
     search_bar: TaskSearchBarComponent,
     modals: ComponentCollection,
     create_task_modal: CollectionKey<TextInputModal>,
     new_tag_modal: CollectionKey<TextInputModal>,
+    add_reminder_modal: CollectionKey<TextInputModal>,
     rename_task_modal: CollectionKey<TextInputModal>,
+    estimate_modal: CollectionKey<TextInputModal>,
     delete_task_modal: CollectionKey<ConfirmationModal>,
+    /// Asks whether to copy the dependencies of a task that is being duplicated.
+    duplicate_task_modal: CollectionKey<ConfirmationModal>,
+    purge_archived_modal: CollectionKey<ConfirmationModal>,
+    /// Replaces [`Self::purge_archived_modal`] when many tasks would be purged.
+    purge_archived_typed_modal: CollectionKey<TypedConfirmationModal>,
+    import_path_modal: CollectionKey<TextInputModal>,
+    import_confirm_modal: CollectionKey<ConfirmationModal>,
+    pending_import: Vec<Task>,
     edit_modal: CollectionKey<KeybindSelectModal>,
+    suggestion_modal: CollectionKey<KeybindSelectModal>,
+    /// Asks what to do when completing a task that still has open dependencies.
+    complete_modal: CollectionKey<KeybindSelectModal>,
+    /// The task that is completed when [`Self::complete_modal`] is answered.
+    task_to_complete: Option<TaskId>,
+    /// Asks until when to snooze the selected task.
+    snooze_modal: CollectionKey<KeybindSelectModal>,
+    /// Asks for a time to snooze the selected task until, if none of the presets fit.
+    snooze_time_modal: CollectionKey<TextInputModal>,
+    suggestions: Vec<(SimpleKeybind, TaskId)>,
     search_box_depend_on: CollectionKey<ListSearchModal<TaskId>>,
+    /// Chooses a task that the selected task blocks, so it depends on the selected task.
+    search_box_blocks: CollectionKey<ListSearchModal<TaskId>>,
+    dependency_edit_modal: CollectionKey<DependencyEditModal>,
+    task_link_modal: CollectionKey<MessageModal>,
+    /// Chooses which project to show the tasks of, or `None` for all projects.
+    project_modal: CollectionKey<ListSearchModal<Option<String>>>,
+    assignee_modal: CollectionKey<ListSearchModal<Option<String>>>,
+}
+
+/// The visible tasks in the order they are shown, with the state they were found for. Sorting and
+/// filtering every frame is too slow for large databases.
+struct CachedList {
+    revision: u64,
+    filters: TaskFilters,
+    /// When the first snoozed task comes back, which changes the list without changing the
+    /// database or the filters.
+    valid_until: Option<OffsetDateTime>,
+    task_ids: Vec<TaskId>,
 }
 
 enum TaskListFocus {
     SearchBar,
-    Task(usize),
+    Task,
 }
 
 impl TaskList {
-    const SCROLL_PAGE_UP_DOWN: usize = 32;
+    const SUGGESTION_COUNT: usize = 5;
+    /// How many tasks on each side of the selected task are remembered as its neighbors.
+    const NEIGHBOR_COUNT: usize = 10;
 
     pub fn new() -> Self {
         let mut modal_collection = ComponentCollection::default();
         Self {
-            focus: TaskListFocus::Task(0),
+            focus: TaskListFocus::Task,
+            selected_id: RefCell::new(None),
+            selected_index: Cell::new(0),
+            neighbors: RefCell::new(vec![]),
+            offset: Cell::new(0),
+            viewport_height: Cell::new(0),
+            cached_list: RefCell::new(None),
             search_bar: TaskSearchBarComponent::default(),
             create_task_modal: modal_collection
                 .insert(TextInputModal::new("Create new task".to_string())),
             new_tag_modal: modal_collection.insert(TextInputModal::new("Add new tag".to_string())),
+            add_reminder_modal: modal_collection.insert(TextInputModal::new(
+                "Remind at (+30m, +2h, +1d or YYYY-MM-DD HH:MM)".to_string(),
+            )),
             rename_task_modal: modal_collection
                 .insert(TextInputModal::new("Rename task".to_string())),
+            estimate_modal: modal_collection.insert(TextInputModal::new(
+                "Estimate (such as 2h 30m, empty to clear)".to_string(),
+            )),
             delete_task_modal: modal_collection.insert(
                 ConfirmationModal::new("Do you want to delete this task?".to_string())
                     .with_title("Delete Task".to_string()),
             ),
+            duplicate_task_modal: modal_collection.insert(
+                ConfirmationModal::new("Should the copy have the same dependencies?".to_string())
+                    .with_title("Duplicate Task".to_string()),
+            ),
+            purge_archived_modal: modal_collection.insert(
+                ConfirmationModal::new(String::new())
+                    .with_title("Purge Archived Tasks".to_string()),
+            ),
+            purge_archived_typed_modal: modal_collection.insert(TypedConfirmationModal::new(
+                "Purge Archived Tasks".to_string(),
+            )),
+            import_path_modal: modal_collection
+                .insert(TextInputModal::new("Import from todo.txt file".to_string())),
+            import_confirm_modal: modal_collection.insert(
+                ConfirmationModal::new(String::new()).with_title("Import Tasks".to_string()),
+            ),
+            pending_import: vec![],
             edit_modal: modal_collection.insert(KeybindSelectModal::new("Select an action".into())),
+            suggestion_modal: modal_collection
+                .insert(KeybindSelectModal::new("Start working on...".into())),
+            complete_modal: modal_collection
+                .insert(KeybindSelectModal::new("Complete task".into())),
+            task_to_complete: None,
+            snooze_modal: modal_collection.insert(KeybindSelectModal::new("Snooze task".into())),
+            snooze_time_modal: modal_collection.insert(TextInputModal::new(
+                "Snooze until (+2h, +3d or YYYY-MM-DD HH:MM)".to_string(),
+            )),
+            suggestions: vec![],
             search_box_depend_on: modal_collection.insert(ListSearchModal::new(
                 "Choose which task to depend on".to_string(),
             )),
+            search_box_blocks: modal_collection.insert(ListSearchModal::new(
+                "Choose which task this blocks".to_string(),
+            )),
+            dependency_edit_modal: modal_collection.insert(DependencyEditModal::new()),
+            task_link_modal: modal_collection.insert(MessageModal::new("Link to task".into())),
+            project_modal: modal_collection.insert(ListSearchModal::new("Switch project".into())),
+            assignee_modal: modal_collection.insert(ListSearchModal::new("Switch assignee".into())),
             modals: modal_collection,
         }
     }
 
-    fn get_task_list(&self, state: &AppState) -> Vec<Task> {
-        let mut tasks = state.database.get_all_tasks().cloned().collect::<Vec<_>>();
+    /// Gets the visible tasks, in the order they are shown. They are borrowed from the database so
+    /// rendering the list does not need to copy them.
+    ///
+    /// Outside of searching, the order is only found again when the database or the filters
+    /// change, or when a snoozed task comes back.
+    fn get_task_list<'a>(&self, state: &'a AppState) -> Vec<&'a Task> {
+        let filters = state.filters();
+        if filters.filter_search {
+            return self.find_tasks(state);
+        }
 
-        // sort
-        tasks.sort_by(|a, b| a.time_created.cmp(&b.time_created));
-        if !state.sort_oldest_first {
-            tasks.reverse();
+        let now = OffsetDateTime::now_utc();
+        let revision = state.database.revision();
+        let mut cached_list = self.cached_list.borrow_mut();
+        if let Some(cached) = cached_list.as_ref().filter(|cached| {
+            cached.revision == revision
+                && cached.filters == *filters
+                && cached.valid_until.is_none_or(|until| now < until)
+        }) {
+            return cached
+                .task_ids
+                .iter()
+                .filter_map(|id| state.database.get_task(id))
+                .collect();
         }
 
-        // filter
-        tasks.retain(|x| state.get_task_filter_predicate().eval(x));
-        if state.filter_search {
-            tasks.retain(|t| self.search_bar.filter(t));
+        let tasks = self.find_tasks(state);
+        *cached_list = Some(CachedList {
+            revision,
+            filters: filters.clone(),
+            valid_until: state.database.next_snooze_end(now),
+            task_ids: tasks.iter().map(|t| t.id().clone()).collect(),
+        });
+        tasks
+    }
+
+    /// Sorts and filters the tasks for [`Self::get_task_list`].
+    fn find_tasks<'a>(&self, state: &'a AppState) -> Vec<&'a Task> {
+        let filters = state.filters();
+        let mut tasks = match filters.filter_search {
+            true => self.search_bar.search(state),
+            false => state.database.get_all_tasks().collect(),
+        };
+
+        // sort, keeping tasks that are equal in the sort mode in order of creation
+        tasks.sort_by_key(|t| t.time_created);
+        if !filters.sort_oldest_first {
+            tasks.reverse();
+        }
+        if filters.sort_mode != SortMode::Created {
+            filters.sort_mode.sort(&state.database, &mut tasks);
+        }
+        // what a task unblocks is shown in the order it gets unblocked
+        if filters.unblocks_filter.is_some() {
+            SortMode::Topological.sort(&state.database, &mut tasks);
         }
 
+        // filter, showing the hidden tasks that a search includes
+        let predicate = match filters.filter_search {
+            true => {
+                state.get_search_filter_predicate(|hidden| self.search_bar.includes(state, hidden))
+            }
+            false => state.get_task_filter_predicate(),
+        };
+        tasks.retain(|x| predicate.eval(x));
+
         tasks
     }
 
-    fn task_to_span(&self, state: &AppState, task: &Task) -> Line {
+    fn task_to_span<'a>(
+        &self,
+        state: &AppState,
+        task: &'a Task,
+        counts: DependencyCounts,
+    ) -> Line<'a> {
         let mut spans = vec![];
 
-        let dependents_count = state.database.get_inverse_dependencies(task.id()).count();
+        let dependents_count = counts.dependents;
         if dependents_count > 0 {
             spans.push(Span::styled(
                 format!("{:>2}⤣", dependents_count.to_string()),
@@ -96,12 +264,7 @@ impl TaskList {
             ));
         }
 
-        let unfullfilled_dependency_count = state
-            .database
-            .get_dependencies(task.id())
-            .filter(|t| t.time_completed.is_none())
-            .count();
-
+        let unfullfilled_dependency_count = counts.unfulfilled_dependencies;
         if unfullfilled_dependency_count > 0 {
             spans.push(Span::styled(
                 format!("{:>2}⤥", unfullfilled_dependency_count.to_string()),
@@ -114,53 +277,156 @@ impl TaskList {
         }
 
         // add title
-        let text_style = if task.time_completed.is_some() {
+        let text_style = if task.time_archived.is_some() {
+            LIST_STYLE.patch(ARCHIVED_TASK)
+        } else if task.time_completed.is_some() {
             LIST_STYLE.patch(COMPLETED_TASK)
         } else if task.time_started.is_some() {
             LIST_STYLE.patch(STARTED_TASK)
+        } else if state.is_newly_ready(task) {
+            LIST_STYLE.patch(READY_TASK)
         } else {
             LIST_STYLE
         };
-        spans.push(Span::styled(task.title.clone(), text_style));
+        match state
+            .filters()
+            .filter_search
+            .then(|| self.search_bar.get_match(task))
+            .flatten()
+        {
+            Some(search_match) => spans.extend(highlight_spans(
+                &task.title,
+                &search_match.indices,
+                text_style,
+                SEARCH_MATCH_STYLE,
+            )),
+            None => spans.push(Span::styled(task.title.as_str(), text_style)),
+        }
 
         // add tags
         for tag in &task.tags {
             spans.push(Span::raw(" "));
-            spans.push(Span::styled(tag.clone(), FG_DIM.patch(ITALIC)));
+            spans.push(Span::styled(
+                tag.as_str(),
+                tag_style(state.database.tag_color(tag)),
+            ));
         }
 
         spans.into()
     }
 
+    /// Selects the given task, if it is visible.
+    pub fn select_task(&mut self, state: &AppState, task_id: &TaskId) {
+        let tasks = self.get_task_list(state);
+        if let Some(index) = tasks.iter().position(|t| t.id() == task_id) {
+            self.select_index(&tasks, index);
+            self.set_focus(TaskListFocus::Task);
+        }
+    }
+
+    /// Finds the selected task in the given list. If it is not in the list anymore, such as after
+    /// it was deleted or filtered out, a task that was restored by the last undo or redo is
+    /// selected, or otherwise the nearest task around it that is still visible. If none of those
+    /// are visible either, the task that took its place is.
+    fn resolve_selection(&self, state: &AppState, tasks: &[impl ListedTask]) -> usize {
+        let position = |id: &TaskId| tasks.iter().position(|t| t.task_id() == id);
+        let selected = self.selected_id.borrow().as_ref().and_then(position);
+        let index = selected
+            .or_else(|| {
+                let changed = state.database.last_changed_tasks();
+                changed.iter().find_map(position)
+            })
+            .or_else(|| self.neighbors.borrow().iter().find_map(position))
+            .unwrap_or_else(|| self.selected_index.get().min(tasks.len().saturating_sub(1)));
+
+        // the neighbors only change when the list around the selected task changes
+        if selected != Some(self.selected_index.get()) {
+            self.select_index(tasks, index);
+        }
+        index
+    }
+
+    /// Selects the task at the given index in the list.
+    fn select_index(&self, tasks: &[impl ListedTask], index: usize) {
+        *self.selected_id.borrow_mut() = tasks.get(index).map(|t| t.task_id().clone());
+        self.selected_index.set(index);
+
+        // remember the tasks around it, alternating between the ones below and above it
+        let mut neighbors = self.neighbors.borrow_mut();
+        neighbors.clear();
+        for distance in 1..=Self::NEIGHBOR_COUNT {
+            let around = [index.checked_add(distance), index.checked_sub(distance)];
+            neighbors.extend(
+                around
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| tasks.get(i))
+                    .map(|t| t.task_id().clone()),
+            );
+        }
+    }
+
+    /// Searches for the given query, or stops searching if it is empty.
+    pub fn search(&mut self, state: &mut AppState, query: &str) {
+        state.filters_mut().filter_search = !query.is_empty();
+        self.search_bar.set_query(state, query);
+        self.set_focus(TaskListFocus::Task);
+    }
+
     fn set_focus(&mut self, value: TaskListFocus) {
         self.focus = value;
         match self.focus {
             TaskListFocus::SearchBar => self.search_bar.set_focus(true),
-            TaskListFocus::Task(_) => self.search_bar.set_focus(false),
+            TaskListFocus::Task => self.search_bar.set_focus(false),
         }
     }
 }
 
+/// An entry of the task list, which is either a task or only its id.
+trait ListedTask {
+    fn task_id(&self) -> &TaskId;
+}
+
+impl ListedTask for Task {
+    fn task_id(&self) -> &TaskId {
+        self.id()
+    }
+}
+
+impl ListedTask for &Task {
+    fn task_id(&self) -> &TaskId {
+        self.id()
+    }
+}
+
+impl ListedTask for TaskId {
+    fn task_id(&self) -> &TaskId {
+        self
+    }
+}
+
 impl Component for TaskList {
     fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
         match self.focus {
             TaskListFocus::SearchBar => {
                 // select top-most task if possible. it's better than having none selected
                 let task_list = self.get_task_list(global_state);
-                frame_storage.selected_task_id = task_list.get(0).map(|x| x.id().clone());
+                frame_storage.selected_task_id = task_list.first().map(|x| x.id().clone());
 
                 // NOTE: there should never be an open modal with the searchbar selected, but this
                 // makes sure that they would work if it happened regardless.
                 self.modals.pre_render(global_state, frame_storage);
+                self.search_bar.pre_render(global_state, frame_storage);
 
                 // show list navigation if there is at least 1 item to navigate to
                 frame_storage
                     .register_keybind(KEYBIND_CONTROLS_LIST_NAV_EXT, !task_list.is_empty());
                 frame_storage.register_keybind(KEYBIND_TASK_CLOSE_SEARCH, true);
             }
-            TaskListFocus::Task(task_index) => {
+            TaskListFocus::Task => {
                 // store currently selected task in frame storage
                 let task_list = self.get_task_list(global_state);
+                let task_index = self.resolve_selection(global_state, &task_list);
                 frame_storage.selected_task_id = task_list.get(task_index).map(|x| x.id().clone());
 
                 self.modals.pre_render(global_state, frame_storage);
@@ -168,15 +434,58 @@ impl Component for TaskList {
                 frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV_EXT, task_list.len() >= 2);
 
                 let is_task_selected = frame_storage.selected_task_id.is_some();
+                frame_storage.register_keybind(KEYBIND_TASK_OPEN_DETAILS, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_MARK_STARTED, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_MARK_DONE, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_CLOCK, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_NEW, true);
+                frame_storage.register_keybind(KEYBIND_TASK_IMPORT_TODOTXT, true);
+                frame_storage.register_keybind(KEYBIND_TASK_SUGGEST, true);
+                frame_storage.register_keybind(KEYBIND_TASK_PICK_RANDOM, !task_list.is_empty());
                 frame_storage.register_keybind(KEYBIND_TASK_DELETE, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_ADD_TAG, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_ADD_DEPENDENCY, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_ADD_BLOCKED, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_EDIT_DEPENDENCIES, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_ADD_REMINDER, is_task_selected);
                 frame_storage.register_keybind(KEYBIND_TASK_RENAME, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_SET_ESTIMATE, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_DUPLICATE, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_SHOW_LINK, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_COPY_ID, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_COPY_ID_AND_TITLE, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_TOGGLE_ENCRYPTED, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_ARCHIVE, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_SHOW_UNBLOCKED, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_TOGGLE_SOMEDAY, is_task_selected);
+                frame_storage.register_keybind(KEYBIND_TASK_SNOOZE, is_task_selected);
+                frame_storage.register_keybind(
+                    match global_state.filters().show_someday {
+                        true => KEYBIND_TASK_HIDE_SOMEDAY,
+                        false => KEYBIND_TASK_SHOW_SOMEDAY,
+                    },
+                    true,
+                );
+                frame_storage.register_keybind(
+                    KEYBIND_TASK_CLOSE_UNBLOCKED,
+                    global_state.filters().unblocks_filter.is_some(),
+                );
                 frame_storage.register_keybind(KEYBIND_TASK_EDIT, is_task_selected);
+                frame_storage.register_keybind(
+                    KEYBIND_TASK_PURGE_ARCHIVED,
+                    global_state
+                        .database
+                        .get_all_tasks()
+                        .any(|t| t.time_archived.is_some()),
+                );
                 frame_storage.register_keybind(KEYBIND_TASK_TOGGLE_SEARCH, true);
+                frame_storage.register_keybind(KEYBIND_TASK_SWITCH_PROJECT, true);
+                frame_storage.register_keybind(KEYBIND_TASK_SWITCH_ASSIGNEE, true);
+                frame_storage.register_keybind(KEYBIND_TASK_TIMELINE, !task_list.is_empty());
+                frame_storage.register_keybind(
+                    KEYBIND_TASK_PROJECT_BURNDOWN,
+                    global_state.filters().project.is_some(),
+                );
             }
         }
     }
@@ -192,7 +501,7 @@ impl Component for TaskList {
 
         let list_area;
 
-        if state.filter_search {
+        if state.filters().filter_search {
             list_area = area.skip_y(1);
 
             let search_area = area.take_y(1);
@@ -202,24 +511,47 @@ impl Component for TaskList {
             list_area = area;
         }
 
-        // render the list
+        let height = list_area.height as usize;
+        let selected = match self.focus {
+            TaskListFocus::Task => self.resolve_selection(state, &task_list),
+            TaskListFocus::SearchBar => 0,
+        };
+        let offset = scroll_offset(self.offset.get(), selected, height, task_list.len());
+        self.offset.set(offset);
+        self.viewport_height.set(height);
+
+        let list_area = if task_list.len() > height {
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(FG_DIM);
+            let mut scrollbar_state = ScrollbarState::new(task_list.len() - height)
+                .viewport_content_length(height)
+                .position(offset);
+            frame.render_stateful_widget(scrollbar, list_area, &mut scrollbar_state);
+            list_area.skip_last_x(1)
+        } else {
+            list_area
+        };
+
+        // only the rows that fit on screen are rendered, so long lists stay fast
+        let stats = state.database.dependency_stats();
         let list_items = task_list
             .iter()
-            .map(|t| ListItem::new(self.task_to_span(state, t)))
+            .skip(offset)
+            .take(height)
+            .map(|t| ListItem::new(self.task_to_span(state, t, stats.get(t.id()))))
             .collect::<Vec<_>>();
         let list = List::new(list_items)
-            .highlight_style(if matches!(self.focus, TaskListFocus::Task(_)) {
+            .highlight_style(if matches!(self.focus, TaskListFocus::Task) {
                 LIST_HIGHLIGHT_STYLE
             } else {
                 LIST_HIGHLIGHT_STYLE_DISABLED
             })
             .style(LIST_STYLE);
-        let mut list_state = ListState::default();
-        if let TaskListFocus::Task(task_index) = self.focus {
-            list_state.select((!task_list.is_empty()).then_some(task_index));
-        } else {
-            list_state.select((!task_list.is_empty()).then_some(0));
-        }
+
+        let mut list_state = ListState::default()
+            .with_selected((!task_list.is_empty()).then(|| selected.saturating_sub(offset)));
         frame.render_stateful_widget(list, list_area, &mut list_state);
 
         // if needed, render popups
@@ -238,29 +570,30 @@ impl Component for TaskList {
             return true;
         }
 
-        let tasks = self.get_task_list(state);
-
-        // safety checks
-        if let TaskListFocus::Task(task_index) = &mut self.focus {
-            if !tasks.is_empty() {
-                *task_index = (*task_index).clamp(0, tasks.len() - 1);
-            }
-        }
+        // only the ids are copied, the tasks are looked up because the database may be modified
+        let tasks = self
+            .get_task_list(state)
+            .into_iter()
+            .map(|t| t.id().clone())
+            .collect::<Vec<_>>();
 
         match self.focus {
             TaskListFocus::SearchBar => {
                 if KEYBIND_CONTROLS_LIST_NAV_EXT.get_match(key) == Some(UpDownExtendedKey::Down) {
-                    self.set_focus(TaskListFocus::Task(0));
+                    self.select_index(&tasks, 0);
+                    self.set_focus(TaskListFocus::Task);
                     true
                 } else if KEYBIND_TASK_CLOSE_SEARCH.is_match(key) {
-                    state.filter_search = false;
-                    self.set_focus(TaskListFocus::Task(0));
+                    state.filters_mut().filter_search = false;
+                    self.select_index(&tasks, 0);
+                    self.set_focus(TaskListFocus::Task);
                     true
                 } else {
                     self.search_bar.process_input(key, state, frame_storage)
                 }
             }
-            TaskListFocus::Task(task_index) => {
+            TaskListFocus::Task => {
+                let task_index = self.resolve_selection(state, &tasks);
                 if self.handle_modals(key, state, &tasks, task_index) {
                     return true;
                 }
@@ -268,37 +601,40 @@ impl Component for TaskList {
                 // take our own input
                 // start by checking actions that require a task to present
                 let handled_by_task = if !tasks.is_empty() {
-                    if KEYBIND_TASK_MARK_STARTED.is_match(key) {
-                        state.database.modify(|db| {
-                            let task = &mut db[tasks[task_index].id()];
-                            if task.time_started.is_none() {
-                                task.time_started = Some(
-                                    OffsetDateTime::now_local()
-                                        .unwrap_or_else(|_| OffsetDateTime::now_utc()),
-                                );
-                            } else {
-                                task.time_started = None;
-                            }
-                        });
-
+                    if KEYBIND_TASK_OPEN_DETAILS.is_match(key) {
+                        state.push_view(TaskDetailPage::new(tasks[task_index].clone()));
+                        true
+                    } else if KEYBIND_TASK_MARK_STARTED.is_match(key) {
+                        state.dispatch(Action::ToggleStarted(tasks[task_index].clone()));
                         true
                     } else if KEYBIND_TASK_MARK_DONE.is_match(key) {
-                        state.database.modify(|db| {
-                            let task = &mut db[tasks[task_index].id()];
-                            if task.time_completed.is_none() {
-                                task.time_completed = Some(
-                                    OffsetDateTime::now_local()
-                                        .unwrap_or_else(|_| OffsetDateTime::now_utc()),
-                                );
-                            } else {
-                                task.time_completed = None;
-                            }
-                        });
-
+                        self.toggle_done(state, &tasks[task_index]);
+                        true
+                    } else if KEYBIND_TASK_CLOCK.is_match(key) {
+                        state.dispatch(Action::ToggleClockedIn(tasks[task_index].clone()));
+                        true
+                    } else if KEYBIND_TASK_ARCHIVE.is_match(key) {
+                        state.dispatch(Action::ToggleArchived(tasks[task_index].clone()));
+                        true
+                    } else if KEYBIND_TASK_SHOW_UNBLOCKED.is_match(key) {
+                        let task_id = tasks[task_index].clone();
+                        state.dispatch(Action::ShowUnblockedBy(Some(task_id)));
+                        true
+                    } else if KEYBIND_TASK_TOGGLE_SOMEDAY.is_match(key) {
+                        state.dispatch(Action::ToggleSomeday(tasks[task_index].clone()));
+                        true
+                    } else if KEYBIND_TASK_SNOOZE.is_match(key) {
+                        self.open_snooze_dialog(state, &tasks[task_index]);
                         true
                     } else if KEYBIND_TASK_RENAME.is_match(key) {
                         self.modals[self.rename_task_modal]
-                            .open_with_text(tasks[task_index].title.clone());
+                            .open_with_text(state.database[&tasks[task_index]].title.clone());
+                        true
+                    } else if KEYBIND_TASK_SET_ESTIMATE.is_match(key) {
+                        self.open_estimate_dialog(state, &tasks[task_index]);
+                        true
+                    } else if KEYBIND_TASK_DUPLICATE.is_match(key) {
+                        self.duplicate_task(state, &tasks[task_index]);
                         true
                     } else if KEYBIND_TASK_DELETE.is_match(key) {
                         self.modals[self.delete_task_modal].open(true);
@@ -315,12 +651,47 @@ impl Component for TaskList {
                         let modal = &mut self.modals[self.search_box_depend_on];
                         Self::open_add_dependency_dialog(modal, state, task_index, &tasks);
                         true
+                    } else if KEYBIND_TASK_ADD_BLOCKED.is_match(key) {
+                        let modal = &mut self.modals[self.search_box_blocks];
+                        Self::open_add_blocked_dialog(modal, state, task_index, &tasks);
+                        true
+                    } else if KEYBIND_TASK_EDIT_DEPENDENCIES.is_match(key) {
+                        let task_id = &tasks[task_index];
+                        self.modals[self.dependency_edit_modal].open(state, task_id);
+                        true
+                    } else if KEYBIND_TASK_ADD_REMINDER.is_match(key) {
+                        self.modals[self.add_reminder_modal].open();
+                        true
+                    } else if KEYBIND_TASK_SHOW_LINK.is_match(key) {
+                        self.open_task_link(state, &tasks[task_index]);
+                        true
+                    } else if KEYBIND_TASK_COPY_ID.is_match(key) {
+                        let short_id = state.database.short_id(&tasks[task_index]);
+                        Self::copy_to_clipboard(state, short_id.to_string());
+                        true
+                    } else if KEYBIND_TASK_COPY_ID_AND_TITLE.is_match(key) {
+                        let task = &state.database[&tasks[task_index]];
+                        let text = format!("{} {}", state.database.short_id(task.id()), task.title);
+                        Self::copy_to_clipboard(state, text);
+                        true
+                    } else if KEYBIND_TASK_TOGGLE_ENCRYPTED.is_match(key) {
+                        state.toggle_task_encrypted(&tasks[task_index]);
+                        true
                     } else if KEYBIND_TASK_EDIT.is_match(key) {
                         self.modals[self.edit_modal].open(vec![
                             KEYBIND_TASK_RENAME.clone(),
+                            KEYBIND_TASK_SET_ESTIMATE.clone(),
                             KEYBIND_TASK_DELETE.clone(),
+                            KEYBIND_TASK_DUPLICATE.clone(),
+                            KEYBIND_TASK_ARCHIVE.clone(),
                             KEYBIND_TASK_ADD_DEPENDENCY.clone(),
+                            KEYBIND_TASK_ADD_BLOCKED.clone(),
+                            KEYBIND_TASK_EDIT_DEPENDENCIES.clone(),
                             KEYBIND_TASK_ADD_TAG.clone(),
+                            KEYBIND_TASK_ADD_REMINDER.clone(),
+                            KEYBIND_TASK_SNOOZE.clone(),
+                            KEYBIND_TASK_SHOW_LINK.clone(),
+                            KEYBIND_TASK_TOGGLE_ENCRYPTED.clone(),
                         ]);
                         true
                     } else {
@@ -335,11 +706,71 @@ impl Component for TaskList {
                     || if KEYBIND_TASK_NEW.is_match(key) {
                         self.modals[self.create_task_modal].open();
                         true
+                    } else if KEYBIND_TASK_SWITCH_PROJECT.is_match(key) {
+                        self.open_project_switcher(state);
+                        true
+                    } else if KEYBIND_TASK_SWITCH_ASSIGNEE.is_match(key) {
+                        self.open_assignee_switcher(state);
+                        true
+                    } else if KEYBIND_TASK_PROJECT_BURNDOWN.is_match(key)
+                        && state.filters().project.is_some()
+                    {
+                        let project = state.filters().project.clone().unwrap_or_default();
+                        state.push_view(BurndownView::new(BurndownScope::Project(project)));
+                        true
+                    } else if KEYBIND_TASK_TIMELINE.is_match(key) && !tasks.is_empty() {
+                        state.push_view(TimelineView::new());
+                        true
+                    } else if KEYBIND_TASK_CLOSE_UNBLOCKED.is_match(key)
+                        && state.filters().unblocks_filter.is_some()
+                    {
+                        state.dispatch(Action::ShowUnblockedBy(None));
+                        true
+                    } else if KEYBIND_TASK_SHOW_SOMEDAY.is_match(key) {
+                        state.dispatch(Action::ShowSomeday(!state.filters().show_someday));
+                        true
+                    } else if KEYBIND_TASK_PICK_RANDOM.is_match(key) {
+                        // only pick from the tasks that are currently visible
+                        let visible = tasks.iter().collect::<HashSet<_>>();
+                        let picked = state
+                            .database
+                            .pick_random_task(&mut rand::thread_rng(), |t| {
+                                visible.contains(t.id())
+                            });
+                        if let Some(index) =
+                            picked.and_then(|picked| tasks.iter().position(|id| id == picked.id()))
+                        {
+                            self.select_index(&tasks, index);
+                        }
+                        true
+                    } else if KEYBIND_TASK_SUGGEST.is_match(key) {
+                        self.open_suggestions(state);
+                        true
+                    } else if KEYBIND_TASK_IMPORT_TODOTXT.is_match(key) {
+                        self.modals[self.import_path_modal].open();
+                        true
+                    } else if KEYBIND_TASK_PURGE_ARCHIVED.is_match(key) {
+                        let summary = state.preview_changes(|db| _ = db.take_archived());
+                        let archived_count = summary.tasks_removed;
+                        let text = format!(
+                            "Do you want to move all archived tasks to the archive file? This can \
+                             not be undone.\n\n{summary}"
+                        );
+                        if state.needs_typed_confirmation(archived_count) {
+                            self.modals[self.purge_archived_typed_modal]
+                                .open(text, archived_count.to_string());
+                        } else if archived_count > 0 {
+                            let modal = &mut self.modals[self.purge_archived_modal];
+                            modal.set_text(text);
+                            modal.open(false);
+                        }
+                        true
                     } else if KEYBIND_TASK_TOGGLE_SEARCH.is_match(key) {
-                        state.filter_search = !state.filter_search;
+                        let filters = state.filters_mut();
+                        filters.filter_search = !filters.filter_search;
 
                         // if we are turning *on* search, focus the search bar
-                        if state.filter_search {
+                        if filters.filter_search {
                             self.set_focus(TaskListFocus::SearchBar);
                         }
 
@@ -347,46 +778,36 @@ impl Component for TaskList {
                     } else if let Some(key) = KEYBIND_CONTROLS_LIST_NAV_EXT.get_match(key) {
                         // handle kb navigation
 
-                        if key == UpDownExtendedKey::Up && task_index == 0 && state.filter_search {
+                        if key == UpDownExtendedKey::Up
+                            && task_index == 0
+                            && state.filters().filter_search
+                        {
                             self.set_focus(TaskListFocus::SearchBar);
                             return true;
                         }
 
-                        let TaskListFocus::Task(task_index) = &mut self.focus else {unreachable!();};
-
-                        match key {
-                            UpDownExtendedKey::Up => {
-                                *task_index = task_index.saturating_sub(1);
-                                true
-                            }
-                            UpDownExtendedKey::Down => {
-                                if !tasks.is_empty() && *task_index != tasks.len() - 1 {
-                                    *task_index += 1;
-                                }
-                                true
-                            }
+                        let last_index = tasks.len().saturating_sub(1);
+                        let page = self.viewport_height.get().max(1);
+                        let new_index = match key {
+                            UpDownExtendedKey::Up => task_index.saturating_sub(1),
+                            UpDownExtendedKey::Down => (task_index + 1).min(last_index),
+                            // the viewport moves along by a page, so the selection stays in the
+                            // same place on screen
                             UpDownExtendedKey::PageUp => {
-                                *task_index = task_index.saturating_sub(Self::SCROLL_PAGE_UP_DOWN);
-                                true
+                                self.offset.set(self.offset.get().saturating_sub(page));
+                                task_index.saturating_sub(page)
                             }
                             UpDownExtendedKey::PageDown => {
-                                if !tasks.is_empty() && *task_index != tasks.len() - 1 {
-                                    *task_index += Self::SCROLL_PAGE_UP_DOWN;
-                                    *task_index = (*task_index).min(tasks.len() - 1);
-                                }
-                                true
-                            }
-                            UpDownExtendedKey::Home => {
-                                *task_index = 0;
-                                true
-                            }
-                            UpDownExtendedKey::End => {
-                                if !tasks.is_empty() && *task_index != tasks.len() - 1 {
-                                    *task_index = tasks.len() - 1;
+                                if task_index != last_index {
+                                    self.offset.set(self.offset.get() + page);
                                 }
-                                true
+                                (task_index + page).min(last_index)
                             }
-                        }
+                            UpDownExtendedKey::Home => 0,
+                            UpDownExtendedKey::End => last_index,
+                        };
+                        self.select_index(&tasks, new_index);
+                        true
                     } else {
                         false
                     }
@@ -396,11 +817,99 @@ impl Component for TaskList {
 }
 
 impl TaskList {
+    /// Duplicates the given task, first asking whether to copy its dependencies if it has any.
+    fn duplicate_task(&mut self, state: &mut AppState, task_id: &TaskId) {
+        if state.database.get_dependencies(task_id).next().is_some() {
+            self.modals[self.duplicate_task_modal].open(false);
+        } else {
+            state.dispatch(Action::DuplicateTask {
+                task: task_id.clone(),
+                with_dependencies: false,
+            });
+        }
+    }
+
+    /// Marks the given task as done or not done. When completing a task that still has open
+    /// dependencies, this first asks what to do with them.
+    fn toggle_done(&mut self, state: &mut AppState, task_id: &TaskId) {
+        let task = &state.database[task_id];
+        let open = match task.time_completed {
+            Some(_) => vec![],
+            None => state.database.open_dependencies(task_id),
+        };
+        if open.is_empty() {
+            state.dispatch(Action::ToggleDone(task_id.clone()));
+            return;
+        }
+
+        let text = format!(
+            "'{}' still depends on {} open {}:\n{}",
+            task.title,
+            open.len(),
+            if open.len() == 1 { "task" } else { "tasks" },
+            open.iter()
+                .map(|task| format!("- {}", task.title))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        self.modals[self.complete_modal].open_with_text(
+            text,
+            vec![
+                KEYBIND_COMPLETE_WITH_DEPENDENCIES.clone(),
+                KEYBIND_COMPLETE_GO_TO_DEPENDENCY.clone(),
+                KEYBIND_COMPLETE_ANYWAY.clone(),
+            ],
+        );
+        self.task_to_complete = Some(task_id.clone());
+    }
+
+    /// Asks how long the given task will take, starting from its current estimate.
+    fn open_estimate_dialog(&mut self, state: &AppState, task_id: &TaskId) {
+        let estimate = state.database[task_id].estimate;
+        self.modals[self.estimate_modal]
+            .open_with_text(estimate.map(format_duration).unwrap_or_default());
+    }
+
+    /// Asks until when to snooze the given task, offering to unsnooze it if it is snoozed.
+    fn open_snooze_dialog(&mut self, state: &AppState, task_id: &TaskId) {
+        let task = &state.database[task_id];
+        let mut options = vec![
+            KEYBIND_SNOOZE_TOMORROW.clone(),
+            KEYBIND_SNOOZE_NEXT_WEEK.clone(),
+            KEYBIND_SNOOZE_CUSTOM.clone(),
+        ];
+        if task.is_snoozed(OffsetDateTime::now_utc()) {
+            options.push(KEYBIND_SNOOZE_WAKE.clone());
+        }
+        let text = format!("Until when should '{}' be hidden?", task.title);
+        self.modals[self.snooze_modal].open_with_text(text, options);
+    }
+
+    /// Selects the first open dependency of the given task, which can be worked on right away. If
+    /// it is not in the list, its details are shown instead.
+    fn go_to_first_open_dependency(
+        &self,
+        state: &mut AppState,
+        tasks: &[TaskId],
+        task_id: &TaskId,
+    ) {
+        let Some(first) = state
+            .database
+            .open_dependencies(task_id)
+            .first()
+            .map(|t| t.id().clone())
+        else {return;};
+        match tasks.iter().position(|id| *id == first) {
+            Some(index) => self.select_index(tasks, index),
+            None => state.push_view(TaskDetailPage::new(first)),
+        }
+    }
+
     fn handle_modals(
         &mut self,
         key: KeyEvent,
         state: &mut AppState,
-        tasks: &[Task],
+        tasks: &[TaskId],
         task_index: usize,
     ) -> bool {
         if self.modals[self.edit_modal].is_open() {
@@ -408,18 +917,36 @@ impl TaskList {
                 match selected {
                     _ if selected == *KEYBIND_TASK_RENAME => {
                         self.modals[self.rename_task_modal]
-                            .open_with_text(tasks[task_index].title.clone());
+                            .open_with_text(state.database[&tasks[task_index]].title.clone());
                         return true;
                     }
                     _ if selected == *KEYBIND_TASK_DELETE => {
                         self.modals[self.delete_task_modal].open(true);
                         return true;
                     }
+                    _ if selected == *KEYBIND_TASK_DUPLICATE => {
+                        self.duplicate_task(state, &tasks[task_index]);
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_ARCHIVE => {
+                        state.dispatch(Action::ToggleArchived(tasks[task_index].clone()));
+                        return true;
+                    }
                     _ if selected == *KEYBIND_TASK_ADD_DEPENDENCY => {
                         let modal = &mut self.modals[self.search_box_depend_on];
                         Self::open_add_dependency_dialog(modal, state, task_index, tasks);
                         return true;
                     }
+                    _ if selected == *KEYBIND_TASK_ADD_BLOCKED => {
+                        let modal = &mut self.modals[self.search_box_blocks];
+                        Self::open_add_blocked_dialog(modal, state, task_index, tasks);
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_EDIT_DEPENDENCIES => {
+                        let task_id = &tasks[task_index];
+                        self.modals[self.dependency_edit_modal].open(state, task_id);
+                        return true;
+                    }
                     _ if selected == *KEYBIND_TASK_ADD_TAG => {
                         if !tasks.is_empty() {
                             // add tag to currently selected task
@@ -427,19 +954,85 @@ impl TaskList {
                         }
                         return true;
                     }
+                    _ if selected == *KEYBIND_TASK_ADD_REMINDER => {
+                        self.modals[self.add_reminder_modal].open();
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_SNOOZE => {
+                        self.open_snooze_dialog(state, &tasks[task_index]);
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_SET_ESTIMATE => {
+                        self.open_estimate_dialog(state, &tasks[task_index]);
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_SHOW_LINK => {
+                        self.open_task_link(state, &tasks[task_index]);
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_TASK_TOGGLE_ENCRYPTED => {
+                        state.toggle_task_encrypted(&tasks[task_index]);
+                        return true;
+                    }
                     _ => (),
                 }
             }
             // always return true because the modal should be blocking input propagation but it
             // can't since it blocks us from checking the modal result. thus, we block here.
             true
+        } else if self.modals[self.complete_modal].is_open() {
+            let selected = self.modals[self.complete_modal].take_selected_keybind();
+            if let (Some(selected), Some(task_id)) = (selected, self.task_to_complete.take()) {
+                match selected {
+                    _ if selected == *KEYBIND_COMPLETE_WITH_DEPENDENCIES => {
+                        state.dispatch(Action::CompleteWithDependencies(task_id));
+                    }
+                    _ if selected == *KEYBIND_COMPLETE_GO_TO_DEPENDENCY => {
+                        self.go_to_first_open_dependency(state, tasks, &task_id);
+                    }
+                    _ if selected == *KEYBIND_COMPLETE_ANYWAY => {
+                        state.dispatch(Action::ToggleDone(task_id));
+                    }
+                    _ => self.task_to_complete = Some(task_id),
+                }
+            }
+            // see above
+            true
+        } else if self.modals[self.snooze_modal].is_open() {
+            let selected = self.modals[self.snooze_modal].take_selected_keybind();
+            if let (Some(selected), Some(task_id)) = (selected, tasks.get(task_index)) {
+                let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+                let until = match selected {
+                    _ if selected == *KEYBIND_SNOOZE_TOMORROW => Some(reminders::tomorrow(now)),
+                    _ if selected == *KEYBIND_SNOOZE_NEXT_WEEK => Some(reminders::next_week(now)),
+                    _ if selected == *KEYBIND_SNOOZE_CUSTOM => {
+                        self.modals[self.snooze_time_modal].open();
+                        return true;
+                    }
+                    _ if selected == *KEYBIND_SNOOZE_WAKE => None,
+                    _ => return true,
+                };
+                state.dispatch(Action::Snooze(task_id.clone(), until));
+            }
+            // see above
+            true
+        } else if self.modals[self.suggestion_modal].is_open() {
+            if let Some(selected) = self.modals[self.suggestion_modal].take_selected_keybind() {
+                let task_id = self
+                    .suggestions
+                    .iter()
+                    .find_map(|(keybind, task_id)| (*keybind == selected).then(|| task_id.clone()));
+                if let Some(task_id) = task_id {
+                    self.start_suggestion(state, task_id);
+                }
+            }
+            // see above
+            true
         } else if self.modals[self.create_task_modal].is_open() {
             // popup is open
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
                 if let Some(text) = self.modals[self.create_task_modal].close() {
-                    state
-                        .database
-                        .modify(|x| x.add_task(Task::create_now(text)));
+                    state.dispatch(Action::NewTask(text));
                 }
                 true
             } else {
@@ -449,10 +1042,7 @@ impl TaskList {
             // popup is open
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
                 if let Some(text) = self.modals[self.rename_task_modal].close() {
-                    state.database.modify(|db| {
-                        let selected_task = &mut db[tasks[task_index].id()];
-                        selected_task.title = text;
-                    });
+                    state.dispatch(Action::RenameTask(tasks[task_index].clone(), text));
                 }
                 true
             } else {
@@ -462,10 +1052,60 @@ impl TaskList {
             // popup is open
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
                 if self.modals[self.delete_task_modal].close() && !tasks.is_empty() {
-                    // delete
-                    state
-                        .database
-                        .modify(|x| x.remove_task(tasks[task_index].id()));
+                    state.dispatch(Action::DeleteTask(tasks[task_index].clone()));
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.duplicate_task_modal].is_open() {
+            // popup is open
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                let with_dependencies = self.modals[self.duplicate_task_modal].close();
+                if !tasks.is_empty() {
+                    state.dispatch(Action::DuplicateTask {
+                        task: tasks[task_index].clone(),
+                        with_dependencies,
+                    });
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.purge_archived_modal].is_open()
+            || self.modals[self.purge_archived_typed_modal].is_open()
+        {
+            // popup is open
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                let confirmed = match self.modals[self.purge_archived_modal].is_open() {
+                    true => self.modals[self.purge_archived_modal].close(),
+                    false => self.modals[self.purge_archived_typed_modal].close(),
+                };
+                if confirmed {
+                    if let Err(e) = state.purge_archived() {
+                        state.show_toast(format!("Could not purge archived tasks: {e}"));
+                    }
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.import_path_modal].is_open() {
+            // popup is open
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(path) = self.modals[self.import_path_modal].close() {
+                    self.open_import_preview(state, &path);
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.import_confirm_modal].is_open() {
+            // popup is open
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                let tasks = std::mem::take(&mut self.pending_import);
+                if self.modals[self.import_confirm_modal].close() && !tasks.is_empty() {
+                    state.dispatch(Action::ImportTasks(tasks));
                 }
                 true
             } else {
@@ -475,22 +1115,113 @@ impl TaskList {
             // popup is open
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
                 if let Some(text) = self.modals[self.new_tag_modal].close() {
-                    state.database.modify(|db| {
-                        let selected_task = &mut db[tasks[task_index].id()];
-                        selected_task.tags.push(text);
-                    });
+                    state.dispatch(Action::AddTag(tasks[task_index].clone(), text));
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.add_reminder_modal].is_open() {
+            // popup is open
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(text) = self.modals[self.add_reminder_modal].close() {
+                    let now =
+                        OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+                    match reminders::parse_time(&text, now) {
+                        Some(time) => {
+                            let task_id = tasks[task_index].clone();
+                            state.dispatch(Action::AddReminder(task_id, time));
+                        }
+                        None => state.show_toast(format!("Invalid reminder time: {text}")),
+                    }
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.estimate_modal].is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(text) = self.modals[self.estimate_modal].close() {
+                    let task_id = tasks[task_index].clone();
+                    match text.trim() {
+                        "" => state.dispatch(Action::SetEstimate(task_id, None)),
+                        text => match parse_duration(text) {
+                            Some(estimate) => {
+                                state.dispatch(Action::SetEstimate(task_id, Some(estimate)));
+                            }
+                            None => state.show_toast(format!("Invalid estimate: {text}")),
+                        },
+                    }
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.snooze_time_modal].is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(text) = self.modals[self.snooze_time_modal].close() {
+                    let now =
+                        OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+                    match reminders::parse_time(&text, now) {
+                        Some(time) => {
+                            let task_id = tasks[task_index].clone();
+                            state.dispatch(Action::Snooze(task_id, Some(time)));
+                        }
+                        None => state.show_toast(format!("Invalid snooze time: {text}")),
+                    }
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.project_modal].is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(project) = self.modals[self.project_modal].close() {
+                    state.dispatch(Action::SwitchProject(project));
+                }
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.assignee_modal].is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(assignee) = self.modals[self.assignee_modal].close() {
+                    state.dispatch(Action::SwitchAssignee(assignee));
                 }
                 true
             } else {
                 false
             }
+        } else if self.modals[self.dependency_edit_modal].is_open() {
+            if KEYBIND_DEPENDENCIES_APPLY.is_match(key) {
+                let modal = &mut self.modals[self.dependency_edit_modal];
+                if let Some((task, dependencies)) = modal.close() {
+                    state.dispatch(Action::SetDependencies { task, dependencies });
+                }
+            }
+            // the modal takes all input while it is open
+            true
         } else if self.modals[self.search_box_depend_on].is_open() {
             // popup is open
             if KEYBIND_MODAL_SUBMIT.is_match(key) {
-                if let Some(selected_task_id) = self.modals[self.search_box_depend_on].close() {
-                    state
-                        .database
-                        .modify(|x| x.add_dependency(tasks[task_index].id(), &selected_task_id));
+                if let Some(dependency) = self.modals[self.search_box_depend_on].close() {
+                    state.dispatch(Action::AddDependency {
+                        task: tasks[task_index].clone(),
+                        dependency,
+                    });
+                }
+
+                true
+            } else {
+                false
+            }
+        } else if self.modals[self.search_box_blocks].is_open() {
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(blocked) = self.modals[self.search_box_blocks].close() {
+                    state.dispatch(Action::AddDependency {
+                        task: blocked,
+                        dependency: tasks[task_index].clone(),
+                    });
                 }
 
                 true
@@ -502,25 +1233,263 @@ impl TaskList {
         }
     }
 
+    fn open_suggestions(&mut self, state: &AppState) {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        let suggestions = state.database.suggest_tasks(now, Self::SUGGESTION_COUNT);
+        if suggestions.is_empty() {
+            return;
+        }
+
+        self.suggestions = suggestions
+            .iter()
+            .zip('1'..='9')
+            .map(|(suggestion, c)| {
+                let keybind =
+                    SimpleKeybind::new_owned(KeyCode::Char(c), suggestion.task.title.clone());
+                (keybind, suggestion.task.id().clone())
+            })
+            .collect();
+        let keybinds = self.suggestions.iter().map(|(k, _)| k.clone()).collect();
+        self.modals[self.suggestion_modal].open(keybinds);
+    }
+
+    /// Marks a suggested task as started and selects it, if it is visible in the list.
+    fn start_suggestion(&mut self, state: &mut AppState, task_id: TaskId) {
+        state.dispatch(Action::StartTask(task_id.clone()));
+        self.select_task(state, &task_id);
+    }
+
+    fn open_project_switcher(&mut self, state: &AppState) {
+        let all_count = state.database.get_all_tasks().count();
+        let mut items = vec![(None, format!("All projects ({all_count})"))];
+        items.extend(
+            state
+                .database
+                .all_projects()
+                .into_iter()
+                .map(|(project, count)| {
+                    (Some(project.to_string()), format!("{project} ({count})"))
+                }),
+        );
+        self.modals[self.project_modal].open(items);
+    }
+
+    fn open_assignee_switcher(&mut self, state: &AppState) {
+        let all_count = state.database.get_all_tasks().count();
+        let mut items = vec![(None, format!("Everyone ({all_count})"))];
+        items.extend(
+            state
+                .database
+                .all_assignees()
+                .into_iter()
+                .map(|(assignee, count)| {
+                    (Some(assignee.to_string()), format!("{assignee} ({count})"))
+                }),
+        );
+        self.modals[self.assignee_modal].open(items);
+    }
+
+    /// Reads a todo.txt file and asks the user to confirm importing its tasks.
+    fn open_import_preview(&mut self, state: &AppState, path: &str) {
+        const PREVIEW_COUNT: usize = 5;
+
+        let text = match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                self.pending_import = todotxt::parse(&contents);
+                let summary = state.preview_changes(|db| {
+                    for task in &self.pending_import {
+                        db.add_task(task.clone());
+                    }
+                });
+
+                let mut text = format!("Import {} tasks? ({summary})", self.pending_import.len());
+                for task in self.pending_import.iter().take(PREVIEW_COUNT) {
+                    text.push_str(&format!("\n- {}", task.title));
+                }
+                if self.pending_import.len() > PREVIEW_COUNT {
+                    let remaining = self.pending_import.len() - PREVIEW_COUNT;
+                    text.push_str(&format!("\n(and {remaining} more)"));
+                }
+                text
+            }
+            Err(e) => {
+                self.pending_import.clear();
+                format!("Could not read {path}: {e}")
+            }
+        };
+
+        let modal = &mut self.modals[self.import_confirm_modal];
+        modal.set_text(text);
+        modal.open(!self.pending_import.is_empty());
+    }
+
+    /// Shows a link to the task that can be opened with the command line, along with a QR code of
+    /// it.
+    fn copy_to_clipboard(state: &mut AppState, text: String) {
+        match clipboard::copy(&text) {
+            Ok(()) => state.show_toast(format!("Copied '{text}'")),
+            Err(e) => state.show_toast(format!("Could not copy to the clipboard: {e}")),
+        }
+    }
+
+    fn open_task_link(&mut self, state: &mut AppState, task_id: &TaskId) {
+        let link = TaskLink::new(&state.database_id(), task_id.clone()).to_string();
+        let mut text = link.clone();
+        if let Some(qr_code) = render_qr_code(&link) {
+            text = format!("{qr_code}\n\n{text}");
+        }
+
+        let modal = &mut self.modals[self.task_link_modal];
+        let width = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        modal.set_width(width as u16);
+        modal.open(text);
+    }
+
     fn open_add_dependency_dialog(
         modal: &mut ListSearchModal<TaskId>,
         state: &AppState,
         task_index: usize,
-        tasks: &[Task],
+        tasks: &[TaskId],
     ) {
         // link to other task
         let selected = &tasks[task_index];
         let existing_dependency_ids = state
             .database
-            .get_dependencies(selected.id())
+            .get_dependencies(selected)
+            .map(|x| x.id().clone())
+            .collect::<HashSet<_>>();
+        let candidate_tasks = tasks
+            .iter()
+            .filter(|id| *id != selected)
+            .filter(|candidate| !existing_dependency_ids.contains(candidate))
+            .map(|id| (id.clone(), state.database[id].title.clone()))
+            .collect();
+        modal.open(candidate_tasks);
+    }
+
+    /// Opens the dialog to choose a task that the selected task blocks, leaving out the tasks that
+    /// already depend on it.
+    fn open_add_blocked_dialog(
+        modal: &mut ListSearchModal<TaskId>,
+        state: &AppState,
+        task_index: usize,
+        tasks: &[TaskId],
+    ) {
+        let selected = &tasks[task_index];
+        let existing_dependent_ids = state
+            .database
+            .get_inverse_dependencies(selected)
             .map(|x| x.id().clone())
             .collect::<HashSet<_>>();
         let candidate_tasks = tasks
             .iter()
-            .filter(|t| t.id() != selected.id())
-            .filter(|candidate| !existing_dependency_ids.contains(candidate.id()))
-            .map(|w| (w.id().clone(), w.title.clone()))
+            .filter(|id| *id != selected)
+            .filter(|candidate| !existing_dependent_ids.contains(candidate))
+            .map(|id| (id.clone(), state.database[id].title.clone()))
             .collect();
         modal.open(candidate_tasks);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_moves_to_nearest_visible_neighbor() {
+        let tasks = (0..6)
+            .map(|i| Task::create_now(format!("task {i}")))
+            .collect::<Vec<_>>();
+        let state = AppState::default();
+        let list = TaskList::new();
+        list.select_index(&tasks, 2);
+
+        // the selected task stays selected when tasks above it are hidden
+        let visible = [&tasks[0], &tasks[2], &tasks[4]];
+        assert_eq!(list.resolve_selection(&state, &visible), 1);
+
+        // when it is hidden itself, the nearest task that is still visible is selected
+        let visible = [&tasks[0], &tasks[4], &tasks[5]];
+        assert_eq!(list.resolve_selection(&state, &visible), 1);
+        assert_eq!(list.selected_id.borrow().as_ref(), Some(tasks[4].id()));
+
+        // without any known tasks left, the selection stays in place
+        let others = (0..3)
+            .map(|i| Task::create_now(format!("other {i}")))
+            .collect::<Vec<_>>();
+        assert_eq!(list.resolve_selection(&state, &others), 1);
+        assert_eq!(list.resolve_selection(&state, &[] as &[Task]), 0);
+    }
+
+    #[test]
+    fn selection_moves_to_task_restored_by_undo() {
+        let mut state = AppState::default();
+        let [other, original, first, second] =
+            ["other", "original", "first", "second"].map(|title| Task::create_now(title.into()));
+        let original_id = original.id().clone();
+        let visible = [other.clone(), first.clone(), second.clone()];
+        state.database.modify("add", |db| {
+            db.add_task(other.clone());
+            db.add_task(original);
+        });
+        state.database.modify("split", |db| {
+            db.remove_task(&original_id);
+            db.add_task(first);
+            db.add_task(second);
+        });
+
+        let list = TaskList::new();
+        list.select_index(&visible, 1);
+
+        // undoing the split brings back the original task, which is preferred over the neighbors
+        state.database.undo();
+        let restored = [other, state.database[&original_id].clone()];
+        assert_eq!(list.resolve_selection(&state, &restored), 1);
+        assert_eq!(list.selected_id.borrow().as_ref(), Some(&original_id));
+    }
+
+    #[test]
+    fn unblocks_filter_shows_dependents_in_order() {
+        let mut state = AppState::default();
+        let tasks = ["design", "build", "release", "unrelated"]
+            .map(|title| Task::create_now(title.to_string()));
+        let [design, build, release, _] = tasks.each_ref().map(|t| t.id().clone());
+        state.database.modify("add", |db| {
+            for task in tasks {
+                db.add_task(task);
+            }
+            db.add_dependency(&release, &build);
+            db.add_dependency(&build, &design);
+        });
+
+        state.dispatch(Action::ShowUnblockedBy(Some(design)));
+        let list = TaskList::new();
+        let titles = list
+            .get_task_list(&state)
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["build", "release"]);
+
+        state.dispatch(Action::ShowUnblockedBy(None));
+        assert_eq!(list.get_task_list(&state).len(), 4);
+    }
+
+    #[test]
+    fn cached_list_follows_changes_and_ending_snoozes() {
+        let mut state = AppState::default();
+        let task = Task::create_now("snoozed".into());
+        let task_id = task.id().clone();
+        state.database.modify("add", |db| db.add_task(task));
+        let until = OffsetDateTime::now_utc() + td_lib::time::Duration::milliseconds(100);
+        state.dispatch(Action::Snooze(task_id, Some(until)));
+
+        let list = TaskList::new();
+        assert_eq!(list.get_task_list(&state).len(), 0);
+        state.dispatch(Action::NewTask("new".into()));
+        assert_eq!(list.get_task_list(&state).len(), 1);
+
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        assert_eq!(list.get_task_list(&state).len(), 2);
+    }
+}
@@ -1,28 +1,120 @@
+use std::cell::RefCell;
+
+use predicates::Predicate;
 use ratatui::widgets::Paragraph;
+use td_lib::{
+    database::ArchivePolicy,
+    stats::TaskStats,
+    time::{Duration, OffsetDateTime, UtcOffset},
+};
 
 use crate::{
+    background::BackgroundJob,
     keybinds::*,
     ui::{
         constants::{LIST_HIGHLIGHT_STYLE, NO_STYLE, SETTINGS_HEADER},
-        Component,
+        AppState, Component, TaskFilters,
     },
-    utils::RectExt,
+    utils::{format_duration, RectExt},
 };
 
 #[derive(Default)]
 pub struct TaskListSettings {
     index: usize,
+    /// The summary of the filtered tasks, which is calculated in the background.
+    summary: RefCell<BackgroundJob<SummaryKey, Summary>>,
+}
+
+/// The state that the summary of the filtered tasks depends on.
+#[derive(PartialEq, Eq)]
+struct SummaryKey {
+    revision: u64,
+    filters: TaskFilters,
+    /// When the last snoozed task that was accounted for came back, since that changes the
+    /// summary without changing the database or the filters.
+    snooze_ended: Option<OffsetDateTime>,
+}
+
+/// The stats of the filtered tasks.
+struct Summary {
+    /// The [`SummaryKey::snooze_ended`] this was calculated for.
+    snooze_ended: Option<OffsetDateTime>,
+    /// When the first snoozed task comes back, after which this is outdated.
+    valid_until: Option<OffsetDateTime>,
+    stats: TaskStats,
 }
 
 impl TaskListSettings {
-    pub const UI_HEIGHT: u16 = Self::SETTING_COUNT as u16 + 2 + 1;
+    pub const UI_HEIGHT: u16 = Self::SETTING_COUNT as u16 + 4 + 3 + Self::SUMMARY_HEIGHT;
+
+    /// An empty line, a header and 2 lines of stats.
+    const SUMMARY_HEIGHT: u16 = 4;
+
+    const SETTING_COUNT: usize = 11;
+
+    const INDEX_SORT_MODE: usize = 0;
+    const INDEX_SORT_OLDEST: usize = 1;
+    const INDEX_FILTER_COMPLETED: usize = 2;
+    const INDEX_FILTER_ARCHIVED: usize = 3;
+    const INDEX_FILTER_UNACTIONABLE: usize = 4;
+    const INDEX_FILTER_SEARCH: usize = 5;
+    const INDEX_SEARCH_HIDDEN: usize = 6;
+    const INDEX_ARCHIVE_AFTER: usize = 7;
+    const INDEX_GIT_SYNC: usize = 8;
+    const INDEX_VIM_MODE: usize = 9;
+    const INDEX_READY_TASKS: usize = 10;
+
+    /// The choices for how many days completed tasks are kept before they are auto-archived.
+    const ARCHIVE_AFTER_DAYS: [Option<i64>; 4] = [None, Some(7), Some(30), Some(90)];
+
+    /// Gets the archive policy that comes after the current one in [`Self::ARCHIVE_AFTER_DAYS`].
+    fn next_archive_policy(current: ArchivePolicy) -> ArchivePolicy {
+        let current_days = current.archive_completed_after.map(|d| d.whole_days());
+        let next_days = Self::ARCHIVE_AFTER_DAYS
+            .iter()
+            .position(|days| *days == current_days)
+            .map_or(Self::ARCHIVE_AFTER_DAYS[0], |i| {
+                Self::ARCHIVE_AFTER_DAYS[(i + 1) % Self::ARCHIVE_AFTER_DAYS.len()]
+            });
+
+        ArchivePolicy {
+            archive_completed_after: next_days.map(Duration::days),
+        }
+    }
 
-    const SETTING_COUNT: usize = 4;
+    /// Starts calculating the stats of the tasks that pass the current filters, if the database
+    /// or the filters changed since the last time, or a snoozed task came back.
+    fn request_summary(&self, state: &AppState) {
+        let now = OffsetDateTime::now_utc();
+        let mut summary = self.summary.borrow_mut();
+        summary.poll();
+        let snooze_ended = match summary.latest() {
+            Some(latest) if latest.valid_until.is_some_and(|until| until <= now) => {
+                latest.valid_until
+            }
+            Some(latest) => latest.snooze_ended,
+            None => None,
+        };
 
-    const INDEX_SORT_OLDEST: usize = 0;
-    const INDEX_FILTER_COMPLETED: usize = 1;
-    const INDEX_FILTER_UNACTIONABLE: usize = 2;
-    const INDEX_FILTER_SEARCH: usize = 3;
+        // every filter is part of the key, so one that is added later can't be forgotten here
+        let key = SummaryKey {
+            revision: state.database.revision(),
+            filters: state.filters().clone(),
+            snooze_ended,
+        };
+        let database = state.database_snapshot();
+        let filters = state.filters().clone();
+        summary.request(key, move || {
+            let predicate = filters.predicate(&database, |_| false);
+            Summary {
+                snooze_ended,
+                valid_until: database.next_snooze_end(now),
+                stats: TaskStats::from_tasks(
+                    database.get_all_tasks().filter(|t| predicate.eval(t)),
+                ),
+            }
+        });
+    }
 }
 
 impl Component for TaskListSettings {
@@ -33,9 +125,8 @@ impl Component for TaskListSettings {
     ) {
         frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, Self::SETTING_COUNT > 1);
 
-        if self.index == Self::INDEX_SORT_OLDEST || self.index == Self::INDEX_FILTER_COMPLETED {
-            frame_storage.register_keybind(KEYBIND_CONTROLS_CHECKBOX_TOGGLE, true);
-        }
+        // all settings are checkboxes or cycle through a list of choices
+        frame_storage.register_keybind(KEYBIND_CONTROLS_CHECKBOX_TOGGLE, true);
     }
 
     fn render(
@@ -45,7 +136,10 @@ impl Component for TaskListSettings {
         state: &crate::ui::AppState,
         _frame_storage: &crate::ui::FrameLocalStorage,
     ) {
-        let (area_sorting, area_filter) = area.split_y(3);
+        let (area_sorting, area_rest) = area.split_y(4);
+        let (area_filter, area_rest) = area_rest.split_y(7);
+        let (area_database, area_rest) = area_rest.split_y(4);
+        let (area_input, area_summary) = area_rest.split_y(4);
 
         let checkbox = |b: bool| if b { 'x' } else { ' ' };
         let list_style = |i: usize| {
@@ -61,13 +155,18 @@ impl Component for TaskListSettings {
             Paragraph::new("Sorting:").style(SETTINGS_HEADER),
             area_sorting.slice_y(0..=0).take_x("Sorting:".len() as u16),
         );
+        frame.render_widget(
+            Paragraph::new(format!(" <{}> Sort by", state.filters().sort_mode.name()))
+                .style(list_style(Self::INDEX_SORT_MODE)),
+            area_sorting.slice_y(1..=1),
+        );
         frame.render_widget(
             Paragraph::new(format!(
                 " [{}] Show oldest first",
-                checkbox(state.sort_oldest_first)
+                checkbox(state.filters().sort_oldest_first)
             ))
             .style(list_style(Self::INDEX_SORT_OLDEST)),
-            area_sorting.slice_y(1..=1),
+            area_sorting.slice_y(2..=2),
         );
 
         // Filter
@@ -78,24 +177,134 @@ impl Component for TaskListSettings {
         frame.render_widget(
             Paragraph::new(format!(
                 " [{}] Hide completed",
-                checkbox(state.filter_completed)
+                checkbox(state.filters().filter_completed)
             ))
             .style(list_style(Self::INDEX_FILTER_COMPLETED)),
             area_filter.slice_y(1..=1),
         );
         frame.render_widget(
             Paragraph::new(format!(
-                " [{}] Hide unactionable (unfinished dependencies)",
-                checkbox(state.filter_unactionable)
+                " [{}] Hide archived",
+                checkbox(state.filters().filter_archived)
             ))
-            .style(list_style(Self::INDEX_FILTER_UNACTIONABLE)),
+            .style(list_style(Self::INDEX_FILTER_ARCHIVED)),
             area_filter.slice_y(2..=2),
         );
         frame.render_widget(
-            Paragraph::new(format!(" [{}] Text search", checkbox(state.filter_search)))
-                .style(list_style(Self::INDEX_FILTER_SEARCH)),
+            Paragraph::new(format!(
+                " [{}] Hide unactionable (unfinished dependencies)",
+                checkbox(state.filters().filter_unactionable)
+            ))
+            .style(list_style(Self::INDEX_FILTER_UNACTIONABLE)),
             area_filter.slice_y(3..=3),
         );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " [{}] Text search",
+                checkbox(state.filters().filter_search)
+            ))
+            .style(list_style(Self::INDEX_FILTER_SEARCH)),
+            area_filter.slice_y(4..=4),
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " [{}] Search completed and archived tasks",
+                checkbox(state.filters().search_hidden)
+            ))
+            .style(list_style(Self::INDEX_SEARCH_HIDDEN)),
+            area_filter.slice_y(5..=5),
+        );
+
+        // Archival
+        frame.render_widget(
+            Paragraph::new("Archival:").style(SETTINGS_HEADER),
+            area_database
+                .slice_y(0..=0)
+                .take_x("Archival:".len() as u16),
+        );
+        let archive_after = match state.database.archive_policy().archive_completed_after {
+            Some(after) => format!("after {} days", after.whole_days()),
+            None => "never".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(format!(" <{archive_after}> Archive completed tasks"))
+                .style(list_style(Self::INDEX_ARCHIVE_AFTER)),
+            area_database.slice_y(1..=1),
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " [{}] Commit to git repository on save",
+                checkbox(state.git_sync_enabled())
+            ))
+            .style(list_style(Self::INDEX_GIT_SYNC)),
+            area_database.slice_y(2..=2),
+        );
+
+        // Input
+        frame.render_widget(
+            Paragraph::new("Behavior:").style(SETTINGS_HEADER),
+            area_input.slice_y(0..=0).take_x("Behavior:".len() as u16),
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " [{}] Vim keys (j/k, gg/G, dd, /)",
+                checkbox(state.vim_mode_enabled())
+            ))
+            .style(list_style(Self::INDEX_VIM_MODE)),
+            area_input.slice_y(1..=1),
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " <{}> Announce tasks when their dependencies are done",
+                state.ready_task_alert().name()
+            ))
+            .style(list_style(Self::INDEX_READY_TASKS)),
+            area_input.slice_y(2..=2),
+        );
+
+        // Summary
+        frame.render_widget(
+            Paragraph::new("Summary:").style(SETTINGS_HEADER),
+            area_summary.slice_y(0..=0).take_x("Summary:".len() as u16),
+        );
+        if state.is_starting() {
+            return;
+        }
+        self.request_summary(state);
+        let summary = self.summary.borrow();
+        let Some(Summary { stats, .. }) = summary.latest() else {
+            frame.render_widget(
+                Paragraph::new(" Calculating..."),
+                area_summary.slice_y(1..=1),
+            );
+            return;
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                " {} todo, {} started, {} done",
+                stats.todo, stats.started, stats.completed
+            )),
+            area_summary.slice_y(1..=1),
+        );
+        let due_text = match stats.nearest_due {
+            Some(due) => {
+                let due =
+                    due.to_offset(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+                format!("next due {}", due.date())
+            }
+            None => "nothing due".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                " {} remaining, {due_text}",
+                format_duration(stats.estimate_remaining)
+            )),
+            area_summary.slice_y(2..=2),
+        );
+    }
+
+    fn on_tick(&mut self, _state: &mut AppState) -> bool {
+        self.summary.get_mut().poll()
     }
 
     fn process_input(
@@ -117,22 +326,64 @@ impl Component for TaskListSettings {
             }
         } else {
             match self.index {
+                Self::INDEX_SORT_MODE if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    let filters = state.filters_mut();
+                    filters.sort_mode = filters.sort_mode.next();
+                    true
+                }
                 Self::INDEX_SORT_OLDEST if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
-                    state.sort_oldest_first = !state.sort_oldest_first;
+                    let filters = state.filters_mut();
+                    filters.sort_oldest_first = !filters.sort_oldest_first;
                     true
                 }
                 Self::INDEX_FILTER_COMPLETED if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
-                    state.filter_completed = !state.filter_completed;
+                    let filters = state.filters_mut();
+                    filters.filter_completed = !filters.filter_completed;
+                    true
+                }
+                Self::INDEX_FILTER_ARCHIVED if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    let filters = state.filters_mut();
+                    filters.filter_archived = !filters.filter_archived;
+                    if !filters.filter_archived {
+                        state.load_archive();
+                    }
                     true
                 }
                 Self::INDEX_FILTER_UNACTIONABLE
                     if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) =>
                 {
-                    state.filter_unactionable = !state.filter_unactionable;
+                    let filters = state.filters_mut();
+                    filters.filter_unactionable = !filters.filter_unactionable;
                     true
                 }
                 Self::INDEX_FILTER_SEARCH if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
-                    state.filter_search = !state.filter_search;
+                    let filters = state.filters_mut();
+                    filters.filter_search = !filters.filter_search;
+                    true
+                }
+                Self::INDEX_SEARCH_HIDDEN if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    let filters = state.filters_mut();
+                    filters.search_hidden = !filters.search_hidden;
+                    if filters.search_hidden {
+                        state.load_archive();
+                    }
+                    true
+                }
+                Self::INDEX_ARCHIVE_AFTER if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    let policy = Self::next_archive_policy(state.database.archive_policy());
+                    state.set_archive_policy(policy);
+                    true
+                }
+                Self::INDEX_GIT_SYNC if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    state.set_git_sync(!state.git_sync_enabled());
+                    true
+                }
+                Self::INDEX_VIM_MODE if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    state.set_vim_mode(!state.vim_mode_enabled());
+                    true
+                }
+                Self::INDEX_READY_TASKS if KEYBIND_CONTROLS_CHECKBOX_TOGGLE.is_match(key) => {
+                    state.set_ready_task_alert(state.ready_task_alert().next());
                     true
                 }
                 _ => false,
@@ -140,3 +391,68 @@ impl Component for TaskListSettings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use td_lib::database::{Task, TaskId};
+
+    use super::*;
+    use crate::ui::Action;
+
+    fn summary(settings: &TaskListSettings, state: &AppState) -> TaskStats {
+        settings.request_summary(state);
+        let mut summary = settings.summary.borrow_mut();
+        summary.wait();
+        summary.latest().unwrap().stats.clone()
+    }
+
+    fn add_task(state: &mut AppState, title: &str) -> TaskId {
+        let task = Task::create_now(title.into());
+        let id = task.id().clone();
+        state.database.modify("Add task", |db| db.add_task(task));
+        id
+    }
+
+    #[test]
+    fn summary_follows_the_someday_filter() {
+        let mut state = AppState::default();
+        add_task(&mut state, "first");
+        add_task(&mut state, "second");
+        let someday = add_task(&mut state, "someday");
+        state.dispatch(Action::ToggleSomeday(someday));
+
+        let settings = TaskListSettings::default();
+        assert_eq!(summary(&settings, &state).todo, 2);
+        state.filters_mut().show_someday = true;
+        assert_eq!(summary(&settings, &state).todo, 1);
+    }
+
+    #[test]
+    fn summary_follows_the_unblocks_filter() {
+        let mut state = AppState::default();
+        let blocker = add_task(&mut state, "blocker");
+        let blocked = add_task(&mut state, "blocked");
+        add_task(&mut state, "unrelated");
+        state
+            .database
+            .modify("Add dependency", |db| db.add_dependency(&blocked, &blocker));
+
+        let settings = TaskListSettings::default();
+        assert_eq!(summary(&settings, &state).todo, 3);
+        state.dispatch(Action::ShowUnblockedBy(Some(blocker)));
+        assert_eq!(summary(&settings, &state).todo, 1);
+    }
+
+    #[test]
+    fn summary_includes_tasks_once_their_snooze_ends() {
+        let mut state = AppState::default();
+        let task = add_task(&mut state, "snoozed");
+        let until = OffsetDateTime::now_utc() + Duration::milliseconds(100);
+        state.dispatch(Action::Snooze(task, Some(until)));
+
+        let settings = TaskListSettings::default();
+        assert_eq!(summary(&settings, &state).todo, 0);
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        assert_eq!(summary(&settings, &state).todo, 1);
+    }
+}
@@ -1,9 +1,29 @@
-use td_lib::database::Task;
+use std::cell::RefCell;
 
-use crate::ui::{input::TextBoxComponent, Component};
+use ratatui::{layout::Alignment, widgets::Paragraph};
+use td_lib::{
+    database::{Task, TaskId},
+    errors::QueryParseError,
+    query::{HiddenTasks, TaskQuery},
+};
+
+use crate::{
+    fuzzy::{fuzzy_match, FuzzyMatch},
+    ui::{constants::FG_RED, input::TextBoxComponent, AppState, Component},
+};
 
 pub struct TaskSearchBarComponent {
     textbox: TextBoxComponent,
+    query: Result<TaskQuery, QueryParseError>,
+    /// The tasks that matched the last search, so they can be filtered further when the query is
+    /// extended.
+    last_result: RefCell<Option<SearchResult>>,
+}
+
+struct SearchResult {
+    query: TaskQuery,
+    revision: u64,
+    task_ids: Vec<TaskId>,
 }
 
 impl Default for TaskSearchBarComponent {
@@ -12,16 +32,94 @@ impl Default for TaskSearchBarComponent {
             textbox: TextBoxComponent::default()
                 .with_background(true)
                 .with_focus(false),
+            query: Ok(TaskQuery::default()),
+            last_result: RefCell::new(None),
         }
     }
 }
 
 impl TaskSearchBarComponent {
+    /// Checks if the task matches the query. Free text in the query is fuzzy matched against the
+    /// title, falling back to a plain match against the description, tags and id. Invalid queries
+    /// match nothing.
     pub fn filter(&self, task: &Task) -> bool {
-        // PERF: allocates new string every time which is fairly wasteful
-        task.title
-            .to_lowercase()
-            .contains(&self.textbox.text().to_lowercase())
+        match &self.query {
+            Ok(query) => {
+                query.matches_filters(task)
+                    && (self.get_match(task).is_some() || query.matches_text(task))
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Gets all tasks that match the query. If the query narrows down the last search and the
+    /// database did not change since, only the tasks found by the last search are checked.
+    pub fn search<'a>(&self, state: &'a AppState) -> Vec<&'a Task> {
+        let Ok(query) = &self.query else {return vec![];};
+        let revision = state.database.revision();
+
+        let mut last_result = self.last_result.borrow_mut();
+        let previous = last_result
+            .as_ref()
+            .filter(|last| last.revision == revision && query.narrows(&last.query));
+        let tasks = match previous {
+            Some(previous) if previous.query == *query => previous
+                .task_ids
+                .iter()
+                .filter_map(|id| state.database.get_task(id))
+                .collect::<Vec<_>>(),
+            Some(previous) => previous
+                .task_ids
+                .iter()
+                .filter_map(|id| state.database.get_task(id))
+                .filter(|t| self.filter(t))
+                .collect(),
+            None => match query.candidates(&state.database) {
+                Some(candidates) => candidates.into_iter().filter(|t| self.filter(t)).collect(),
+                None => state
+                    .database
+                    .get_all_tasks()
+                    .filter(|t| self.filter(t))
+                    .collect(),
+            },
+        };
+
+        *last_result = Some(SearchResult {
+            query: query.clone(),
+            revision,
+            task_ids: tasks.iter().map(|t| t.id().clone()).collect(),
+        });
+        tasks
+    }
+
+    /// Fuzzy matches the free text of the search query against the title of the given task.
+    pub fn get_match(&self, task: &Task) -> Option<FuzzyMatch> {
+        let query = self.query.as_ref().ok()?;
+        fuzzy_match(&query.text(), &task.title)
+    }
+
+    /// Checks if the search covers the given hidden tasks, because of the query or because the
+    /// search is set to include all hidden tasks.
+    pub fn includes(&self, state: &AppState, hidden: HiddenTasks) -> bool {
+        state.filters().search_hidden || self.query.as_ref().is_ok_and(|q| q.includes(hidden))
+    }
+
+    /// Replaces the query with the given text.
+    pub fn set_query(&mut self, state: &mut AppState, text: &str) {
+        let focused = self.textbox.is_focused();
+        self.textbox = TextBoxComponent::default()
+            .with_background(true)
+            .with_focus(focused)
+            .with_text(text.to_string());
+        self.update_query(state);
+    }
+
+    /// Parses the query after it was changed.
+    fn update_query(&mut self, state: &mut AppState) {
+        self.query = TaskQuery::parse(self.textbox.text());
+        if self.includes(state, HiddenTasks::Archived) {
+            state.load_archive();
+        }
     }
 
     pub fn set_focus(&mut self, value: bool) {
@@ -46,6 +144,13 @@ impl Component for TaskSearchBarComponent {
         frame_storage: &crate::ui::FrameLocalStorage,
     ) {
         self.textbox.render(frame, area, state, frame_storage);
+
+        if let Err(e) = &self.query {
+            let error = Paragraph::new(e.to_string())
+                .style(FG_RED)
+                .alignment(Alignment::Right);
+            frame.render_widget(error, area);
+        }
     }
 
     fn process_input(
@@ -54,6 +159,10 @@ impl Component for TaskSearchBarComponent {
         state: &mut crate::ui::AppState,
         frame_storage: &crate::ui::FrameLocalStorage,
     ) -> bool {
-        self.textbox.process_input(key, state, frame_storage)
+        let handled = self.textbox.process_input(key, state, frame_storage);
+        if handled {
+            self.update_query(state);
+        }
+        handled
     }
 }
@@ -0,0 +1,182 @@
+use std::cell::Cell;
+
+use crossterm::event::KeyEvent;
+use predicates::Predicate;
+use ratatui::{layout::Rect, style::Style, widgets::Paragraph, Frame};
+use td_lib::{
+    time::{format_description, OffsetDateTime, UtcOffset},
+    timeline::{Timeline, TimelineRow},
+};
+
+use super::task_detail::TaskDetailPage;
+use crate::{
+    keybinds::*,
+    ui::{constants::*, AppState, Component, FrameLocalStorage, View},
+    utils::RectExt,
+};
+
+/// A full-screen view that lays the visible tasks out on a timeline, with arrows from the tasks
+/// they depend on.
+pub struct TimelineView {
+    selected: usize,
+    /// The first row that was shown, so the selection can move without the view jumping.
+    scroll: Cell<usize>,
+}
+
+impl TimelineView {
+    /// The widest the column with the titles of the tasks can be.
+    const MAX_TITLE_WIDTH: u16 = 30;
+
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            scroll: Cell::new(0),
+        }
+    }
+
+    fn timeline(state: &AppState) -> Timeline {
+        let predicate = state.get_task_filter_predicate();
+        Timeline::new(&state.database, OffsetDateTime::now_utc(), |task| {
+            predicate.eval(task)
+        })
+    }
+
+    fn bar_style(state: &AppState, row: &TimelineRow) -> (&'static str, Style) {
+        let task = &state.database[&row.task];
+        match (task.time_completed, task.time_started) {
+            (Some(_), _) => ("█", FG_GREEN),
+            (None, Some(_)) => ("█", STARTED_TASK),
+            (None, None) => ("░", FG_LIGHT),
+        }
+    }
+}
+
+impl View for TimelineView {
+    fn title(&self, _state: &AppState) -> String {
+        "Timeline".to_string()
+    }
+}
+
+impl Component for TimelineView {
+    fn pre_render(&self, _global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        frame_storage.register_keybind(KEYBIND_CONTROLS_LIST_NAV, true);
+        frame_storage.register_keybind(KEYBIND_TASK_OPEN_DETAILS, true);
+    }
+
+    fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        state: &AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) {
+        let timeline = Self::timeline(state);
+        if timeline.rows.is_empty() {
+            frame.render_widget(Paragraph::new("There are no tasks to show"), area);
+            return;
+        }
+
+        let title_width = (area.width / 3).min(Self::MAX_TITLE_WIDTH);
+        let (header_area, rows_area) = area.split_y(1);
+        let bars_x = area.x + title_width + 1;
+        let bars_width = area.width.saturating_sub(title_width + 1);
+        if bars_width == 0 || area.height < 2 {
+            return;
+        }
+
+        // show the dates at both ends of the timeline
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let date_format = format_description::parse("[year]-[month]-[day]").unwrap();
+        let date = |time: OffsetDateTime| time.to_offset(offset).format(&date_format).unwrap();
+        let header = format!(
+            "{:<width$}{}",
+            date(timeline.start),
+            date(timeline.end),
+            width = usize::from(bars_width.saturating_sub(10)),
+        );
+        let header_area = Rect::new(bars_x, header_area.y, bars_width, 1);
+        frame.render_widget(Paragraph::new(header).style(FG_DIM), header_area);
+
+        // keep the selected row in view
+        let height = usize::from(rows_area.height);
+        let selected = self.selected.min(timeline.rows.len() - 1);
+        let mut scroll = self.scroll.get().min(selected);
+        if selected >= scroll + height {
+            scroll = selected + 1 - height;
+        }
+        self.scroll.set(scroll);
+        let visible = scroll..(scroll + height).min(timeline.rows.len());
+        let row_y = |row: usize| rows_area.y + (row - scroll) as u16;
+        let column = |time| bars_x + timeline.column(time, bars_width);
+
+        let buffer = frame.buffer_mut();
+
+        // the arrows are drawn first, so the bars are drawn over them where they cross
+        for (index, row) in timeline.rows.iter().enumerate() {
+            for &dependency in &row.dependencies {
+                // the arrow runs down from the end of the dependency, then right to the start
+                let x = column(timeline.rows[dependency].end);
+                let from = (dependency + 1).max(visible.start);
+                let to = index.min(visible.end);
+                for y in (from..to).map(row_y) {
+                    buffer.get_mut(x, y).set_symbol("│").set_style(FG_DIM);
+                }
+                if !visible.contains(&index) {
+                    continue;
+                }
+                let y = row_y(index);
+                buffer.get_mut(x, y).set_symbol("└").set_style(FG_DIM);
+                for x in x + 1..column(row.start) {
+                    buffer.get_mut(x, y).set_symbol("─").set_style(FG_DIM);
+                }
+            }
+        }
+
+        for index in visible.clone() {
+            let row = &timeline.rows[index];
+            let y = row_y(index);
+
+            let title_style = match index == selected {
+                true => LIST_HIGHLIGHT_STYLE,
+                false => LIST_STYLE,
+            };
+            let title = &state.database[&row.task].title;
+            buffer.set_stringn(area.x, y, title, usize::from(title_width), title_style);
+
+            let (symbol, style) = Self::bar_style(state, row);
+            let (start, end) = (column(row.start), column(row.end));
+            for x in start..=end {
+                buffer.get_mut(x, y).set_symbol(symbol).set_style(style);
+            }
+            if row.open_ended && end + 1 < bars_x + bars_width {
+                buffer.get_mut(end + 1, y).set_symbol("›").set_style(style);
+            }
+        }
+    }
+
+    fn process_input(
+        &mut self,
+        key: KeyEvent,
+        state: &mut AppState,
+        _frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        let timeline = Self::timeline(state);
+        let last = timeline.rows.len().saturating_sub(1);
+        self.selected = self.selected.min(last);
+
+        if let Some(key) = KEYBIND_CONTROLS_LIST_NAV.get_match(key) {
+            self.selected = match key {
+                UpDownKey::Up => self.selected.saturating_sub(1),
+                UpDownKey::Down => (self.selected + 1).min(last),
+            };
+            true
+        } else if KEYBIND_TASK_OPEN_DETAILS.is_match(key) {
+            if let Some(row) = timeline.rows.get(self.selected) {
+                state.push_view(TaskDetailPage::new(row.task.clone()));
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
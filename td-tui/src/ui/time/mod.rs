@@ -0,0 +1,258 @@
+use std::cell::RefCell;
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+};
+use td_lib::{
+    database::Database,
+    stats::TimeReport,
+    time::{Date, Duration, OffsetDateTime, UtcOffset},
+};
+
+use super::{
+    constants::{FG_WHITE, LIST_STYLE},
+    modal::TextInputModal,
+    AppState, Component,
+};
+use crate::{
+    background::BackgroundJob,
+    keybinds::*,
+    utils::{format_duration, RectExt},
+};
+
+/// The days a [`TimePage`] can summarize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeRange {
+    Today,
+    ThisWeek,
+    Last7Days,
+    Last30Days,
+    AllTime,
+}
+
+impl TimeRange {
+    const ALL: [Self; 5] = [
+        Self::Today,
+        Self::ThisWeek,
+        Self::Last7Days,
+        Self::Last30Days,
+        Self::AllTime,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Today => "Today",
+            Self::ThisWeek => "This week",
+            Self::Last7Days => "Last 7 days",
+            Self::Last30Days => "Last 30 days",
+            Self::AllTime => "All time",
+        }
+    }
+
+    /// Gets the first and last day of this range.
+    fn dates(self, database: &Database, today: Date) -> (Date, Date) {
+        let from = match self {
+            Self::Today => today,
+            Self::ThisWeek => {
+                today - Duration::days(today.weekday().number_days_from_monday().into())
+            }
+            Self::Last7Days => today - Duration::days(6),
+            Self::Last30Days => today - Duration::days(29),
+            Self::AllTime => database
+                .get_all_tasks()
+                .flat_map(|t| &t.work_sessions)
+                .map(|s| s.start.date())
+                .min()
+                .unwrap_or(today),
+        };
+        (from, today)
+    }
+}
+
+/// A page that summarizes the time worked on tasks, per task, tag and day.
+pub struct TimePage {
+    range_index: usize,
+    export_modal: TextInputModal,
+    /// The report for the selected range, which is calculated in the background.
+    report: RefCell<BackgroundJob<ReportKey, TimeReport>>,
+}
+
+/// The state that the report of a [`TimePage`] depends on.
+#[derive(PartialEq, Eq)]
+struct ReportKey {
+    revision: u64,
+    range: TimeRange,
+    /// The current minute, so time spent on ongoing sessions keeps counting.
+    minute: i64,
+}
+
+impl TimePage {
+    pub fn new() -> Self {
+        Self {
+            range_index: 0,
+            export_modal: TextInputModal::new("Export to CSV".into()),
+            report: RefCell::default(),
+        }
+    }
+
+    fn range(&self) -> TimeRange {
+        TimeRange::ALL[self.range_index]
+    }
+
+    fn get_report(database: &Database, range: TimeRange, now: OffsetDateTime) -> TimeReport {
+        let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let (from, to) = range.dates(database, now.to_offset(offset).date());
+        TimeReport::new(database, from, to, offset, now)
+    }
+
+    /// Starts calculating the report for the current state, if it changed since the last one.
+    fn request_report(&self, state: &AppState) {
+        let now = OffsetDateTime::now_utc();
+        let key = ReportKey {
+            revision: state.database.revision(),
+            range: self.range(),
+            minute: now.unix_timestamp() / 60,
+        };
+
+        let mut report = self.report.borrow_mut();
+        report.poll();
+        let database = state.database_snapshot();
+        let range = self.range();
+        report.request(key, move || Self::get_report(&database, range, now));
+    }
+
+    fn export(&self, state: &mut AppState, path: &str) {
+        let report = Self::get_report(&state.database, self.range(), OffsetDateTime::now_utc());
+        let csv = report.to_csv();
+        match std::fs::write(path, csv) {
+            Ok(()) => state.show_toast(format!("Exported to {path}")),
+            Err(e) => state.show_toast(format!("Could not write {path}: {e}")),
+        }
+    }
+}
+
+impl Component for TimePage {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut super::FrameLocalStorage) {
+        self.request_report(global_state);
+
+        if self.export_modal.is_open() {
+            self.export_modal.pre_render(global_state, frame_storage);
+            return;
+        }
+
+        frame_storage.register_keybind(KEYBIND_TIMEPAGE_RANGE, true);
+        frame_storage.register_keybind(KEYBIND_TIMEPAGE_EXPORT, true);
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppState,
+        frame_storage: &super::FrameLocalStorage,
+    ) {
+        let job = self.report.borrow();
+        let (header_area, lists_area) = area.split_y(1);
+
+        let Some(report) = job.latest() else {
+            let header = format!(" < {} >  Calculating...", self.range().name());
+            frame.render_widget(Paragraph::new(header).style(FG_WHITE), header_area);
+            self.export_modal.render(frame, area, state, frame_storage);
+            return;
+        };
+
+        let header = format!(
+            " < {} >  Total: {}{}",
+            self.range().name(),
+            format_duration(report.total()),
+            if job.is_up_to_date() {
+                ""
+            } else {
+                " (updating)"
+            },
+        );
+        frame.render_widget(Paragraph::new(header).style(FG_WHITE), header_area);
+
+        let layout = Layout::default()
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+            ])
+            .direction(Direction::Horizontal)
+            .split(lists_area);
+
+        let list = |title: &'static str, items: Vec<String>| {
+            let block = Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded);
+            List::new(items.into_iter().map(ListItem::new).collect::<Vec<_>>())
+                .block(block)
+                .style(LIST_STYLE)
+        };
+
+        let tasks = report
+            .by_task()
+            .into_iter()
+            .map(|(_, title, duration)| format!("{:>7} {title}", format_duration(duration)))
+            .collect();
+        frame.render_widget(list("By task", tasks), layout[0]);
+
+        let tags = report
+            .by_tag()
+            .into_iter()
+            .map(|(tag, duration)| format!("{:>7} {tag}", format_duration(duration)))
+            .collect();
+        frame.render_widget(list("By tag", tags), layout[1]);
+
+        let days = report
+            .by_day()
+            .into_iter()
+            .map(|(date, duration)| format!("{date} {:>7}", format_duration(duration)))
+            .collect();
+        frame.render_widget(list("By day", days), layout[2]);
+
+        self.export_modal.render(frame, area, state, frame_storage);
+    }
+
+    fn on_tick(&mut self, _state: &mut AppState) -> bool {
+        self.report.get_mut().poll()
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        frame_storage: &super::FrameLocalStorage,
+    ) -> bool {
+        if self.export_modal.is_open() {
+            if self.export_modal.process_input(key, state, frame_storage) {
+                return true;
+            }
+            if KEYBIND_MODAL_SUBMIT.is_match(key) {
+                if let Some(path) = self.export_modal.close() {
+                    self.export(state, &path);
+                }
+            }
+            return true;
+        }
+
+        if let Some(key) = KEYBIND_TIMEPAGE_RANGE.get_match(key) {
+            let count = TimeRange::ALL.len();
+            self.range_index = match key {
+                LeftRightKey::Left => (self.range_index + count - 1) % count,
+                LeftRightKey::Right => (self.range_index + 1) % count,
+            };
+            true
+        } else if KEYBIND_TIMEPAGE_EXPORT.is_match(key) {
+            let path = state.path.with_extension("time.csv");
+            self.export_modal
+                .open_with_text(path.to_string_lossy().into_owned());
+            true
+        } else {
+            false
+        }
+    }
+}
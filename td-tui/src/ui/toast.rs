@@ -0,0 +1,173 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    style::Style,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+use super::{constants::ACCENT_COLOR, AppState, Component};
+use crate::{
+    keybinds::{Keybind, KEYBIND_UNDO},
+    utils::RectExt,
+};
+
+/// A short message that is shown in the corner of the screen for a few seconds.
+pub struct Toast {
+    text: String,
+    shown_at: Instant,
+    /// Whether the toast tells how to undo the change it is about.
+    offers_undo: bool,
+}
+
+impl Toast {
+    const DURATION: Duration = Duration::from_secs(4);
+
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            shown_at: Instant::now(),
+            offers_undo: false,
+        }
+    }
+
+    /// Creates a toast about a change that can be undone, which tells how to undo it.
+    pub fn with_undo(text: String) -> Self {
+        Self {
+            text: format!("{text}, press {} to undo", KEYBIND_UNDO.key_hint()),
+            offers_undo: true,
+            ..Self::new(String::new())
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.shown_at.elapsed() >= Self::DURATION
+    }
+}
+
+/// Toasts that are waiting to be shown. They are shown one at a time, in the order they were
+/// added.
+#[derive(Default)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+}
+
+impl ToastQueue {
+    /// The most toasts that are kept. When more are added, the oldest waiting ones are dropped.
+    const MAX_LEN: usize = 5;
+
+    pub fn push(&mut self, toast: Toast) {
+        self.toasts.push_back(toast);
+        while self.toasts.len() > Self::MAX_LEN {
+            // keep the toast that is being shown, so it doesn't disappear before it was read
+            self.toasts.remove(1);
+        }
+    }
+
+    /// Gets the toast that is being shown.
+    pub fn current(&self) -> Option<&Toast> {
+        self.toasts.front().filter(|t| !t.is_expired())
+    }
+
+    /// Gets the amount of toasts waiting behind the current one.
+    pub fn waiting(&self) -> usize {
+        self.toasts.len().saturating_sub(1)
+    }
+
+    /// Moves on to the next toast if the current one has been shown long enough. Returns `true` if
+    /// the toast that is shown changed.
+    pub fn expire(&mut self) -> bool {
+        let expired = self.toasts.front().is_some_and(Toast::is_expired);
+        if expired {
+            self.toasts.pop_front();
+            self.restart_current();
+        }
+        expired
+    }
+
+    /// Removes the toasts that offer to undo a change, because it was undone.
+    pub fn dismiss_undo_offers(&mut self) {
+        let front_offered_undo = self.toasts.front().is_some_and(|t| t.offers_undo);
+        self.toasts.retain(|t| !t.offers_undo);
+        if front_offered_undo {
+            self.restart_current();
+        }
+    }
+
+    /// Starts showing the current toast for its full duration.
+    fn restart_current(&mut self) {
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.shown_at = Instant::now();
+        }
+    }
+}
+
+/// Renders the current toast of the [`AppState`], if any.
+pub struct ToastDisplay;
+
+impl Component for ToastDisplay {
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppState,
+        _frame_storage: &super::FrameLocalStorage,
+    ) {
+        let Some(toast) = state.toasts.current() else {return;};
+
+        let text = match state.toasts.waiting() {
+            0 => format!(" {}", toast.text),
+            waiting => format!(" {} (+{waiting})", toast.text),
+        };
+        let width = (text.chars().count() as u16 + 3).min(area.width);
+        let toast_area = area.take_last_y(3).take_last_x(width);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(ACCENT_COLOR));
+        let paragraph = Paragraph::new(text).block(block);
+
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(paragraph, toast_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_toasts_in_order_and_drops_undo_offers_after_undo() {
+        let mut queue = ToastQueue::default();
+        queue.push(Toast::new("first".into()));
+        queue.push(Toast::with_undo("Completed 'task'".into()));
+        queue.push(Toast::new("third".into()));
+        assert_eq!(queue.current().unwrap().text, "first");
+        assert_eq!(queue.waiting(), 2);
+
+        queue.toasts[0].shown_at -= Toast::DURATION;
+        assert!(queue.expire());
+        assert_eq!(
+            queue.current().unwrap().text,
+            "Completed 'task', press u to undo"
+        );
+        assert!(!queue.expire());
+
+        queue.dismiss_undo_offers();
+        assert_eq!(queue.current().unwrap().text, "third");
+        assert_eq!(queue.waiting(), 0);
+
+        for i in 0..10 {
+            queue.push(Toast::new(i.to_string()));
+        }
+        let texts = queue
+            .toasts
+            .iter()
+            .map(|t| t.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["third", "6", "7", "8", "9"]);
+    }
+}
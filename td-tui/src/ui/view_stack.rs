@@ -0,0 +1,92 @@
+use ratatui::widgets::{Block, BorderType, Borders};
+
+use super::{constants::FG_WHITE, AppState, Component, FrameLocalStorage};
+use crate::keybinds::*;
+
+/// A full-screen view that can be shown on top of the tabs with [`AppState::push_view`].
+pub trait View: Component {
+    /// The title of the view, shown in the border around it.
+    fn title(&self, state: &AppState) -> String;
+}
+
+/// A stack of views on top of the tabs. Only the view on top is shown, and only it can register
+/// keybinds and receive input. If it does not handle Esc, the view is popped.
+#[derive(Default)]
+pub struct ViewStack {
+    views: Vec<Box<dyn View>>,
+}
+
+impl ViewStack {
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    /// Closes all views, so the tabs are shown again.
+    pub fn clear(&mut self) {
+        self.views.clear();
+    }
+
+    /// Pushes the views that were opened with [`AppState::push_view`] since the last call.
+    pub fn push_pending(&mut self, state: &mut AppState) {
+        self.views.append(&mut state.pending_views);
+    }
+}
+
+impl Component for ViewStack {
+    fn pre_render(&self, global_state: &AppState, frame_storage: &mut FrameLocalStorage) {
+        if let Some(view) = self.views.last() {
+            view.pre_render(global_state, frame_storage);
+            frame_storage.register_keybind(KEYBIND_VIEW_BACK, true);
+        }
+    }
+
+    fn render(
+        &self,
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        state: &AppState,
+        frame_storage: &FrameLocalStorage,
+    ) {
+        let Some(view) = self.views.last() else {return;};
+
+        // show the path that led to this view
+        let title = self
+            .views
+            .iter()
+            .map(|view| view.title(state))
+            .collect::<Vec<_>>()
+            .join(" › ");
+        let block = Block::default()
+            .title(title)
+            .style(FG_WHITE)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+        view.render(frame, inner_area, state, frame_storage);
+    }
+
+    fn on_tick(&mut self, state: &mut AppState) -> bool {
+        self.views
+            .last_mut()
+            .is_some_and(|view| view.on_tick(state))
+    }
+
+    fn process_input(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut AppState,
+        frame_storage: &FrameLocalStorage,
+    ) -> bool {
+        let Some(view) = self.views.last_mut() else {return false;};
+
+        if view.process_input(key, state, frame_storage) {
+            true
+        } else if KEYBIND_VIEW_BACK.is_match(key) {
+            self.views.pop();
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Vim-style keys. They are translated into the keys of the regular keybinds, so components do not
+//! have to know whether vim mode is enabled.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::keybinds::*;
+
+/// Translates vim keys into regular keys, keeping track of keys that are only complete when
+/// pressed twice, like `gg` and `dd`.
+#[derive(Default)]
+pub struct VimKeys {
+    pending: Option<KeyEvent>,
+}
+
+impl VimKeys {
+    /// Translates the given key. Returns no keys if it starts a sequence and the next key is
+    /// needed, or multiple keys if the next key does not complete the sequence.
+    pub fn translate(&mut self, key: KeyEvent) -> Vec<KeyEvent> {
+        if let Some(pending) = self.pending.take() {
+            if KEYBIND_VIM_TOP.is_match(pending) && KEYBIND_VIM_TOP.is_match(key) {
+                return vec![key_event(KeyCode::Home)];
+            }
+            if KEYBIND_VIM_DELETE.is_match(pending) && KEYBIND_VIM_DELETE.is_match(key) {
+                return vec![KEYBIND_TASK_DELETE.key_event()];
+            }
+
+            // not a sequence, so the pending key does what it does without vim mode
+            let mut keys = vec![pending];
+            keys.extend(self.translate(key));
+            return keys;
+        }
+
+        if KEYBIND_VIM_TOP.is_match(key) || KEYBIND_VIM_DELETE.is_match(key) {
+            self.pending = Some(key);
+            vec![]
+        } else if KEYBIND_VIM_DOWN.is_match(key) {
+            vec![key_event(KeyCode::Down)]
+        } else if KEYBIND_VIM_UP.is_match(key) {
+            vec![key_event(KeyCode::Up)]
+        } else if KEYBIND_VIM_BOTTOM.is_match(key) {
+            vec![key_event(KeyCode::End)]
+        } else if KEYBIND_VIM_SEARCH.is_match(key) {
+            vec![KEYBIND_TASK_TOGGLE_SEARCH.key_event()]
+        } else {
+            vec![key]
+        }
+    }
+}
+
+fn key_event(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(vim_keys: &mut VimKeys, c: char) -> Vec<KeyEvent> {
+        vim_keys.translate(key_event(KeyCode::Char(c)))
+    }
+
+    #[test]
+    fn translates_keys_and_sequences() {
+        let mut vim_keys = VimKeys::default();
+        let key = key_event;
+
+        assert_eq!(translate(&mut vim_keys, 'j'), [key(KeyCode::Down)]);
+        assert_eq!(translate(&mut vim_keys, 'G'), [key(KeyCode::End)]);
+
+        assert_eq!(translate(&mut vim_keys, 'g'), []);
+        assert_eq!(translate(&mut vim_keys, 'g'), [key(KeyCode::Home)]);
+
+        assert_eq!(translate(&mut vim_keys, 'd'), []);
+        assert_eq!(
+            translate(&mut vim_keys, 'd'),
+            [KEYBIND_TASK_DELETE.key_event()]
+        );
+
+        // an unfinished sequence falls back to the regular keybinds
+        assert_eq!(translate(&mut vim_keys, 'd'), []);
+        assert_eq!(
+            translate(&mut vim_keys, 'k'),
+            [key(KeyCode::Char('d')), key(KeyCode::Up)]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Undo history that is kept between sessions, stored next to the database file.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use td_lib::database::{Database, DatabaseOp};
+use td_util::undo::{UndoStep, UndoWrapper};
+
+#[derive(Serialize, Deserialize)]
+struct StoredUndoHistory<'a> {
+    /// The modification time of the database file the history belongs to. If the file was changed
+    /// since, the history no longer applies to it.
+    database_modified: SystemTime,
+    undo_count: usize,
+    steps: Cow<'a, [UndoStep<Vec<DatabaseOp>>]>,
+}
+
+/// Gets the path of the undo history file that belongs to the given database.
+pub fn path_for(database_path: &Path) -> PathBuf {
+    database_path.with_extension("undo.json")
+}
+
+/// Loads the database with the undo history that was stored for it. If there is no history for
+/// the current version of the database file, the undo history is empty.
+pub fn load(
+    database_path: &Path,
+    database: Database,
+    database_modified: Option<SystemTime>,
+) -> UndoWrapper<Database> {
+    let history = std::fs::read_to_string(path_for(database_path))
+        .ok()
+        .and_then(|json| serde_json::from_str::<StoredUndoHistory>(&json).ok())
+        .filter(|history| Some(history.database_modified) == database_modified);
+
+    match history {
+        Some(history) => {
+            UndoWrapper::with_history(database, history.steps.into_owned(), history.undo_count)
+        }
+        None => UndoWrapper::new(database),
+    }
+}
+
+/// Stores the undo history of the database, which must be the version that was just written to
+/// the database file.
+pub fn save(
+    database_path: &Path,
+    database: &UndoWrapper<Database>,
+    database_modified: SystemTime,
+) -> std::io::Result<()> {
+    let history = StoredUndoHistory {
+        database_modified,
+        undo_count: database.undo_count(),
+        steps: Cow::Borrowed(database.steps()),
+    };
+    let json = serde_json::to_string(&history)?;
+    std::fs::write(path_for(database_path), json)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use td_lib::database::Task;
+
+    use super::*;
+
+    #[test]
+    fn roundtrips_for_same_database_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("todo.json");
+
+        let mut database = UndoWrapper::new(Database::default());
+        database.modify("Add task", |db| {
+            db.add_task(Task::create_now("task".into()));
+        });
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        save(&path, &database, modified).unwrap();
+
+        let mut loaded = load(&path, (*database).clone(), Some(modified));
+        assert_eq!(loaded.undo_count(), 1);
+        assert!(loaded.undo());
+        assert_eq!(loaded.get_all_tasks().count(), 0);
+
+        let other_version = load(&path, (*database).clone(), Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(other_version.undo_count(), 0);
+    }
+}
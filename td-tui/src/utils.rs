@@ -10,6 +10,7 @@ use ratatui::{
     layout::Rect,
     text::{Line, Span},
 };
+use td_lib::time::Duration;
 use tui_input::InputRequest;
 
 pub trait RectExt {
@@ -28,16 +29,12 @@ pub trait RectExt {
     where
         Self: Sized;
     fn split_y(self, index: u16) -> (Self, Self)
-    where
-        Self: Sized;
-    fn split_last_x(self, index: u16) -> (Self, Self)
     where
         Self: Sized;
     fn split_last_y(self, index: u16) -> (Self, Self)
     where
         Self: Sized;
 
-    fn slice_x(self, range: impl RangeBounds<u16>) -> Self;
     fn slice_y(self, range: impl RangeBounds<u16>) -> Self;
 
     /// Creates a rect in the center of this one.
@@ -84,28 +81,10 @@ impl RectExt for Rect {
         (self.take_y(index), self.skip_y(index))
     }
 
-    fn split_last_x(self, index: u16) -> (Self, Self) {
-        (self.skip_last_x(index), self.take_last_x(index))
-    }
-
     fn split_last_y(self, index: u16) -> (Self, Self) {
         (self.skip_last_y(index), self.take_last_y(index))
     }
 
-    fn slice_x(self, range: impl RangeBounds<u16>) -> Self {
-        let start = match range.start_bound() {
-            Bound::Included(&x) => x,
-            Bound::Excluded(&x) => x + 1,
-            Bound::Unbounded => 0,
-        };
-        let end = match range.end_bound() {
-            Bound::Included(&x) => x + 1,
-            Bound::Excluded(&x) => x,
-            Bound::Unbounded => self.width,
-        };
-        self.skip_x(start).take_x(end - start)
-    }
-
     fn slice_y(self, range: impl RangeBounds<u16>) -> Self {
         let start = match range.start_bound() {
             Bound::Included(&y) => y,
@@ -191,6 +170,70 @@ pub fn process_textbox_input(key: &KeyEvent) -> Option<InputRequest> {
     }
 }
 
+/// Formats a duration in a short, human-readable way such as `1d 4h` or `2h 30m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.whole_minutes();
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+
+    match (days, hours, minutes) {
+        (0, 0, m) => format!("{m}m"),
+        (0, h, 0) => format!("{h}h"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, 0, _) => format!("{d}d"),
+        (d, h, _) => format!("{d}d {h}h"),
+    }
+}
+
+/// Parses a duration in the format written by [`format_duration`], such as `1d 4h` or `45m`.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut any = false;
+    for part in input.split_whitespace() {
+        let unit_index = part.find(|c: char| !c.is_ascii_digit())?;
+        let (amount, unit) = part.split_at(unit_index);
+        let amount = amount.parse::<i64>().ok()?;
+        total += match unit {
+            "m" => Duration::minutes(amount),
+            "h" => Duration::hours(amount),
+            "d" => Duration::days(amount),
+            _ => return None,
+        };
+        any = true;
+    }
+    any.then_some(total)
+}
+
+/// Gets the offset of a scrolling viewport of the given height, so the selected item stays visible
+/// with a few items of context around it. The offset moves as little as possible, so the viewport
+/// does not jump around while moving the selection.
+pub fn scroll_offset(offset: usize, selected: usize, height: usize, len: usize) -> usize {
+    const MARGIN: usize = 2;
+
+    let margin = MARGIN.min(height.saturating_sub(1) / 2);
+    let mut offset = offset;
+    if selected < offset + margin {
+        offset = selected.saturating_sub(margin);
+    }
+    if selected + margin >= offset + height {
+        offset = (selected + margin + 1).saturating_sub(height);
+    }
+    offset.min(len.saturating_sub(height))
+}
+
+/// Renders text as a QR code made of block characters, two modules per line. The colors are
+/// inverted so the code can be scanned from a terminal with a dark background.
+pub fn render_qr_code(text: &str) -> Option<String> {
+    use qrcode::{render::unicode::Dense1x2, QrCode};
+
+    let code = QrCode::new(text).ok()?;
+    let rendered = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    Some(rendered)
+}
+
 /// A predicate to adapt another one by mapping its input.
 ///
 /// See also https://github.com/assert-rs/predicates-rs/issues/142
@@ -315,14 +358,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_split_last_x() {
-        assert_eq!(
-            START_RECT.split_last_x(2),
-            (Rect::new(100, 100, 8, 20), Rect::new(108, 100, 2, 20))
-        );
-    }
-
     #[test]
     fn test_split_last_y() {
         assert_eq!(
@@ -331,19 +366,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_slice_x() {
-        assert_eq!(START_RECT.slice_x(..), START_RECT);
-        assert_eq!(START_RECT.slice_x(0..), START_RECT);
-        assert_eq!(START_RECT.slice_x(..10), START_RECT);
-        assert_eq!(START_RECT.slice_x(0..10), START_RECT);
-        assert_eq!(START_RECT.slice_x(..=9), START_RECT);
-        assert_eq!(START_RECT.slice_x(0..=9), START_RECT);
-
-        assert_eq!(START_RECT.slice_x(2..8), Rect::new(102, 100, 6, 20));
-        assert_eq!(START_RECT.slice_x(2..=7), Rect::new(102, 100, 6, 20));
-    }
-
     #[test]
     fn test_slice_y() {
         assert_eq!(START_RECT.slice_y(..), START_RECT);
@@ -357,6 +379,50 @@ mod tests {
         assert_eq!(START_RECT.slice_y(2..=17), Rect::new(100, 102, 10, 16));
     }
 
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(45)), "45m");
+        assert_eq!(format_duration(Duration::minutes(150)), "2h 30m");
+        assert_eq!(format_duration(Duration::hours(3)), "3h");
+        assert_eq!(format_duration(Duration::hours(28)), "1d 4h");
+        assert_eq!(format_duration(Duration::days(2)), "2d");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45m"), Some(Duration::minutes(45)));
+        assert_eq!(parse_duration(" 2h 30m "), Some(Duration::minutes(150)));
+        assert_eq!(parse_duration("1d 4h"), Some(Duration::hours(28)));
+
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("2"), None);
+        assert_eq!(parse_duration("2y"), None);
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        // the viewport only moves once the selection gets close to its edge
+        assert_eq!(scroll_offset(0, 5, 10, 100), 0);
+        assert_eq!(scroll_offset(0, 8, 10, 100), 1);
+        assert_eq!(scroll_offset(20, 21, 10, 100), 19);
+        assert_eq!(scroll_offset(20, 25, 10, 100), 20);
+
+        // the viewport does not scroll past the end of the list
+        assert_eq!(scroll_offset(0, 99, 10, 100), 90);
+        assert_eq!(scroll_offset(50, 3, 10, 5), 0);
+    }
+
+    #[test]
+    fn test_render_qr_code() {
+        let code = render_qr_code("td://3f2a9c1e/k4bW7xnP").unwrap();
+        let widths = code.lines().map(|l| l.chars().count()).collect::<Vec<_>>();
+
+        // square, with a quiet zone of 4 modules on each side
+        assert!(widths.iter().all(|&w| w == widths[0]));
+        assert_eq!(widths.len(), widths[0].div_ceil(2));
+        assert!(code.starts_with("████"));
+    }
+
     #[test]
     fn test_center_rect() {
         assert_eq!(START_RECT.center_rect(6, 4), Rect::new(102, 108, 6, 4));
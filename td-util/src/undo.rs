@@ -1,54 +1,164 @@
 //! Provides generic undo functionality on an arbitrary state object.
 
-use std::ops::Deref;
+use std::{
+    ops::{Deref, Range},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-// TODO: trim start of stack to ensure memory usage doesn't grow out of control
+use serde::{Deserialize, Serialize};
+use td_lib::database::{Database, DatabaseOp, TaskId};
+
+/// A state that can record its changes as a reversible patch.
+pub trait Undoable {
+    /// A change that can be applied to the state in either direction.
+    type Patch;
+
+    /// Changes the state with `func` and gets the patch that describes the change.
+    fn record<F: FnOnce(&mut Self)>(&mut self, func: F) -> Self::Patch;
+
+    /// Applies a patch created by [`Self::record`], or reverts it if `reverse` is set.
+    fn apply(&mut self, patch: &Self::Patch, reverse: bool);
+}
+
+impl Undoable for Database {
+    type Patch = Vec<DatabaseOp>;
+
+    fn record<F: FnOnce(&mut Self)>(&mut self, func: F) -> Self::Patch {
+        self.record_changes(func)
+    }
+
+    fn apply(&mut self, patch: &Self::Patch, reverse: bool) {
+        self.apply_ops(patch, reverse);
+    }
+}
+
+/// A single labeled change in the undo history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoStep<P> {
+    /// A short description of the change, such as "Delete task".
+    pub label: String,
+    /// The patch that applies the change.
+    pub patch: P,
+}
 
 /// A wrapper for a state, allowing rolling back changes using an undo-redo system.
 ///
-/// This operates by keeping around copies of the state, with a pointer to the current state.
-pub struct UndoWrapper<T: Clone> {
-    states: Vec<T>,
+/// This operates by keeping around the current state and a list of patches, with a pointer to the
+/// patch that was applied last. Undoing and redoing applies these patches to the current state.
+pub struct UndoWrapper<T: Undoable> {
+    state: T,
+    steps: Vec<UndoStep<T::Patch>>,
     current_index: usize,
     clean_index: Option<usize>,
+    max_depth: Option<usize>,
+    revision: u64,
+    /// The steps that the last undo or redo went through.
+    traversed: Range<usize>,
 }
 
-impl<T: Clone> UndoWrapper<T> {
+impl<T: Undoable> UndoWrapper<T> {
     /// Create a new instance with the given state as the current (and only) state.
     pub fn new(initial_state: T) -> Self {
         Self {
-            states: vec![initial_state],
+            state: initial_state,
+            steps: vec![],
             current_index: 0,
             clean_index: None,
+            max_depth: None,
+            revision: next_revision(),
+            traversed: 0..0,
         }
     }
 
-    /// Gets a reference to the current state.
+    /// Create a new instance with undo history that was stored earlier, see [`Self::steps`].
+    /// `state` must be the state the history was stored for, after `undo_count` steps were
+    /// applied. The history is ignored if it is not consistent with `undo_count`.
+    pub fn with_history(state: T, steps: Vec<UndoStep<T::Patch>>, undo_count: usize) -> Self {
+        if undo_count > steps.len() {
+            return Self::new(state);
+        }
+
+        Self {
+            steps,
+            current_index: undo_count,
+            ..Self::new(state)
+        }
+    }
+
+    /// Gets the steps that make up the undo history, oldest first. The first
+    /// [`Self::undo_count`] steps lead up to the current state, the others can be redone.
     #[must_use]
-    pub fn state(&self) -> &T {
-        debug_assert!(!self.states.is_empty());
-        debug_assert!(self.states.len() > self.current_index);
-        &self.states[self.current_index]
+    pub fn steps(&self) -> &[UndoStep<T::Patch>] {
+        &self.steps
     }
 
-    fn state_mut(&mut self) -> &mut T {
-        debug_assert!(!self.states.is_empty());
-        debug_assert!(self.states.len() > self.current_index);
-        &mut self.states[self.current_index]
+    /// Returns how many changes are kept in the undo history at most, if limited.
+    #[must_use]
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Limits how many changes are kept in the undo history. When the limit is reached, the
+    /// oldest changes are forgotten and can no longer be undone.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+        self.trim_history();
     }
 
-    /// Gets a mutable reference to the current state. Doing this will create a new copy of the
-    /// state that gets mutated, allowing calling undo to roll back to the previous state later.
-    pub fn modify<F: FnOnce(&mut T)>(&mut self, func: F) {
+    fn trim_history(&mut self) {
+        let Some(max_depth) = self.max_depth else {return;};
+
+        // changes that can still be redone are not trimmed
+        let trimmed = self
+            .steps
+            .len()
+            .saturating_sub(max_depth)
+            .min(self.current_index);
+        if trimmed == 0 {
+            return;
+        }
+
+        self.steps.drain(..trimmed);
+        self.current_index -= trimmed;
+        self.traversed = 0..0;
+        self.clean_index = self
+            .clean_index
+            .and_then(|clean_index| clean_index.checked_sub(trimmed));
+    }
+
+    /// Gets a reference to the current state.
+    #[must_use]
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    /// Gets a mutable reference to the current state. The changes made to it are stored as a
+    /// patch with the given label, allowing calling undo to roll back to the previous state later.
+    pub fn modify<F: FnOnce(&mut T)>(&mut self, label: impl Into<String>, func: F) {
         self.clear_redo_states();
 
-        self.states.push(self.state().clone());
+        let patch = self.state.record(func);
+        self.steps.push(UndoStep {
+            label: label.into(),
+            patch,
+        });
         self.current_index += 1;
-        func(self.state_mut());
+        self.revision = next_revision();
+        self.traversed = 0..0;
+        self.trim_history();
+    }
+
+    /// Changes the current state without storing an undo step, for changes that should not be
+    /// undone such as loading data that was left out before. The change must not touch anything
+    /// the steps in the undo history refer to.
+    pub fn modify_untracked<F: FnOnce(&mut T)>(&mut self, func: F) {
+        func(&mut self.state);
+        self.revision = next_revision();
+        self.traversed = 0..0;
     }
 
     fn clear_redo_states(&mut self) {
-        self.states.truncate(self.current_index + 1);
+        self.steps.truncate(self.current_index);
 
         if let Some(clean_index) = self.clean_index {
             if clean_index > self.current_index {
@@ -57,11 +167,14 @@ impl<T: Clone> UndoWrapper<T> {
         }
     }
 
-    /// Sets the current state pointer back one state, if possible. Returns `true` if the current
-    /// state has changed.
+    /// Reverts the last change, if possible. Returns `true` if the current state has changed.
     pub fn undo(&mut self) -> bool {
         if self.current_index > 0 {
             self.current_index -= 1;
+            self.state
+                .apply(&self.steps[self.current_index].patch, true);
+            self.revision = next_revision();
+            self.traversed = self.current_index..self.current_index + 1;
             true
         } else {
             false
@@ -77,8 +190,12 @@ impl<T: Clone> UndoWrapper<T> {
     /// Forwards the state one stage after calling [`Self::undo`]. This will only work right before
     /// an undo, modifying the current state using [`Self::modify`] will clear the redo queue.
     pub fn redo(&mut self) -> bool {
-        if self.current_index < self.states.len() - 1 {
+        if self.current_index < self.steps.len() {
+            self.state
+                .apply(&self.steps[self.current_index].patch, false);
+            self.traversed = self.current_index..self.current_index + 1;
             self.current_index += 1;
+            self.revision = next_revision();
             true
         } else {
             false
@@ -88,7 +205,43 @@ impl<T: Clone> UndoWrapper<T> {
     /// Returns how many times the state can be forwarded.
     #[must_use]
     pub fn redo_count(&self) -> usize {
-        self.states.len() - 1 - self.current_index
+        self.steps.len() - self.current_index
+    }
+
+    /// Undoes or redoes changes until [`Self::undo_count`] is the given count, to go back or
+    /// forward several steps at once. Returns `true` if the current state has changed.
+    pub fn jump_to(&mut self, undo_count: usize) -> bool {
+        let undo_count = undo_count.min(self.steps.len());
+        let start = self.current_index;
+        let changed = undo_count != start;
+
+        while self.current_index > undo_count {
+            self.undo();
+        }
+        while self.current_index < undo_count {
+            self.redo();
+        }
+        if changed {
+            self.traversed = start.min(undo_count)..start.max(undo_count);
+        }
+        changed
+    }
+
+    /// Gets the steps that were undone or redone by the last call to [`Self::undo`],
+    /// [`Self::redo`] or [`Self::jump_to`], oldest first. This is empty once the state is changed
+    /// in any other way.
+    #[must_use]
+    pub fn last_traversed(&self) -> &[UndoStep<T::Patch>] {
+        &self.steps[self.traversed.clone()]
+    }
+
+    /// Returns a number that changes every time the current state changes, through
+    /// [`Self::modify`], [`Self::undo`] or [`Self::redo`]. Revisions are unique across all
+    /// instances, so this can be used to invalidate data derived from the state even if the
+    /// wrapper itself is replaced.
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
     /// Marks the current state as the "clean" state. This can be used to keep track of which state
@@ -104,13 +257,41 @@ impl<T: Clone> UndoWrapper<T> {
     }
 }
 
-impl<T: Clone + Default> Default for UndoWrapper<T> {
+fn next_revision() -> u64 {
+    static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl UndoWrapper<Database> {
+    /// Gets the tasks that were added, removed or changed by the last undo or redo, including the
+    /// tasks whose dependencies changed. See [`Self::last_traversed`].
+    #[must_use]
+    pub fn last_changed_tasks(&self) -> Vec<TaskId> {
+        let mut task_ids = Vec::new();
+        let ops = self.last_traversed().iter().flat_map(|step| &step.patch);
+        for op in ops {
+            let ids = match op {
+                DatabaseOp::SetTask { id, .. } => vec![id],
+                DatabaseOp::SetDependency { from, to, .. } => vec![from, to],
+                _ => vec![],
+            };
+            for id in ids {
+                if !task_ids.contains(id) {
+                    task_ids.push(id.clone());
+                }
+            }
+        }
+        task_ids
+    }
+}
+
+impl<T: Undoable + Default> Default for UndoWrapper<T> {
     fn default() -> Self {
         Self::new(T::default())
     }
 }
 
-impl<T: Clone> Deref for UndoWrapper<T> {
+impl<T: Undoable> Deref for UndoWrapper<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -120,26 +301,66 @@ impl<T: Clone> Deref for UndoWrapper<T> {
 
 #[cfg(test)]
 mod tests {
+    use td_lib::database::Task;
+
     use super::*;
 
+    impl Undoable for i32 {
+        type Patch = Self;
+
+        fn record<F: FnOnce(&mut Self)>(&mut self, func: F) -> Self::Patch {
+            let old = *self;
+            func(self);
+            *self - old
+        }
+
+        fn apply(&mut self, patch: &Self::Patch, reverse: bool) {
+            *self += if reverse { -patch } else { *patch };
+        }
+    }
+
+    impl Undoable for () {
+        type Patch = ();
+
+        fn record<F: FnOnce(&mut Self)>(&mut self, func: F) -> Self::Patch {
+            func(self);
+        }
+
+        fn apply(&mut self, _patch: &Self::Patch, _reverse: bool) {}
+    }
+
     #[test]
     fn undo() {
         let mut undo = UndoWrapper::new(0i32);
         assert_eq!(undo.state(), &0);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &1);
 
         undo.undo();
         assert_eq!(undo.state(), &0);
     }
 
+    #[test]
+    fn revision_changes_with_state() {
+        let mut undo = UndoWrapper::new(0i32);
+        let initial = undo.revision();
+
+        undo.modify("change", |x| *x += 1);
+        let modified = undo.revision();
+        assert_ne!(initial, modified);
+
+        undo.undo();
+        assert_ne!(undo.revision(), modified);
+        assert_ne!(UndoWrapper::new(0i32).revision(), undo.revision());
+    }
+
     #[test]
     fn redo() {
         let mut undo = UndoWrapper::new(0i32);
         assert_eq!(undo.state(), &0);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &1);
 
         undo.undo();
@@ -154,10 +375,10 @@ mod tests {
         let mut undo = UndoWrapper::new(0i32);
         assert_eq!(undo.state(), &0);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &1);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &2);
 
         undo.undo();
@@ -178,7 +399,7 @@ mod tests {
         let mut undo = UndoWrapper::new(0i32);
         assert_eq!(undo.state(), &0);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &1);
 
         undo.undo();
@@ -200,10 +421,10 @@ mod tests {
 
         assert_eq!(undo.undo_count(), 0);
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
         assert_eq!(undo.undo_count(), 1);
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
         assert_eq!(undo.undo_count(), 2);
     }
 
@@ -213,8 +434,8 @@ mod tests {
 
         assert_eq!(undo.redo_count(), 0);
 
-        undo.modify(|_| ());
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
+        undo.modify("change", |_| ());
         assert_eq!(undo.redo_count(), 0);
 
         undo.undo();
@@ -235,10 +456,10 @@ mod tests {
         let mut undo = UndoWrapper::new(0i32);
         assert_eq!(undo.state(), &0);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &1);
 
-        undo.modify(|x| *x += 1);
+        undo.modify("change", |x| *x += 1);
         assert_eq!(undo.state(), &2);
 
         undo.undo();
@@ -248,7 +469,7 @@ mod tests {
         assert_eq!(undo.state(), &0);
 
         // push a completely new value. the redo states should be cleared.
-        undo.modify(|x| *x += 10);
+        undo.modify("change", |x| *x += 10);
         assert_eq!(undo.state(), &10);
 
         // doing redo now should not result in a previous value
@@ -262,7 +483,7 @@ mod tests {
     fn invalid_undo() {
         let mut undo = UndoWrapper::new(());
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
 
         assert!(undo.undo());
         assert!(!undo.undo());
@@ -273,7 +494,7 @@ mod tests {
     fn invalid_redo() {
         let mut undo = UndoWrapper::new(());
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
         assert!(undo.undo());
 
         assert!(undo.redo());
@@ -289,7 +510,7 @@ mod tests {
         undo.mark_clean();
         assert!(!undo.is_dirty());
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
         assert!(undo.is_dirty());
 
         undo.undo();
@@ -307,12 +528,132 @@ mod tests {
         let mut undo = UndoWrapper::new(());
         assert!(undo.is_dirty());
 
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
         undo.mark_clean();
         assert!(!undo.is_dirty());
 
         undo.undo();
-        undo.modify(|_| ());
+        undo.modify("change", |_| ());
+        assert!(undo.is_dirty());
+    }
+
+    #[test]
+    fn max_depth_trims_oldest_changes() {
+        let mut undo = UndoWrapper::new(0i32);
+        undo.set_max_depth(Some(2));
+        undo.mark_clean();
+
+        undo.modify("change", |x| *x += 1);
+        undo.modify("change", |x| *x += 1);
+        undo.modify("change", |x| *x += 1);
+        assert_eq!(undo.undo_count(), 2);
+        assert_eq!(undo.state(), &3);
+
+        // the clean state was trimmed, so it can't be reached anymore
+        assert!(undo.undo());
+        assert!(undo.undo());
+        assert!(!undo.undo());
+        assert_eq!(undo.state(), &1);
         assert!(undo.is_dirty());
     }
+
+    #[test]
+    fn restores_history() {
+        let mut undo = UndoWrapper::new(0i32);
+        undo.modify("change", |x| *x += 1);
+        undo.modify("change", |x| *x += 2);
+        undo.undo();
+
+        let mut restored = UndoWrapper::with_history(*undo.state(), undo.steps().to_vec(), 1);
+        assert_eq!(restored.undo_count(), 1);
+        assert_eq!(restored.redo_count(), 1);
+        assert!(restored.redo());
+        assert_eq!(restored.state(), &3);
+
+        let invalid = UndoWrapper::with_history(
+            0i32,
+            vec![UndoStep {
+                label: "change".into(),
+                patch: 1,
+            }],
+            2,
+        );
+        assert_eq!(invalid.undo_count(), 0);
+        assert_eq!(invalid.redo_count(), 0);
+    }
+
+    #[test]
+    fn jumps_to_labeled_step() {
+        let mut undo = UndoWrapper::new(0i32);
+        undo.modify("add 1", |x| *x += 1);
+        undo.modify("add 2", |x| *x += 2);
+        undo.modify("add 3", |x| *x += 3);
+        let labels = undo
+            .steps()
+            .iter()
+            .map(|s| s.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["add 1", "add 2", "add 3"]);
+
+        assert!(undo.jump_to(1));
+        assert_eq!(undo.state(), &1);
+        assert_eq!(undo.redo_count(), 2);
+
+        assert!(undo.jump_to(3));
+        assert_eq!(undo.state(), &6);
+        assert!(!undo.jump_to(3));
+
+        assert!(undo.jump_to(0));
+        assert_eq!(undo.state(), &0);
+    }
+
+    #[test]
+    fn undo_database() {
+        let mut undo = UndoWrapper::new(Database::default());
+        let task = Task::create_now("task".into());
+        let id = task.id().clone();
+
+        undo.modify("change", |db| db.add_task(task));
+        undo.modify("change", |db| db[&id].title = "renamed".into());
+        assert_eq!(undo[&id].title, "renamed");
+
+        undo.undo();
+        assert_eq!(undo[&id].title, "task");
+
+        undo.undo();
+        assert_eq!(undo.get_all_tasks().count(), 0);
+
+        undo.redo();
+        undo.redo();
+        assert_eq!(undo[&id].title, "renamed");
+    }
+
+    #[test]
+    fn last_changed_tasks() {
+        let mut undo = UndoWrapper::new(Database::default());
+        let [a, b, c] = ["a", "b", "c"].map(|title| Task::create_now(title.into()));
+        let [a_id, b_id, c_id] = [&a, &b, &c].map(|t| t.id().clone());
+
+        undo.modify("add a", |db| db.add_task(a));
+        undo.modify("add b and c", |db| {
+            db.add_task(b);
+            db.add_task(c);
+        });
+        assert!(undo.last_changed_tasks().is_empty());
+
+        undo.undo();
+        let changed = undo.last_changed_tasks();
+        assert_eq!(changed.len(), 2);
+        assert!(changed.contains(&b_id) && changed.contains(&c_id));
+
+        undo.redo();
+        assert_eq!(undo.last_changed_tasks().len(), 2);
+
+        undo.jump_to(0);
+        assert_eq!(undo.last_changed_tasks().len(), 3);
+        assert!(undo.last_changed_tasks().contains(&a_id));
+
+        undo.modify("add a", |db| db.add_task(Task::create_now("a".into())));
+        assert!(undo.last_changed_tasks().is_empty());
+    }
 }